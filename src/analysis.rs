@@ -0,0 +1,282 @@
+//! Item-response analysis of accumulated results: how hard each puzzle is
+//! and how able each model is, estimated together from every model's
+//! scores rather than from the puzzles' source ratings, and which puzzles
+//! don't tell models apart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::puzzle::PuzzleCollection;
+use crate::results::BenchmarkResult;
+
+/// Metadata key `write_difficulties` stores a puzzle's difficulty under.
+pub const DIFFICULTY_KEY: &str = "irt_difficulty";
+
+const MAX_ITERATIONS: usize = 500;
+const TOLERANCE: f64 = 1e-6;
+
+/// Estimated ability of a solver, in logits: a solver of ability `a` is
+/// expected to score `1 / (1 + e^(d - a))` on a puzzle of difficulty `d`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelAbility {
+    pub solver_name: String,
+    pub ability: f64,
+    pub puzzles: usize,
+}
+
+/// Estimated difficulty of a puzzle, in logits on the same scale as
+/// `ModelAbility::ability`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PuzzleDifficulty {
+    pub puzzle_id: String,
+    pub difficulty: f64,
+    /// How many attempts the estimate rests on.
+    pub responses: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrtFit {
+    pub abilities: Vec<ModelAbility>,
+    pub difficulties: Vec<PuzzleDifficulty>,
+}
+
+/// One attempt: solver, puzzle and the fraction of the puzzle solved.
+struct Response {
+    solver: usize,
+    puzzle: usize,
+    score: f64,
+}
+
+/// Fit a Rasch (one-parameter logistic) model to every attempt in
+/// `results`, scored by the fraction of the puzzle solved. Results from the
+/// same solver are pooled, as in `rate_results`. Abilities and difficulties
+/// get a standard normal prior, which keeps puzzles every model solved (or
+/// none did) finite and centres the scale at zero.
+pub fn fit_irt(results: &[BenchmarkResult]) -> IrtFit {
+    let mut solvers: Vec<String> = Vec::new();
+    let mut puzzles: Vec<String> = Vec::new();
+    let mut puzzle_index: HashMap<String, usize> = HashMap::new();
+    let mut responses = Vec::new();
+    for result in results {
+        let solver = match solvers.iter().position(|name| name == &result.solver_name) {
+            Some(solver) => solver,
+            None => {
+                solvers.push(result.solver_name.clone());
+                solvers.len() - 1
+            }
+        };
+        for score in &result.puzzle_scores {
+            if score.max_possible_score <= 0.0 {
+                continue;
+            }
+            let puzzle = *puzzle_index.entry(score.puzzle_id.clone()).or_insert_with(|| {
+                puzzles.push(score.puzzle_id.clone());
+                puzzles.len() - 1
+            });
+            responses.push(Response {
+                solver,
+                puzzle,
+                score: (score.score / score.max_possible_score).clamp(0.0, 1.0),
+            });
+        }
+    }
+
+    let mut abilities = vec![0.0; solvers.len()];
+    let mut difficulties = vec![0.0; puzzles.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let ability_change = newton_step(&mut abilities, &difficulties, &responses, 1.0, |r| (r.solver, r.puzzle));
+        let difficulty_change =
+            newton_step(&mut difficulties, &abilities, &responses, -1.0, |r| (r.puzzle, r.solver));
+        if ability_change.max(difficulty_change) < TOLERANCE {
+            break;
+        }
+    }
+
+    let mut solver_counts = vec![0; solvers.len()];
+    let mut puzzle_counts = vec![0; puzzles.len()];
+    for response in &responses {
+        solver_counts[response.solver] += 1;
+        puzzle_counts[response.puzzle] += 1;
+    }
+    IrtFit {
+        abilities: solvers
+            .into_iter()
+            .zip(abilities)
+            .zip(solver_counts)
+            .map(|((solver_name, ability), puzzles)| ModelAbility {
+                solver_name,
+                ability,
+                puzzles,
+            })
+            .collect(),
+        difficulties: puzzles
+            .into_iter()
+            .zip(difficulties)
+            .zip(puzzle_counts)
+            .map(|((puzzle_id, difficulty), responses)| PuzzleDifficulty {
+                puzzle_id,
+                difficulty,
+                responses,
+            })
+            .collect(),
+    }
+}
+
+/// One Newton step on the posterior of each of `params` with `others` held
+/// fixed, returning the largest change. `sign` is 1 for abilities and -1
+/// for difficulties, so the expected score is `σ(sign · (param - other))`;
+/// `indices` maps a response to its param and other.
+fn newton_step(
+    params: &mut [f64],
+    others: &[f64],
+    responses: &[Response],
+    sign: f64,
+    indices: impl Fn(&Response) -> (usize, usize),
+) -> f64 {
+    // Start from the prior's gradient and curvature.
+    let mut gradient: Vec<f64> = params.iter().map(|param| -param).collect();
+    let mut curvature = vec![1.0; params.len()];
+    for response in responses {
+        let (own, other) = indices(response);
+        let expected = 1.0 / (1.0 + (-sign * (params[own] - others[other])).exp());
+        gradient[own] += sign * (response.score - expected);
+        curvature[own] += expected * (1.0 - expected);
+    }
+    let mut largest = 0.0f64;
+    for ((param, gradient), curvature) in params.iter_mut().zip(gradient).zip(curvature) {
+        let step = gradient / curvature;
+        *param += step;
+        largest = largest.max(step.abs());
+    }
+    largest
+}
+
+/// Store each fitted difficulty in the metadata of the matching puzzle of
+/// `collection` under `DIFFICULTY_KEY`, returning how many puzzles were
+/// updated. Puzzles no result attempted are left as they are.
+pub fn write_difficulties(collection: &mut PuzzleCollection, fit: &IrtFit) -> usize {
+    let difficulties: HashMap<&str, f64> = fit
+        .difficulties
+        .iter()
+        .map(|d| (d.puzzle_id.as_str(), d.difficulty))
+        .collect();
+    let mut updated = 0;
+    for puzzle in &mut collection.puzzles {
+        if let Some(&difficulty) = difficulties.get(puzzle.id.as_str()) {
+            let rounded = (difficulty * 1000.0).round() / 1000.0;
+            puzzle.metadata.insert(DIFFICULTY_KEY.to_string(), serde_json::json!(rounded));
+            updated += 1;
+        }
+    }
+    updated
+}
+
+/// Why a puzzle is worth reviewing or removing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewReason {
+    /// Every model solved it, so it tells none apart.
+    AllSolved,
+    /// No model solved any of it.
+    NoneSolved,
+    /// Every model scored the same partial credit.
+    SameScore,
+    /// Weaker models did better on it than stronger ones, which often
+    /// means a wrong or ambiguous solution.
+    Inverted,
+}
+
+/// A puzzle flagged by `review_puzzles`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PuzzleReview {
+    pub puzzle_id: String,
+    pub reason: ReviewReason,
+    /// How many models attempted it.
+    pub models: usize,
+    /// The models' mean fraction of the puzzle solved.
+    pub mean_score: f64,
+    /// Correlation between the models' abilities and their scores on the
+    /// puzzle; `None` when the scores don't vary.
+    pub discrimination: Option<f64>,
+}
+
+/// Puzzles attempted by at least `min_models` models that don't tell them
+/// apart: every model scored the same, or the correlation between the
+/// models' abilities (from `fit`) and their scores on the puzzle is below
+/// `max_discrimination`. A model's attempts at a puzzle across results are
+/// averaged. Inverted puzzles come first, most inverted first.
+pub fn review_puzzles(
+    results: &[BenchmarkResult],
+    fit: &IrtFit,
+    min_models: usize,
+    max_discrimination: f64,
+) -> Vec<PuzzleReview> {
+    let abilities: HashMap<&str, f64> = fit
+        .abilities
+        .iter()
+        .map(|a| (a.solver_name.as_str(), a.ability))
+        .collect();
+    let mut attempts: BTreeMap<&str, BTreeMap<&str, (f64, usize)>> = BTreeMap::new();
+    for result in results {
+        for score in &result.puzzle_scores {
+            if score.max_possible_score <= 0.0 {
+                continue;
+            }
+            let entry = attempts
+                .entry(&score.puzzle_id)
+                .or_default()
+                .entry(&result.solver_name)
+                .or_default();
+            entry.0 += (score.score / score.max_possible_score).clamp(0.0, 1.0);
+            entry.1 += 1;
+        }
+    }
+
+    let mut reviews = Vec::new();
+    for (puzzle_id, by_solver) in attempts {
+        if by_solver.len() < min_models.max(2) {
+            continue;
+        }
+        let points: Vec<(f64, f64)> = by_solver
+            .iter()
+            .map(|(solver, (total, count))| (abilities.get(solver).copied().unwrap_or(0.0), total / *count as f64))
+            .collect();
+        let n = points.len() as f64;
+        let mean_ability = points.iter().map(|(a, _)| a).sum::<f64>() / n;
+        let mean_score = points.iter().map(|(_, s)| s).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|(a, s)| (a - mean_ability) * (s - mean_score)).sum();
+        let ability_spread = points.iter().map(|(a, _)| (a - mean_ability).powi(2)).sum::<f64>().sqrt();
+        let score_spread = points.iter().map(|(_, s)| (s - mean_score).powi(2)).sum::<f64>().sqrt();
+
+        let discrimination = (score_spread > 1e-9 && ability_spread > 1e-9)
+            .then(|| covariance / (ability_spread * score_spread));
+        let reason = if score_spread <= 1e-9 {
+            Some(if mean_score >= 1.0 - 1e-9 {
+                ReviewReason::AllSolved
+            } else if mean_score <= 1e-9 {
+                ReviewReason::NoneSolved
+            } else {
+                ReviewReason::SameScore
+            })
+        } else {
+            discrimination
+                .filter(|&d| d < max_discrimination)
+                .map(|_| ReviewReason::Inverted)
+        };
+        if let Some(reason) = reason {
+            reviews.push(PuzzleReview {
+                puzzle_id: puzzle_id.to_string(),
+                reason,
+                models: points.len(),
+                mean_score,
+                discrimination,
+            });
+        }
+    }
+    reviews.sort_by(|a, b| {
+        (a.reason != ReviewReason::Inverted)
+            .cmp(&(b.reason != ReviewReason::Inverted))
+            .then(a.discrimination.unwrap_or(0.0).total_cmp(&b.discrimination.unwrap_or(0.0)))
+    });
+    reviews
+}
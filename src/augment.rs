@@ -0,0 +1,318 @@
+//! Image perturbations for robustness studies: the same positions shown to
+//! vision models through JPEG artifacts, blur, a tilted or skewed camera and
+//! shifted colors.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AugmentError {
+    #[error("Invalid augmentation: {0}")]
+    InvalidSpec(String),
+    #[error("Image error: {0}")]
+    Image(String),
+    #[error("Image augmentation requires the `render` feature")]
+    Unsupported,
+}
+
+/// Perturbations applied to a board image, each off unless set. Written as
+/// a comma-separated spec like `jpeg=30,blur=1.5,rotate=3`.
+///
+/// Random amounts are drawn from a seed derived from the puzzle id and
+/// state index, so every model sees the same perturbed image.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Augmentation {
+    /// Re-encode as JPEG at this quality (1-100).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jpeg: Option<u8>,
+    /// Gaussian blur with this sigma in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blur: Option<f32>,
+    /// Rotate by up to this many degrees either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate: Option<f32>,
+    /// Move each corner by up to this fraction of the image size, as if
+    /// photographed at an angle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perspective: Option<f32>,
+    /// Scale brightness, contrast and saturation by up to this fraction
+    /// either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<f32>,
+    /// Mixed into every image's seed, to draw a different set of
+    /// perturbations.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub seed: u64,
+}
+
+fn is_zero(seed: &u64) -> bool {
+    *seed == 0
+}
+
+impl Augmentation {
+    pub fn is_none(&self) -> bool {
+        self.jpeg.is_none()
+            && self.blur.is_none()
+            && self.rotate.is_none()
+            && self.perspective.is_none()
+            && self.jitter.is_none()
+    }
+
+    /// The seed for state `index` of puzzle `puzzle_id`.
+    pub fn image_seed(&self, puzzle_id: &str, index: usize) -> u64 {
+        let digest = Sha256::digest(format!("{}:{}:{}", self.seed, puzzle_id, index));
+        u64::from_le_bytes(digest[..8].try_into().expect("digest has 8 bytes"))
+    }
+
+    /// Perturb the image in a `data:` URI, returning a PNG data URI.
+    pub fn apply_to_data_uri(&self, uri: &str, seed: u64) -> Result<String, AugmentError> {
+        if self.is_none() {
+            return Ok(uri.to_string());
+        }
+        apply_to_data_uri(self, uri, seed)
+    }
+}
+
+#[cfg(feature = "render")]
+fn apply_to_data_uri(augmentation: &Augmentation, uri: &str, seed: u64) -> Result<String, AugmentError> {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+
+    let data = uri
+        .split_once(";base64,")
+        .filter(|(header, _)| header.starts_with("data:"))
+        .map(|(_, data)| data)
+        .ok_or_else(|| AugmentError::Image("not a base64 data URI".to_string()))?;
+    let bytes = BASE64.decode(data).map_err(|e| AugmentError::Image(e.to_string()))?;
+    let image = image::load_from_memory(&bytes).map_err(|e| AugmentError::Image(e.to_string()))?;
+    let image = apply(augmentation, image.to_rgba8(), seed)?;
+    let mut png = Vec::new();
+    image
+        .write_with_encoder(image::codecs::png::PngEncoder::new(std::io::Cursor::new(&mut png)))
+        .map_err(|e| AugmentError::Image(e.to_string()))?;
+    Ok(format!("data:image/png;base64,{}", BASE64.encode(png)))
+}
+
+#[cfg(not(feature = "render"))]
+fn apply_to_data_uri(_augmentation: &Augmentation, _uri: &str, _seed: u64) -> Result<String, AugmentError> {
+    Err(AugmentError::Unsupported)
+}
+
+#[cfg(feature = "render")]
+pub use render::apply;
+
+#[cfg(feature = "render")]
+mod render {
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use std::io::Cursor;
+
+    use super::{AugmentError, Augmentation};
+
+    /// Uncovered areas after warping, and what JPEG flattens transparency onto.
+    const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// Apply `augmentation` to `image` in the order a camera would: color
+    /// jitter, the perspective and rotation warp, blur, then JPEG
+    /// compression.
+    pub fn apply(augmentation: &Augmentation, mut image: RgbaImage, seed: u64) -> Result<RgbaImage, AugmentError> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut spread = |amount: f32| rng.random_range(-amount.abs()..=amount.abs());
+
+        if let Some(jitter) = augmentation.jitter {
+            let factors = [1.0 + spread(jitter), 1.0 + spread(jitter), 1.0 + spread(jitter)];
+            jitter_colors(&mut image, factors);
+        }
+
+        let angle = augmentation.rotate.map_or(0.0, &mut spread).to_radians();
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let corners = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+        let mut moved = corners;
+        if let Some(perspective) = augmentation.perspective {
+            for corner in &mut moved {
+                corner.0 += spread(perspective) * width;
+                corner.1 += spread(perspective) * height;
+            }
+        }
+        if angle != 0.0 || augmentation.perspective.is_some() {
+            let (cx, cy) = (width / 2.0, height / 2.0);
+            let (sin, cos) = angle.sin_cos();
+            let moved = moved.map(|(x, y)| (cx + (x - cx) * cos - (y - cy) * sin, cy + (x - cx) * sin + (y - cy) * cos));
+            // Shrink the warped board to fit, so no square is cut off.
+            let (min_x, max_x) = moved.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+            let (min_y, max_y) = moved.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+            let scale = (width / (max_x - min_x)).min(height / (max_y - min_y)).min(1.0);
+            let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+            let moved = moved.map(|(x, y)| (cx + (x - mid_x) * scale, cy + (y - mid_y) * scale));
+            // Map output pixels back to where they come from.
+            if let Some(homography) = homography(moved, corners) {
+                image = warp(&image, &homography);
+            }
+        }
+
+        if let Some(sigma) = augmentation.blur
+            && sigma > 0.0
+        {
+            image = image::imageops::blur(&image, sigma);
+        }
+
+        if let Some(quality) = augmentation.jpeg {
+            image = compress(&image, quality)?;
+        }
+        Ok(image)
+    }
+
+    /// Round-trip `image` through JPEG at `quality`, keeping its artifacts.
+    fn compress(image: &RgbaImage, quality: u8) -> Result<RgbaImage, AugmentError> {
+        let mut flat = RgbaImage::from_pixel(image.width(), image.height(), BACKGROUND);
+        image::imageops::overlay(&mut flat, image, 0, 0);
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgba8(flat)
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(Cursor::new(&mut jpeg), quality.clamp(1, 100)))
+            .map_err(|e| AugmentError::Image(e.to_string()))?;
+        let decoded = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg)
+            .map_err(|e| AugmentError::Image(e.to_string()))?;
+        Ok(decoded.to_rgba8())
+    }
+
+    /// Scale brightness, contrast (around mid-grey) and saturation by the
+    /// given factors.
+    fn jitter_colors(image: &mut RgbaImage, [brightness, contrast, saturation]: [f32; 3]) {
+        for pixel in image.pixels_mut() {
+            let [r, g, b, _] = pixel.0.map(|c| c as f32);
+            let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+            for channel in 0..3 {
+                let value = luma + (pixel[channel] as f32 - luma) * saturation;
+                let value = 127.5 + (value - 127.5) * contrast;
+                pixel[channel] = (value * brightness).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    /// The projective transform taking each of the points `from` to the
+    /// matching point in `to`, as a row-major 3x3 matrix; `None` if the
+    /// points are degenerate.
+    fn homography(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<[f64; 9]> {
+        // Solve for h0..h7 (h8 = 1) by Gaussian elimination.
+        let mut rows = [[0.0f64; 9]; 8];
+        for (i, (&(x, y), &(u, v))) in from.iter().zip(&to).enumerate() {
+            let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+            rows[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+        }
+        for column in 0..8 {
+            let pivot = (column..8).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+            if rows[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            rows.swap(column, pivot);
+            let pivot_row = rows[column];
+            for (index, row) in rows.iter_mut().enumerate() {
+                if index != column {
+                    let factor = row[column] / pivot_row[column];
+                    for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(column) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+        let h = |i: usize| rows[i][8] / rows[i][i];
+        Some([h(0), h(1), h(2), h(3), h(4), h(5), h(6), h(7), 1.0])
+    }
+
+    /// Resample `image` so each output pixel takes the bilinearly
+    /// interpolated color at `homography` applied to its center.
+    fn warp(image: &RgbaImage, homography: &[f64; 9]) -> RgbaImage {
+        let h = homography;
+        RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let (x, y) = (x as f64 + 0.5, y as f64 + 0.5);
+            let w = h[6] * x + h[7] * y + h[8];
+            let u = (h[0] * x + h[1] * y + h[2]) / w - 0.5;
+            let v = (h[3] * x + h[4] * y + h[5]) / w - 0.5;
+            sample(image, u, v)
+        })
+    }
+
+    fn sample(image: &RgbaImage, u: f64, v: f64) -> Rgba<u8> {
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        let at = |x: f64, y: f64| {
+            if x < 0.0 || y < 0.0 || x >= image.width() as f64 || y >= image.height() as f64 {
+                BACKGROUND
+            } else {
+                *image.get_pixel(x as u32, y as u32)
+            }
+        };
+        let (a, b, c, d) = (at(x0, y0), at(x0 + 1.0, y0), at(x0, y0 + 1.0), at(x0 + 1.0, y0 + 1.0));
+        let mut pixel = [0u8; 4];
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let top = a[channel] as f64 * (1.0 - fx) + b[channel] as f64 * fx;
+            let bottom = c[channel] as f64 * (1.0 - fx) + d[channel] as f64 * fx;
+            *value = (top * (1.0 - fy) + bottom * fy).round() as u8;
+        }
+        Rgba(pixel)
+    }
+}
+
+impl FromStr for Augmentation {
+    type Err = AugmentError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut augmentation = Augmentation::default();
+        for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| AugmentError::InvalidSpec(format!("expected key=value, got {:?}", part)))?;
+            let invalid = || AugmentError::InvalidSpec(format!("invalid value for {}: {:?}", key, value));
+            let amount = || value.parse::<f32>().ok().filter(|v| v.is_finite() && *v >= 0.0).ok_or_else(invalid);
+            match key {
+                "jpeg" => {
+                    augmentation.jpeg = Some(value.parse().ok().filter(|q| (1..=100).contains(q)).ok_or_else(invalid)?)
+                }
+                "blur" => augmentation.blur = Some(amount()?),
+                "rotate" => augmentation.rotate = Some(amount()?),
+                "perspective" => augmentation.perspective = Some(amount()?),
+                "jitter" => augmentation.jitter = Some(amount()?),
+                "seed" => augmentation.seed = value.parse().map_err(|_| invalid())?,
+                _ => {
+                    return Err(AugmentError::InvalidSpec(format!(
+                        "unknown perturbation {:?} (expected jpeg, blur, rotate, perspective, jitter or seed)",
+                        key
+                    )));
+                }
+            }
+        }
+        Ok(augmentation)
+    }
+}
+
+impl fmt::Display for Augmentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(quality) = self.jpeg {
+            parts.push(format!("jpeg={}", quality));
+        }
+        for (key, amount) in [
+            ("blur", self.blur),
+            ("rotate", self.rotate),
+            ("perspective", self.perspective),
+            ("jitter", self.jitter),
+        ] {
+            if let Some(amount) = amount {
+                parts.push(format!("{}={}", key, amount));
+            }
+        }
+        if self.seed != 0 {
+            parts.push(format!("seed={}", self.seed));
+        }
+        write!(f, "{}", parts.join(","))
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use boardgamebench::augment::Augmentation;
+use boardgamebench::budget::{ContextCheck, ReasoningEffort};
+use boardgamebench::compression::PromptPart;
+use boardgamebench::duplicates::DuplicatePolicy;
+use boardgamebench::evaluation::ProviderProfile;
+use boardgamebench::notation::MoveNotation;
+use boardgamebench::prompt::{Modality, PromptLanguage, PromptStyle, Representation};
+use boardgamebench::puzzle::Scorer;
+use boardgamebench::scoring::AnswerScoring;
+
+use crate::{AblationVariant, Args, TrackingBackend};
+
+/// A benchmark run described in a TOML file (`--config run.toml`). Every
+/// key mirrors the command-line flag of the same name; flags given on the
+/// command line take precedence over the file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfig {
+    pub profile: Option<String>,
+    /// Named providers, selected with `profile`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProviderProfile>,
+    pub model: Option<Vec<String>>,
+    pub solver_command: Option<String>,
+    pub modality: Option<Modality>,
+    pub representation: Option<Representation>,
+    pub ablate: Option<Vec<AblationVariant>>,
+    pub prompt_style: Option<PromptStyle>,
+    pub ablate_prompts: Option<Vec<PromptStyle>>,
+    pub intuition: Option<bool>,
+    pub answer_only_tokens: Option<u32>,
+    pub sequences: Option<bool>,
+    pub answer_notation: Option<MoveNotation>,
+    pub prompt_lang: Option<PromptLanguage>,
+    pub augment: Option<Augmentation>,
+    pub temperature: Option<f64>,
+    pub max_completion_tokens: Option<u32>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub context_window: Option<u64>,
+    pub context_check: Option<ContextCheck>,
+    pub prompt_budget: Option<u64>,
+    pub compression_priority: Option<Vec<PromptPart>>,
+    pub confidence: Option<bool>,
+    pub sweep_completion_tokens: Option<Vec<u32>>,
+    pub sweep_effort: Option<Vec<ReasoningEffort>>,
+    /// One collection file or a list of them.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub puzzle_file: Option<Vec<String>>,
+    pub duplicates: Option<DuplicatePolicy>,
+    pub output: Option<String>,
+    pub threads: Option<usize>,
+    pub passes: Option<usize>,
+    pub scorer: Option<Scorer>,
+    pub sequential: Option<bool>,
+    pub answer_scoring: Option<AnswerScoring>,
+    pub engine: Option<String>,
+    pub engine_depth: Option<u32>,
+    pub retries: Option<u32>,
+    pub ids: Option<Vec<String>>,
+    pub theme: Option<String>,
+    pub include_tags: Option<Vec<String>>,
+    pub exclude_tags: Option<Vec<String>>,
+    /// `--metadata` filters as a table, e.g. `{ difficulty = "hard" }`.
+    pub metadata: Option<BTreeMap<String, String>>,
+    pub breakdown_by: Option<Vec<String>>,
+    pub sample: Option<usize>,
+    pub stratify_by: Option<String>,
+    pub shuffle: Option<bool>,
+    pub seed: Option<u64>,
+    pub skip: Option<usize>,
+    pub limit: Option<usize>,
+    pub max_cost: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub input_price: Option<f64>,
+    pub output_price: Option<f64>,
+    pub interrupt_grace: Option<f64>,
+    pub time_budget: Option<f64>,
+    pub time_decay: Option<f64>,
+    pub stop_precision: Option<f64>,
+    pub stop_baseline: Option<String>,
+    pub stop_confidence: Option<f64>,
+    pub stop_min_puzzles: Option<usize>,
+    pub judge_model: Option<String>,
+    pub blind: Option<bool>,
+    pub blind_key: Option<String>,
+    pub sign_key: Option<String>,
+    pub answers_output: Option<String>,
+    pub solution_key: Option<String>,
+    pub tracking: Option<TrackingBackend>,
+    pub tracking_uri: Option<String>,
+    pub mlflow_experiment: Option<String>,
+    pub notify_url: Option<String>,
+    pub otlp_endpoint: Option<String>,
+    #[cfg(feature = "serve")]
+    pub progress_addr: Option<String>,
+}
+
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(Option::<OneOrMany>::deserialize(deserializer)?.map(|files| match files {
+        OneOrMany::One(file) => vec![file],
+        OneOrMany::Many(files) => files,
+    }))
+}
+
+impl RunConfig {
+    pub fn load_from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse config {}", path))
+    }
+
+    /// Fill in every argument not given on the command line from the file.
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = self.$field
+                    && !from_cli(stringify!($field))
+                {
+                    args.$field = value;
+                }
+            )*};
+        }
+        macro_rules! merge_optional {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = self.$field
+                    && !from_cli(stringify!($field))
+                {
+                    args.$field = Some(value);
+                }
+            )*};
+        }
+
+        args.profiles = self.profiles;
+        if let Some(metadata) = self.metadata
+            && !from_cli("metadata")
+        {
+            args.metadata = metadata.into_iter().collect();
+        }
+        merge!(
+            model,
+            modality,
+            representation,
+            ablate,
+            prompt_style,
+            ablate_prompts,
+            intuition,
+            answer_only_tokens,
+            sweep_completion_tokens,
+            sweep_effort,
+            sequences,
+            confidence,
+            context_check,
+            compression_priority,
+            puzzle_file,
+            duplicates,
+            threads,
+            passes,
+            scorer,
+            sequential,
+            engine_depth,
+            retries,
+            ids,
+            include_tags,
+            exclude_tags,
+            breakdown_by,
+            shuffle,
+            seed,
+            skip,
+            input_price,
+            interrupt_grace,
+            output_price,
+            stop_confidence,
+            stop_min_puzzles,
+            blind,
+            blind_key,
+            mlflow_experiment,
+        );
+        merge_optional!(
+            solver_command,
+            answer_notation,
+            prompt_lang,
+            augment,
+            profile,
+            temperature,
+            max_completion_tokens,
+            reasoning_effort,
+            context_window,
+            prompt_budget,
+            answer_scoring,
+            engine,
+            output,
+            theme,
+            limit,
+            sample,
+            stratify_by,
+            max_cost,
+            max_tokens,
+            time_budget,
+            time_decay,
+            stop_precision,
+            stop_baseline,
+            judge_model,
+            tracking,
+            tracking_uri,
+            notify_url,
+            otlp_endpoint,
+            sign_key,
+            answers_output,
+            solution_key,
+        );
+        #[cfg(feature = "serve")]
+        merge_optional!(progress_addr);
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Checksums of the assets that never change, as `sha256sum` writes them.
+const PINNED_CHECKSUMS: &str = include_str!("../../assets.sha256");
+
+/// Checksums of downloaded assets without a pinned one, recorded on first
+/// download and checked from then on.
+const MANIFEST: &str = "downloads.sha256";
+
+const BOARD_THEMES: [&str; 9] = [
+    "brown",
+    "dark_wood",
+    "glass",
+    "newspaper",
+    "purple",
+    "sky",
+    "tournament",
+    "translucent",
+    "walnut",
+];
+
+const PIECE_SETS: [(&str, &str); 3] = [
+    ("classic", "https://images.chesscomfiles.com/chess-themes/pieces/classic/150"),
+    ("club", "https://images.chesscomfiles.com/chess-themes/pieces/game_room/150"),
+    ("neo", "https://assets-themes.chess.com/image/ejgfv/150"),
+];
+
+const THREE_PIECE_TABLES: [&str; 5] = ["KBvK", "KNvK", "KPvK", "KQvK", "KRvK"];
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Asset {
+    /// The Lichess puzzle database the generator reads (about 1 GB unpacked)
+    LichessPuzzles,
+    /// The board themes the generator renders on
+    BoardImages,
+    /// The piece sets the generator renders with
+    PieceImages,
+    /// Three-piece Syzygy endgame tablebases, e.g. for an engine's SyzygyPath
+    Tablebases,
+}
+
+impl Asset {
+    /// The assets the generator needs.
+    pub const REQUIRED: [Asset; 3] = [Asset::LichessPuzzles, Asset::BoardImages, Asset::PieceImages];
+
+    fn files(self) -> Vec<RemoteFile> {
+        match self {
+            Asset::LichessPuzzles => vec![RemoteFile {
+                url: "https://database.lichess.org/lichess_db_puzzle.csv.zst".to_string(),
+                path: PathBuf::from("database/lichess_db_puzzle.csv"),
+                zstd: true,
+            }],
+            Asset::BoardImages => BOARD_THEMES
+                .iter()
+                .map(|theme| RemoteFile {
+                    url: format!("https://images.chesscomfiles.com/chess-themes/boards/{}/200.png", theme),
+                    path: PathBuf::from(format!("images/chess/board/{}.png", theme)),
+                    zstd: false,
+                })
+                .collect(),
+            Asset::PieceImages => PIECE_SETS
+                .iter()
+                .flat_map(|(name, prefix)| {
+                    ["w", "b"].into_iter().flat_map(move |color| {
+                        ["b", "k", "n", "q", "r", "p"].into_iter().map(move |piece| RemoteFile {
+                            url: format!("{}/{}{}.png", prefix, color, piece),
+                            path: PathBuf::from(format!("images/chess/pieces/{}/{}{}.png", name, color, piece)),
+                            zstd: false,
+                        })
+                    })
+                })
+                .collect(),
+            Asset::Tablebases => THREE_PIECE_TABLES
+                .iter()
+                .flat_map(|table| {
+                    ["rtbw", "rtbz"].into_iter().map(move |extension| RemoteFile {
+                        url: format!("https://tablebase.lichess.ovh/tables/standard/3-4-5/{}.{}", table, extension),
+                        path: PathBuf::from(format!("tablebases/syzygy/{}.{}", table, extension)),
+                        zstd: false,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One file of an asset: where it comes from and where it goes under the
+/// cache directory.
+struct RemoteFile {
+    url: String,
+    path: PathBuf,
+    /// The download is zstd-compressed and stored unpacked.
+    zstd: bool,
+}
+
+/// What to do with files that are already there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Download missing files and replace ones that fail verification.
+    Fetch,
+    /// Download everything again.
+    Force,
+    /// Only verify the files that are there.
+    Verify,
+}
+
+/// Fetch `assets` into `cache_dir`, laid out as the generator expects, and
+/// verify every file against its pinned or recorded SHA-256.
+pub fn download(assets: &[Asset], cache_dir: &str, mode: Mode) -> Result<()> {
+    let cache_dir = Path::new(cache_dir);
+    let pinned = parse_checksums(PINNED_CHECKSUMS);
+    let manifest_path = cache_dir.join(MANIFEST);
+    let mut recorded = match fs::read_to_string(&manifest_path) {
+        Ok(content) => parse_checksums(&content),
+        Err(_) => BTreeMap::new(),
+    };
+    let runtime = tokio::runtime::Runtime::new()?;
+    let client = reqwest::Client::new();
+
+    let mut failed = 0;
+    for asset in assets {
+        for file in asset.files() {
+            let key = file.path.to_string_lossy().replace('\\', "/");
+            let expected = pinned.get(&key).or_else(|| recorded.get(&key)).cloned();
+            let target = cache_dir.join(&file.path);
+            let present = match (&expected, target.exists()) {
+                (Some(expected), true) => Some(&sha256_file(&target)? == expected),
+                (None, true) => None,
+                (_, false) => Some(false),
+            };
+            match (mode, present) {
+                (Mode::Verify, Some(true)) => info!(path = %key, "verified"),
+                (Mode::Verify, None) => warn!(path = %key, "no checksum to verify against"),
+                (Mode::Verify, Some(false)) => {
+                    warn!(path = %key, "missing or failed verification");
+                    failed += 1;
+                }
+                (Mode::Fetch, Some(true)) => info!(path = %key, "up to date"),
+                (Mode::Fetch, None) => {
+                    // Present but never recorded, e.g. placed by hand.
+                    recorded.insert(key.clone(), sha256_file(&target)?);
+                    info!(path = %key, "recorded checksum of existing file");
+                }
+                _ => match runtime.block_on(fetch(&client, &file, &target)) {
+                    Ok(checksum) => match expected {
+                        Some(expected) if checksum != expected && pinned.contains_key(&key) => {
+                            let _ = fs::remove_file(&target);
+                            warn!(
+                                path = %key,
+                                url = %file.url,
+                                expected = %expected,
+                                actual = %checksum,
+                                "checksum mismatch"
+                            );
+                            failed += 1;
+                        }
+                        _ => {
+                            if !pinned.contains_key(&key) {
+                                recorded.insert(key.clone(), checksum);
+                            }
+                            info!(path = %key, "downloaded");
+                        }
+                    },
+                    Err(e) => {
+                        warn!(path = %key, url = %file.url, error = %format!("{:#}", e), "download failed");
+                        failed += 1;
+                    }
+                },
+            }
+        }
+    }
+
+    if mode != Mode::Verify && !recorded.is_empty() {
+        let manifest: String = recorded
+            .iter()
+            .map(|(path, checksum)| format!("{}  {}\n", checksum, path))
+            .collect();
+        fs::create_dir_all(cache_dir)?;
+        fs::write(&manifest_path, manifest)
+            .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    }
+    if failed > 0 {
+        anyhow::bail!("{} files failed to download or verify", failed);
+    }
+    Ok(())
+}
+
+/// Download `file` to `target`, unpacking it if needed, and return the
+/// SHA-256 of what was stored. Goes through a `.part` file so an
+/// interrupted download never looks complete.
+async fn fetch(client: &reqwest::Client, file: &RemoteFile, target: &Path) -> Result<String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let part = target.with_extension("part");
+    let mut response = client.get(&file.url).send().await?.error_for_status()?;
+    let checksum = if file.zstd {
+        let packed = target.with_extension("zst.part");
+        let mut out = BufWriter::new(File::create(&packed)?);
+        while let Some(chunk) = response.chunk().await? {
+            out.write_all(&chunk)?;
+        }
+        out.flush()?;
+        drop(out);
+        let checksum = unpack_zstd(&packed, &part);
+        let _ = fs::remove_file(&packed);
+        checksum?
+    } else {
+        let mut out = HashingWriter::new(BufWriter::new(File::create(&part)?));
+        while let Some(chunk) = response.chunk().await? {
+            out.write_all(&chunk)?;
+        }
+        out.finish()?
+    };
+    fs::rename(&part, target)?;
+    Ok(checksum)
+}
+
+/// Unpack every zstd frame of `packed` into `target`, returning the SHA-256
+/// of the unpacked data.
+fn unpack_zstd(packed: &Path, target: &Path) -> Result<String> {
+    let mut input = BufReader::new(File::open(packed)?);
+    let mut out = HashingWriter::new(BufWriter::new(File::create(target)?));
+    while !input.fill_buf()?.is_empty() {
+        let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut input)
+            .map_err(|e| anyhow::anyhow!("invalid zstd data in {}: {}", packed.display(), e))?;
+        io::copy(&mut decoder, &mut out)?;
+    }
+    out.finish()
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `sha256sum` output as a map from path to checksum.
+fn parse_checksums(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (checksum, path) = line.split_once(char::is_whitespace)?;
+            Some((path.trim_start().trim_start_matches('*').to_string(), checksum.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Writes through to `inner` while hashing everything written.
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn finish(mut self) -> Result<String> {
+        self.inner.flush()?;
+        Ok(format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
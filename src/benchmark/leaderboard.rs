@@ -0,0 +1,329 @@
+use anyhow::Result;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use boardgamebench::prompt::Modality;
+use boardgamebench::puzzle::{PuzzleCollection, Scorer};
+use boardgamebench::results::BenchmarkResult;
+use boardgamebench::signing;
+
+use crate::serve::{ApiError, api_error};
+
+/// Shared state of the leaderboard server. Accepted results live both in
+/// memory, as scored by the server, and as uploaded in one JSON file each in
+/// `store`.
+struct LeaderboardState {
+    collection: PuzzleCollection,
+    checksum: String,
+    store: PathBuf,
+    results: Mutex<Vec<(String, BenchmarkResult)>>,
+}
+
+/// A solver's best accepted result.
+#[derive(Serialize)]
+struct LeaderboardEntry<'a> {
+    rank: usize,
+    solver_name: &'a str,
+    modality: Modality,
+    average_score: f64,
+    total_score: f64,
+    max_possible_score: f64,
+    total_puzzles: usize,
+    reasoning_score: Option<f64>,
+    timestamp: &'a str,
+    result_id: &'a str,
+    /// How many results this solver has uploaded in this modality.
+    submissions: usize,
+}
+
+impl LeaderboardState {
+    /// `result` as it goes on this leaderboard, or why it can't. The seal
+    /// must verify, and every puzzle of the collection must have been
+    /// answered. The claimed scores are replaced by the sealed answers
+    /// scored again, per state with the collection's answer scoring.
+    fn accept(&self, result: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+        signing::verify(result).map_err(|e| e.to_string())?;
+        let Some(metadata) = &result.metadata else {
+            return Err("result has no run metadata to verify".to_string());
+        };
+        if metadata.collection_checksum != self.checksum {
+            return Err(format!(
+                "collection checksum {} does not match {} ({})",
+                metadata.collection_checksum, self.checksum, self.collection.name
+            ));
+        }
+        if let Some(score) = result
+            .puzzle_scores
+            .iter()
+            .find(|s| !self.collection.puzzles.iter().any(|p| p.id == s.puzzle_id))
+        {
+            return Err(format!("unknown puzzle id {}", score.puzzle_id));
+        }
+        if let Some(puzzle) = self
+            .collection
+            .puzzles
+            .iter()
+            .filter(|p| !p.is_canary())
+            .find(|p| !result.puzzle_scores.iter().any(|s| s.puzzle_id == p.id))
+        {
+            return Err(format!("puzzle {} was not answered", puzzle.id));
+        }
+
+        // Only the answers are sealed, so score those rather than the
+        // responses or per-pass scores.
+        let mut scored = result.clone();
+        scored.pass_results = None;
+        for score in &mut scored.puzzle_scores {
+            score.responses.clear();
+        }
+        scored.rescore(&self.collection, Scorer::PerState, self.collection.answer_scoring.unwrap_or_default());
+        Ok(scored)
+    }
+
+    /// The id after the highest one in `store`, counting files that failed
+    /// to load too.
+    fn next_id(&self) -> std::io::Result<u64> {
+        let highest = std::fs::read_dir(&self.store)?
+            .filter_map(|entry| entry.ok()?.path().file_stem()?.to_str()?.parse::<u64>().ok())
+            .max();
+        Ok(highest.map_or(0, |id| id + 1))
+    }
+
+    /// Load previously accepted results, skipping any that no longer verify
+    /// against the collection.
+    fn load(&self) -> Result<()> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.store)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut results = self.results.lock().unwrap();
+        for path in paths {
+            let id = path.file_stem().unwrap().to_string_lossy().into_owned();
+            match BenchmarkResult::load_from_file(&path.to_string_lossy()) {
+                Ok(result) => match self.accept(&result) {
+                    Ok(scored) => results.push((id, scored)),
+                    Err(reason) => warn!(path = %path.display(), reason, "skipping stored result"),
+                },
+                Err(e) => warn!(path = %path.display(), error = %e, "failed to load stored result"),
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn upload(
+    State(state): State<Arc<LeaderboardState>>,
+    Json(document): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let result =
+        BenchmarkResult::from_value(document).map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    result.check_writable().map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let scored = state.accept(&result).map_err(|reason| api_error(StatusCode::UNPROCESSABLE_ENTITY, reason))?;
+
+    let internal = |e: std::io::Error| api_error(StatusCode::INTERNAL_SERVER_ERROR, e);
+    let mut results = state.results.lock().unwrap();
+    let json = serde_json::to_string_pretty(&result)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    // Never overwrite a stored file, even one another process just wrote.
+    let mut next = state.next_id().map_err(internal)?;
+    let (id, mut file) = loop {
+        let id = format!("{:06}", next);
+        match OpenOptions::new().write(true).create_new(true).open(state.store.join(format!("{}.json", id))) {
+            Ok(file) => break (id, file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => next += 1,
+            Err(e) => return Err(internal(e)),
+        }
+    };
+    file.write_all(json.as_bytes()).map_err(internal)?;
+
+    info!(id, solver = %scored.solver_name, score = scored.average_score, "accepted result");
+    results.push((id.clone(), scored));
+    Ok(Json(json!({ "id": id })))
+}
+
+async fn get_result(
+    State(state): State<Arc<LeaderboardState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BenchmarkResult>, ApiError> {
+    state
+        .results
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(result_id, _)| *result_id == id)
+        .map(|(_, result)| Json(result.clone()))
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, format!("no result with id {}", id)))
+}
+
+/// Each solver's best result per modality, ranked by average score.
+async fn leaderboard(State(state): State<Arc<LeaderboardState>>) -> Json<serde_json::Value> {
+    let results = state.results.lock().unwrap();
+    let mut best: BTreeMap<(&str, Modality), (&str, &BenchmarkResult, usize)> = BTreeMap::new();
+    for (id, result) in results.iter() {
+        let entry = best
+            .entry((result.solver_name.as_str(), result.modality))
+            .or_insert((id.as_str(), result, 0));
+        entry.2 += 1;
+        if result.average_score > entry.1.average_score {
+            entry.0 = id;
+            entry.1 = result;
+        }
+    }
+
+    let mut ranked: Vec<_> = best.into_values().collect();
+    ranked.sort_by(|a, b| b.1.average_score.total_cmp(&a.1.average_score));
+    let entries: Vec<LeaderboardEntry> = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, result, submissions))| LeaderboardEntry {
+            rank: i + 1,
+            solver_name: &result.solver_name,
+            modality: result.modality,
+            average_score: result.average_score,
+            total_score: result.total_score,
+            max_possible_score: result.max_possible_score,
+            total_puzzles: result.total_puzzles,
+            reasoning_score: result.reasoning_score,
+            timestamp: &result.timestamp,
+            result_id: id,
+            submissions,
+        })
+        .collect();
+
+    Json(json!({
+        "collection_name": state.collection.name,
+        "collection_checksum": state.checksum,
+        "entries": entries,
+    }))
+}
+
+/// Run the leaderboard server for `collection` until interrupted, keeping
+/// accepted results in `store`:
+///
+/// - `POST /results`: upload a sealed result file answering every puzzle;
+///   its seal must verify and its run metadata name the served collection's
+///   checksum, and it is ranked by its answers scored again
+/// - `GET /results/{id}`: an accepted result, as scored by the server
+/// - `GET /leaderboard`: each solver's best result per modality, ranked
+pub fn serve_leaderboard(collection: &PuzzleCollection, store: &str, addr: &str) -> Result<()> {
+    std::fs::create_dir_all(store)?;
+    let state = Arc::new(LeaderboardState {
+        collection: collection.clone(),
+        checksum: collection.source_checksum(),
+        store: PathBuf::from(store),
+        results: Mutex::new(Vec::new()),
+    });
+    state.load()?;
+    info!(
+        results = state.results.lock().unwrap().len(),
+        checksum = %state.checksum,
+        "loaded leaderboard"
+    );
+
+    let app = Router::new()
+        .route("/results", post(upload))
+        .route("/results/:id", get(get_result))
+        .route("/leaderboard", get(leaderboard))
+        .with_state(state);
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!(addr = %listener.local_addr()?, "serving leaderboard");
+        axum::serve(listener, app).await?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boardgamebench::puzzle::testing::{random_collection, responses};
+    use boardgamebench::results::RunMetadata;
+
+    fn state(collection: &PuzzleCollection) -> LeaderboardState {
+        LeaderboardState {
+            collection: collection.clone(),
+            checksum: collection.source_checksum(),
+            store: PathBuf::new(),
+            results: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A sealed result on `collection` answering the puzzles at `indices`,
+    /// half of them right.
+    fn sealed(collection: &PuzzleCollection, indices: std::ops::Range<usize>) -> BenchmarkResult {
+        let all_responses = responses(collection, 0.5, 9);
+        let scores = indices
+            .map(|i| Scorer::PerState.score_responses(&collection.puzzles[i], collection, &all_responses[i]))
+            .collect();
+        let mut result = BenchmarkResult::summarize("test".to_string(), "solver", "", collection, scores);
+        result.metadata = Some(RunMetadata {
+            crate_version: String::new(),
+            git_commit: None,
+            collection_name: collection.name.clone(),
+            collection_checksum: collection.checksum(),
+            solver_parameters: BTreeMap::new(),
+            seeds: BTreeMap::new(),
+            environment: BTreeMap::new(),
+            shard: None,
+            selection: None,
+        });
+        signing::seal(&mut result);
+        result
+    }
+
+    #[test]
+    fn claimed_scores_are_replaced_by_the_answers_scored_again() {
+        let collection = random_collection(5, 31);
+        let honest = sealed(&collection, 0..5);
+        let mut inflated = honest.clone();
+        for score in &mut inflated.puzzle_scores {
+            score.score = score.max_possible_score;
+        }
+        inflated.total_score = inflated.max_possible_score;
+        inflated.average_score = 1.0;
+        signing::seal(&mut inflated);
+
+        let accepted = state(&collection).accept(&inflated).unwrap();
+        assert_eq!(accepted.total_score, state(&collection).accept(&honest).unwrap().total_score);
+        assert!(accepted.average_score < 1.0);
+    }
+
+    #[test]
+    fn incomplete_foreign_or_tampered_results_are_rejected() {
+        let collection = random_collection(4, 32);
+        let state = state(&collection);
+        assert!(state.accept(&sealed(&collection, 0..4)).is_ok());
+
+        let partial = sealed(&collection, 0..3);
+        assert!(state.accept(&partial).unwrap_err().contains("was not answered"));
+
+        let other = random_collection(4, 33);
+        assert!(state.accept(&sealed(&other, 0..4)).unwrap_err().contains("does not match"));
+
+        let mut tampered = sealed(&collection, 0..4);
+        tampered.puzzle_scores[0].answers = vec!["e2e4".to_string()];
+        assert!(state.accept(&tampered).unwrap_err().contains("changed after it was sealed"));
+
+        let mut unsealed = sealed(&collection, 0..4);
+        unsealed.seal = None;
+        assert!(state.accept(&unsealed).unwrap_err().contains("not sealed"));
+
+        let mut anonymous = sealed(&collection, 0..4);
+        anonymous.metadata = None;
+        signing::seal(&mut anonymous);
+        assert!(state.accept(&anonymous).unwrap_err().contains("no run metadata"));
+    }
+}
@@ -1,75 +1,3193 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
 
-use boardgamebench::evaluation::{BenchmarkRunner, Solver};
-use boardgamebench::puzzle::PuzzleCollection;
+use boardgamebench::analysis::{ReviewReason, fit_irt, review_puzzles, write_difficulties};
+use boardgamebench::augment::Augmentation;
+use boardgamebench::blind::{BlindKey, blind, unblind};
+use boardgamebench::canary::insert_canaries;
+use boardgamebench::command::{CommandSolver, kill_running};
+use boardgamebench::compression::{Compression, FittedPrompt, PromptBudget, PromptPart, fit_prompt};
+use boardgamebench::duplicates::{DuplicatePolicy, resolve_duplicates};
+use boardgamebench::encryption::{SolutionKey, decrypt_solutions, encrypt_solutions};
+use boardgamebench::engine::UciEngine;
+use boardgamebench::budget::{
+    Budget, ComputeBudget, ContextCheck, EXPECTED_ANSWER_TOKENS, IMAGE_TOKENS, Pricing, ReasoningEffort, TimeBudget,
+    context_window, estimate_tokens,
+};
+use boardgamebench::evaluation::{
+    AblationRow, BenchmarkResult, BenchmarkRunner, BenchmarkRunnerBuilder, ComparisonReport, ComputeSweep, Interrupt,
+    PromptAblation, ProviderProfile, RepresentationAblation, RetryPolicy, Solver,
+};
+use boardgamebench::export::{LicenseFilter, filter_by_license, to_lm_eval_harness, to_openai_evals};
+use boardgamebench::extraction::{AnswerGrammar, ExtractionMethod};
+use boardgamebench::frontier::{FrontierReport, frontier};
+use boardgamebench::holdout::{AnswerSheet, score_answers, score_sheet, withhold_solutions};
+use boardgamebench::judge::Judge;
+use boardgamebench::latency::{LatencyPercentiles, LatencySummary};
+use boardgamebench::notify::{Notification, notify};
+use boardgamebench::notation::MoveNotation;
+#[cfg(feature = "chess")]
+use boardgamebench::blunders::MoveQuality;
+#[cfg(feature = "chess")]
+use boardgamebench::contamination::{FAMOUS_GAME_WEIGHT, PositionCorpus, RISK_KEY};
+#[cfg(feature = "chess")]
+use boardgamebench::play::GameRules;
+#[cfg(feature = "chess")]
+use boardgamebench::playout::PlayOut;
+use boardgamebench::prompt::{
+    Modality, PromptLanguage, PromptStyle, Representation, StateView, build_prompt_with_rules, confidence_request,
+};
+use boardgamebench::puzzle::{PuzzleCollection, PuzzleSelection, Sample, Scorer, Shard};
+use boardgamebench::queue::{Coordinator, RedisQueue, Worker};
+use boardgamebench::rating::{calibration_curves, rate_results};
+use boardgamebench::schema::{SchemaKind, schema_for, validate};
+use boardgamebench::signing::{self, SigningKey};
+use boardgamebench::scoring::AnswerScoring;
+use boardgamebench::stopping::{EarlyStopping, StopReason};
+use boardgamebench::telemetry::Telemetry;
+#[cfg(feature = "chess")]
+use boardgamebench::tournament::Tournament;
+use boardgamebench::tracking::mlflow::MlflowTracker;
+#[cfg(feature = "wandb")]
+use boardgamebench::tracking::wandb::WandbTracker;
+
+mod config;
+#[cfg(feature = "download")]
+mod download;
+#[cfg(feature = "serve")]
+mod leaderboard;
+#[cfg(feature = "serve")]
+mod progress;
+#[cfg(feature = "serve")]
+mod serve;
+
+use config::RunConfig;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Args,
+
+    /// Log more detail (-v debug, -vv trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    log_format: LogFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per event, for machine consumption
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Estimate a Glicko rating per model from result files of rated puzzles
+    Rating {
+        /// Collection files providing the puzzle ratings
+        #[arg(short, long, required = true)]
+        puzzle_file: Vec<String>,
+
+        /// Benchmark result files to rate
+        #[arg(required = true)]
+        results: Vec<String>,
+
+        /// Width of the puzzle rating bins the calibration curves are
+        /// printed in
+        #[arg(long, default_value = "200")]
+        bin_width: f64,
+
+        /// Also write each model's calibration curve (accuracy by puzzle
+        /// rating, binned and fitted) to this JSON file, for plotting
+        #[arg(long)]
+        calibration: Option<String>,
+    },
+    /// Fit an item-response model to result files from many models, estimating each puzzle's difficulty and
+    /// each model's ability, and store the difficulties in the collection's puzzle metadata
+    Difficulty {
+        /// Collection file whose puzzles get an `irt_difficulty`
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Benchmark result files, from as many models as possible
+        #[arg(required = true)]
+        results: Vec<String>,
+
+        /// Where to write the updated collection [default: the collection file itself]
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Flag puzzles that don't tell models apart, as candidates for review or removal: puzzles every model
+    /// solved or failed, and puzzles that weaker models do better on than stronger ones
+    Review {
+        /// Benchmark result files on the same collection, from as many models as possible
+        #[arg(required = true)]
+        results: Vec<String>,
+
+        /// Fewest models that must have attempted a puzzle for it to be judged
+        #[arg(long, default_value = "3")]
+        min_models: usize,
+
+        /// Flag puzzles whose correlation between model ability and score is below this
+        #[arg(long, default_value = "-0.2", allow_negative_numbers = true)]
+        max_discrimination: f64,
+
+        /// Also write the flagged puzzles to this JSON file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Set each model's score against its cost and latency per puzzle and
+    /// mark the Pareto-optimal models
+    Frontier {
+        /// Result or comparison files on the same collection
+        #[arg(required = true)]
+        results: Vec<String>,
+
+        /// Also write the frontier to this JSON file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Score how exposed a collection's chess puzzles are in public game collections a model may have
+    /// trained on, and store the risk in the puzzles' metadata
+    #[cfg(feature = "chess")]
+    Contamination {
+        /// Collection whose puzzles get a `contamination_risk`
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// PGN files of ordinary games, such as Lichess game dumps ("-" reads standard input)
+        #[arg(long, value_delimiter = ',')]
+        games: Vec<String>,
+
+        /// PGN files of famous games, each counting as many ordinary games
+        #[arg(long, value_delimiter = ',')]
+        famous_games: Vec<String>,
+
+        /// Only record positions up to this many plies into each game
+        #[arg(long, default_value = "200")]
+        max_plies: usize,
+
+        /// Where to write the updated collection [default: the collection file itself]
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Hide canary puzzles, nonsense positions with made-up solutions, in a collection; a model that
+    /// gives their answers has likely seen the collection in training
+    Canary {
+        /// Collection to add the canaries to
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// How many canaries to add
+        #[arg(short = 'n', long, default_value = "5")]
+        count: usize,
+
+        /// Seed the canaries are made from; keep it private
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Where to write the updated collection [default: the collection file itself]
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Convert a result file for use in other evaluation tools
+    Export {
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+
+        /// Result file (lm-eval) or puzzle collection (openai-evals, collection) to convert
+        input: String,
+
+        /// Output file; lm-eval samples go next to it as <stem>_samples.jsonl
+        #[arg(short, long)]
+        output: String,
+
+        /// Task name used in the exported results
+        #[arg(long, default_value = "boardgamebench")]
+        task: String,
+
+        /// Collection the results (lm-eval) were run on, to credit its
+        /// puzzles' sources and licenses in the export
+        #[arg(short, long)]
+        puzzle_file: Option<String>,
+
+        /// Export only puzzles under these licenses (SPDX identifiers, comma
+        /// separated)
+        #[arg(long, value_delimiter = ',')]
+        license: Vec<String>,
+
+        /// Leave out puzzles under these licenses, e.g. non-redistributable ones
+        #[arg(long, value_delimiter = ',')]
+        exclude_license: Vec<String>,
+
+        /// Keep puzzles of unknown license, which --license and
+        /// --exclude-license otherwise leave out
+        #[arg(long)]
+        allow_unknown_license: bool,
+    },
+    /// Ask the model one puzzle, showing the prompt, the raw response, the
+    /// extracted answer and the score, then again on Enter; for debugging
+    /// prompts and extraction without a full run. Takes the run's options
+    Try {
+        /// Id of the puzzle to ask
+        puzzle_id: String,
+
+        #[command(flatten)]
+        run: Box<Args>,
+    },
+    /// Score the answers recorded in a result file again, e.g. after fixing a
+    /// solution or to compare scorers, without asking the solver again
+    Rescore {
+        /// Collection the results were run on
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Result file to rescore
+        input: String,
+
+        /// Where to write the rescored results
+        #[arg(short, long)]
+        output: String,
+
+        /// How each puzzle's answers are scored
+        #[arg(long, value_enum, default_value = "per-state")]
+        scorer: Scorer,
+
+        /// How each answer is credited [default: the collection's, or goal-based]
+        #[arg(long, value_enum)]
+        answer_scoring: Option<AnswerScoring>,
+
+        /// Key the collection's solutions are encrypted with (see `encrypt`)
+        #[arg(long)]
+        solution_key: Option<String>,
+    },
+    /// Combine the result files of one model's `--shard` runs, or of a run
+    /// and its resumptions or reruns, into a single result
+    MergeResults {
+        /// Collection the runs were on
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Result files of the runs, earliest first
+        #[arg(required = true)]
+        results: Vec<String>,
+
+        /// Where to write the merged result
+        #[arg(short, long)]
+        output: String,
+
+        /// Take puzzles scored in several files from the last of them, as
+        /// after resuming a run or rerunning its failed puzzles, rather than
+        /// requiring disjoint shards
+        #[arg(long)]
+        allow_overlap: bool,
+    },
+    /// Push a run's tasks to a Redis queue for `worker` processes to solve,
+    /// and write each model's result once they are all answered
+    Coordinate {
+        /// Redis server holding the queue, as redis://[:password@]host[:port][/db]
+        #[arg(long)]
+        queue: String,
+
+        /// Name the queue's keys start with; coordinators and workers must agree
+        #[arg(long, default_value = "boardgamebench")]
+        queue_name: String,
+
+        /// Models to benchmark (comma separated)
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        model: Vec<String>,
+
+        /// Puzzle file to run; image paths must resolve on the workers too
+        #[arg(short, long, default_value = "builtin:chess_sample")]
+        puzzle_file: String,
+
+        /// Only queue the first this many puzzles
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// How each puzzle's answers are scored
+        #[arg(long, value_enum, default_value = "per-state")]
+        scorer: Scorer,
+
+        /// How each answer is credited [default: the collection's, or goal-based]
+        #[arg(long, value_enum)]
+        answer_scoring: Option<AnswerScoring>,
+
+        /// Give up on unanswered tasks after this many seconds without any reply
+        #[arg(long, default_value = "600")]
+        timeout: u64,
+
+        /// Directory each model's result is written to, as <model>.json
+        #[arg(long, default_value = "results")]
+        output_dir: String,
+
+        /// Show puzzles as FEN text or as their board images (vision models only)
+        #[arg(long, value_enum, default_value = "text")]
+        modality: Modality,
+
+        /// How states are written in text prompts: as stored (FEN), as an ASCII or Unicode board diagram, or as PGN (chess only)
+        #[arg(long, value_enum, default_value = "fen")]
+        representation: Representation,
+
+        /// How much guidance the instructions give: full, minimal, no-rules or answer-only
+        #[arg(long, value_enum, default_value = "full")]
+        prompt_style: PromptStyle,
+
+        /// Retry failed API calls this many times, with exponential backoff
+        #[arg(long, default_value = "0")]
+        retries: u32,
+
+        /// Per-puzzle wall-clock budget in seconds; slower answers score zero
+        #[arg(long)]
+        time_budget: Option<f64>,
+
+        /// Key the collection's solutions are encrypted with; workers are
+        /// sent them decrypted
+        #[arg(long)]
+        solution_key: Option<String>,
+    },
+    /// Solve tasks from a Redis queue filled by `coordinate`; start as many
+    /// workers, on as many machines, as the sweep needs
+    Worker {
+        /// Redis server holding the queue, as redis://[:password@]host[:port][/db]
+        #[arg(long)]
+        queue: String,
+
+        /// Name the queue's keys start with; coordinators and workers must agree
+        #[arg(long, default_value = "boardgamebench")]
+        queue_name: String,
+
+        /// Tasks solved at once
+        #[arg(short, long, default_value = "4")]
+        threads: usize,
+
+        /// Exit once the queue has been empty this many seconds [default: never]
+        #[arg(long)]
+        idle_timeout: Option<u64>,
+
+        /// Solve every task with this program instead of the task's model,
+        /// as with the benchmark's --solver-command
+        #[arg(long)]
+        solver_command: Option<String>,
+
+        /// Provider profile from the config file's [profiles.<name>] tables
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Write the public copy of a collection, with every solution withheld,
+    /// for a held-out split; runs on it write answer sheets for `score-heldout`
+    Withhold {
+        /// Collection with the solutions
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Where to write the public copy
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Encrypt a collection's solutions so the file can be shared without
+    /// giving the answers away; runs and scoring decrypt them with --solution-key
+    Encrypt {
+        /// Collection with plain solutions
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Where to write the encrypted collection
+        #[arg(short, long)]
+        output: String,
+
+        /// Solution key file; a new key is created there if it doesn't exist
+        #[arg(short, long, default_value = "solution_key")]
+        key: String,
+    },
+    /// Write a collection with its encrypted solutions decrypted
+    Decrypt {
+        /// Collection with encrypted solutions
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Where to write the decrypted collection
+        #[arg(short, long)]
+        output: String,
+
+        /// Solution key the collection was encrypted with
+        #[arg(short, long, default_value = "solution_key")]
+        key: String,
+    },
+    /// Score answer sheets from runs on a withheld copy against the private
+    /// collection, reporting only each sheet's total score
+    ScoreHeldout {
+        /// The private collection, with the solutions
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Answer sheets to score
+        #[arg(required = true)]
+        answers: Vec<String>,
+
+        /// How each puzzle's answers are scored
+        #[arg(long, value_enum, default_value = "per-state")]
+        scorer: Scorer,
+
+        /// How each answer is credited [default: the collection's, or goal-based]
+        #[arg(long, value_enum)]
+        answer_scoring: Option<AnswerScoring>,
+
+        /// Also write the scores here as JSON
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Key the collection's solutions are encrypted with (see `encrypt`)
+        #[arg(long)]
+        solution_key: Option<String>,
+    },
+    /// Score a file of answers produced by any system into a full result,
+    /// as if a run had given them
+    Score {
+        /// Collection the answers are to
+        #[arg(short, long)]
+        puzzle_file: String,
+
+        /// Answers: an answer sheet, a JSON object mapping puzzle ids to
+        /// answers, or JSONL lines of {"puzzle_id", "answers"}
+        answers: String,
+
+        /// Where to write the result
+        #[arg(short, long)]
+        output: String,
+
+        /// Solver name for the result [default: the sheet's, or the file name]
+        #[arg(long)]
+        solver_name: Option<String>,
+
+        /// The answers are raw responses to extract each state's answer from
+        #[arg(long)]
+        extract: bool,
+
+        /// How each puzzle's answers are scored
+        #[arg(long, value_enum, default_value = "per-state")]
+        scorer: Scorer,
+
+        /// How each answer is credited [default: the collection's, or goal-based]
+        #[arg(long, value_enum)]
+        answer_scoring: Option<AnswerScoring>,
+
+        /// Key the collection's solutions are encrypted with (see `encrypt`)
+        #[arg(long)]
+        solution_key: Option<String>,
+
+        /// Sign the result with this Ed25519 key (see `keygen`)
+        #[arg(long)]
+        sign_key: Option<String>,
+    },
+    /// Print the JSON Schema of a puzzle, result or comparison file
+    Schema {
+        /// Which file format to describe
+        #[arg(value_enum)]
+        kind: SchemaKind,
+
+        /// Write the schema here instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Check puzzle collections for broken puzzles, or any file against a
+    /// JSON Schema with --schema
+    Validate {
+        /// Schema to check against instead of checking puzzle collections
+        #[arg(long, value_enum)]
+        schema: Option<SchemaKind>,
+
+        /// Files to check
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Check the files again whenever one changes, until interrupted
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Fetch the Lichess puzzle database, board and piece images and
+    /// tablebases, verifying their checksums
+    #[cfg(feature = "download")]
+    Download {
+        /// Which assets to fetch [default: lichess-puzzles, board-images, piece-images]
+        #[arg(value_enum)]
+        assets: Vec<download::Asset>,
+
+        /// Directory the assets are stored under, laid out as the generator expects
+        #[arg(long, default_value = ".")]
+        cache_dir: String,
+
+        /// Download again even when a verified copy is there
+        #[arg(long)]
+        force: bool,
+
+        /// Only verify the files that are there, downloading nothing
+        #[arg(long, conflicts_with = "force")]
+        verify: bool,
+    },
+    /// Serve puzzles and score submitted answers over a REST API
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Puzzle file to serve
+        #[arg(short, long, default_value = "builtin:chess_sample")]
+        puzzle_file: String,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// How submitted answers are scored
+        #[arg(long, value_enum, default_value = "per-state")]
+        scorer: Scorer,
+
+        /// Answer submissions with their total score only, never per-puzzle
+        /// results, e.g. when serving a held-out split's private solutions
+        #[arg(long)]
+        aggregate_only: bool,
+
+        /// With --aggregate-only, how many submissions one client address
+        /// may make an hour, so solutions can't be probed for one at a time
+        #[arg(long, default_value_t = 10)]
+        submissions_per_hour: usize,
+
+        /// Key the collection's solutions are encrypted with (see `encrypt`)
+        #[arg(long)]
+        solution_key: Option<String>,
+    },
+    /// Accept uploaded result files and serve a JSON leaderboard
+    #[cfg(feature = "serve")]
+    Leaderboard {
+        /// Puzzle file uploaded results must have been run on
+        #[arg(short, long, default_value = "builtin:chess_sample")]
+        puzzle_file: String,
+
+        /// Directory accepted results are stored in
+        #[arg(long, default_value = "leaderboard")]
+        store: String,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Play complete games between models, with the rules engine judging
+    /// legality and results, and rate them by Elo
+    #[cfg(feature = "chess")]
+    Tournament {
+        /// Models to play each other (comma separated), at least two
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        model: Vec<String>,
+
+        /// Collection whose rules, answer notation and prompt template the
+        /// moves are asked for with
+        #[arg(short, long, default_value = "builtin:chess_sample")]
+        puzzle_file: String,
+
+        /// Play from the first position of each puzzle instead of the
+        /// starting position
+        #[arg(long)]
+        from_puzzles: bool,
+
+        /// Only play from the first this many puzzle positions
+        #[arg(long, requires = "from_puzzles")]
+        limit: Option<usize>,
+
+        /// Adjudicate a draw after this many moves by each side
+        #[arg(long, default_value = "100")]
+        max_moves: u32,
+
+        /// Times a model is asked for a move before an illegal answer
+        /// forfeits the game
+        #[arg(long, default_value = "3")]
+        move_attempts: u32,
+
+        /// Maximum number of games played at once
+        #[arg(short, long, default_value = "4")]
+        threads: usize,
+
+        /// Retry failed API calls this many times, with exponential backoff
+        #[arg(long, default_value = "0")]
+        retries: u32,
+
+        /// Sampling temperature for the models [default: 0.5]
+        #[arg(long)]
+        temperature: Option<f64>,
+
+        /// UCI engine command that grades every move once a game is over,
+        /// e.g. "stockfish", for each model's centipawn loss and blunders
+        #[arg(long)]
+        engine: Option<String>,
+
+        /// Search depth of --engine
+        #[arg(long, default_value = "12")]
+        engine_depth: u32,
+
+        /// Where to write the games, standings and head-to-head records
+        #[arg(short, long, default_value = "tournament.json")]
+        output: String,
+    },
+    /// Play on from each puzzle position against a fixed-strength engine and
+    /// score the model by the results
+    #[cfg(feature = "chess")]
+    Playout {
+        /// Model to evaluate
+        #[arg(short, long)]
+        model: String,
+
+        /// Collection whose chess puzzles the games start from
+        #[arg(short, long, default_value = "builtin:chess_sample")]
+        puzzle_file: String,
+
+        /// UCI engine command the model plays against, e.g. "stockfish"
+        #[arg(long)]
+        engine: String,
+
+        /// Search depth of --engine
+        #[arg(long, default_value = "8")]
+        engine_depth: u32,
+
+        /// Set the engine's "Skill Level" option (0 to 20 for Stockfish)
+        #[arg(long)]
+        engine_skill: Option<u32>,
+
+        /// Score a game still running after this many moves by each side
+        /// by the engine's evaluation
+        #[arg(long, default_value = "20")]
+        max_moves: u32,
+
+        /// Times the model is asked for a move before an illegal answer
+        /// forfeits the game
+        #[arg(long, default_value = "3")]
+        move_attempts: u32,
+
+        /// Only play out the first this many puzzles
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Maximum number of games played at once
+        #[arg(short, long, default_value = "4")]
+        threads: usize,
+
+        /// Retry failed API calls this many times, with exponential backoff
+        #[arg(long, default_value = "0")]
+        retries: u32,
+
+        /// Sampling temperature for the model [default: 0.5]
+        #[arg(long)]
+        temperature: Option<f64>,
+
+        /// Also grade every move of the model with --engine at full
+        /// strength, for its centipawn loss and blunders
+        #[arg(long)]
+        annotate: bool,
+
+        /// Where to write the games and score
+        #[arg(short, long, default_value = "playout.json")]
+        output: String,
+    },
+    /// Create an Ed25519 key for signing results with --sign-key
+    Keygen {
+        /// Where to write the private key; keep it secret
+        #[arg(short, long, default_value = "signing_key")]
+        output: String,
+    },
+    /// Seal a result file again, e.g. after editing it by hand, and sign it
+    /// with --key
+    Sign {
+        /// Result file to seal in place
+        file: String,
+
+        /// Private key written by `keygen`
+        #[arg(short, long)]
+        key: Option<String>,
+    },
+    /// Check result files for changes since they were sealed, and optionally
+    /// for the signer and the collection they were run on
+    Verify {
+        /// Require the results to be run on exactly this collection
+        #[arg(short, long)]
+        puzzle_file: Option<String>,
+
+        /// Require a signature by one of these base64 public keys (comma separated)
+        #[arg(long, value_delimiter = ',')]
+        trusted_key: Vec<String>,
+
+        /// Result files to check
+        #[arg(required = true)]
+        results: Vec<String>,
+    },
+    /// Restore model names in a result or comparison file written with --blind
+    Unblind {
+        /// Result or comparison file to unblind in place
+        file: String,
+
+        /// Key written by --blind
+        #[arg(short, long, default_value = "blind_key.json")]
+        key: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    /// lm-evaluation-harness results JSON
+    LmEval,
+    /// OpenAI Evals / simple-evals JSONL samples
+    OpenaiEvals,
+    /// The puzzle collection itself, e.g. for a public release
+    Collection,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TrackingBackend {
+    Mlflow,
+}
+
+/// One way of showing puzzles to the models in an `--ablate` run.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum AblationVariant {
+    /// States as stored (FEN for chess)
+    Fen,
+    /// ASCII board diagrams
+    Ascii,
+    /// Unicode board diagrams
+    Unicode,
+    /// The game so far as PGN
+    Pgn,
+    /// Board images
+    Image,
+}
+
+impl AblationVariant {
+    fn label(self) -> &'static str {
+        match self {
+            AblationVariant::Fen => "fen",
+            AblationVariant::Ascii => "ascii",
+            AblationVariant::Unicode => "unicode",
+            AblationVariant::Pgn => "pgn",
+            AblationVariant::Image => "image",
+        }
+    }
+
+    fn presentation(self) -> (Modality, Representation) {
+        match self {
+            AblationVariant::Fen => (Modality::Text, Representation::Fen),
+            AblationVariant::Ascii => (Modality::Text, Representation::Ascii),
+            AblationVariant::Unicode => (Modality::Text, Representation::Unicode),
+            AblationVariant::Pgn => (Modality::Text, Representation::Pgn),
+            AblationVariant::Image => (Modality::Image, Representation::Fen),
+        }
+    }
+}
+
+/// Enough for a move or a FEN with the answer marker around it, but not
+/// for any reasoning before it.
+const ANSWER_ONLY_TOKENS: u32 = 64;
+
+/// How one solver run per model shows the puzzles and words its prompts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Presentation {
+    modality: Modality,
+    representation: Representation,
+    style: PromptStyle,
+}
+
+#[derive(clap::Args, Debug, Serialize)]
 struct Args {
-    /// Model name in OpenAI API
-    #[arg(short, long, default_value = "deepseek-chat")]
-    model: String,
+    /// TOML file with run settings; command-line flags override its values
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Print every prompt with estimated token counts and cost instead of
+    /// calling the API
+    #[arg(long)]
+    dry_run: bool,
+
+    /// With --dry-run, print the prompts again whenever a puzzle file
+    /// changes, until interrupted
+    #[arg(long)]
+    watch: bool,
+
+    /// Model name(s) in OpenAI API; pass several (comma separated) to compare them
+    #[arg(short, long, value_delimiter = ',', default_value = "deepseek-chat")]
+    model: Vec<String>,
+
+    /// Benchmark an external program instead of --model, e.g. "python3 solver.py";
+    /// it gets each request as JSON on stdin and prints {"response": ...} to stdout
+    #[arg(long)]
+    solver_command: Option<String>,
+
+    /// Provider profile from the config file's [profiles.<name>] tables
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Providers defined in the config file
+    #[arg(skip)]
+    #[serde(skip)]
+    profiles: BTreeMap<String, ProviderProfile>,
+
+    /// Show puzzles as FEN text or as their board images (vision models only)
+    #[arg(long, value_enum, default_value = "text")]
+    modality: Modality,
+
+    /// How states are written in text prompts: as stored (FEN), as an ASCII or Unicode board diagram, or as PGN (chess only)
+    #[arg(long, value_enum, default_value = "fen")]
+    representation: Representation,
+
+    /// Run every model once per listed representation and compare the scores,
+    /// e.g. "fen,ascii,pgn,image"; replaces --modality and --representation
+    #[arg(long, value_enum, value_delimiter = ',')]
+    ablate: Vec<AblationVariant>,
+
+    /// How much guidance the instructions give: step-by-step analysis
+    /// (full), the goal and answer format only (minimal), full without
+    /// explaining the rules (no-rules), or the answer alone (answer-only)
+    #[arg(long, value_enum, default_value = "full")]
+    prompt_style: PromptStyle,
+
+    /// Run every model once per listed prompt style and compare the scores,
+    /// e.g. "full,minimal,no-rules,answer-only"; replaces --prompt-style
+    #[arg(long, value_enum, value_delimiter = ',')]
+    ablate_prompts: Vec<PromptStyle>,
+
+    /// Run every model both with the full prompt and answer-only, and
+    /// compare intuition (answer-only) with deliberation (full); replaces
+    /// --prompt-style and --ablate-prompts
+    #[arg(long)]
+    intuition: bool,
+
+    /// Cut answer-only responses off after this many tokens, so the model
+    /// can't reason before answering
+    #[arg(long, default_value_t = ANSWER_ONLY_TOKENS)]
+    answer_only_tokens: u32,
+
+    /// With board images, also attach the moves leading to each state where
+    /// the collection has them (see `sequences` in the puzzle format)
+    #[arg(long)]
+    sequences: bool,
+
+    /// Rewrite chess solutions in this move notation and ask for answers in it
+    #[arg(long, value_enum)]
+    answer_notation: Option<MoveNotation>,
+
+    /// Language of the prompts, with the collection's goal and rules taken
+    /// from its translations [default: the collection's language]
+    #[arg(long, value_enum)]
+    prompt_lang: Option<PromptLanguage>,
+
+    /// Perturb board images in the image modality, e.g. "jpeg=30,blur=1.5,rotate=3,perspective=0.05,jitter=0.2"
+    #[arg(long)]
+    augment: Option<Augmentation>,
+
+    /// Sampling temperature for the solver models [default: the profile's, or 0.5]
+    #[arg(long)]
+    temperature: Option<f64>,
+
+    /// Cut every response off after this many tokens
+    #[arg(long)]
+    max_completion_tokens: Option<u32>,
+
+    /// Reasoning effort for reasoning models
+    #[arg(long, value_enum)]
+    reasoning_effort: Option<ReasoningEffort>,
+
+    /// Context window of the models in tokens [default: the profile's, or
+    /// the model's if well known]
+    #[arg(long)]
+    context_window: Option<u64>,
+
+    /// What to do before the run about prompts that, with room for the
+    /// answer, would exceed the context window
+    #[arg(long, value_enum, default_value = "warn")]
+    context_check: ContextCheck,
+
+    /// Compress prompts of more than this many tokens [default: whatever
+    /// the context window leaves for the prompt]
+    #[arg(long)]
+    prompt_budget: Option<u64>,
+
+    /// Prompt parts to keep longest when compressing, most important first
+    /// (comma separated); parts left out are never shortened
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "state,rules,examples")]
+    compression_priority: Vec<PromptPart>,
+
+    /// Ask the models how confident they are in each answer and report how
+    /// well calibrated that is (Brier score, ECE, reliability bins)
+    #[arg(long)]
+    confidence: bool,
+
+    /// Run every model once per listed response token cap, e.g.
+    /// "256,1024,4096", and report accuracy and cost at each; replaces
+    /// --max-completion-tokens
+    #[arg(long, value_delimiter = ',')]
+    sweep_completion_tokens: Vec<u32>,
+
+    /// Run every model once per listed reasoning effort, e.g.
+    /// "low,medium,high", crossed with --sweep-completion-tokens; replaces
+    /// --reasoning-effort
+    #[arg(long, value_enum, value_delimiter = ',')]
+    sweep_effort: Vec<ReasoningEffort>,
+
+    /// Puzzle file to load, or builtin:<name> for an embedded collection;
+    /// repeat to run several collections as one
+    #[arg(short, long, default_value = "builtin:chess_sample")]
+    puzzle_file: Vec<String>,
+
+    /// What to do about puzzles whose position another loaded collection
+    /// already has
+    #[arg(long, value_enum, default_value = "warn")]
+    duplicates: DuplicatePolicy,
+
+    /// Where to write the results [default: benchmark_results.json, or
+    /// benchmark_comparison.json when comparing models]
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Key the collection's solutions are encrypted with (see `encrypt`);
+    /// they are decrypted only to score each answered puzzle
+    #[arg(long)]
+    solution_key: Option<String>,
+
+    /// Also write each model's answers as an answer sheet for `score-heldout`
+    /// [default: answers.json when the collection withholds its solutions]
+    #[arg(long)]
+    answers_output: Option<String>,
+
+    /// Maximum number of puzzles evaluated concurrently
+    #[arg(short, long, default_value = "16")]
+    threads: usize,
+
+    /// Number of passes to run for each test case
+    #[arg(short = 'N', long, default_value = "1")]
+    passes: usize,
+
+    /// How each puzzle's answers are scored
+    #[arg(long, value_enum, default_value = "per-state")]
+    scorer: Scorer,
+
+    /// Stop asking about a multi-state puzzle after its first wrong answer,
+    /// as on Lichess; the remaining states score zero and cost no requests
+    #[arg(long)]
+    sequential: bool,
+
+    /// How each answer is credited [default: the collection's, or goal-based]
+    #[arg(long, value_enum)]
+    answer_scoring: Option<AnswerScoring>,
+
+    /// UCI engine command that grades wrong moves for --answer-scoring engine-graded, e.g. "stockfish"
+    #[arg(long)]
+    engine: Option<String>,
+
+    /// Search depth of --engine
+    #[arg(long, default_value = "12")]
+    engine_depth: u32,
+
+    /// Retry failed API calls this many times, with exponential backoff
+    #[arg(long, default_value = "0")]
+    retries: u32,
+
+    /// Only run puzzles with these ids (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    ids: Vec<String>,
+
+    /// Only run puzzles tagged with this theme, or whose id or description
+    /// mentions it
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// Only run puzzles matching one of these tag expressions (comma
+    /// separated), each a tag or tags joined with "+" that must all be
+    /// present, e.g. "endgame+quietMove,mateIn2"
+    #[arg(long, value_delimiter = ',')]
+    include_tags: Vec<String>,
+
+    /// Skip puzzles matching any of these tag expressions, e.g. "mateIn1"
+    #[arg(long, value_delimiter = ',')]
+    exclude_tags: Vec<String>,
+
+    /// Only run puzzles whose metadata has this value, e.g.
+    /// "difficulty=hard"; may be repeated
+    #[arg(long, value_parser = parse_key_value)]
+    metadata: Vec<(String, String)>,
+
+    /// Break scores down by these metadata keys (comma separated), e.g.
+    /// "difficulty,source"
+    #[arg(long, value_delimiter = ',')]
+    breakdown_by: Vec<String>,
+
+    /// Run a random sample of this many of the filtered puzzles
+    #[arg(long)]
+    sample: Option<usize>,
+
+    /// Keep each group's share of the puzzles in --sample, grouping by this
+    /// metadata key, "game_type" or "tags"
+    #[arg(long, requires = "sample")]
+    stratify_by: Option<String>,
+
+    /// Shuffle the puzzles before applying --skip and --limit
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Seed used by --sample and --shuffle
+    #[arg(long, default_value = "3407")]
+    seed: u64,
+
+    /// Number of puzzles to skip
+    #[arg(long, default_value = "0")]
+    skip: usize,
+
+    /// Maximum number of puzzles to run
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Run only part i of N of the selected puzzles (e.g. 2/4), dealt out in
+    /// order, to split a run across machines; see `merge-results`
+    #[arg(long)]
+    shard: Option<Shard>,
+
+    /// Stop dispatching requests once a model has spent this much (USD)
+    #[arg(long)]
+    max_cost: Option<f64>,
+
+    /// Stop dispatching requests once a model has used this many tokens
+    #[arg(long)]
+    max_tokens: Option<u64>,
+
+    /// Input token price in USD per million tokens, used for --max-cost
+    #[arg(long, default_value = "0")]
+    input_price: f64,
+
+    /// Output token price in USD per million tokens, used for --max-cost
+    #[arg(long, default_value = "0")]
+    output_price: f64,
+
+    /// Seconds puzzles in flight may still finish after Ctrl-C before the
+    /// partial results are written
+    #[arg(long, default_value = "30")]
+    interrupt_grace: f64,
+
+    /// Per-puzzle wall-clock budget in seconds; slower answers score zero
+    #[arg(long)]
+    time_budget: Option<f64>,
+
+    /// Give late answers credit halving every this many seconds past --time-budget
+    #[arg(long, requires = "time_budget")]
+    time_decay: Option<f64>,
+
+    /// Stop a model's run once its score's confidence interval is at most ± this wide, e.g. 0.05
+    #[arg(long)]
+    stop_precision: Option<f64>,
+
+    /// Stop a model's run once its score is shown to differ from the score in this result file
+    #[arg(long)]
+    stop_baseline: Option<String>,
+
+    /// Confidence level of the interval used by --stop-precision and --stop-baseline
+    #[arg(long, default_value = "0.95")]
+    stop_confidence: f64,
+
+    /// Never stop early before this many puzzles are scored
+    #[arg(long, default_value = "30")]
+    stop_min_puzzles: usize,
+
+    /// Model used to grade the reasoning of each response
+    #[arg(long)]
+    judge_model: Option<String>,
+
+    /// Replace model names with anonymous labels in reports until `unblind`
+    #[arg(long)]
+    blind: bool,
+
+    /// Where --blind writes the label-to-model key
+    #[arg(long, default_value = "blind_key.json")]
+    blind_key: String,
+
+    /// Sign the results with this private key (see `bench keygen`); they are
+    /// always sealed against later edits
+    #[arg(long)]
+    sign_key: Option<String>,
+
+    /// Experiment tracking backend
+    #[arg(long, value_enum)]
+    tracking: Option<TrackingBackend>,
+
+    /// Tracking server URI, e.g. http://localhost:5000 for MLflow
+    #[arg(long, required_if_eq("tracking", "mlflow"))]
+    tracking_uri: Option<String>,
+
+    /// MLflow experiment name
+    #[arg(long, default_value = "boardgamebench")]
+    mlflow_experiment: String,
+
+    /// POST a run summary to this webhook (Slack/Discord compatible) when the run finishes or aborts
+    #[arg(long)]
+    notify_url: Option<String>,
+
+    /// Export OpenTelemetry spans and metrics to this OTLP/HTTP endpoint, e.g. http://localhost:4318
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+
+    /// Stream per-puzzle progress as server-sent events from this address,
+    /// e.g. 127.0.0.1:8081, at /events
+    #[cfg(feature = "serve")]
+    #[arg(long)]
+    progress_addr: Option<String>,
+
+    /// Log the run to this Weights & Biases project (needs WANDB_API_KEY and WANDB_ENTITY)
+    #[cfg(feature = "wandb")]
+    #[arg(long)]
+    wandb_project: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    init_logging(cli.verbose, cli.quiet, cli.log_format);
+    if let Some(path) = &cli.run.config {
+        RunConfig::load_from_file(path)?.apply(&mut cli.run, &matches);
+    }
+    match cli.command {
+        Some(Command::Rating {
+            puzzle_file,
+            results,
+            bin_width,
+            calibration,
+        }) => rating(&puzzle_file, &results, bin_width, calibration.as_deref()),
+        Some(Command::Difficulty {
+            puzzle_file,
+            results,
+            output,
+        }) => difficulty(&puzzle_file, &results, output.as_deref()),
+        Some(Command::Frontier { results, output }) => frontier_report(&results, output.as_deref()),
+        Some(Command::Review {
+            results,
+            min_models,
+            max_discrimination,
+            output,
+        }) => review(&results, min_models, max_discrimination, output.as_deref()),
+        Some(Command::Canary {
+            puzzle_file,
+            count,
+            seed,
+            output,
+        }) => {
+            let mut collection = PuzzleCollection::load_from_file(&puzzle_file)?;
+            let ids = insert_canaries(&mut collection, count, seed.unwrap_or_else(rand::random));
+            let output = output.unwrap_or(puzzle_file);
+            collection.save_to_file(&output)?;
+            println!("Added {} canaries to {}: {}", ids.len(), output, ids.join(", "));
+            Ok(())
+        }
+        #[cfg(feature = "chess")]
+        Some(Command::Contamination {
+            puzzle_file,
+            games,
+            famous_games,
+            max_plies,
+            output,
+        }) => contamination(&puzzle_file, &games, &famous_games, max_plies, output.as_deref()),
+        Some(Command::Export {
+            format,
+            input,
+            output,
+            task,
+            puzzle_file,
+            license,
+            exclude_license,
+            allow_unknown_license,
+        }) => {
+            let filter = LicenseFilter {
+                allow: license,
+                deny: exclude_license,
+                allow_unknown: allow_unknown_license,
+            };
+            export(format, &input, &output, &task, puzzle_file.as_deref(), &filter)
+        }
+        Some(Command::Try { puzzle_id, mut run }) => {
+            if let (Some(path), Some(matches)) = (&run.config, matches.subcommand_matches("try")) {
+                RunConfig::load_from_file(path)?.apply(&mut run, matches);
+            }
+            try_puzzle(&run, &puzzle_id)
+        }
+        Some(Command::Rescore {
+            puzzle_file,
+            input,
+            output,
+            scorer,
+            answer_scoring,
+            solution_key,
+        }) => {
+            let collection = load_scoring_collection(&puzzle_file, solution_key.as_deref())?;
+            rescore(&collection, &input, &output, scorer, answer_scoring)
+        }
+        Some(Command::MergeResults {
+            puzzle_file,
+            results,
+            output,
+            allow_overlap,
+        }) => merge_results(&puzzle_file, &results, &output, allow_overlap),
+        Some(Command::Coordinate {
+            queue,
+            queue_name,
+            model,
+            puzzle_file,
+            limit,
+            scorer,
+            answer_scoring,
+            timeout,
+            output_dir,
+            modality,
+            representation,
+            prompt_style,
+            retries,
+            time_budget,
+            solution_key,
+        }) => {
+            let mut collection = load_scoring_collection(&puzzle_file, solution_key.as_deref())?;
+            if let Some(limit) = limit {
+                collection.puzzles.truncate(limit);
+            }
+            let answer_scoring = answer_scoring.or(collection.answer_scoring).unwrap_or_default();
+            let mut coordinator = Coordinator::new(RedisQueue::new(&queue).with_name(&queue_name), collection)
+                .with_scorer(scorer)
+                .with_answer_scoring(answer_scoring)
+                .with_idle_timeout(Duration::from_secs(timeout))
+                .with_retries(retries)
+                .with_prompts(modality, representation, prompt_style);
+            if let Some(limit) = time_budget {
+                coordinator = coordinator.with_time_budget(Duration::from_secs_f64(limit));
+            }
+            coordinate(&coordinator, &model, &output_dir)
+        }
+        Some(Command::Worker {
+            queue,
+            queue_name,
+            threads,
+            idle_timeout,
+            solver_command,
+            profile,
+        }) => {
+            let profile = profile
+                .map(|name| {
+                    cli.run
+                        .profiles
+                        .get(&name)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Unknown provider profile {}", name))
+                })
+                .transpose()?;
+            let mut worker = Worker::new(RedisQueue::new(&queue).with_name(&queue_name)).with_concurrency(threads);
+            if let Some(idle_timeout) = idle_timeout {
+                worker = worker.with_idle_timeout(Duration::from_secs(idle_timeout));
+            }
+            run_worker(&worker, solver_command.as_deref(), profile.as_ref())
+        }
+        Some(Command::Withhold { puzzle_file, output }) => {
+            let collection = PuzzleCollection::load_from_file(&puzzle_file)?;
+            let public = withhold_solutions(&collection);
+            public.save_to_file(&output)?;
+            println!(
+                "Wrote {} puzzles without solutions to {} (checksum {})",
+                public.puzzles.len(),
+                output,
+                public.checksum()
+            );
+            Ok(())
+        }
+        Some(Command::Encrypt {
+            puzzle_file,
+            output,
+            key,
+        }) => {
+            let mut collection = PuzzleCollection::load_from_file(&puzzle_file)?;
+            let solution_key = if Path::new(&key).exists() {
+                SolutionKey::load_from_file(&key)?
+            } else {
+                let solution_key = SolutionKey::generate()?;
+                solution_key
+                    .save_to_file(&key)
+                    .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", key, e))?;
+                println!("Wrote a new solution key to {}; keep it out of the shared files", key);
+                solution_key
+            };
+            let encrypted = encrypt_solutions(&mut collection, &solution_key)?;
+            collection.save_to_file(&output)?;
+            println!("Encrypted the solutions of {} puzzles into {} (key {})", encrypted, output, solution_key.id());
+            Ok(())
+        }
+        Some(Command::Decrypt {
+            puzzle_file,
+            output,
+            key,
+        }) => {
+            let collection = load_scoring_collection(&puzzle_file, Some(&key))?;
+            collection.save_to_file(&output)?;
+            println!("Decrypted the solutions of {} puzzles into {}", collection.puzzles.len(), output);
+            Ok(())
+        }
+        Some(Command::ScoreHeldout {
+            puzzle_file,
+            answers,
+            scorer,
+            answer_scoring,
+            output,
+            solution_key,
+        }) => {
+            let collection = load_scoring_collection(&puzzle_file, solution_key.as_deref())?;
+            score_heldout(&collection, &answers, scorer, answer_scoring, output.as_deref())
+        }
+        Some(Command::Score {
+            puzzle_file,
+            answers,
+            output,
+            solver_name,
+            extract,
+            scorer,
+            answer_scoring,
+            solution_key,
+            sign_key,
+        }) => {
+            let collection = load_scoring_collection(&puzzle_file, solution_key.as_deref())?;
+            let sign_key = sign_key.as_deref().map(SigningKey::load_from_file).transpose()?;
+            score_answer_file(
+                &collection,
+                &answers,
+                &output,
+                solver_name.as_deref(),
+                extract,
+                scorer,
+                answer_scoring,
+                sign_key.as_ref(),
+            )
+        }
+        Some(Command::Schema { kind, output }) => schema(kind, output.as_deref()),
+        Some(Command::Validate { schema, files, watch }) => {
+            let check = || match schema {
+                Some(schema) => validate_files(schema, &files),
+                None => check_collections(&files),
+            };
+            if watch {
+                watch_files(&files, check)
+            } else {
+                check()
+            }
+        }
+        #[cfg(feature = "download")]
+        Some(Command::Download {
+            assets,
+            cache_dir,
+            force,
+            verify,
+        }) => {
+            let assets = if assets.is_empty() { download::Asset::REQUIRED.to_vec() } else { assets };
+            let mode = match (force, verify) {
+                (true, _) => download::Mode::Force,
+                (_, true) => download::Mode::Verify,
+                _ => download::Mode::Fetch,
+            };
+            download::download(&assets, &cache_dir, mode)
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve {
+            puzzle_file,
+            addr,
+            scorer,
+            aggregate_only,
+            submissions_per_hour,
+            solution_key,
+        }) => serve::serve(
+            load_scoring_collection(&puzzle_file, solution_key.as_deref())?,
+            scorer,
+            aggregate_only,
+            submissions_per_hour,
+            &addr,
+        ),
+        #[cfg(feature = "serve")]
+        Some(Command::Leaderboard {
+            puzzle_file,
+            store,
+            addr,
+        }) => leaderboard::serve_leaderboard(&PuzzleCollection::load_from_file(&puzzle_file)?, &store, &addr),
+        #[cfg(feature = "chess")]
+        Some(Command::Tournament {
+            model,
+            puzzle_file,
+            from_puzzles,
+            limit,
+            max_moves,
+            move_attempts,
+            threads,
+            retries,
+            temperature,
+            engine,
+            engine_depth,
+            output,
+        }) => {
+            let mut tournament = Tournament::new(PuzzleCollection::load_from_file(&puzzle_file)?)
+                .with_rules(GameRules {
+                    max_moves,
+                    move_attempts,
+                })
+                .with_retry(RetryPolicy {
+                    max_retries: retries,
+                    ..RetryPolicy::default()
+                })
+                .with_concurrency(threads);
+            if from_puzzles {
+                tournament = tournament.from_puzzle_positions();
+                let openings = tournament
+                    .openings()
+                    .iter()
+                    .take(limit.unwrap_or(usize::MAX))
+                    .cloned()
+                    .collect();
+                tournament = tournament.with_openings(openings);
+            }
+            if let Some(engine) = engine {
+                tournament = tournament.with_annotator(UciEngine::from_command_line(&engine, engine_depth)?);
+            }
+            run_tournament(&tournament, &model, temperature, &output)
+        }
+        #[cfg(feature = "chess")]
+        Some(Command::Playout {
+            model,
+            puzzle_file,
+            engine,
+            engine_depth,
+            engine_skill,
+            max_moves,
+            move_attempts,
+            limit,
+            threads,
+            retries,
+            temperature,
+            annotate,
+            output,
+        }) => {
+            let mut collection = PuzzleCollection::load_from_file(&puzzle_file)?;
+            if let Some(limit) = limit {
+                collection.puzzles.truncate(limit);
+            }
+            let annotator = UciEngine::from_command_line(&engine, engine_depth)?;
+            let mut engine = annotator.clone();
+            if let Some(skill) = engine_skill {
+                engine = engine.with_option("Skill Level", &skill.to_string());
+            }
+            let mut play_out = PlayOut::new(collection, engine)
+                .with_rules(GameRules {
+                    max_moves,
+                    move_attempts,
+                })
+                .with_retry(RetryPolicy {
+                    max_retries: retries,
+                    ..RetryPolicy::default()
+                })
+                .with_concurrency(threads);
+            if annotate {
+                play_out = play_out.with_annotator(annotator);
+            }
+            run_play_out(&play_out, &model, temperature, &output)
+        }
+        Some(Command::Keygen { output }) => {
+            let key = SigningKey::generate()?;
+            key.save_to_file(&output)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+            println!("Wrote private key to {}", output);
+            println!("Public key: {}", key.public_key());
+            Ok(())
+        }
+        Some(Command::Sign { file, key }) => {
+            let key = key.as_deref().map(SigningKey::load_from_file).transpose()?;
+            sign_file(&file, key.as_ref())
+        }
+        Some(Command::Verify {
+            puzzle_file,
+            trusted_key,
+            results,
+        }) => verify_files(puzzle_file.as_deref(), &trusted_key, &results),
+        Some(Command::Unblind { file, key }) => unblind_file(&file, &key),
+        None => run(cli.run),
+    }
+}
+
+/// Log to stderr at a level chosen by -v/-q unless `RUST_LOG` is set.
+fn init_logging(verbose: u8, quiet: bool, format: LogFormat) {
+    let level = match (quiet, verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+fn rating(
+    puzzle_files: &[String],
+    result_files: &[String],
+    bin_width: f64,
+    calibration: Option<&str>,
+) -> Result<()> {
+    if bin_width <= 0.0 {
+        anyhow::bail!("--bin-width must be positive");
+    }
+    let collections = puzzle_files
+        .iter()
+        .map(|path| PuzzleCollection::load_from_file(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let results = result_files
+        .iter()
+        .map(|path| {
+            BenchmarkResult::load_from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("Model Ratings:");
+    for rating in rate_results(&collections, &results) {
+        println!(
+            "  {}: {:.0} ± {:.0} ({} rated puzzles)",
+            rating.solver_name,
+            rating.rating,
+            2.0 * rating.deviation,
+            rating.games
+        );
+    }
+
+    let curves = calibration_curves(&collections, &results, bin_width);
+    println!("\nAccuracy by Puzzle Rating:");
+    for curve in &curves {
+        match curve.puzzle_rating {
+            Some(puzzle_rating) => println!(
+                "  {}: solves half of the puzzles rated {:.0} ({:+.2} log-odds per 400 points)",
+                curve.solver_name, puzzle_rating, curve.slope
+            ),
+            None => println!(
+                "  {}: accuracy doesn't fall with puzzle rating; no puzzle rating implied",
+                curve.solver_name
+            ),
+        }
+        for bin in &curve.bins {
+            println!(
+                "    {:>4.0}-{:<4.0} {:>5.1}% (fitted {:>5.1}%, {} puzzles)",
+                bin.low,
+                bin.high,
+                bin.accuracy * 100.0,
+                bin.fitted * 100.0,
+                bin.puzzles
+            );
+        }
+    }
+    if let Some(path) = calibration {
+        std::fs::write(path, serde_json::to_string_pretty(&curves)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        println!("\nWrote calibration curves to {}", path);
+    }
+
+    Ok(())
+}
+
+fn difficulty(puzzle_file: &str, result_files: &[String], output: Option<&str>) -> Result<()> {
+    let mut collection = PuzzleCollection::load_from_file(puzzle_file)?;
+    let results = result_files
+        .iter()
+        .map(|path| {
+            BenchmarkResult::load_from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let fit = fit_irt(&results);
+    println!("Model Abilities:");
+    let mut abilities = fit.abilities.clone();
+    abilities.sort_by(|a, b| b.ability.total_cmp(&a.ability));
+    for ability in &abilities {
+        println!("  {}: {:+.2} ({} puzzles)", ability.solver_name, ability.ability, ability.puzzles);
+    }
+    println!("Puzzle Difficulties:");
+    let mut difficulties = fit.difficulties.clone();
+    difficulties.sort_by(|a, b| b.difficulty.total_cmp(&a.difficulty));
+    for difficulty in &difficulties {
+        println!("  {}: {:+.2} ({} attempts)", difficulty.puzzle_id, difficulty.difficulty, difficulty.responses);
+    }
+
+    let updated = write_difficulties(&mut collection, &fit);
+    let output = output.unwrap_or(puzzle_file);
+    collection.save_to_file(output)?;
+    println!("Wrote {} difficulties to {}", updated, output);
+    Ok(())
+}
+
+fn frontier_report(result_files: &[String], output: Option<&str>) -> Result<()> {
+    let mut results = Vec::new();
+    for path in result_files {
+        let content = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
+        match ComparisonReport::from_json(&content) {
+            Ok(report) => results.extend(report.results),
+            Err(_) => results.push(
+                BenchmarkResult::from_json(&content).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?,
+            ),
+        }
+    }
+    let frontier = frontier(&results)?;
+    print_frontier(&frontier);
+    if let Some(path) = output {
+        std::fs::write(path, serde_json::to_string_pretty(&frontier)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        println!("\nWrote the frontier to {}", path);
+    }
+    Ok(())
+}
+
+fn print_frontier(frontier: &FrontierReport) {
+    println!("\nAccuracy vs Cost and Latency (* Pareto-optimal):");
+    println!("  {:<40} {:>8} {:>12} {:>10}", "Model", "Score", "Cost/puzzle", "Latency");
+    for point in &frontier.points {
+        let cost = match point.cost_per_puzzle {
+            Some(cost) => format!("${:.4}{}", cost, if point.cost_optimal { "*" } else { " " }),
+            None => "-".to_string(),
+        };
+        let latency = match point.latency_ms {
+            Some(latency) => format!("{:.0}ms{}", latency, if point.latency_optimal { "*" } else { " " }),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {:<40} {:>7.2}% {:>12} {:>10}",
+            point.solver_name,
+            point.average_score * 100.0,
+            cost,
+            latency
+        );
+    }
+}
+
+fn review(result_files: &[String], min_models: usize, max_discrimination: f64, output: Option<&str>) -> Result<()> {
+    let results = result_files
+        .iter()
+        .map(|path| {
+            BenchmarkResult::load_from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let fit = fit_irt(&results);
+    let reviews = review_puzzles(&results, &fit, min_models, max_discrimination);
+    println!("Puzzles to Review ({} of {}):", reviews.len(), fit.difficulties.len());
+    for review in &reviews {
+        let reason = match review.reason {
+            ReviewReason::AllSolved => "every model solved it".to_string(),
+            ReviewReason::NoneSolved => "no model solved it".to_string(),
+            ReviewReason::SameScore => format!("every model scored {:.2}", review.mean_score),
+            ReviewReason::Inverted => format!(
+                "weaker models did better (discrimination {:+.2})",
+                review.discrimination.unwrap_or(0.0)
+            ),
+        };
+        println!("  {}: {} ({} models)", review.puzzle_id, reason, review.models);
+    }
+
+    if let Some(path) = output {
+        std::fs::write(path, serde_json::to_string_pretty(&reviews)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        println!("Wrote {} flagged puzzles to {}", reviews.len(), path);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chess")]
+fn contamination(
+    puzzle_file: &str,
+    games: &[String],
+    famous_games: &[String],
+    max_plies: usize,
+    output: Option<&str>,
+) -> Result<()> {
+    if games.is_empty() && famous_games.is_empty() {
+        anyhow::bail!("Give at least one PGN file with --games or --famous-games");
+    }
+    let mut collection = PuzzleCollection::load_from_file(puzzle_file)?;
+    let mut corpus = PositionCorpus::new(max_plies);
+    let corpora = games.iter().map(|path| (path, 1.0)).chain(famous_games.iter().map(|path| (path, FAMOUS_GAME_WEIGHT)));
+    for (path, weight) in corpora {
+        let read = if path == "-" {
+            corpus.add_pgn(std::io::stdin().lock(), weight)
+        } else {
+            std::fs::File::open(path).and_then(|file| corpus.add_pgn(std::io::BufReader::new(file), weight))
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        println!("Read {} games from {}", read, path);
+    }
+
+    let annotated = corpus.annotate(&mut collection);
+    let mut risks: Vec<(&str, f64)> = collection
+        .puzzles
+        .iter()
+        .filter_map(|puzzle| Some((puzzle.id.as_str(), puzzle.metadata.get(RISK_KEY)?.as_f64()?)))
+        .collect();
+    risks.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let risky = risks.iter().filter(|(_, risk)| *risk >= 0.5).count();
+    println!("Contamination Risk ({} of {} puzzles at 0.5 or more):", risky, annotated);
+    for (id, risk) in risks.iter().take_while(|(_, risk)| *risk > 0.0).take(20) {
+        println!("  {}: {:.3}", id, risk);
+    }
+
+    let output = output.unwrap_or(puzzle_file);
+    collection.save_to_file(output)?;
+    println!("Wrote {} contamination risks to {}", annotated, output);
+    Ok(())
+}
+
+fn merge_results(puzzle_file: &str, result_files: &[String], output: &str, allow_overlap: bool) -> Result<()> {
+    let collection = PuzzleCollection::load_from_file(puzzle_file)?;
+    let runs = result_files
+        .iter()
+        .map(|path| {
+            BenchmarkResult::load_from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (path, run) in result_files.iter().zip(&runs) {
+        run.check_writable().map_err(|e| anyhow::anyhow!("Can't merge {}: {}", path, e))?;
+    }
+    let mut merged = if allow_overlap {
+        BenchmarkResult::merge(&collection, runs)?
+    } else {
+        BenchmarkResult::merge_shards(&collection, runs)?
+    };
+    signing::seal(&mut merged);
+    std::fs::write(output, serde_json::to_string_pretty(&merged)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+    println!(
+        "{}: {} puzzles from {} runs, {:.2}%",
+        merged.solver_name,
+        merged.total_puzzles,
+        result_files.len(),
+        merged.average_score * 100.0
+    );
+    for run in &merged.merged_from {
+        println!("  run of {}: {} puzzles", run.timestamp, run.puzzle_ids.len());
+    }
+    println!("Wrote merged results to {}", output);
+    Ok(())
+}
+
+fn coordinate(coordinator: &Coordinator, models: &[String], output_dir: &str) -> Result<()> {
+    let mut results = coordinator.run(models)?;
+    seal_results(&mut results, None);
+    std::fs::create_dir_all(output_dir).map_err(|e| anyhow::anyhow!("Failed to create {}: {}", output_dir, e))?;
+    for (model, result) in models.iter().zip(&results) {
+        let path = format!("{}/{}.json", output_dir, model.replace('/', "_"));
+        std::fs::write(&path, serde_json::to_string_pretty(result)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        print_results(result, 1);
+        println!("Wrote results to {}", path);
+    }
+    Ok(())
+}
+
+fn run_worker(worker: &Worker, solver_command: Option<&str>, profile: Option<&ProviderProfile>) -> Result<()> {
+    dotenvy::dotenv().ok();
+    info!(worker = %worker.name, queue = %worker.queue.name, "waiting for tasks");
+    let answered = worker.run(|model| {
+        let solver = match solver_command {
+            Some(command_line) => create_command_solver(command_line),
+            None => create_solver(model, profile),
+        };
+        solver.map_err(|e| e.to_string())
+    })?;
+    println!("Answered {} tasks", answered);
+    Ok(())
+}
+
+/// Load a collection to score answers with, decrypting its solutions with
+/// the key at `key_path` if given.
+fn load_scoring_collection(path: &str, key_path: Option<&str>) -> Result<PuzzleCollection> {
+    let mut collection = PuzzleCollection::load_from_file(path)?;
+    if let Some(key_path) = key_path {
+        decrypt_solutions(&mut collection, &SolutionKey::load_from_file(key_path)?)?;
+    } else if collection.solution_key_id.is_some() {
+        anyhow::bail!("the solutions of {} are encrypted; pass --solution-key", path);
+    }
+    Ok(collection)
+}
+
+/// Load the answers at `path`, named after the file if they don't name
+/// their solver.
+fn load_answer_sheet(path: &str) -> Result<AnswerSheet> {
+    let mut sheet = AnswerSheet::load_from_file(path).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
+    if sheet.solver_name.is_empty() {
+        sheet.solver_name = Path::new(path).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    }
+    Ok(sheet)
+}
+
+fn score_heldout(
+    collection: &PuzzleCollection,
+    sheet_files: &[String],
+    scorer: Scorer,
+    answer_scoring: Option<AnswerScoring>,
+    output: Option<&str>,
+) -> Result<()> {
+    let answer_scoring = answer_scoring.or(collection.answer_scoring).unwrap_or_default();
+    let mut scores = Vec::new();
+    for path in sheet_files {
+        let sheet = load_answer_sheet(path)?;
+        let score = score_sheet(collection, &sheet, scorer, answer_scoring)
+            .map_err(|e| anyhow::anyhow!("Failed to score {}: {}", path, e))?;
+        println!(
+            "{}: {:.2}% ({}/{}, {} of {} puzzles answered)",
+            score.solver_name,
+            score.average_score * 100.0,
+            score.total_score,
+            score.max_possible_score,
+            score.answered,
+            score.total_puzzles
+        );
+        scores.push(score);
+    }
+    if let Some(output) = output {
+        std::fs::write(output, serde_json::to_string_pretty(&scores)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+        println!("Wrote scores to {}", output);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn score_answer_file(
+    collection: &PuzzleCollection,
+    path: &str,
+    output: &str,
+    solver_name: Option<&str>,
+    extract: bool,
+    scorer: Scorer,
+    answer_scoring: Option<AnswerScoring>,
+    sign_key: Option<&SigningKey>,
+) -> Result<()> {
+    let mut sheet = load_answer_sheet(path)?;
+    if let Some(name) = solver_name {
+        sheet.solver_name = name.to_string();
+    }
+    let answer_scoring = answer_scoring.or(collection.answer_scoring).unwrap_or_default();
+    let mut result = score_answers(collection, &sheet, scorer, answer_scoring, extract)
+        .map_err(|e| anyhow::anyhow!("Failed to score {}: {}", path, e))?;
+    seal_results(std::slice::from_mut(&mut result), sign_key);
+    std::fs::write(output, serde_json::to_string_pretty(&result)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+    print_results(&result, 1);
+    println!("Wrote the result to {}", output);
+    Ok(())
+}
+
+fn rescore(
+    collection: &PuzzleCollection,
+    input: &str,
+    output: &str,
+    scorer: Scorer,
+    answer_scoring: Option<AnswerScoring>,
+) -> Result<()> {
+    let mut results =
+        BenchmarkResult::load_from_file(input).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", input, e))?;
+    results.check_writable()?;
+    let previous = results.average_score;
+    results.rescore(
+        collection,
+        scorer,
+        answer_scoring.or(collection.answer_scoring).unwrap_or_default(),
+    );
+    signing::seal(&mut results);
+    std::fs::write(output, serde_json::to_string_pretty(&results)?)?;
+    println!(
+        "{}: {:.2}% -> {:.2}%",
+        results.solver_name,
+        previous * 100.0,
+        results.average_score * 100.0
+    );
+    if let Some(pass_results) = &results.pass_results {
+        println!(
+            "  pass@1: {:.2}%, pass@{}: {:.2}%",
+            pass_results.pass_at_1 * 100.0,
+            pass_results.individual_pass_scores.len(),
+            pass_results.pass_at_n * 100.0
+        );
+    }
+    Ok(())
+}
+
+fn export(
+    format: ExportFormat,
+    input: &str,
+    output: &str,
+    task: &str,
+    puzzle_file: Option<&str>,
+    filter: &LicenseFilter,
+) -> Result<()> {
+    // Releases are filtered by license before converting.
+    let load_release = || -> Result<PuzzleCollection> {
+        let collection = PuzzleCollection::load_from_file(input)?;
+        if filter.is_empty() {
+            return Ok(collection);
+        }
+        let (collection, summary) = filter_by_license(&collection, filter);
+        let report = |verb: &str, licenses: &BTreeMap<String, usize>| {
+            let counts: Vec<String> = licenses.iter().map(|(license, count)| format!("{} {}", count, license)).collect();
+            let total: usize = licenses.values().sum();
+            if counts.is_empty() {
+                println!("{} {} puzzles", verb, total);
+            } else {
+                println!("{} {} puzzles ({})", verb, total, counts.join(", "));
+            }
+        };
+        report("Kept", &summary.kept);
+        report("Dropped", &summary.dropped);
+        if collection.puzzles.is_empty() {
+            anyhow::bail!("no puzzle of {} passes the license filter", input);
+        }
+        Ok(collection)
+    };
+    match format {
+        ExportFormat::LmEval => {
+            if !filter.is_empty() {
+                anyhow::bail!("--license and --exclude-license apply to collection exports, not lm-eval results");
+            }
+            let results = BenchmarkResult::load_from_file(input)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", input, e))?;
+            let collection = puzzle_file.map(PuzzleCollection::load_from_file).transpose()?;
+            let export = to_lm_eval_harness(&results, task, collection.as_ref());
+            std::fs::write(output, serde_json::to_string_pretty(&export.results)?)?;
+
+            let samples_path = Path::new(output).with_file_name(format!(
+                "{}_samples.jsonl",
+                Path::new(output)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or(task)
+            ));
+            let mut samples = String::new();
+            for sample in &export.samples {
+                samples.push_str(&serde_json::to_string(sample)?);
+                samples.push('\n');
+            }
+            std::fs::write(&samples_path, samples)?;
+
+            println!(
+                "Exported lm-eval results to {} and samples to {}",
+                output,
+                samples_path.display()
+            );
+        }
+        ExportFormat::OpenaiEvals => {
+            let collection = load_release()?;
+            let mut samples = String::new();
+            for sample in to_openai_evals(&collection) {
+                samples.push_str(&serde_json::to_string(&sample)?);
+                samples.push('\n');
+            }
+            std::fs::write(output, samples)?;
+            println!("Exported OpenAI Evals samples to {}", output);
+        }
+        ExportFormat::Collection => {
+            load_release()?.save_to_file(output)?;
+            println!("Exported collection to {}", output);
+        }
+    }
+    Ok(())
+}
+
+fn schema(kind: SchemaKind, output: Option<&str>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&schema_for(kind))?;
+    match output {
+        Some(path) => std::fs::write(path, json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+fn validate_files(kind: SchemaKind, files: &[String]) -> Result<()> {
+    let mut invalid = 0;
+    for path in files {
+        let content = std::fs::read_to_string(path)?;
+        let document: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(document) => document,
+            Err(e) => {
+                println!("{}:{}:{}: {}", path, e.line(), e.column(), e);
+                invalid += 1;
+                continue;
+            }
+        };
+        let violations = validate(kind, &document);
+        if violations.is_empty() {
+            println!("{}: ok", path);
+            continue;
+        }
+        invalid += 1;
+        for violation in violations {
+            let location = if violation.path.is_empty() { "/" } else { &violation.path };
+            println!("{}: {}: {}", path, location, violation.message);
+        }
+    }
+
+    if invalid > 0 {
+        anyhow::bail!("{} of {} files failed validation", invalid, files.len());
+    }
+    Ok(())
+}
+
+/// How often `--watch` looks for changed files.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Run `check`, then again whenever one of `paths` is modified, until
+/// interrupted. Failures are printed and the watch goes on; on a terminal
+/// the screen is cleared before each run.
+fn watch_files(paths: &[String], mut check: impl FnMut() -> Result<()>) -> Result<()> {
+    let modified = || -> Vec<Option<std::time::SystemTime>> {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+            .collect()
+    };
+    let terminal = std::io::stdout().is_terminal();
+    loop {
+        let seen = modified();
+        if terminal {
+            print!("\x1B[2J\x1B[H");
+        }
+        if let Err(e) = check() {
+            println!("Error: {:#}", e);
+        }
+        println!("\nWatching {} for changes; press Ctrl-C to stop", paths.join(", "));
+        while modified() == seen {
+            std::thread::sleep(WATCH_INTERVAL);
+        }
+        // Editors may write a file in several steps; let them finish.
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+fn check_collections(files: &[String]) -> Result<()> {
+    let mut invalid = 0;
+    for path in files {
+        let collection = match PuzzleCollection::load_from_file(path) {
+            Ok(collection) => collection,
+            Err(e) => {
+                println!("{}: {}", path, e);
+                invalid += 1;
+                continue;
+            }
+        };
+        let issues = collection.check();
+        if issues.is_empty() {
+            println!("{}: ok ({} puzzles)", path, collection.puzzles.len());
+            continue;
+        }
+        invalid += 1;
+        for issue in issues {
+            match issue.puzzle_id {
+                Some(id) => println!("{}: {}: {}", path, id, issue.message),
+                None => println!("{}: {}", path, issue.message),
+            }
+        }
+    }
+
+    if invalid > 0 {
+        anyhow::bail!("{} of {} collections failed validation", invalid, files.len());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chess")]
+fn run_tournament(
+    tournament: &Tournament,
+    models: &[String],
+    temperature: Option<f64>,
+    output: &str,
+) -> Result<()> {
+    if models.len() < 2 {
+        anyhow::bail!("a tournament needs at least two models");
+    }
+    if tournament.openings().is_empty() {
+        anyhow::bail!("no chess positions to play from");
+    }
+    dotenvy::dotenv().ok();
+    let mut solvers = Vec::new();
+    for model in models {
+        let mut solver = create_solver(model, None)?;
+        if let Some(temperature) = temperature {
+            solver = solver.with_temperature(temperature);
+        }
+        solvers.push(solver);
+    }
+    let players: Vec<&Solver> = solvers.iter().collect();
+    info!(
+        players = players.len(),
+        openings = tournament.openings().len(),
+        "starting tournament"
+    );
+    let report = tournament.run(&players);
+
+    println!("\nTournament Standings:");
+    for (rank, standing) in report.standings.iter().enumerate() {
+        println!(
+            "  {}. {}: Elo {:.0}, {}/{} points (+{} ={} -{})",
+            rank + 1,
+            standing.player,
+            standing.elo,
+            standing.points,
+            standing.games,
+            standing.wins,
+            standing.draws,
+            standing.losses
+        );
+        if let Some(quality) = &standing.move_quality {
+            print_move_quality(quality);
+        }
+    }
+    println!("\nHead to Head:");
+    for record in &report.head_to_head {
+        println!(
+            "  {} vs {}: +{} ={} -{}",
+            record.player, record.opponent, record.wins, record.draws, record.losses
+        );
+    }
+    report
+        .save_to_file(output)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+    println!("\nWrote {} games to {}", report.games.len(), output);
+    Ok(())
+}
+
+#[cfg(feature = "chess")]
+fn run_play_out(play_out: &PlayOut, model: &str, temperature: Option<f64>, output: &str) -> Result<()> {
+    dotenvy::dotenv().ok();
+    let mut solver = create_solver(model, None)?;
+    if let Some(temperature) = temperature {
+        solver = solver.with_temperature(temperature);
+    }
+    info!(engine = %play_out.engine.name(), "starting play-outs");
+    let report = play_out.run(&solver);
+    if report.games.is_empty() {
+        anyhow::bail!("no chess puzzles could be played out");
+    }
+
+    println!("\nPlay-out against {}:", report.engine);
+    println!("  Score: {:.2}% over {} games", report.score, report.games.len());
+    println!(
+        "  Finished games: +{} ={} -{}; cut off by the move limit: {}",
+        report.wins, report.draws, report.losses, report.unfinished
+    );
+    if let Some(quality) = &report.move_quality {
+        print_move_quality(quality);
+    }
+    report
+        .save_to_file(output)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output, e))?;
+    println!("\nWrote {} games to {}", report.games.len(), output);
+    Ok(())
+}
+
+#[cfg(feature = "chess")]
+fn print_move_quality(quality: &MoveQuality) {
+    println!(
+        "     Average centipawn loss {:.0} over {} moves; {} inaccuracies, {} mistakes, {} blunders",
+        quality.average_loss, quality.moves, quality.inaccuracies, quality.mistakes, quality.blunders
+    );
+}
+
+fn unblind_file(path: &str, key_path: &str) -> Result<()> {
+    let key = BlindKey::load_from_file(key_path)
+        .map_err(|e| anyhow::anyhow!("Failed to load blind key {}: {}", key_path, e))?;
+    let content = std::fs::read_to_string(path)?;
+
+    let json = if let Ok(mut report) = ComparisonReport::from_json(&content) {
+        report.results.iter().try_for_each(BenchmarkResult::check_writable)?;
+        unblind(&mut report.results, &key);
+        seal_results(&mut report.results, None);
+        serde_json::to_string_pretty(&report)?
+    } else {
+        let mut results = BenchmarkResult::from_json(&content)?;
+        results.check_writable()?;
+        unblind(std::slice::from_mut(&mut results), &key);
+        signing::seal(&mut results);
+        serde_json::to_string_pretty(&results)?
+    };
+    std::fs::write(path, json)?;
+
+    println!("Unblinded {} using {}", path, key_path);
+    Ok(())
+}
+
+fn sign_file(path: &str, key: Option<&SigningKey>) -> Result<()> {
+    let mut result =
+        BenchmarkResult::load_from_file(path).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
+    result.check_writable()?;
+    seal_results(std::slice::from_mut(&mut result), key);
+    std::fs::write(path, serde_json::to_string_pretty(&result)?)?;
+    match key {
+        Some(key) => println!("Signed {} with {}", path, key.public_key()),
+        None => println!("Sealed {}", path),
+    }
+    Ok(())
+}
+
+fn verify_files(puzzle_file: Option<&str>, trusted_keys: &[String], result_files: &[String]) -> Result<()> {
+    let collection = puzzle_file.map(PuzzleCollection::load_from_file).transpose()?;
+    let mut failed = 0;
+    for path in result_files {
+        let result =
+            BenchmarkResult::load_from_file(path).map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
+        match signing::verify_submission(&result, collection.as_ref(), trusted_keys) {
+            Ok(Some(signer)) => println!("OK      {} ({}, signed by {})", path, result.solver_name, signer),
+            Ok(None) => println!("OK      {} ({}, unsigned)", path, result.solver_name),
+            Err(e) => {
+                println!("FAILED  {}: {}", path, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} result files failed verification", failed, result_files.len());
+    }
+    Ok(())
+}
+
+/// Seal each result, signed with `key` if given.
+fn seal_results(results: &mut [BenchmarkResult], key: Option<&SigningKey>) {
+    for result in results {
+        match key {
+            Some(key) => signing::sign(result, key),
+            None => signing::seal(result),
+        }
+    }
+}
+
+/// Write each result's answers as an answer sheet: to `path` for a single
+/// result, else to `path` with the solver's name before the extension.
+fn write_answer_sheets(results: &[BenchmarkResult], path: &str) -> Result<()> {
+    for result in results {
+        let path = match results.len() {
+            1 => path.to_string(),
+            _ => {
+                let path = Path::new(path);
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let name = format!("{}.{}.json", stem, result.solver_name.replace(['/', ' '], "_"));
+                path.with_file_name(name).to_string_lossy().into_owned()
+            }
+        };
+        AnswerSheet::from_result(result)
+            .save_to_file(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        info!(path = %path, solver = %result.solver_name, "answer sheet written");
+    }
+    Ok(())
+}
 
-    /// Puzzle file to load
-    #[arg(short, long, default_value = "data/sample_puzzles.json")]
-    puzzle_file: String,
+/// Blind `results`, saving the key to `key_path`. Fails if the key can't be
+/// saved, since the blinded results could never be unblinded.
+fn blind_results(results: &mut [BenchmarkResult], key_path: &str) -> Result<()> {
+    let key = blind(results);
+    if let Err(e) = key.save_to_file(key_path) {
+        anyhow::bail!("Failed to save the blind key to {}: {}", key_path, e);
+    }
+    info!(key = key_path, "blinded model names");
+    Ok(())
+}
 
-    /// Number of threads for parallel evaluation
-    #[arg(short, long, default_value = "16")]
-    threads: usize,
+/// What a finished run produced, for notifications.
+struct RunOutcome {
+    results: Vec<BenchmarkResult>,
+    output_path: String,
+}
 
-    /// Number of passes to run for each test case
-    #[arg(short = 'N', long, default_value = "1")]
-    passes: usize,
+fn run(args: Args) -> Result<()> {
+    if args.watch && !args.dry_run {
+        anyhow::bail!("--watch only works with --dry-run");
+    }
+    if args.dry_run {
+        if args.watch {
+            return watch_files(&args.puzzle_file, || dry_run(&args));
+        }
+        return dry_run(&args);
+    }
+    let start = Instant::now();
+    let outcome = execute(&args);
+
+    if let Some(url) = &args.notify_url {
+        let notification = match &outcome {
+            Ok(outcome) => Notification::finished(&outcome.results, &outcome.output_path, start.elapsed()),
+            Err(e) => Notification::aborted(&e.to_string(), start.elapsed()),
+        };
+        if let Err(e) = notify(url, &notification) {
+            warn!(error = %e, "could not send notification");
+        }
+    }
+
+    outcome.map(|_| ())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let puzzles = PuzzleCollection::load_from_file(&args.puzzle_file)?;
-    println!(
-        "Loaded {} puzzles from collection: {}",
-        puzzles.puzzles.len(),
-        puzzles.name
-    );
+/// `key=value`, for `--metadata`.
+fn parse_key_value(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected key=value, got {:?}", arg))
+}
+
+fn selection(args: &Args) -> PuzzleSelection {
+    PuzzleSelection {
+        ids: args.ids.clone(),
+        game_type: None,
+        theme: args.theme.clone(),
+        include_tags: args.include_tags.clone(),
+        exclude_tags: args.exclude_tags.clone(),
+        metadata: args.metadata.clone(),
+        shuffle_seed: args.shuffle.then_some(args.seed),
+        sample: args.sample.map(|size| Sample {
+            size,
+            stratify_by: args.stratify_by.clone(),
+            seed: args.seed,
+        }),
+        skip: args.skip,
+        limit: args.limit,
+        shard: args.shard,
+    }
+}
+
+/// Load the puzzle file; JSONL collections are streamed so only the puzzles
+/// the selection picks are held in memory. The selection's order, skip,
+/// limit and shard span all the files, so with several only its filters,
+/// and without a shuffle or sample the number of puzzles it could reach,
+/// are applied while streaming.
+fn load_puzzles(args: &Args) -> Result<PuzzleCollection> {
+    let mut streamed = selection(args);
+    if args.puzzle_file.len() > 1 {
+        let ordered = streamed.shuffle_seed.is_none() && streamed.sample.is_none();
+        streamed = PuzzleSelection {
+            shuffle_seed: None,
+            sample: None,
+            skip: 0,
+            limit: streamed.limit.filter(|_| ordered).map(|limit| streamed.skip + limit),
+            shard: None,
+            ..streamed
+        };
+    }
+    let collections = args
+        .puzzle_file
+        .iter()
+        .map(|path| {
+            if path.ends_with(".jsonl") {
+                PuzzleCollection::stream_from_file(path)?.collect_selected(&streamed)
+            } else {
+                PuzzleCollection::load_from_file(path)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut puzzles = PuzzleCollection::combine(collections)?;
+    if args.puzzle_file.len() > 1 {
+        let groups = resolve_duplicates(&mut puzzles, args.duplicates);
+        if !groups.is_empty() {
+            warn!(
+                positions = groups.len(),
+                puzzles = groups.iter().map(|group| group.puzzle_ids.len()).sum::<usize>(),
+                policy = ?args.duplicates,
+                "some puzzles share a position"
+            );
+            for group in &groups {
+                info!(hash = %group.hash, puzzles = %group.puzzle_ids.join(", "), "duplicate position");
+            }
+        }
+    }
+    if let Some(notation) = args.answer_notation {
+        puzzles.with_notation(notation)?;
+    }
+    if let Some(language) = args.prompt_lang
+        && !puzzles.with_language(language)
+    {
+        warn!(?language, "the collection has no translation of its goal and rules; prompts will quote them as written");
+    }
+    Ok(puzzles)
+}
+
+/// The context window `model` will be checked against: `--context-window`,
+/// the profile's, or the model's if well known.
+fn known_context_window(args: &Args, model: &str) -> Option<u64> {
+    let profile = args.profile.as_ref().and_then(|name| args.profiles.get(name));
+    args.context_window
+        .or_else(|| profile.and_then(|profile| profile.context_window))
+        .or_else(|| args.solver_command.is_none().then(|| context_window(model)).flatten())
+}
+
+/// Ask `puzzle_id` as a run with `args` would, printing each state's prompt,
+/// response and extracted answer and the puzzle's score, and again until
+/// told to stop; another puzzle id switches to that puzzle.
+fn try_puzzle(args: &Args, puzzle_id: &str) -> Result<()> {
+    let puzzles = load_puzzles(args)?;
+    if !puzzles.puzzles.iter().any(|puzzle| puzzle.id == puzzle_id) {
+        anyhow::bail!("no puzzle {} in {}", puzzle_id, puzzles.name);
+    }
 
     dotenvy::dotenv().ok();
+    let profile = args
+        .profile
+        .as_ref()
+        .map(|name| {
+            args.profiles
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown provider profile {}", name))
+        })
+        .transpose()?;
+    let solver = match (&args.solver_command, args.model.first()) {
+        (Some(command_line), _) => create_command_solver(command_line)?,
+        (None, Some(model)) => create_solver(model, profile)?,
+        (None, None) => anyhow::bail!("no model to ask; pass -m"),
+    };
+    let presentation = Presentation {
+        modality: args.modality,
+        representation: args.representation,
+        style: args.prompt_style,
+    };
+    let compute = ComputeBudget {
+        max_completion_tokens: args.max_completion_tokens,
+        reasoning_effort: args.reasoning_effort,
+    };
+    let solver = configure_solver(args, solver, &presentation, &compute);
+    let runner = scoring_runner(args, puzzles, profile)?.build()?;
+    let collection = &runner.puzzles;
+    let image = args.modality == Modality::Image;
+    let runtime = tokio::runtime::Runtime::new()?;
 
-    let solver: Box<boardgamebench::evaluation::Solver> = {
-        println!("Using Solver with model: {}", args.model);
-        match Solver::new(args.model) {
-            Ok(solver) => {
-                // Test API reachability before running benchmark
-                println!("Testing API reachability...");
-                match solver.test_api_reachability() {
-                    Ok(response) => {
-                        println!("API test successful. Response: {}", response);
-                        Box::new(solver)
+    let mut id = puzzle_id.to_string();
+    let mut line = String::new();
+    loop {
+        match collection.puzzles.iter().find(|puzzle| puzzle.id == id) {
+            Some(puzzle) => {
+                for i in 0..puzzle.game_states.len() {
+                    let prompt = solver.prompt(puzzle, collection, i, image, image && args.sequences);
+                    println!("=== {} state {} prompt (~{} tokens) ===", puzzle.id, i, estimate_tokens(&prompt.text));
+                    println!("{}", prompt.text);
+                    if image {
+                        println!("[board image attached]");
                     }
-                    Err(e) => {
-                        eprintln!("API test failed: {}", e);
-                        eprintln!("Please check your OPENAI_API_KEY and OPENAI_BASE_URL environment variables.");
-                        return Err(anyhow::anyhow!("API reachability test failed: {}", e));
+                    if !prompt.compression.is_empty() {
+                        let steps: Vec<&str> = prompt.compression.iter().map(|step| step.label()).collect();
+                        println!("[compressed: {}]", steps.join(", "));
                     }
                 }
+                let Some(score) = runtime.block_on(runner.evaluate_one(&solver, puzzle)) else {
+                    anyhow::bail!("the solver's budget is used up");
+                };
+                let asked = score.responses.len() - score.skipped_states;
+                for (i, response) in score.responses.iter().enumerate().take(asked) {
+                    println!("=== {} state {} response ===", puzzle.id, i);
+                    println!("{}", response);
+                    let method = score.extraction_methods.get(i).copied().unwrap_or(ExtractionMethod::NotFound);
+                    let expected = puzzle.solutions.get(i).map_or("(withheld)", String::as_str);
+                    println!("Extracted: {:?} ({:?}), expected: {}", score.answers[i], method, expected);
+                }
+                if score.skipped_states > 0 {
+                    println!("Skipped {} states after a wrong answer", score.skipped_states);
+                }
+                println!("Score: {}/{}", score.score, score.max_possible_score);
             }
-            Err(e) => {
-                eprintln!("Failed to create solver: {}", e);
-                return Err(anyhow::anyhow!("Failed to create solver: {}", e));
+            None => println!("No puzzle {} in {}", id, collection.name),
+        }
+
+        print!("\n[Enter] ask again, a puzzle id to switch, q to quit: ");
+        std::io::stdout().flush()?;
+        line.clear();
+        if std::io::stdin().read_line(&mut line)? == 0 || line.trim() == "q" {
+            return Ok(());
+        }
+        if !line.trim().is_empty() {
+            id = line.trim().to_string();
+        }
+    }
+}
+
+/// Print the prompts a run with `args` would send, and what they would cost,
+/// without creating any solver.
+fn dry_run(args: &Args) -> Result<()> {
+    let mut puzzles = load_puzzles(args)?;
+    puzzles.select(&selection(args));
+
+    let mut requests = 0u64;
+    let mut prompt_tokens = 0u64;
+    let mut compressed = 0u64;
+    // The largest prompt of each presentation, with the answer's allowance.
+    let mut largest: Vec<(u64, u64)> = Vec::new();
+    // Prompts are compressed to fit the smallest context window of the models.
+    let smallest_window = args.model.iter().filter_map(|model| known_context_window(args, model)).min();
+    for (label, presentation) in presentations(args) {
+        let answer_tokens = match presentation.style {
+            PromptStyle::AnswerOnly => Some(args.answer_only_tokens),
+            _ => args.max_completion_tokens,
+        }
+        .map_or(EXPECTED_ANSWER_TOKENS, u64::from);
+        let mut largest_prompt = 0;
+        for puzzle in &puzzles.puzzles {
+            for i in 0..puzzle.game_states.len() {
+                let (view, images) = match presentation.modality {
+                    Modality::Text => (StateView::Text(presentation.representation), 0),
+                    Modality::Image if args.sequences && puzzle.sequences.get(i).is_some() => (StateView::Sequence, 2),
+                    Modality::Image => (StateView::Image, 1),
+                };
+                let build = |rules: &str, representation: Representation| {
+                    let view = match view {
+                        StateView::Text(_) => StateView::Text(representation),
+                        view => view,
+                    };
+                    let prompt = build_prompt_with_rules(puzzle, &puzzles, i, view, presentation.style, rules);
+                    if args.confidence {
+                        format!("{}\n\n{}", prompt, confidence_request(puzzles.language))
+                    } else {
+                        prompt
+                    }
+                };
+                let budget = args
+                    .prompt_budget
+                    .or_else(|| smallest_window.map(|window| window.saturating_sub(answer_tokens + images * IMAGE_TOKENS)));
+                let prompt = match budget {
+                    Some(tokens) => {
+                        let budget = PromptBudget {
+                            tokens,
+                            priority: args.compression_priority.clone(),
+                        };
+                        fit_prompt(&budget, &puzzles.game_rule, presentation.representation, build)
+                    }
+                    None => FittedPrompt {
+                        text: build(&puzzles.game_rule, presentation.representation),
+                        compression: Vec::new(),
+                    },
+                };
+                let tokens = estimate_tokens(&prompt.text) + images * IMAGE_TOKENS;
+                largest_prompt = largest_prompt.max(tokens);
+                let mut notes = vec![format!("~{} tokens", tokens)];
+                if !prompt.compression.is_empty() {
+                    let steps: Vec<&str> = prompt.compression.iter().map(|step| step.label()).collect();
+                    notes.push(steps.join(", "));
+                    compressed += 1;
+                }
+                match label {
+                    Some(label) => println!("=== {} state {} [{}] ({}) ===", puzzle.id, i, label, notes.join("; ")),
+                    None => println!("=== {} state {} ({}) ===", puzzle.id, i, notes.join("; ")),
+                }
+                println!("{}\n", prompt.text);
+                requests += 1;
+                prompt_tokens += tokens;
+            }
+        }
+        largest.push((largest_prompt, answer_tokens));
+    }
+
+    let runs = (args.passes.max(1) * args.model.len() * compute_budgets(args).len()) as u64;
+    let pricing = Pricing {
+        input_per_million: args.input_price,
+        output_per_million: args.output_price,
+    };
+    println!("Dry run on {} ({} puzzles):", puzzles.name, puzzles.puzzles.len());
+    println!("  Models: {}", args.model.join(", "));
+    println!("  Requests: {} per model and pass, {} in total", requests, requests * runs);
+    println!(
+        "  Prompt tokens: ~{} per model and pass, ~{} in total",
+        prompt_tokens,
+        prompt_tokens * runs
+    );
+    println!(
+        "  Estimated prompt cost: ${:.4} (completion tokens not included)",
+        pricing.cost(prompt_tokens * runs, 0)
+    );
+    if presentations(args).iter().any(|(_, presentation)| presentation.modality == Modality::Image) {
+        println!("  Image tokens are estimated at {} per image", IMAGE_TOKENS);
+    }
+    let (largest_prompt, answer_tokens) = largest
+        .into_iter()
+        .max_by_key(|(prompt, answer)| prompt + answer)
+        .unwrap_or_default();
+    println!(
+        "  Largest prompt: ~{} tokens, plus {} for the answer",
+        largest_prompt, answer_tokens
+    );
+    if compressed > 0 {
+        println!("  Compressed prompts: {} of {}", compressed, requests);
+    }
+    for model in &args.model {
+        match known_context_window(args, model) {
+            Some(window) if largest_prompt + answer_tokens > window => {
+                println!("  Context window of {}: {} tokens, exceeded", model, window)
             }
+            Some(window) => println!("  Context window of {}: {} tokens", model, window),
+            None => println!("  Context window of {}: unknown; pass --context-window to check it", model),
         }
+    }
+    if args.judge_model.is_some() {
+        println!("  Judge requests are not included");
+    }
+    Ok(())
+}
+
+/// The prompt styles compared per model: full and answer-only with
+/// `--intuition`, else those of `--ablate-prompts`.
+fn prompt_ablation(args: &Args) -> Vec<PromptStyle> {
+    if args.intuition {
+        vec![PromptStyle::Full, PromptStyle::AnswerOnly]
+    } else {
+        args.ablate_prompts.clone()
+    }
+}
+
+/// The presentation of each solver run per model, labelled when ablating:
+/// one per `--ablate` variant or compared prompt style, or just the one
+/// from `--modality`, `--representation` and `--prompt-style`, labelled
+/// with the style unless it's the full one so it's scored apart from it.
+fn presentations(args: &Args) -> Vec<(Option<&'static str>, Presentation)> {
+    let single = Presentation {
+        modality: args.modality,
+        representation: args.representation,
+        style: args.prompt_style,
+    };
+    if !args.ablate.is_empty() {
+        args.ablate
+            .iter()
+            .map(|&variant| {
+                let (modality, representation) = variant.presentation();
+                let presentation = Presentation {
+                    modality,
+                    representation,
+                    ..single
+                };
+                (Some(variant.label()), presentation)
+            })
+            .collect()
+    } else if !prompt_ablation(args).is_empty() {
+        prompt_ablation(args)
+            .into_iter()
+            .map(|style| (Some(style.label()), Presentation { style, ..single }))
+            .collect()
+    } else {
+        let label = (single.style != PromptStyle::Full).then(|| single.style.label());
+        vec![(label, single)]
+    }
+}
+
+/// Whether every model runs once per point of a compute grid.
+fn sweeping(args: &Args) -> bool {
+    !args.sweep_completion_tokens.is_empty() || !args.sweep_effort.is_empty()
+}
+
+/// The compute budgets each presentation runs under: the sweep's grid, or
+/// just the one from `--max-completion-tokens` and `--reasoning-effort`.
+fn compute_budgets(args: &Args) -> Vec<ComputeBudget> {
+    if sweeping(args) {
+        ComputeBudget::grid(&args.sweep_completion_tokens, &args.sweep_effort)
+    } else {
+        vec![ComputeBudget {
+            max_completion_tokens: args.max_completion_tokens,
+            reasoning_effort: args.reasoning_effort,
+        }]
+    }
+}
+
+fn execute(args: &Args) -> Result<RunOutcome> {
+    let puzzles = load_puzzles(args)?;
+    let sign_key = args.sign_key.as_deref().map(SigningKey::load_from_file).transpose()?;
+
+    dotenvy::dotenv().ok();
+
+    let budget = Budget {
+        max_cost: args.max_cost,
+        max_tokens: args.max_tokens,
+        pricing: Pricing {
+            input_per_million: args.input_price,
+            output_per_million: args.output_price,
+        },
     };
+    let profile = args
+        .profile
+        .as_ref()
+        .map(|name| {
+            args.profiles
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown provider profile {}", name))
+        })
+        .transpose()?;
+    let telemetry = args
+        .otlp_endpoint
+        .as_ref()
+        .map(|endpoint| Arc::new(Telemetry::new(endpoint, "boardgamebench")));
+    if args.ablate.is_empty() && args.representation != Representation::Fen && args.modality == Modality::Image {
+        warn!("--representation only affects text prompts; it is ignored with --modality image");
+    }
+    let presentations = presentations(args);
+    if puzzles.answer_format.grammar == AnswerGrammar::Fen
+        && presentations.iter().any(|(_, presentation)| {
+            (presentation.modality, presentation.representation) == (Modality::Text, Representation::Fen)
+        })
+    {
+        warn!("this is a transcription collection; FEN text prompts contain the answer, so use board images or diagrams");
+    }
+    let shows_images = presentations.iter().any(|(_, presentation)| presentation.modality == Modality::Image);
+    if args.augment.is_some() && !shows_images {
+        warn!("--augment only affects board images; pass --modality image to use it");
+    }
+    if args.sequences && !shows_images {
+        warn!("--sequences only affects board images; pass --modality image to use it");
+    }
+    let ablating = !args.ablate.is_empty() || !prompt_ablation(args).is_empty() || sweeping(args);
+    if args.intuition && !args.ablate_prompts.is_empty() {
+        anyhow::bail!("--intuition already compares the full and answer-only prompts; drop --ablate-prompts");
+    }
+    if !args.ablate.is_empty() && !prompt_ablation(args).is_empty() {
+        anyhow::bail!("--ablate and --ablate-prompts can't be combined; run them separately");
+    }
+    if sweeping(args) && (!args.ablate.is_empty() || !prompt_ablation(args).is_empty()) {
+        anyhow::bail!("a compute sweep can't be combined with --ablate, --ablate-prompts or --intuition");
+    }
+    if ablating && args.blind {
+        anyhow::bail!("--ablate groups results by model, which --blind would reveal; run them separately");
+    }
+    let answer_scoring = args.answer_scoring.or(puzzles.answer_scoring).unwrap_or_default();
+    if answer_scoring == AnswerScoring::EngineGraded && args.engine.is_none() {
+        anyhow::bail!("engine-graded scoring needs a UCI engine; pass --engine");
+    }
+    if answer_scoring == AnswerScoring::JudgeModel && args.judge_model.is_none() {
+        anyhow::bail!("judge-model scoring needs a judge; pass --judge-model");
+    }
+    if puzzles.withheld_from.is_some() && (args.judge_model.is_some() || args.engine.is_some()) {
+        anyhow::bail!("judging and engine grading need the solutions, which this collection withholds");
+    }
+    let answers_output = args
+        .answers_output
+        .clone()
+        .or_else(|| puzzles.withheld_from.is_some().then(|| "answers.json".to_string()));
+    let models = match &args.solver_command {
+        Some(command_line) => vec![command_line.clone()],
+        None => args.model.clone(),
+    };
+    let compute_budgets = compute_budgets(args);
+    let mut solvers = Vec::new();
+    for model in &models {
+        for (label, presentation) in &presentations {
+            for compute in &compute_budgets {
+                let solver = match &args.solver_command {
+                    Some(command_line) => create_command_solver(command_line)?,
+                    None => create_solver(model, profile)?,
+                };
+                let mut solver = configure_solver(args, solver.with_budget(budget), presentation, compute);
+                let label = if sweeping(args) {
+                    Some(compute.label())
+                } else {
+                    label.map(str::to_string)
+                };
+                if let Some(label) = label {
+                    solver.name = format!("{} [{}]", solver.name, label);
+                }
+                if let Some(telemetry) = &telemetry {
+                    solver = solver.with_telemetry(telemetry.clone());
+                }
+                solvers.push(solver);
+            }
+        }
+    }
+
+    if args.context_check != ContextCheck::Off {
+        let mut selected = puzzles.clone();
+        selected.select(&selection(args));
+        for solver in &solvers {
+            let overflows = solver.check_context(&selected);
+            let Some(largest) = overflows.iter().max_by_key(|overflow| overflow.prompt_tokens) else {
+                continue;
+            };
+            let message = format!(
+                "{}: {} prompts, up to ~{} tokens ({} state {}), leave less than {} tokens for the answer in the {}-token context window",
+                solver.name,
+                overflows.len(),
+                largest.prompt_tokens,
+                largest.puzzle_id,
+                largest.state_index,
+                largest.answer_tokens,
+                largest.context_window
+            );
+            if args.context_check == ContextCheck::Fail {
+                anyhow::bail!("{}; shorten them or pass --context-check warn", message);
+            }
+            warn!("{}", message);
+        }
+    }
+
+    info!(threads = args.threads, passes = args.passes, "starting benchmark");
+    let mut builder = scoring_runner(args, puzzles, profile)?
+        .select(selection(args))
+        .concurrency(args.threads)
+        .passes(args.passes)
+        .interrupt_grace(Duration::from_secs_f64(args.interrupt_grace));
+    if let Some(telemetry) = &telemetry {
+        builder = builder.telemetry(telemetry.clone());
+    }
+    for key in &args.breakdown_by {
+        builder = builder.breakdown_by(key);
+    }
+    if let (Some(TrackingBackend::Mlflow), Some(uri)) = (args.tracking, &args.tracking_uri) {
+        builder = builder.tracker(Box::new(MlflowTracker::new(uri, &args.mlflow_experiment)));
+    }
+    #[cfg(feature = "serve")]
+    if let Some(addr) = &args.progress_addr {
+        builder = builder.tracker(Box::new(progress::ProgressServer::start(addr)?));
+    }
+    #[cfg(feature = "wandb")]
+    if let Some(project) = &args.wandb_project {
+        match WandbTracker::from_env(project) {
+            Ok(tracker) => builder = builder.tracker(Box::new(tracker)),
+            Err(e) => warn!(error = %e, "W&B logging disabled"),
+        }
+    }
+    if let Some(limit) = args.time_budget {
+        builder = builder.time_budget(TimeBudget {
+            limit: Duration::from_secs_f64(limit),
+            decay_half_life: args.time_decay.map(Duration::from_secs_f64),
+        });
+    }
+    if args.stop_precision.is_some() || args.stop_baseline.is_some() {
+        let mut early_stopping = EarlyStopping::default()
+            .with_confidence(args.stop_confidence)
+            .with_min_puzzles(args.stop_min_puzzles);
+        if let Some(precision) = args.stop_precision {
+            early_stopping = early_stopping.with_precision(precision);
+        }
+        if let Some(path) = &args.stop_baseline {
+            let baseline = BenchmarkResult::load_from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
+            early_stopping = early_stopping.with_baseline(baseline.average_score);
+        }
+        if !args.shuffle && args.sample.is_none() {
+            warn!("stopping early on puzzles in file order; pass --shuffle for a fair estimate");
+        }
+        builder = builder.early_stopping(early_stopping);
+    }
+    let runner = builder.build()?;
+    info!(
+        puzzles = runner.puzzles.puzzles.len(),
+        collection = %runner.puzzles.name,
+        "loaded puzzles"
+    );
+
+    interrupt_on_ctrl_c(runner.interrupt());
+    runner.start_tracking(&serde_json::to_value(args)?);
+
+    if solvers.len() > 1 || ablating {
+        let solver_refs: Vec<&Solver> = solvers.iter().collect();
+        let mut report = runner.run_all(&solver_refs);
+        if args.blind {
+            blind_results(&mut report.results, &args.blind_key)?;
+        }
+        seal_results(&mut report.results, sign_key.as_ref());
+        if let Some(path) = &answers_output {
+            write_answer_sheets(&report.results, path)?;
+        }
+
+        for results in &report.results {
+            print_results(results, args.passes);
+        }
+
+        if !args.ablate.is_empty() {
+            let labels: Vec<String> = args.ablate.iter().map(|variant| variant.label().to_string()).collect();
+            let ablation = RepresentationAblation::new(&models, &labels, &report.results);
+            print_ablation("Representation Ablation", &ablation.representations, &ablation.models);
+            report.representation_ablation = Some(ablation);
+        }
+        if !prompt_ablation(args).is_empty() {
+            let labels: Vec<String> = prompt_ablation(args).iter().map(|style| style.label().to_string()).collect();
+            let ablation = PromptAblation::new(&models, &labels, &report.results);
+            let title = if args.intuition { "Intuition vs Deliberation" } else { "Prompt Ablation" };
+            print_ablation(title, &ablation.prompt_styles, &ablation.models);
+            report.prompt_ablation = Some(ablation);
+        }
+        if sweeping(args) {
+            let sweep = ComputeSweep::new(&models, &compute_budgets, &report.results);
+            print_compute_sweep(&sweep);
+            report.compute_sweep = Some(sweep);
+        }
+
+        println!("\nModel Comparison:");
+        for (rank, results) in report.ranking().iter().enumerate() {
+            println!(
+                "  {}. {}: {:.2}% ({}/{})",
+                rank + 1,
+                results.solver_name,
+                results.average_score * 100.0,
+                results.total_score,
+                results.max_possible_score
+            );
+        }
+
+        match frontier(&report.results) {
+            Ok(frontier) => {
+                print_frontier(&frontier);
+                report.frontier = Some(frontier);
+            }
+            Err(e) => warn!(error = %e, "could not compute the cost frontier"),
+        }
+
+        let output_path = args
+            .output
+            .clone()
+            .unwrap_or_else(|| "benchmark_comparison.json".to_string());
+        if let Err(e) = runner.export_comparison(&report, &output_path) {
+            warn!(error = %e, "could not export comparison");
+        } else {
+            info!(path = %output_path, "comparison exported");
+        }
+        runner.end_tracking();
+        return Ok(RunOutcome {
+            results: report.results,
+            output_path,
+        });
+    }
+
+    let solver = &solvers[0];
+    let mut results = runner.run(solver);
+    if args.blind {
+        blind_results(std::slice::from_mut(&mut results), &args.blind_key)?;
+    }
+    seal_results(std::slice::from_mut(&mut results), sign_key.as_ref());
+    if let Some(path) = &answers_output {
+        write_answer_sheets(std::slice::from_ref(&results), path)?;
+    }
 
-    println!("Using {} threads for parallel evaluation", args.threads);
-    println!("Running {} passes for each test case", args.passes);
-    let runner = BenchmarkRunner::new(puzzles);
+    print_results(&results, args.passes);
 
-    let results = if args.passes > 1 {
-        runner.run_benchmark_multiple_passes(solver.as_ref(), args.threads, args.passes)
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| "benchmark_results.json".to_string());
+    if let Err(e) = runner.export_results(&results, &output_path) {
+        warn!(error = %e, "could not export results");
     } else {
-        runner.run_benchmark_parallel(solver.as_ref(), args.threads)
+        info!(path = %output_path, "results exported");
+    }
+    runner.end_tracking();
+
+    Ok(RunOutcome {
+        results: vec![results],
+        output_path,
+    })
+}
+
+/// `solver` set up to show puzzles as `presentation` within `compute`, with
+/// the run's other prompt and sampling options.
+fn configure_solver(args: &Args, solver: Solver, presentation: &Presentation, compute: &ComputeBudget) -> Solver {
+    let mut solver = solver
+        .with_modality(presentation.modality)
+        .with_representation(presentation.representation)
+        .with_prompt_style(presentation.style)
+        .with_sequences(args.sequences)
+        .with_confidence(args.confidence);
+    if presentation.style == PromptStyle::AnswerOnly {
+        solver = solver.with_max_completion_tokens(args.answer_only_tokens);
+    }
+    if let Some(max_tokens) = compute.max_completion_tokens {
+        solver = solver.with_max_completion_tokens(max_tokens);
+    }
+    if let Some(effort) = compute.reasoning_effort {
+        solver = solver.with_reasoning_effort(effort);
+    }
+    if let Some(tokens) = args.context_window {
+        solver = solver.with_context_window(tokens);
+    }
+    if let Some(tokens) = args.prompt_budget {
+        solver = solver.with_prompt_budget(tokens);
+    }
+    solver = solver.with_compression_priority(args.compression_priority.clone());
+    if let Some(augmentation) = &args.augment {
+        solver = solver.with_augmentation(augmentation.clone());
+    }
+    if let Some(temperature) = args.temperature {
+        solver = solver.with_temperature(temperature);
+    }
+    solver
+}
+
+/// A runner for `puzzles` that scores answers as the run's options say.
+fn scoring_runner(
+    args: &Args,
+    puzzles: PuzzleCollection,
+    profile: Option<&ProviderProfile>,
+) -> Result<BenchmarkRunnerBuilder> {
+    let mut builder = BenchmarkRunner::builder()
+        .puzzles(puzzles)
+        .scorer(args.scorer)
+        .sequential(args.sequential)
+        .retry(RetryPolicy {
+            max_retries: args.retries,
+            ..RetryPolicy::default()
+        });
+    if let Some(path) = &args.solution_key {
+        builder = builder.solution_key(SolutionKey::load_from_file(path)?);
+    }
+    if let Some(judge_model) = &args.judge_model {
+        builder = builder.judge(Judge::new(create_solver(judge_model, profile)?));
+    }
+    if let Some(answer_scoring) = args.answer_scoring {
+        builder = builder.answer_scoring(answer_scoring);
+    }
+    if let Some(engine) = &args.engine {
+        builder = builder.engine(UciEngine::from_command_line(engine, args.engine_depth)?);
+    }
+    Ok(builder)
+}
+
+/// Interrupt the run on the first Ctrl-C so partial results still get
+/// written; a second Ctrl-C kills the command solvers still running and
+/// exits immediately.
+fn interrupt_on_ctrl_c(interrupt: Interrupt) {
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build signal runtime");
+        runtime.block_on(async {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            warn!("interrupted; finishing puzzles in flight, press Ctrl-C again to abort");
+            interrupt.trigger();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                kill_running();
+                std::process::exit(130);
+            }
+        });
+    });
+}
+
+fn create_command_solver(command_line: &str) -> Result<Solver> {
+    info!(command = command_line, "creating command solver");
+    let solver = Solver::from_command(CommandSolver::from_command_line(command_line)?);
+    solver
+        .test_api_reachability()
+        .map_err(|e| anyhow::anyhow!("Solver command test failed: {}", e))?;
+    Ok(solver)
+}
+
+fn create_solver(model: &str, profile: Option<&ProviderProfile>) -> Result<Solver> {
+    info!(model, "creating solver");
+    let solver = match profile {
+        Some(profile) => Solver::from_profile(model.to_string(), profile),
+        None => Solver::new(model.to_string()),
+    };
+    match solver {
+        Ok(solver) => {
+            // Test API reachability before running benchmark
+            info!("testing API reachability");
+            match solver.test_api_reachability() {
+                Ok(response) => {
+                    info!(%response, "API test successful");
+                    Ok(solver)
+                }
+                Err(e) => {
+                    error!(error = %e, "API test failed; check the provider profile or the OPENAI_API_KEY and OPENAI_BASE_URL environment variables");
+                    Err(anyhow::anyhow!("API reachability test failed: {}", e))
+                }
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "failed to create solver");
+            Err(anyhow::anyhow!("Failed to create solver: {}", e))
+        }
+    }
+}
+
+fn print_ablation(title: &str, labels: &[String], rows: &[AblationRow]) {
+    println!("\n{}:", title);
+    let width = labels.iter().map(String::len).max().unwrap_or(0).max(8);
+    println!(
+        "  {:<30} {} {:>8}",
+        "Model",
+        labels
+            .iter()
+            .map(|label| format!("{:>width$}", label))
+            .collect::<Vec<_>>()
+            .join(" "),
+        "spread"
+    );
+    for row in rows {
+        println!(
+            "  {:<30} {} {:>7.2}%",
+            row.model,
+            row.average_scores
+                .iter()
+                .map(|score| format!("{:>w$.2}%", score * 100.0, w = width - 1))
+                .collect::<Vec<_>>()
+                .join(" "),
+            row.spread * 100.0
+        );
+    }
+}
+
+fn print_compute_sweep(sweep: &ComputeSweep) {
+    println!("\nCompute Sweep:");
+    let width = sweep.budgets.iter().map(|budget| budget.label().len()).max().unwrap_or(0).max(6);
+    for row in &sweep.models {
+        println!("  {}", row.model);
+        println!("    {:<width$} {:>8} {:>12} {:>10}", "Budget", "score", "completion", "cost");
+        for (budget, point) in sweep.budgets.iter().zip(&row.points) {
+            println!(
+                "    {:<width$} {:>7.2}% {:>12} {:>10}",
+                budget.label(),
+                point.average_score * 100.0,
+                point.completion_tokens,
+                format!("${:.4}", point.cost)
+            );
+        }
+    }
+}
+
+/// One line each for request and puzzle latency percentiles, where timed.
+fn print_latency(latency: &LatencySummary, indent: &str) {
+    let seconds = |ms: u64| format!("{:.2}s", ms as f64 / 1000.0);
+    let line = |percentiles: &LatencyPercentiles| {
+        format!(
+            "{} / {} / {} (max {}, n={})",
+            seconds(percentiles.p50_ms),
+            seconds(percentiles.p90_ms),
+            seconds(percentiles.p99_ms),
+            seconds(percentiles.max_ms),
+            percentiles.count
+        )
     };
+    if let Some(requests) = &latency.requests {
+        println!("{}Per request: {}", indent, line(requests));
+    }
+    if let Some(puzzles) = &latency.puzzles {
+        println!("{}Per puzzle:  {}", indent, line(puzzles));
+    }
+}
 
+fn print_results(results: &BenchmarkResult, passes: usize) {
     println!("\nBenchmark Results:");
     println!("Benchmark: {}", results.benchmark_name);
     println!(
@@ -82,16 +3200,120 @@ fn main() -> Result<()> {
         results.total_score, results.max_possible_score
     );
     println!("Scoring average: {:.2}%", results.average_score * 100.0);
+    if let Some(usage) = &results.usage {
+        println!(
+            "Usage: {} requests, {} prompt + {} completion tokens, ${:.4}",
+            usage.requests, usage.prompt_tokens, usage.completion_tokens, usage.cost
+        );
+    }
+    if let Some(reasoning_score) = results.reasoning_score {
+        println!("Reasoning score (judge): {:.2}%", reasoning_score * 100.0);
+    }
+    if let Some(calibration) = &results.confidence {
+        println!(
+            "Confidence: {:.2}% stated vs {:.2}% correct over {} answers ({} without one); Brier {:.4}, ECE {:.4}",
+            calibration.mean_confidence * 100.0,
+            calibration.accuracy * 100.0,
+            calibration.answers,
+            calibration.missing,
+            calibration.brier_score,
+            calibration.expected_calibration_error
+        );
+        for bin in &calibration.bins {
+            println!(
+                "  {:>3.0}-{:<3.0}%: {:>4} answers, {:.2}% stated, {:.2}% correct",
+                bin.low * 100.0,
+                bin.high * 100.0,
+                bin.answers,
+                bin.mean_confidence * 100.0,
+                bin.accuracy * 100.0
+            );
+        }
+    }
+    if results.budget_exceeded {
+        println!("⚠️  Budget exceeded: run stopped early, results are partial");
+    }
+    if results.interrupted {
+        println!("⚠️  Interrupted: run stopped early, results are partial");
+    }
+    if !results.unanswered_puzzles.is_empty() {
+        println!(
+            "⚠️  Unanswered: no reply for {} puzzles ({}), results are partial",
+            results.unanswered_puzzles.len(),
+            results.unanswered_puzzles.join(", ")
+        );
+    }
+    if let Some(canaries) = &results.canaries {
+        if canaries.leaked() {
+            println!(
+                "⚠️  Likely training-data leakage: reproduced {} of {} canary answers ({})",
+                canaries.reproduced.len(),
+                canaries.canaries,
+                canaries.reproduced.join(", ")
+            );
+        } else {
+            println!("Canaries: none of {} reproduced", canaries.canaries);
+        }
+    }
+    if let Some(stop) = &results.early_stop {
+        let reason = match (stop.reason, stop.baseline) {
+            (StopReason::Baseline, Some(baseline)) => format!("differs from baseline {:.2}%", baseline * 100.0),
+            _ => "precise enough".to_string(),
+        };
+        println!(
+            "Stopped early after {} puzzles: {:.2}% ± {:.2}% at {:.0}% confidence, {}",
+            stop.evaluated,
+            stop.score * 100.0,
+            stop.half_width * 100.0,
+            stop.confidence * 100.0,
+            reason
+        );
+    }
+    if results.modality == Modality::Image {
+        println!("Modality: board images");
+    } else {
+        match results.representation {
+            Representation::Fen => {}
+            Representation::Pgn => println!("Representation: PGN of the game so far"),
+            representation => println!("Representation: {:?} board diagrams", representation),
+        }
+    }
+    if results.prompt_style != PromptStyle::Full {
+        println!("Prompt style: {}", results.prompt_style.label());
+    }
+    if !results.prompt_language.is_english() {
+        println!("Prompt language: {:?}", results.prompt_language);
+    }
 
     // Display pass@1 and pass@n results if multiple passes were run
-    if args.passes > 1 {
-        if let Some(pass_results) = &results.pass_results {
-            println!("\nResults:");
-            println!("  Pass@1: {:.2}%", pass_results.pass_at_1 * 100.0);
-            println!("  Pass@{}: {:.2}%", args.passes, pass_results.pass_at_n * 100.0);
+    if passes > 1
+        && let Some(pass_results) = &results.pass_results
+    {
+        println!("\nResults:");
+        println!("  Pass@1: {:.2}%", pass_results.pass_at_1 * 100.0);
+        println!("  Pass@{}: {:.2}%", passes, pass_results.pass_at_n * 100.0);
+        println!(
+            "  Score std dev across passes: {:.2}%",
+            pass_results.score_std_dev * 100.0
+        );
+    }
+
+    if !results.time_curve.is_empty() {
+        println!("\nScore vs Time Budget:");
+        for point in &results.time_curve {
+            println!(
+                "  {:>4}s: {:.2}%",
+                point.budget_ms / 1000,
+                point.average_score * 100.0
+            );
         }
     }
 
+    if let Some(latency) = &results.latency {
+        println!("\nLatency (p50 / p90 / p99):");
+        print_latency(latency, "  ");
+    }
+
     println!("\nGame Type Breakdown:");
     for game_type in &results.game_type_breakdown {
         println!(
@@ -100,6 +3322,82 @@ fn main() -> Result<()> {
             game_type.average_score * 100.0,
             game_type.count
         );
+        if results.game_type_breakdown.len() > 1
+            && let Some(latency) = &game_type.latency
+        {
+            print_latency(latency, "    ");
+        }
+    }
+
+    let groups = std::iter::once(("Tag", &results.tag_breakdown))
+        .filter(|(_, scores)| !scores.is_empty())
+        .chain(results.metadata_breakdown.iter().map(|(key, scores)| (key.as_str(), scores)));
+    for (title, scores) in groups {
+        println!("\n{} Breakdown:", title);
+        for score in scores {
+            println!(
+                "  {}: {:.2}% ({} puzzles)",
+                score.group,
+                score.average_score * 100.0,
+                score.count
+            );
+        }
+    }
+
+    let stopped = results.puzzle_scores.iter().filter(|score| score.skipped_states > 0).count();
+    if stopped > 0 {
+        let skipped: usize = results.puzzle_scores.iter().map(|score| score.skipped_states).sum();
+        println!(
+            "\nSequential scoring: stopped early on {} puzzles, skipping {} states",
+            stopped, skipped
+        );
+    }
+
+    let compressed = results.puzzle_scores.iter().filter(|score| !score.compression.is_empty()).count();
+    if compressed > 0 {
+        println!("\nPrompt Compression ({} of {} puzzles):", compressed, results.puzzle_scores.len());
+        for compression in [
+            Compression::DroppedExamples,
+            Compression::SummarizedRules,
+            Compression::TruncatedRules,
+            Compression::PlainState,
+        ] {
+            let puzzles = results
+                .puzzle_scores
+                .iter()
+                .filter(|score| score.compression.contains(&compression))
+                .count();
+            if puzzles > 0 {
+                println!("  {}: {} puzzles", compression.label(), puzzles);
+            }
+        }
+    }
+
+    if !results.skill_profile.is_empty() {
+        println!("\nSkill Profile:");
+        for skill in &results.skill_profile {
+            match skill.average_score {
+                Some(score) => println!("  {}: {:.2}% ({} puzzles)", skill.axis.label(), score * 100.0, skill.puzzles),
+                None => println!("  {}: no puzzles", skill.axis.label()),
+            }
+        }
+    }
+
+    if let Some(breakdown) = &results.render_style_breakdown {
+        for (title, styles) in [
+            ("Board Theme", &breakdown.board_themes),
+            ("Piece Style", &breakdown.piece_styles),
+        ] {
+            println!("\n{} Breakdown:", title);
+            for style in styles {
+                println!(
+                    "  {}: {:.2}% ({} puzzles)",
+                    style.style,
+                    style.average_score * 100.0,
+                    style.count
+                );
+            }
+        }
     }
 
     println!("\nIndividual Puzzle Results:");
@@ -114,12 +3412,4 @@ fn main() -> Result<()> {
             status, score.puzzle_id, score.score, score.max_possible_score
         );
     }
-
-    if let Err(e) = runner.export_results(&results, "benchmark_results.json") {
-        eprintln!("Warning: Could not export results: {}", e);
-    } else {
-        println!("\nResults exported to benchmark_results.json");
-    }
-
-    Ok(())
 }
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use boardgamebench::puzzle::PuzzleScore;
+use boardgamebench::results::BenchmarkResult;
+use boardgamebench::tracking::{RunProgress, Tracker};
+
+/// Events a slow subscriber may fall behind by before it misses some.
+const EVENT_BUFFER: usize = 1024;
+
+/// One run event as sent to subscribers: the SSE event name and its JSON
+/// data.
+#[derive(Clone)]
+struct ProgressEvent {
+    kind: &'static str,
+    data: Value,
+}
+
+/// Shared state of the progress server.
+struct ProgressState {
+    events: broadcast::Sender<ProgressEvent>,
+    /// The latest progress of each solver, for subscribers joining mid-run.
+    latest: Mutex<BTreeMap<String, RunProgress>>,
+}
+
+#[derive(Serialize)]
+struct PuzzleEvent<'a> {
+    puzzle_id: &'a str,
+    score: f64,
+    max_possible_score: f64,
+    elapsed_ms: u64,
+    progress: &'a RunProgress,
+    accuracy: f64,
+}
+
+/// Streams run progress to dashboards as server-sent events. Serves:
+///
+/// - `GET /events`: an event stream; each subscriber first gets a
+///   `progress` event per solver so far, then `start` (the run
+///   configuration), `puzzle` (after every scored puzzle, with the running
+///   totals), `finish` (each solver's summary) and `end`
+/// - `GET /progress`: the latest totals of every solver as JSON
+pub struct ProgressServer {
+    state: Arc<ProgressState>,
+}
+
+impl ProgressServer {
+    /// Start serving on `addr` from a background thread, for as long as the
+    /// process runs.
+    pub fn start(addr: &str) -> Result<Self> {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let state = Arc::new(ProgressState {
+            events,
+            latest: Mutex::new(BTreeMap::new()),
+        });
+        let app = Router::new()
+            .route("/events", get(subscribe))
+            .route("/progress", get(latest_progress))
+            .with_state(state.clone());
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let listener = runtime.block_on(tokio::net::TcpListener::bind(addr))?;
+        info!(addr = %listener.local_addr()?, "streaming run progress at /events");
+        std::thread::spawn(move || {
+            if let Err(e) = runtime.block_on(async { axum::serve(listener, app).await }) {
+                warn!(error = %e, "progress server stopped");
+            }
+        });
+        Ok(Self { state })
+    }
+
+    fn send(&self, kind: &'static str, data: Value) {
+        // Sending only fails when nobody is subscribed.
+        let _ = self.state.events.send(ProgressEvent { kind, data });
+    }
+}
+
+impl Tracker for ProgressServer {
+    fn start(&self, config: &Value) {
+        self.send("start", config.clone());
+    }
+
+    fn puzzle_finished(&self, score: &PuzzleScore, progress: &RunProgress) {
+        self.state
+            .latest
+            .lock()
+            .unwrap()
+            .insert(progress.solver_name.clone(), progress.clone());
+        let event = PuzzleEvent {
+            puzzle_id: &score.puzzle_id,
+            score: score.score,
+            max_possible_score: score.max_possible_score,
+            elapsed_ms: score.elapsed_ms,
+            progress,
+            accuracy: progress.accuracy(),
+        };
+        self.send("puzzle", serde_json::to_value(event).unwrap_or_default());
+    }
+
+    fn finish(&self, result: &BenchmarkResult) {
+        self.send(
+            "finish",
+            json!({
+                "solver_name": result.solver_name,
+                "total_puzzles": result.total_puzzles,
+                "average_score": result.average_score,
+                "interrupted": result.interrupted,
+            }),
+        );
+    }
+
+    fn end(&self) {
+        self.send("end", json!({}));
+    }
+}
+
+async fn subscribe(
+    State(state): State<Arc<ProgressState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.events.subscribe();
+    let snapshot: Vec<ProgressEvent> = state
+        .latest
+        .lock()
+        .unwrap()
+        .values()
+        .map(|progress| ProgressEvent {
+            kind: "progress",
+            data: serde_json::to_value(progress).unwrap_or_default(),
+        })
+        .collect();
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "progress subscriber fell behind");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::iter(snapshot)
+        .chain(live)
+        .map(|event| Ok(Event::default().event(event.kind).data(event.data.to_string())));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn latest_progress(State(state): State<Arc<ProgressState>>) -> Json<BTreeMap<String, RunProgress>> {
+    Json(state.latest.lock().unwrap().clone())
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use boardgamebench::holdout::AggregateScore;
+use boardgamebench::prompt::build_prompt;
+use boardgamebench::puzzle::{Puzzle, PuzzleCollection, PuzzleGoal, PuzzleScore, Scorer};
+use boardgamebench::results::BenchmarkResult;
+
+/// How many scored submissions `GET /submissions/{id}` can return; older
+/// ones are dropped.
+const KEPT_SUBMISSIONS: usize = 1000;
+
+/// Shared state of the `serve` API.
+struct ServerState {
+    collection: PuzzleCollection,
+    scorer: Scorer,
+    /// Only report each submission's total score.
+    aggregate_only: bool,
+    /// How many submissions one client address may make an hour when
+    /// `aggregate_only`.
+    submissions_per_hour: usize,
+    submissions: Mutex<Submissions>,
+    /// When each client address submitted within the last hour.
+    clients: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+/// The latest scored submissions and their ids.
+#[derive(Default)]
+struct Submissions {
+    next_id: usize,
+    kept: VecDeque<(usize, BenchmarkResult)>,
+}
+
+impl ServerState {
+    /// Count a submission from `client`, unless it already made its
+    /// `submissions_per_hour`.
+    fn admit(&self, client: IpAddr) -> Result<(), ApiError> {
+        if !self.aggregate_only {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let hour = Duration::from_secs(3600);
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|_, times| {
+            while times.front().is_some_and(|time| now.duration_since(*time) >= hour) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+        let times = clients.entry(client).or_default();
+        if times.len() >= self.submissions_per_hour {
+            return Err(api_error(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("at most {} submissions an hour are scored per client", self.submissions_per_hour),
+            ));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// A puzzle as served to solvers: everything but the solutions.
+#[derive(Serialize)]
+struct PublicPuzzle<'a> {
+    id: &'a str,
+    description: &'a str,
+    game_type: &'a str,
+    game_states: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    goal: Option<&'a PuzzleGoal>,
+    /// The prompt a benchmark run would send for each state.
+    prompts: Vec<String>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    images: &'a [String],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    sequences: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct Submission {
+    solver_name: String,
+    #[serde(default)]
+    solver_description: String,
+    /// Extracted answers per puzzle id, one per state.
+    #[serde(default)]
+    answers: HashMap<String, Vec<String>>,
+    /// Raw model responses per puzzle id, run through answer extraction.
+    #[serde(default)]
+    responses: HashMap<String, Vec<String>>,
+}
+
+pub type ApiError = (StatusCode, Json<serde_json::Value>);
+
+pub fn api_error(status: StatusCode, message: impl ToString) -> ApiError {
+    (status, Json(json!({ "error": message.to_string() })))
+}
+
+fn public_puzzle<'a>(collection: &'a PuzzleCollection, puzzle: &'a Puzzle) -> PublicPuzzle<'a> {
+    PublicPuzzle {
+        id: &puzzle.id,
+        description: &puzzle.description,
+        game_type: collection.game_type_of(puzzle),
+        game_states: &puzzle.game_states,
+        goal: puzzle.goal.as_ref(),
+        prompts: (0..puzzle.game_states.len())
+            .map(|i| build_prompt(puzzle, collection, i))
+            .collect(),
+        images: &puzzle.images,
+        sequences: &puzzle.sequences,
+    }
+}
+
+async fn list_puzzles(State(state): State<Arc<ServerState>>) -> Json<serde_json::Value> {
+    let collection = &state.collection;
+    let puzzles: Vec<PublicPuzzle> = collection
+        .puzzles
+        .iter()
+        .map(|puzzle| public_puzzle(collection, puzzle))
+        .collect();
+    Json(json!({
+        "name": collection.name,
+        "description": collection.description,
+        "game_type": collection.game_type,
+        "goal": collection.goal,
+        "answer_notation": collection.answer_format.grammar.description(),
+        "puzzles": puzzles,
+    }))
+}
+
+async fn get_puzzle(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let collection = &state.collection;
+    let puzzle = collection
+        .puzzles
+        .iter()
+        .find(|puzzle| puzzle.id == id)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, format!("no puzzle with id {}", id)))?;
+    Ok(Json(json!(public_puzzle(collection, puzzle))))
+}
+
+/// Score a submission against every puzzle in the collection; puzzles it
+/// doesn't answer score zero.
+async fn submit(
+    State(state): State<Arc<ServerState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(submission): Json<Submission>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let collection = &state.collection;
+    let unknown: Vec<&String> = submission
+        .answers
+        .keys()
+        .chain(submission.responses.keys())
+        .filter(|id| !collection.puzzles.iter().any(|p| &p.id == *id))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            format!("unknown puzzle ids: {:?}", unknown),
+        ));
+    }
+    state.admit(client.ip())?;
+
+    let puzzle_scores: Vec<PuzzleScore> = collection
+        .puzzles
+        .iter()
+        .map(|puzzle| {
+            if let Some(responses) = submission.responses.get(&puzzle.id) {
+                state.scorer.score_responses(puzzle, collection, responses)
+            } else {
+                let answers = submission.answers.get(&puzzle.id).cloned().unwrap_or_default();
+                state.scorer.score(puzzle, collection, &answers)
+            }
+        })
+        .collect();
+    let result = BenchmarkResult::summarize(
+        format!("{} on {} (submitted)", submission.solver_name, collection.name),
+        &submission.solver_name,
+        &submission.solver_description,
+        collection,
+        puzzle_scores,
+    );
+
+    let id = {
+        let mut submissions = state.submissions.lock().unwrap();
+        let id = submissions.next_id;
+        submissions.next_id += 1;
+        // Results of an aggregate-only server are never fetched again.
+        if !state.aggregate_only {
+            submissions.kept.push_back((id, result.clone()));
+            if submissions.kept.len() > KEPT_SUBMISSIONS {
+                submissions.kept.pop_front();
+            }
+        }
+        id
+    };
+    info!(id, solver = %result.solver_name, score = result.average_score, "scored submission");
+    if state.aggregate_only {
+        let answered: HashSet<&String> = submission.answers.keys().chain(submission.responses.keys()).collect();
+        let score = AggregateScore {
+            solver_name: result.solver_name,
+            collection_name: collection.name.clone(),
+            total_puzzles: result.total_puzzles,
+            answered: answered.len(),
+            total_score: result.total_score,
+            max_possible_score: result.max_possible_score,
+            average_score: result.average_score,
+        };
+        return Ok(Json(json!({ "id": id, "score": score })));
+    }
+    Ok(Json(json!({ "id": id, "result": result })))
+}
+
+async fn get_submission(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<usize>,
+) -> Result<Json<BenchmarkResult>, ApiError> {
+    if state.aggregate_only {
+        return Err(api_error(StatusCode::FORBIDDEN, "per-puzzle results of this collection are not shared"));
+    }
+    state
+        .submissions
+        .lock()
+        .unwrap()
+        .kept
+        .iter()
+        .find(|(kept, _)| *kept == id)
+        .map(|(_, result)| Json(result.clone()))
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, format!("no submission with id {}", id)))
+}
+
+/// Serve `collection` over HTTP until interrupted:
+///
+/// - `GET /puzzles`, `GET /puzzles/{id}`: puzzles and prompts, without solutions
+/// - `POST /submissions`: score `{"solver_name", "answers" | "responses"}`
+/// - `GET /submissions/{id}`: one of the last `KEPT_SUBMISSIONS` scored
+///   submissions
+///
+/// With `aggregate_only`, submissions are answered with their total score,
+/// each client address may make `submissions_per_hour` of them, and
+/// `GET /submissions/{id}` is refused.
+pub fn serve(
+    collection: PuzzleCollection,
+    scorer: Scorer,
+    aggregate_only: bool,
+    submissions_per_hour: usize,
+    addr: &str,
+) -> Result<()> {
+    let state = Arc::new(ServerState {
+        collection,
+        scorer,
+        aggregate_only,
+        submissions_per_hour,
+        submissions: Mutex::new(Submissions::default()),
+        clients: Mutex::new(HashMap::new()),
+    });
+    let app = Router::new()
+        .route("/puzzles", get(list_puzzles))
+        .route("/puzzles/:id", get(get_puzzle))
+        .route("/submissions", post(submit))
+        .route("/submissions/:id", get(get_submission))
+        .with_state(state);
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!(addr = %listener.local_addr()?, "serving puzzles");
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+        Ok(())
+    })
+}
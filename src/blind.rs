@@ -0,0 +1,105 @@
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::results::BenchmarkResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlindEntry {
+    pub solver_name: String,
+    pub solver_description: String,
+    /// The `model` solver parameter of the run metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<serde_json::Value>,
+    /// The `openai_base_url` of the run metadata's environment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+/// Mapping from anonymous labels ("model-A") back to the real solvers,
+/// kept apart from the blinded reports until review is finished.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlindKey {
+    pub labels: BTreeMap<String, BlindEntry>,
+}
+
+impl BlindKey {
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Replace solver names in `results` with labels assigned in random order,
+/// and take the model and API base URL out of their run metadata, returning
+/// the key needed to undo it.
+pub fn blind(results: &mut [BenchmarkResult]) -> BlindKey {
+    let mut order: Vec<usize> = (0..results.len()).collect();
+    order.shuffle(&mut rand::rng());
+
+    let mut key = BlindKey::default();
+    for (n, &i) in order.iter().enumerate() {
+        let label = label(n);
+        let result = &mut results[i];
+        result.benchmark_name = result.benchmark_name.replace(&result.solver_name, &label);
+        let (model, base_url) = match result.metadata.as_mut() {
+            Some(metadata) => (
+                metadata.solver_parameters.remove("model"),
+                metadata.environment.remove("openai_base_url"),
+            ),
+            None => (None, None),
+        };
+        key.labels.insert(
+            label.clone(),
+            BlindEntry {
+                solver_name: std::mem::replace(&mut result.solver_name, label),
+                solver_description: std::mem::replace(
+                    &mut result.solver_description,
+                    "(blinded)".to_string(),
+                ),
+                model,
+                base_url,
+            },
+        );
+    }
+    key
+}
+
+/// Restore the real solver names in results blinded with `key`.
+pub fn unblind(results: &mut [BenchmarkResult], key: &BlindKey) {
+    for result in results {
+        if let Some(entry) = key.labels.get(&result.solver_name) {
+            result.benchmark_name = result
+                .benchmark_name
+                .replace(&result.solver_name, &entry.solver_name);
+            result.solver_name = entry.solver_name.clone();
+            result.solver_description = entry.solver_description.clone();
+            if let Some(metadata) = &mut result.metadata {
+                if let Some(model) = &entry.model {
+                    metadata.solver_parameters.insert("model".to_string(), model.clone());
+                }
+                if let Some(base_url) = &entry.base_url {
+                    metadata.environment.insert("openai_base_url".to_string(), base_url.clone());
+                }
+            }
+        }
+    }
+}
+
+/// "model-A", ..., "model-Z", "model-AA", ...
+fn label(mut n: usize) -> String {
+    let mut suffix = String::new();
+    loop {
+        suffix.insert(0, (b'A' + (n % 26) as u8) as char);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    format!("model-{}", suffix)
+}
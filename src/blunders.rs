@@ -0,0 +1,83 @@
+//! Move quality of played games, annotated by an engine: average
+//! centipawn loss and counts of inaccuracies, mistakes and blunders.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::engine::UciEngine;
+use crate::game::chess;
+use crate::play::{GameRecord, PlayError};
+
+/// Losses are capped here, so a missed mate counts as a lost queen or so
+/// rather than swamping the average.
+pub const MAX_LOSS: u32 = 1000;
+/// A move losing at least this many centipawns is an inaccuracy.
+pub const INACCURACY: u32 = 50;
+/// A move losing at least this many centipawns is a mistake.
+pub const MISTAKE: u32 = 100;
+/// A move losing at least this many centipawns is a blunder.
+pub const BLUNDER: u32 = 300;
+
+/// A player's moves over one or more games, graded by how many centipawns
+/// each gave away compared to the engine's best move. Each move is counted
+/// in the worst class it reaches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MoveQuality {
+    pub moves: usize,
+    /// Average centipawn loss per move.
+    pub average_loss: f64,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+impl MoveQuality {
+    pub fn from_losses(losses: &[u32]) -> Self {
+        let count = |low: u32, high: u32| losses.iter().filter(|&&loss| loss >= low && loss < high).count();
+        Self {
+            moves: losses.len(),
+            average_loss: if losses.is_empty() {
+                0.0
+            } else {
+                losses.iter().map(|&loss| loss as f64).sum::<f64>() / losses.len() as f64
+            },
+            inaccuracies: count(INACCURACY, MISTAKE),
+            mistakes: count(MISTAKE, BLUNDER),
+            blunders: count(BLUNDER, u32::MAX),
+        }
+    }
+}
+
+/// The centipawns each move of `game` gives away according to `engine`,
+/// capped at `MAX_LOSS`, for `GameRecord::losses`.
+pub async fn annotate_game(engine: &UciEngine, game: &GameRecord) -> Result<Vec<u32>, PlayError> {
+    let mut fen = game.start.clone();
+    let mut losses = Vec::with_capacity(game.moves.len());
+    for uci in &game.moves {
+        losses.push(engine.loss(&fen, uci).await?.min(MAX_LOSS));
+        fen = chess::play(&fen, uci)?;
+    }
+    Ok(losses)
+}
+
+/// The move quality of each player over the annotated games among
+/// `games`. Games without losses for every move are left out.
+pub fn move_quality(games: &[GameRecord]) -> BTreeMap<String, MoveQuality> {
+    let mut losses: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+    for game in games {
+        if game.moves.is_empty() || game.losses.len() != game.moves.len() {
+            continue;
+        }
+        let Ok(white_first) = chess::white_to_move(&game.start) else {
+            continue;
+        };
+        for (ply, &loss) in game.losses.iter().enumerate() {
+            let player = if (ply % 2 == 0) == white_first { &game.white } else { &game.black };
+            losses.entry(player).or_default().push(loss);
+        }
+    }
+    losses
+        .into_iter()
+        .map(|(player, losses)| (player.to_string(), MoveQuality::from_losses(&losses)))
+        .collect()
+}
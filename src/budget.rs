@@ -0,0 +1,293 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Token prices in USD per million tokens.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Pricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl Pricing {
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.input_per_million
+            + completion_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Estimated token count of `text` for tiktoken-style BPE tokenizers, for
+/// checking prompts before any request is made. The text is split the way
+/// those tokenizers pre-split it, into words (with their leading space),
+/// numbers, punctuation runs and line breaks, and each piece is counted the
+/// way such tokenizers usually encode it: a token per six letters of a word,
+/// per three digits, per two punctuation marks, and per character of scripts
+/// such as CJK that don't merge well.
+pub fn estimate_tokens(text: &str) -> u64 {
+    #[derive(PartialEq)]
+    enum Piece {
+        Word,
+        Number,
+        Punctuation,
+        Space,
+    }
+    let piece_tokens = |piece: &Piece, len: u64, newline: bool| match piece {
+        Piece::Word => len.div_ceil(6),
+        Piece::Number => len.div_ceil(3),
+        Piece::Punctuation => len.div_ceil(2),
+        // A single space belongs to the word after it.
+        Piece::Space => u64::from(newline || len > 1),
+    };
+
+    let mut tokens = 0;
+    let mut current: Option<(Piece, u64, bool)> = None;
+    for c in text.chars() {
+        let piece = if c.is_whitespace() {
+            Piece::Space
+        } else if c.is_ascii_digit() {
+            Piece::Number
+        } else if c.is_alphabetic() && (c as u32) < 0x2E80 {
+            Piece::Word
+        } else if c.is_alphanumeric() {
+            tokens += 1;
+            continue;
+        } else {
+            Piece::Punctuation
+        };
+        match &mut current {
+            Some((kind, len, newline)) if *kind == piece => {
+                *len += 1;
+                *newline |= c == '\n';
+            }
+            _ => {
+                if let Some((kind, len, newline)) = current.take() {
+                    tokens += piece_tokens(&kind, len, newline);
+                }
+                current = Some((piece, 1, c == '\n'));
+            }
+        }
+    }
+    if let Some((kind, len, newline)) = current {
+        tokens += piece_tokens(&kind, len, newline);
+    }
+    tokens
+}
+
+/// Estimated prompt tokens of one attached board image, as a high-detail
+/// image of up to 1024 pixels square is billed by OpenAI vision models.
+pub const IMAGE_TOKENS: u64 = 765;
+
+/// Room left for each answer when checking prompts against a context
+/// window and the response has no token cap.
+pub const EXPECTED_ANSWER_TOKENS: u64 = 4096;
+
+/// Context windows, in tokens, of well-known models, matched by prefix so
+/// dated snapshots (e.g. `gpt-4o-2024-08-06`) are found too. More specific
+/// prefixes come first.
+const CONTEXT_WINDOWS: &[(&str, u64)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-5", 400_000),
+    ("o1-mini", 128_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("gemini-1.5", 1_048_576),
+    ("gemini-2", 1_048_576),
+    ("deepseek", 65_536),
+];
+
+/// The context window of `model` if it is a well-known one. Provider
+/// prefixes such as `openai/` are ignored.
+pub fn context_window(model: &str) -> Option<u64> {
+    let model = model.rsplit('/').next().unwrap_or(model).to_ascii_lowercase();
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, tokens)| *tokens)
+}
+
+/// What to do about prompts that, with room for their answer, would not
+/// fit the model's context window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextCheck {
+    /// Don't check
+    Off,
+    /// Log a warning and send them anyway
+    #[default]
+    Warn,
+    /// Refuse to start the run
+    Fail,
+}
+
+/// Spending limits for a single solver; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    pub max_cost: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub pricing: Pricing,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UsageSummary {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+/// Running token counters, safe to update from the runner's worker threads.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    requests: AtomicU64,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+}
+
+impl UsageTracker {
+    pub fn record(&self, prompt_tokens: u64, completion_tokens: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.prompt_tokens.fetch_add(prompt_tokens, Ordering::Relaxed);
+        self.completion_tokens
+            .fetch_add(completion_tokens, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self, pricing: &Pricing) -> UsageSummary {
+        let prompt_tokens = self.prompt_tokens.load(Ordering::Relaxed);
+        let completion_tokens = self.completion_tokens.load(Ordering::Relaxed);
+        UsageSummary {
+            requests: self.requests.load(Ordering::Relaxed),
+            prompt_tokens,
+            completion_tokens,
+            cost: pricing.cost(prompt_tokens, completion_tokens),
+        }
+    }
+
+    pub fn exceeds(&self, budget: &Budget) -> bool {
+        let usage = self.summary(&budget.pricing);
+        budget
+            .max_tokens
+            .is_some_and(|max| usage.prompt_tokens + usage.completion_tokens >= max)
+            || budget.max_cost.is_some_and(|max| usage.cost >= max)
+    }
+}
+
+/// Wall-clock limit for solving one puzzle.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeBudget {
+    pub limit: Duration,
+    /// When set, late answers keep partial credit that halves every
+    /// `decay_half_life` past the limit instead of dropping to zero.
+    pub decay_half_life: Option<Duration>,
+}
+
+impl TimeBudget {
+    /// Credit multiplier for a puzzle that took `elapsed`.
+    pub fn credit(&self, elapsed: Duration) -> f64 {
+        if elapsed <= self.limit {
+            return 1.0;
+        }
+        match self.decay_half_life {
+            Some(half_life) if !half_life.is_zero() => {
+                let overrun = (elapsed - self.limit).as_secs_f64();
+                0.5f64.powf(overrun / half_life.as_secs_f64())
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Average score the run would have had under a hard time limit.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TimeBudgetPoint {
+    pub budget_ms: u64,
+    pub average_score: f64,
+}
+
+/// Budgets (in seconds) reported in every result's score-vs-time curve.
+pub const TIME_CURVE_BUDGETS_SECS: [u64; 7] = [5, 10, 30, 60, 120, 300, 600];
+
+/// How long a reasoning model may think before answering, for providers
+/// that accept OpenAI's `reasoning_effort`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReasoningEffort::Minimal => "minimal",
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+/// The compute a solver may spend on each response; unset limits are left
+/// to the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeBudget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+}
+
+impl ComputeBudget {
+    pub fn is_unlimited(&self) -> bool {
+        *self == ComputeBudget::default()
+    }
+
+    /// Every combination of `max_completion_tokens` and `reasoning_effort`,
+    /// token caps outermost; an empty list leaves that limit unset.
+    pub fn grid(max_completion_tokens: &[u32], reasoning_efforts: &[ReasoningEffort]) -> Vec<ComputeBudget> {
+        let caps: Vec<Option<u32>> = match max_completion_tokens {
+            [] => vec![None],
+            caps => caps.iter().copied().map(Some).collect(),
+        };
+        let efforts: Vec<Option<ReasoningEffort>> = match reasoning_efforts {
+            [] => vec![None],
+            efforts => efforts.iter().copied().map(Some).collect(),
+        };
+        caps.iter()
+            .flat_map(|&max_completion_tokens| {
+                efforts.iter().map(move |&reasoning_effort| ComputeBudget {
+                    max_completion_tokens,
+                    reasoning_effort,
+                })
+            })
+            .collect()
+    }
+
+    /// e.g. "1024 tokens, high effort", or "unlimited".
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(max_tokens) = self.max_completion_tokens {
+            parts.push(format!("{} tokens", max_tokens));
+        }
+        if let Some(effort) = self.reasoning_effort {
+            parts.push(format!("{} effort", effort.label()));
+        }
+        if parts.is_empty() {
+            "unlimited".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
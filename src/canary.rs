@@ -0,0 +1,161 @@
+//! Canary puzzles for detecting training-data leakage: nonsense positions
+//! with made-up solutions, hidden among a collection's real puzzles. No
+//! model can work out a canary's answer, so one that gives it has most
+//! likely seen the collection, solutions included, during training.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rand::rngs::SmallRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleScore};
+
+/// Metadata key marking a canary puzzle, set to `true`.
+pub const CANARY_KEY: &str = "canary";
+/// Fresh made-up solutions drawn before settling for one outside the
+/// answer grammar.
+const SOLUTION_ATTEMPTS: usize = 100;
+
+/// How a result fared on the collection's canaries. Canaries are left out
+/// of every score; this is all that is reported about them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CanaryReport {
+    pub canaries: usize,
+    /// The canaries whose made-up solution the model gave for some state.
+    pub reproduced: Vec<String>,
+}
+
+impl CanaryReport {
+    /// Whether the model gave any canary's made-up solution, a sign the
+    /// collection leaked into its training data.
+    pub fn leaked(&self) -> bool {
+        !self.reproduced.is_empty()
+    }
+
+    /// The report for `scores` on the canaries of `collection`, or `None`
+    /// if none of the scores are for canaries.
+    pub fn from_scores(collection: &PuzzleCollection, scores: &[PuzzleScore]) -> Option<Self> {
+        let canaries: HashMap<&str, &Puzzle> = collection
+            .puzzles
+            .iter()
+            .filter(|p| p.is_canary())
+            .map(|p| (p.id.as_str(), p))
+            .collect();
+        let mut report = Self::default();
+        for score in scores {
+            let Some(canary) = canaries.get(score.puzzle_id.as_str()) else {
+                continue;
+            };
+            report.canaries += 1;
+            let reproduced = canary
+                .solutions
+                .iter()
+                .zip(&score.answers)
+                .any(|(solution, answer)| solution.eq_ignore_ascii_case(answer.trim()));
+            if reproduced {
+                report.reproduced.push(canary.id.clone());
+            }
+        }
+        (report.canaries > 0).then_some(report)
+    }
+}
+
+/// `count` canaries for `collection`, the same for the same `seed`. Each
+/// takes a random real puzzle's first state and scrambles its first field
+/// (the board, in FEN) into a position that can't arise, keeping the row
+/// separators. Its solution is made up character by character from those
+/// found at the same place in the collection's real solutions, so it looks
+/// like any other answer.
+pub fn canary_puzzles(collection: &PuzzleCollection, count: usize, seed: u64) -> Vec<Puzzle> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let sources: Vec<&Puzzle> = collection
+        .puzzles
+        .iter()
+        .filter(|p| !p.is_canary() && !p.game_states.is_empty() && !p.solutions.is_empty())
+        .collect();
+    let solutions: Vec<&str> = sources.iter().flat_map(|p| &p.solutions).map(String::as_str).collect();
+    let mut canaries = Vec::new();
+    for _ in 0..count {
+        let Some(source) = sources.choose(&mut rng) else {
+            break;
+        };
+        let state = scramble(&source.game_states[0], &mut rng);
+        let solution = made_up_solution(collection, &solutions, &mut rng);
+        canaries.push(Puzzle {
+            id: format!("{}_{:06x}", source.id, rng.random_range(0..0x100_0000)),
+            description: source.description.clone(),
+            game_type: source.game_type.clone(),
+            game_states: vec![state],
+            solutions: vec![solution],
+            encrypted_solutions: None,
+            rating: None,
+            rating_deviation: None,
+            weight: source.weight,
+            state_weights: Vec::new(),
+            images: Vec::new(),
+            sequences: Vec::new(),
+            annotations: Vec::new(),
+            orientations: Vec::new(),
+            history: None,
+            render_style: None,
+            tags: Vec::new(),
+            provenance: Default::default(),
+            metadata: BTreeMap::from([(CANARY_KEY.to_string(), serde_json::json!(true))]),
+            goal_condition: None,
+            goal: None,
+        });
+    }
+    canaries
+}
+
+/// Add `count` canaries (see `canary_puzzles`) to `collection` at random
+/// places, returning their ids.
+pub fn insert_canaries(collection: &mut PuzzleCollection, count: usize, seed: u64) -> Vec<String> {
+    let canaries = canary_puzzles(collection, count, seed);
+    let ids = canaries.iter().map(|p| p.id.clone()).collect();
+    let mut rng = SmallRng::seed_from_u64(seed.wrapping_add(1));
+    for canary in canaries {
+        let at = rng.random_range(0..=collection.puzzles.len());
+        collection.puzzles.insert(at, canary);
+    }
+    ids
+}
+
+/// `state` with the characters of its first whitespace-separated field
+/// shuffled, except `/` row separators.
+fn scramble(state: &str, rng: &mut SmallRng) -> String {
+    let (field, rest) = state.split_once(' ').unwrap_or((state, ""));
+    let mut shuffled: Vec<char> = field.chars().filter(|&c| c != '/').collect();
+    shuffled.shuffle(rng);
+    let mut shuffled = shuffled.into_iter();
+    let field: String = field
+        .chars()
+        .map(|c| if c == '/' { c } else { shuffled.next().expect("as many characters") })
+        .collect();
+    if rest.is_empty() { field } else { format!("{} {}", field, rest) }
+}
+
+/// A solution as long as a random real one, each character drawn from
+/// those at the same place in real solutions, that fits the collection's
+/// answer grammar and isn't a real solution.
+fn made_up_solution(collection: &PuzzleCollection, solutions: &[&str], rng: &mut SmallRng) -> String {
+    let mut candidate = String::new();
+    for _ in 0..SOLUTION_ATTEMPTS {
+        let Some(model) = solutions.choose(rng) else {
+            break;
+        };
+        candidate = (0..model.chars().count())
+            .map(|i| {
+                let seen: Vec<char> = solutions.iter().filter_map(|s| s.chars().nth(i)).collect();
+                *seen.choose(rng).expect("the model solution has this many characters")
+            })
+            .collect();
+        if collection.answer_format.grammar.accepts(&candidate) && !solutions.contains(&candidate.as_str()) {
+            break;
+        }
+    }
+    candidate
+}
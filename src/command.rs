@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::budget::ReasoningEffort;
+use crate::evaluation::ApiError;
+
+/// Process groups of the command solvers running now; see `kill_running`.
+static RUNNING: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// Kill every command solver still running, with the processes it started,
+/// for a run that is aborted rather than left to finish its puzzles.
+pub fn kill_running() {
+    let running = RUNNING.lock().unwrap();
+    #[cfg(unix)]
+    for &group in running.iter() {
+        // SAFETY: killpg only sends a signal; a group that already exited
+        // makes it fail with ESRCH, which is fine to ignore.
+        unsafe {
+            libc::killpg(group as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = running;
+}
+
+/// Leaves `RUNNING` when the program has been waited for or given up on.
+struct Running(Option<u32>);
+
+impl Running {
+    fn new(group: Option<u32>) -> Self {
+        if let Some(group) = group {
+            RUNNING.lock().unwrap().insert(group);
+        }
+        Self(group)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if let Some(group) = self.0 {
+            RUNNING.lock().unwrap().remove(&group);
+        }
+    }
+}
+
+/// An external program used as a solver. It is started once per prompt,
+/// gets a `CommandRequest` as JSON on stdin and must print a `CommandReply`
+/// as JSON on stdout, then exit successfully.
+#[derive(Debug, Clone)]
+pub struct CommandSolver {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Kill the program if it hasn't replied within this long.
+    pub timeout: Option<Duration>,
+}
+
+/// What a command solver is asked. The puzzle fields are absent for prompts
+/// that aren't about a puzzle state, like the reachability check or judging.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CommandRequest<'a> {
+    pub prompt: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub puzzle_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_notation: Option<&'a str>,
+    /// The board as a `data:` URI, sent instead of `state` when the solver
+    /// is evaluated on images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<&'a str>,
+    /// The moves leading to the board as a `data:` URI (an animation or a
+    /// strip of frames), when the solver is shown them; see
+    /// `Puzzle::sequences`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<&'a str>,
+    /// The response length the solver is held to, for answer-only runs;
+    /// see `Solver::max_completion_tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// How long a reasoning solver may think; see
+    /// `Solver::reasoning_effort`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+}
+
+impl<'a> CommandRequest<'a> {
+    pub fn prompt(prompt: &'a str) -> Self {
+        Self {
+            prompt,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandReply {
+    /// Reply text; the answer is extracted from it like from a model's reply,
+    /// so an engine can simply reply `**Answer: e2e4**`.
+    pub response: String,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+impl CommandSolver {
+    pub fn new(program: &str, args: Vec<String>) -> Self {
+        Self {
+            program: program.to_string(),
+            args,
+            timeout: None,
+        }
+    }
+
+    /// Split a command line such as `python3 solver.py --depth 3` on
+    /// whitespace; quoting is not supported.
+    pub fn from_command_line(line: &str) -> Result<Self, ApiError> {
+        let mut words = line.split_whitespace().map(str::to_string);
+        let program = words
+            .next()
+            .ok_or_else(|| ApiError::Command("empty solver command".to_string()))?;
+        Ok(Self::new(&program, words.collect()))
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Run the program once for `request`.
+    pub async fn run(&self, request: &CommandRequest<'_>) -> Result<CommandReply, ApiError> {
+        let failed = |what: &str, e: &dyn std::fmt::Display| {
+            ApiError::Command(format!("{} {}: {}", what, self.program, e))
+        };
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+        // Keep a terminal Ctrl-C from killing programs the runner lets finish.
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command.spawn().map_err(|e| failed("could not start", &e))?;
+        // The program leads its own process group, so its id is the group's.
+        let _running = Running::new(child.id());
+
+        // Write while waiting, so the timeout covers a program that never
+        // reads its input and one that replies before reading all of it.
+        let input = serde_json::to_vec(request).expect("request serializes to JSON");
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let write = async move {
+            match stdin.write_all(&input).await {
+                // The program is free to answer without reading the request.
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                written => written,
+            }
+        };
+        let exchange = async { tokio::join!(write, child.wait_with_output()) };
+        let (written, output) = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, exchange)
+                .await
+                .map_err(|_| failed("timed out waiting for", &format!("{:?}", timeout)))?,
+            None => exchange.await,
+        };
+        written.map_err(|e| failed("could not write to", &e))?;
+        let output = output.map_err(|e| failed("could not wait for", &e))?;
+
+        if !output.status.success() {
+            return Err(failed("failed to answer:", &output.status));
+        }
+        serde_json::from_slice(&output.stdout).map_err(|e| failed("invalid reply from", &e))
+    }
+}
@@ -0,0 +1,176 @@
+//! Prompt compression for small context windows. When a prompt is over its
+//! token budget — as with long `game_rule` texts of custom games — its parts
+//! are shortened one at a time, least important first, until it fits: the
+//! worked examples in the rules dropped, the rules summarized and then cut
+//! off, and the state written as stored instead of as a diagram or PGN.
+//! Results record which of these a puzzle's prompts needed.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::budget::estimate_tokens;
+use crate::prompt::Representation;
+
+/// A part of a prompt that can be shortened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromptPart {
+    /// The collection's rules text
+    Rules,
+    /// Paragraphs of the rules text giving examples
+    Examples,
+    /// The game state
+    State,
+}
+
+/// Kept longest first: the state, then the rules, then their examples.
+pub const DEFAULT_PRIORITY: [PromptPart; 3] = [PromptPart::State, PromptPart::Rules, PromptPart::Examples];
+
+/// A way a prompt was shortened to fit its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+    /// Example paragraphs removed from the rules.
+    DroppedExamples,
+    /// Rules reduced to the first sentence of each paragraph.
+    SummarizedRules,
+    /// Rules cut off where the budget ran out.
+    TruncatedRules,
+    /// The state given as stored rather than as a diagram or PGN.
+    PlainState,
+}
+
+impl Compression {
+    pub fn label(self) -> &'static str {
+        match self {
+            Compression::DroppedExamples => "examples dropped",
+            Compression::SummarizedRules => "rules summarized",
+            Compression::TruncatedRules => "rules truncated",
+            Compression::PlainState => "state as stored",
+        }
+    }
+}
+
+/// The most tokens a prompt may take, and which parts to keep longest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptBudget {
+    pub tokens: u64,
+    /// Most important first; parts left out are never shortened.
+    pub priority: Vec<PromptPart>,
+}
+
+/// A prompt as sent, with what was done to fit it into its budget.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FittedPrompt {
+    pub text: String,
+    /// Empty if the prompt fit as it was.
+    pub compression: Vec<Compression>,
+}
+
+/// Marks the end of rules that were cut off.
+const TRUNCATION_MARK: &str = "[…]";
+
+/// Whether a paragraph of rules text is a worked example.
+fn is_example(paragraph: &str) -> bool {
+    let start = paragraph.trim_start().trim_start_matches(['#', '*', '-', ' ']).to_lowercase();
+    ["example", "for example", "e.g.", "beispiel", "ejemplo", "exemple", "例"]
+        .iter()
+        .any(|prefix| start.starts_with(prefix))
+}
+
+/// `rules` without its example paragraphs.
+pub fn drop_examples(rules: &str) -> String {
+    rules
+        .split("\n\n")
+        .filter(|paragraph| !is_example(paragraph))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The first sentence (or line) of `paragraph`.
+fn first_sentence(paragraph: &str) -> &str {
+    let paragraph = paragraph.trim();
+    let end = [". ", ".\n", "。", "\n"]
+        .iter()
+        .filter_map(|stop| paragraph.find(stop).map(|start| start + stop.trim_end_matches([' ', '\n']).len()))
+        .min()
+        .unwrap_or(paragraph.len());
+    &paragraph[..end]
+}
+
+/// `rules` reduced to the first sentence of each paragraph.
+pub fn summarize_rules(rules: &str) -> String {
+    rules
+        .split("\n\n")
+        .map(first_sentence)
+        .filter(|sentence| !sentence.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The start of `rules` that fits in about `tokens` tokens, marked as cut
+/// off; `rules` itself if it fits.
+pub fn truncate_rules(rules: &str, tokens: u64) -> String {
+    if estimate_tokens(rules) <= tokens {
+        return rules.to_string();
+    }
+    let mut kept = String::new();
+    let mut used = estimate_tokens(TRUNCATION_MARK);
+    for word in rules.split_inclusive(char::is_whitespace) {
+        used += estimate_tokens(word);
+        if used > tokens {
+            break;
+        }
+        kept.push_str(word);
+    }
+    format!("{}{}", kept.trim_end(), TRUNCATION_MARK)
+}
+
+/// The prompt `build` makes from `rules` and `representation`, shortened in
+/// `budget`'s order until it fits; see the module docs. Steps that don't
+/// change the prompt, such as shortening rules the prompt doesn't show,
+/// aren't recorded. May still be over budget once nothing is left to
+/// shorten.
+pub fn fit_prompt(
+    budget: &PromptBudget,
+    rules: &str,
+    representation: Representation,
+    build: impl Fn(&str, Representation) -> String,
+) -> FittedPrompt {
+    let mut rules = rules.to_string();
+    let mut representation = representation;
+    let mut fitted = FittedPrompt {
+        text: build(&rules, representation),
+        compression: Vec::new(),
+    };
+    let fits = |prompt: &str| estimate_tokens(prompt) <= budget.tokens;
+
+    for part in budget.priority.iter().rev() {
+        let steps: &[Compression] = match part {
+            PromptPart::Examples => &[Compression::DroppedExamples],
+            PromptPart::Rules => &[Compression::SummarizedRules, Compression::TruncatedRules],
+            PromptPart::State => &[Compression::PlainState],
+        };
+        for &step in steps {
+            if fits(&fitted.text) {
+                return fitted;
+            }
+            let (shorter_rules, shorter_representation) = match step {
+                Compression::DroppedExamples => (drop_examples(&rules), representation),
+                Compression::SummarizedRules => (summarize_rules(&rules), representation),
+                Compression::TruncatedRules => {
+                    let excess = estimate_tokens(&fitted.text).saturating_sub(budget.tokens);
+                    (truncate_rules(&rules, estimate_tokens(&rules).saturating_sub(excess)), representation)
+                }
+                Compression::PlainState => (rules.clone(), Representation::Fen),
+            };
+            let text = build(&shorter_rules, shorter_representation);
+            if text != fitted.text {
+                (rules, representation) = (shorter_rules, shorter_representation);
+                fitted.text = text;
+                fitted.compression.push(step);
+            }
+        }
+    }
+    fitted
+}
@@ -0,0 +1,103 @@
+//! Calibration of stated confidence. Solvers asked to (see
+//! `Solver::with_confidence`) end each answer with how sure they are of it;
+//! a well-calibrated solver is right about as often as it says it is.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::PuzzleScore;
+
+/// Equal-width confidence bins of the reliability diagram.
+pub const RELIABILITY_BINS: usize = 10;
+
+/// The confidence stated for one state's answer, as a probability, and
+/// whether the answer got full credit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Forecast {
+    pub confidence: f64,
+    pub correct: bool,
+}
+
+/// The answers whose stated confidence falls in `[low, high)`; the last bin
+/// includes 100%.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReliabilityBin {
+    pub low: f64,
+    pub high: f64,
+    pub answers: usize,
+    pub mean_confidence: f64,
+    pub accuracy: f64,
+}
+
+/// How well a solver's stated confidence matched how often it was right.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfidenceCalibration {
+    /// Answers with a stated confidence.
+    pub answers: usize,
+    /// Answers asked for a confidence that didn't state one; left out of
+    /// everything below.
+    pub missing: usize,
+    pub mean_confidence: f64,
+    pub accuracy: f64,
+    /// Mean squared difference between confidence and correctness (1 or
+    /// 0); 0 is perfect, and always saying 50% scores 0.25.
+    pub brier_score: f64,
+    /// Expected calibration error: the gap between mean confidence and
+    /// accuracy in each bin, averaged weighted by the bins' answers.
+    pub expected_calibration_error: f64,
+    /// The reliability diagram, without empty bins.
+    pub bins: Vec<ReliabilityBin>,
+}
+
+impl ConfidenceCalibration {
+    /// Calibration over every forecast in `scores`, or `None` if no
+    /// confidence was asked for.
+    pub fn from_scores(scores: &[PuzzleScore]) -> Option<Self> {
+        let stated: Vec<&Option<Forecast>> = scores.iter().flat_map(|score| &score.forecasts).collect();
+        let forecasts: Vec<Forecast> = stated.iter().filter_map(|forecast| **forecast).collect();
+        let mut calibration = Self::from_forecasts(&forecasts)?;
+        calibration.missing = stated.len() - forecasts.len();
+        Some(calibration)
+    }
+
+    /// Calibration of `forecasts`, or `None` if there are none.
+    pub fn from_forecasts(forecasts: &[Forecast]) -> Option<Self> {
+        if forecasts.is_empty() {
+            return None;
+        }
+        let outcome = |forecast: &Forecast| if forecast.correct { 1.0 } else { 0.0 };
+        let count = forecasts.len() as f64;
+
+        let mut binned: Vec<Vec<&Forecast>> = vec![Vec::new(); RELIABILITY_BINS];
+        for forecast in forecasts {
+            let bin = ((forecast.confidence * RELIABILITY_BINS as f64) as usize).min(RELIABILITY_BINS - 1);
+            binned[bin].push(forecast);
+        }
+        let bins: Vec<ReliabilityBin> = binned
+            .iter()
+            .enumerate()
+            .filter(|(_, bin)| !bin.is_empty())
+            .map(|(i, bin)| ReliabilityBin {
+                low: i as f64 / RELIABILITY_BINS as f64,
+                high: (i + 1) as f64 / RELIABILITY_BINS as f64,
+                answers: bin.len(),
+                mean_confidence: bin.iter().map(|f| f.confidence).sum::<f64>() / bin.len() as f64,
+                accuracy: bin.iter().map(|f| outcome(f)).sum::<f64>() / bin.len() as f64,
+            })
+            .collect();
+        let expected_calibration_error = bins
+            .iter()
+            .map(|bin| bin.answers as f64 / count * (bin.mean_confidence - bin.accuracy).abs())
+            .sum();
+
+        Some(Self {
+            answers: forecasts.len(),
+            missing: 0,
+            mean_confidence: forecasts.iter().map(|f| f.confidence).sum::<f64>() / count,
+            accuracy: forecasts.iter().map(outcome).sum::<f64>() / count,
+            brier_score: forecasts.iter().map(|f| (f.confidence - outcome(f)).powi(2)).sum::<f64>() / count,
+            expected_calibration_error,
+            bins,
+        })
+    }
+}
@@ -0,0 +1,170 @@
+//! Contamination risk of chess puzzles: how often their positions occur in
+//! public game collections a model may have been trained on, such as the
+//! Lichess game dumps or lists of famous games.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
+
+use crate::game::chess;
+use crate::puzzle::PuzzleCollection;
+
+/// Metadata key holding a puzzle's contamination risk, from 0 to 1.
+pub const RISK_KEY: &str = "contamination_risk";
+/// Metadata key holding the exposure the risk was computed from.
+pub const EXPOSURE_KEY: &str = "corpus_exposure";
+/// The exposure at which a position's risk reaches one half.
+pub const HALF_RISK_EXPOSURE: f64 = 5.0;
+/// How much more a famous game counts than an ordinary one: famous games
+/// are reprinted and annotated across books and websites.
+pub const FAMOUS_GAME_WEIGHT: f64 = 50.0;
+
+/// Positions seen in game collections, each with its exposure: the summed
+/// weight of the games it occurs in. Positions are compared like
+/// repetitions (board, side to move, castling and en passant), and only
+/// their hashes are kept so large dumps fit in memory.
+#[derive(Debug, Clone)]
+pub struct PositionCorpus {
+    /// Positions further into a game than this many plies aren't recorded.
+    pub max_plies: usize,
+    pub games: usize,
+    exposure: HashMap<u64, f64>,
+}
+
+impl PositionCorpus {
+    pub fn new(max_plies: usize) -> Self {
+        Self {
+            max_plies,
+            games: 0,
+            exposure: HashMap::new(),
+        }
+    }
+
+    /// Add every game of a PGN file, each weighing `weight`, returning how
+    /// many were read. Games with a `FEN` tag start there; games with an
+    /// unreadable or illegal move count up to that move.
+    pub fn add_pgn<R: BufRead>(&mut self, reader: R, weight: f64) -> io::Result<usize> {
+        let mut added = 0;
+        let mut start: Option<String> = None;
+        let mut movetext = String::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.starts_with('[') {
+                if !movetext.trim().is_empty() {
+                    self.add_game(start.take().as_deref(), &movetext, weight);
+                    movetext.clear();
+                    added += 1;
+                }
+                if let Some(fen) = line.strip_prefix("[FEN \"").and_then(|rest| rest.strip_suffix("\"]")) {
+                    start = Some(fen.to_string());
+                }
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+        if !movetext.trim().is_empty() {
+            self.add_game(start.as_deref(), &movetext, weight);
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    fn add_game(&mut self, start: Option<&str>, movetext: &str, weight: f64) {
+        let moves = san_moves(movetext);
+        let Ok(keys) = chess::san_positions(start.unwrap_or(chess::STARTING_FEN), &moves, self.max_plies) else {
+            return;
+        };
+        let unique: HashSet<u64> = keys.iter().map(|key| hash_key(key)).collect();
+        for hash in unique {
+            *self.exposure.entry(hash).or_default() += weight;
+        }
+        self.games += 1;
+    }
+
+    /// The exposure of the position `fen`; 0 if it was never seen or isn't
+    /// a chess position.
+    pub fn exposure(&self, fen: &str) -> f64 {
+        chess::repetition_key(fen)
+            .ok()
+            .and_then(|key| self.exposure.get(&hash_key(&key)).copied())
+            .unwrap_or(0.0)
+    }
+
+    /// How likely a model has memorized a position with this exposure,
+    /// from 0 (never seen) towards 1 (widely reproduced).
+    pub fn risk(exposure: f64) -> f64 {
+        exposure / (exposure + HALF_RISK_EXPOSURE)
+    }
+
+    /// Store each chess puzzle's contamination risk, the risk of its most
+    /// exposed state, as `contamination_risk` in its metadata, with that
+    /// state's exposure as `corpus_exposure`. Puzzles without a chess state
+    /// are left alone. Returns how many puzzles were annotated.
+    pub fn annotate(&self, collection: &mut PuzzleCollection) -> usize {
+        let mut annotated = 0;
+        for puzzle in &mut collection.puzzles {
+            let exposures: Vec<f64> = puzzle
+                .game_states
+                .iter()
+                .filter(|fen| chess::parse_fen(fen).is_ok())
+                .map(|fen| self.exposure(fen))
+                .collect();
+            let Some(exposure) = exposures.into_iter().reduce(f64::max) else {
+                continue;
+            };
+            let risk = (Self::risk(exposure) * 1000.0).round() / 1000.0;
+            puzzle.metadata.insert(RISK_KEY.to_string(), serde_json::json!(risk));
+            puzzle.metadata.insert(EXPOSURE_KEY.to_string(), serde_json::json!(exposure));
+            annotated += 1;
+        }
+        annotated
+    }
+}
+
+/// The moves of PGN movetext, without comments, variations, numeric
+/// annotations, move numbers or the result.
+fn san_moves(movetext: &str) -> Vec<&str> {
+    let mut moves = Vec::new();
+    let mut variation = 0usize;
+    let mut rest = movetext;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+        match rest.as_bytes()[0] {
+            b'{' => {
+                let Some(end) = rest.find('}') else {
+                    break;
+                };
+                rest = &rest[end + 1..];
+                continue;
+            }
+            b'(' => variation += 1,
+            b')' => variation = variation.saturating_sub(1),
+            b'}' => {}
+            _ => {
+                let end = rest.find(|c: char| c.is_whitespace() || "{}()".contains(c)).unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+                if variation > 0 || word.starts_with('$') || ["1-0", "0-1", "1/2-1/2", "*"].contains(&word) {
+                    continue;
+                }
+                // `12.`, `12...` and `12.e4` all hold a move number.
+                let word = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if !word.is_empty() {
+                    moves.push(word);
+                }
+                continue;
+            }
+        }
+        rest = &rest[1..];
+    }
+    moves
+}
+
+fn hash_key(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,118 @@
+//! Multi-move chess puzzles whose opponent replies come from engine search
+//! at generation time rather than from the source's line. Every state the
+//! scorer asks about is checked to have one clearly best answer, and the
+//! line stops at the first state that doesn't.
+
+use crate::engine::{EngineError, UciEngine};
+use crate::game::chess::{self, ChessError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ContinuationError {
+    #[error(transparent)]
+    Chess(#[from] ChessError),
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+}
+
+/// How far a line is extended and how clear each answer must be.
+#[derive(Debug, Clone, Copy)]
+pub struct ContinuationRules {
+    /// Solver moves in the finished puzzle, counting the first.
+    pub solver_moves: usize,
+    /// How many centipawns the engine's best move must be ahead of its
+    /// second best for the state to have a single right answer.
+    pub margin: i32,
+}
+
+impl Default for ContinuationRules {
+    fn default() -> Self {
+        Self {
+            solver_moves: 3,
+            margin: 150,
+        }
+    }
+}
+
+/// The states and solutions of an extended puzzle, in the puzzle's own
+/// layout: `states[i]` is answered by `solutions[i]`, and each later state
+/// follows the previous solution and the engine's reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    pub states: Vec<String>,
+    pub solutions: Vec<String>,
+    /// Whether the last solution checkmates. Any mate is then a right
+    /// answer, so that state doesn't need a margin.
+    pub ends_in_mate: bool,
+}
+
+/// Extend the puzzle starting at `fen` with solution `first_move` (both
+/// from the source and taken as sound) with the engine's replies and the
+/// solver's answers to them, up to `rules.solver_moves` solver moves.
+pub fn extend(
+    engine: &UciEngine,
+    fen: &str,
+    first_move: &str,
+    rules: &ContinuationRules,
+) -> Result<Continuation, ContinuationError> {
+    crate::runtime::block_on(extend_async(engine, fen, first_move, rules))
+}
+
+pub async fn extend_async(
+    engine: &UciEngine,
+    fen: &str,
+    first_move: &str,
+    rules: &ContinuationRules,
+) -> Result<Continuation, ContinuationError> {
+    chess::check_move(fen, first_move)?;
+    let mut continuation = Continuation {
+        states: vec![fen.to_string()],
+        solutions: vec![first_move.to_string()],
+        ends_in_mate: chess::gives_checkmate(fen, first_move)?,
+    };
+
+    while continuation.solutions.len() < rules.solver_moves && !continuation.ends_in_mate {
+        let (state, solution) = (continuation.states.last().unwrap(), continuation.solutions.last().unwrap());
+        let after = chess::play(state, solution)?;
+        if chess::ending(&after)?.is_some() {
+            break;
+        }
+        let reply = engine.best_move(&after, &[]).await?;
+        let next = chess::play(&after, &reply)?;
+        if chess::ending(&next)?.is_some() {
+            break;
+        }
+        let Some((answer, ends_in_mate)) = clear_best_move(engine, &next, rules.margin).await? else {
+            break;
+        };
+        continuation.states.push(next);
+        continuation.solutions.push(answer);
+        continuation.ends_in_mate = ends_in_mate;
+    }
+    Ok(continuation)
+}
+
+/// The engine's best move in `fen` and whether it mates, if it is the only
+/// right answer there: the only mate, any mate (any is accepted), or at
+/// least `margin` centipawns ahead of the second best move.
+async fn clear_best_move(
+    engine: &UciEngine,
+    fen: &str,
+    margin: i32,
+) -> Result<Option<(String, bool)>, ContinuationError> {
+    let top = engine.top_moves(fen, 2).await?;
+    let Some((best, best_eval)) = top.first() else {
+        return Ok(None);
+    };
+    let Some(best) = chess::normalize_uci(fen, best) else {
+        return Ok(None);
+    };
+    if chess::gives_checkmate(fen, &best)? {
+        return Ok(Some((best, true)));
+    }
+    let clear = match top.get(1) {
+        Some((_, second_eval)) => best_eval - second_eval >= margin,
+        None => true,
+    };
+    Ok(clear.then_some((best, false)))
+}
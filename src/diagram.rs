@@ -0,0 +1,124 @@
+//! Text diagrams of chess positions, for models that read a drawn board
+//! better than a FEN string.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DiagramError {
+    #[error("Invalid FEN {fen:?}: {reason}")]
+    InvalidFen { fen: String, reason: String },
+}
+
+/// The characters pieces are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceSet {
+    /// FEN letters: uppercase for White, lowercase for Black, `.` for empty
+    /// squares.
+    Ascii,
+    /// Chess symbols (♔ for the white king, ♚ for the black king, ...), `·`
+    /// for empty squares.
+    Unicode,
+}
+
+impl PieceSet {
+    fn piece(self, fen_char: char) -> char {
+        match self {
+            PieceSet::Ascii => fen_char,
+            PieceSet::Unicode => match fen_char {
+                'K' => '♔',
+                'Q' => '♕',
+                'R' => '♖',
+                'B' => '♗',
+                'N' => '♘',
+                'P' => '♙',
+                'k' => '♚',
+                'q' => '♛',
+                'r' => '♜',
+                'b' => '♝',
+                'n' => '♞',
+                'p' => '♟',
+                other => other,
+            },
+        }
+    }
+
+    fn empty(self) -> char {
+        match self {
+            PieceSet::Ascii => '.',
+            PieceSet::Unicode => '·',
+        }
+    }
+
+    /// One line explaining the symbols, for the prompt.
+    pub fn legend(self) -> &'static str {
+        match self {
+            PieceSet::Ascii => {
+                "Uppercase letters are White pieces and lowercase letters Black pieces (K king, Q queen, R rook, B bishop, N knight, P pawn); dots are empty squares."
+            }
+            PieceSet::Unicode => "White pieces are ♔♕♖♗♘♙ and Black pieces ♚♛♜♝♞♟; dots are empty squares.",
+        }
+    }
+}
+
+/// Draw the position `fen` as a board with rank 8 at the top, followed by
+/// the side to move, castling rights and en passant square.
+///
+/// ```text
+///   +-----------------+
+/// 8 | r . . . k . . r |
+///   ...
+/// 1 | R . . . K . . R |
+///   +-----------------+
+///     a b c d e f g h
+/// White to move. Castling rights: KQkq. En passant: none.
+/// ```
+pub fn board_diagram(fen: &str, pieces: PieceSet) -> Result<String, DiagramError> {
+    let invalid = |reason: &str| DiagramError::InvalidFen {
+        fen: fen.to_string(),
+        reason: reason.to_string(),
+    };
+    let mut fields = fen.split_whitespace();
+    let placement = fields.next().ok_or_else(|| invalid("empty"))?;
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(invalid("expected 8 ranks"));
+    }
+
+    let mut lines = vec!["  +-----------------+".to_string()];
+    for (i, rank) in ranks.iter().enumerate() {
+        let mut squares = Vec::with_capacity(8);
+        for c in rank.chars() {
+            match c {
+                '1'..='8' => squares.extend(std::iter::repeat_n(pieces.empty(), c as usize - '0' as usize)),
+                'K' | 'Q' | 'R' | 'B' | 'N' | 'P' | 'k' | 'q' | 'r' | 'b' | 'n' | 'p' => squares.push(pieces.piece(c)),
+                _ => return Err(invalid(&format!("unexpected {:?} in the board", c))),
+            }
+        }
+        if squares.len() != 8 {
+            return Err(invalid(&format!("rank {} does not have 8 squares", 8 - i)));
+        }
+        let squares: Vec<String> = squares.iter().map(char::to_string).collect();
+        lines.push(format!("{} | {} |", 8 - i, squares.join(" ")));
+    }
+    lines.push("  +-----------------+".to_string());
+    lines.push("    a b c d e f g h".to_string());
+
+    let side = match fields.next() {
+        Some("w") | None => "White",
+        Some("b") => "Black",
+        Some(_) => return Err(invalid("side to move must be w or b")),
+    };
+    let castling = match fields.next() {
+        Some("-") | None => "none",
+        Some(castling) => castling,
+    };
+    let en_passant = match fields.next() {
+        Some("-") | None => "none",
+        Some(square) => square,
+    };
+    lines.push(format!(
+        "{} to move. Castling rights: {}. En passant: {}.",
+        side, castling, en_passant
+    ));
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,107 @@
+//! Duplicate positions across collections. A run over several collections
+//! may meet the same position more than once — the same Lichess puzzle in a
+//! themed set and in a rated one, say — and would then count it several
+//! times in the composite score. Puzzles are compared by a hash of their
+//! normalized first state, and repeats are warned about, dropped or weighted
+//! down so each position counts once.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "chess")]
+use crate::game::chess;
+use crate::puzzle::PuzzleCollection;
+
+/// What to do about puzzles whose position another puzzle of the run
+/// already has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    /// Log them and keep them all
+    #[default]
+    Warn,
+    /// Keep only the first puzzle with each position
+    Dedup,
+    /// Keep them all, sharing one puzzle's weight between them
+    DownWeight,
+}
+
+/// Puzzles starting from the same position, in collection order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub puzzle_ids: Vec<String>,
+}
+
+/// `state` written the same way for every puzzle with that position: chess
+/// positions as compared for repetitions (board, side to move, castling and
+/// en passant, without move counters), other states with their whitespace
+/// collapsed.
+pub fn normalized_state(game_type: &str, state: &str) -> String {
+    #[cfg(feature = "chess")]
+    if game_type == "chess"
+        && let Ok(key) = chess::repetition_key(state)
+    {
+        return key;
+    }
+    let _ = game_type;
+    state.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Hash of the game type and normalized `state`, as 16 hex digits.
+pub fn state_hash(game_type: &str, state: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", game_type, normalized_state(game_type, state)));
+    digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Every position more than one puzzle of `collection` starts from, in the
+/// order its first puzzle appears.
+pub fn find_duplicates(collection: &PuzzleCollection) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for puzzle in &collection.puzzles {
+        let Some(state) = puzzle.game_states.first() else {
+            continue;
+        };
+        let hash = state_hash(collection.game_type_of(puzzle), state);
+        match index.get(&hash) {
+            Some(&i) => groups[i].puzzle_ids.push(puzzle.id.clone()),
+            None => {
+                index.insert(hash.clone(), groups.len());
+                groups.push(DuplicateGroup {
+                    hash,
+                    puzzle_ids: vec![puzzle.id.clone()],
+                });
+            }
+        }
+    }
+    groups.retain(|group| group.puzzle_ids.len() > 1);
+    groups
+}
+
+/// Apply `policy` to the repeated positions of `collection`, returning
+/// them.
+pub fn resolve_duplicates(collection: &mut PuzzleCollection, policy: DuplicatePolicy) -> Vec<DuplicateGroup> {
+    let groups = find_duplicates(collection);
+    match policy {
+        DuplicatePolicy::Warn => {}
+        DuplicatePolicy::Dedup => {
+            let repeats: Vec<&String> = groups.iter().flat_map(|group| &group.puzzle_ids[1..]).collect();
+            collection.puzzles.retain(|puzzle| !repeats.contains(&&puzzle.id));
+        }
+        DuplicatePolicy::DownWeight => {
+            let shares: HashMap<&String, usize> = groups
+                .iter()
+                .flat_map(|group| group.puzzle_ids.iter().map(|id| (id, group.puzzle_ids.len())))
+                .collect();
+            for puzzle in &mut collection.puzzles {
+                if let Some(&share) = shares.get(&puzzle.id) {
+                    puzzle.weight /= share as f64;
+                }
+            }
+        }
+    }
+    groups
+}
@@ -0,0 +1,210 @@
+//! Solutions encrypted at rest. Each puzzle's solutions are sealed with
+//! ChaCha20-Poly1305 under a key kept apart from the collection file, so a
+//! distributed collection doesn't hand models or crawlers the answers. Runs
+//! given the key decrypt a puzzle's solutions only to score it.
+
+use std::borrow::Cow;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::puzzle::{Puzzle, PuzzleCollection};
+
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("invalid solution key: {0}")]
+    Key(String),
+    #[error("the solutions are encrypted with key {expected}, not {found}")]
+    WrongKey { expected: String, found: String },
+    #[error("the solutions of puzzle {0} can't be decrypted")]
+    Corrupt(String),
+    #[error("the collection's solutions are already encrypted")]
+    AlreadyEncrypted,
+}
+
+/// A 256-bit key for encrypting solutions, stored as base64.
+#[derive(Clone)]
+pub struct SolutionKey([u8; KEY_LEN]);
+
+impl SolutionKey {
+    pub fn generate() -> Result<Self, EncryptionError> {
+        let mut key = [0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| EncryptionError::Key("could not generate a key".to_string()))?;
+        Ok(Self(key))
+    }
+
+    pub fn from_base64(text: &str) -> Result<Self, EncryptionError> {
+        let bytes = BASE64.decode(text.trim()).map_err(|e| EncryptionError::Key(e.to_string()))?;
+        let key = bytes
+            .try_into()
+            .map_err(|_| EncryptionError::Key(format!("expected {} bytes", KEY_LEN)))?;
+        Ok(Self(key))
+    }
+
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.0)
+    }
+
+    /// A short fingerprint naming the key in collection files without
+    /// giving it away.
+    pub fn id(&self) -> String {
+        Sha256::digest(self.0).iter().take(8).map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, EncryptionError> {
+        let text = std::fs::read_to_string(path).map_err(|e| EncryptionError::Key(format!("{}: {}", path, e)))?;
+        Self::from_base64(&text)
+    }
+
+    /// Write the key to `path`, readable only by its owner on unix.
+    pub fn save_to_file(&self, path: &str) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(format!("{}\n", self.to_base64()).as_bytes())
+    }
+
+    fn aead(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &self.0).expect("key has the algorithm's length"))
+    }
+
+    /// Check that this is the key `collection`'s solutions are encrypted
+    /// with; any key fits a collection without encrypted solutions.
+    pub fn check(&self, collection: &PuzzleCollection) -> Result<(), EncryptionError> {
+        match &collection.solution_key_id {
+            Some(expected) if *expected != self.id() => Err(EncryptionError::WrongKey {
+                expected: expected.clone(),
+                found: self.id(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Replace every puzzle's solutions in `collection` with their encryption
+/// under `key`, bound to the puzzle's id so they can't be moved to another
+/// puzzle. Returns how many puzzles were encrypted.
+pub fn encrypt_solutions(collection: &mut PuzzleCollection, key: &SolutionKey) -> Result<usize, EncryptionError> {
+    if collection.solution_key_id.is_some() {
+        return Err(EncryptionError::AlreadyEncrypted);
+    }
+    let aead = key.aead();
+    let random = SystemRandom::new();
+    for puzzle in &mut collection.puzzles {
+        let mut nonce = [0; NONCE_LEN];
+        random
+            .fill(&mut nonce)
+            .map_err(|_| EncryptionError::Key("could not generate a nonce".to_string()))?;
+        let mut sealed = serde_json::to_vec(&puzzle.solutions).expect("solutions serialize to JSON");
+        aead.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(puzzle.id.as_bytes()),
+            &mut sealed,
+        )
+        .map_err(|_| EncryptionError::Corrupt(puzzle.id.clone()))?;
+        puzzle.encrypted_solutions = Some(BASE64.encode([nonce.as_slice(), &sealed].concat()));
+        puzzle.solutions.clear();
+    }
+    collection.solution_key_id = Some(key.id());
+    Ok(collection.puzzles.len())
+}
+
+/// `puzzle` with its solutions decrypted under `key`; borrowed as it is
+/// when they aren't encrypted.
+pub fn decrypt_puzzle<'a>(puzzle: &'a Puzzle, key: &SolutionKey) -> Result<Cow<'a, Puzzle>, EncryptionError> {
+    let Some(encrypted) = &puzzle.encrypted_solutions else {
+        return Ok(Cow::Borrowed(puzzle));
+    };
+    let corrupt = || EncryptionError::Corrupt(puzzle.id.clone());
+    let bytes = BASE64.decode(encrypted).map_err(|_| corrupt())?;
+    if bytes.len() < NONCE_LEN {
+        return Err(corrupt());
+    }
+    let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| corrupt())?;
+    let mut sealed = sealed.to_vec();
+    let plain = key
+        .aead()
+        .open_in_place(nonce, Aad::from(puzzle.id.as_bytes()), &mut sealed)
+        .map_err(|_| corrupt())?;
+    let mut decrypted = puzzle.clone();
+    decrypted.solutions = serde_json::from_slice(plain).map_err(|_| corrupt())?;
+    decrypted.encrypted_solutions = None;
+    Ok(Cow::Owned(decrypted))
+}
+
+/// Decrypt every puzzle's solutions in `collection` in place, remembering
+/// the encrypted collection's checksum in `encrypted_checksum`.
+pub fn decrypt_solutions(collection: &mut PuzzleCollection, key: &SolutionKey) -> Result<(), EncryptionError> {
+    key.check(collection)?;
+    if collection.solution_key_id.is_some() && collection.encrypted_checksum.is_none() {
+        collection.encrypted_checksum = Some(collection.checksum());
+    }
+    for puzzle in &mut collection.puzzles {
+        if let Cow::Owned(decrypted) = decrypt_puzzle(puzzle, key)? {
+            *puzzle = decrypted;
+        }
+    }
+    collection.solution_key_id = None;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::testing::random_collection;
+
+    #[test]
+    fn solutions_round_trip() {
+        let original = random_collection(4, 7);
+        let key = SolutionKey::generate().unwrap();
+        let mut collection = original.clone();
+        assert_eq!(encrypt_solutions(&mut collection, &key).unwrap(), 4);
+        assert!(collection.puzzles.iter().all(|p| p.solutions.is_empty() && p.encrypted_solutions.is_some()));
+        assert!(matches!(encrypt_solutions(&mut collection, &key), Err(EncryptionError::AlreadyEncrypted)));
+        let encrypted_checksum = collection.checksum();
+
+        decrypt_solutions(&mut collection, &key).unwrap();
+        for (decrypted, puzzle) in collection.puzzles.iter().zip(&original.puzzles) {
+            assert_eq!(decrypted.solutions, puzzle.solutions);
+            assert!(decrypted.encrypted_solutions.is_none());
+        }
+        assert_eq!(collection.source_checksum(), encrypted_checksum);
+        assert_eq!(collection.checksum(), original.checksum());
+    }
+
+    #[test]
+    fn wrong_key_and_moved_solutions_are_refused() {
+        let key = SolutionKey::generate().unwrap();
+        let mut collection = random_collection(2, 8);
+        encrypt_solutions(&mut collection, &key).unwrap();
+
+        let other = SolutionKey::generate().unwrap();
+        assert!(matches!(
+            decrypt_solutions(&mut collection.clone(), &other),
+            Err(EncryptionError::WrongKey { .. })
+        ));
+
+        let mut swapped = collection.puzzles[0].clone();
+        swapped.encrypted_solutions = collection.puzzles[1].encrypted_solutions.clone();
+        assert!(matches!(decrypt_puzzle(&swapped, &key), Err(EncryptionError::Corrupt(id)) if id == swapped.id));
+    }
+
+    #[test]
+    fn keys_round_trip_through_base64() {
+        let key = SolutionKey::generate().unwrap();
+        let read = SolutionKey::from_base64(&format!("{}\n", key.to_base64())).unwrap();
+        assert_eq!(read.id(), key.id());
+        assert!(matches!(SolutionKey::from_base64("c2hvcnQ="), Err(EncryptionError::Key(_))));
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+/// A mate is worth this many centipawns, less the moves it takes.
+const MATE_SCORE: i32 = 100_000;
+
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("empty engine command")]
+    EmptyCommand,
+    #[error("could not run engine {program}: {reason}")]
+    Io { program: String, reason: String },
+    #[error("engine {program} timed out after {timeout:?}")]
+    Timeout { program: String, timeout: Duration },
+    #[error("engine {program} gave no score")]
+    NoScore { program: String },
+    #[error("engine {program} gave no move")]
+    NoMove { program: String },
+}
+
+/// A UCI chess engine such as Stockfish, run as an external program to
+/// grade moves that aren't a puzzle's solution or to play against. It is
+/// started once per position and searches to a fixed depth.
+#[derive(Debug, Clone)]
+pub struct UciEngine {
+    pub program: String,
+    pub args: Vec<String>,
+    pub depth: u32,
+    /// Give up on a search that hasn't finished within this long.
+    pub timeout: Option<Duration>,
+    /// UCI options set before every search, e.g. `("Skill Level", "5")`.
+    pub options: Vec<(String, String)>,
+}
+
+/// What a search found: the score of its deepest iteration, the move it
+/// settled on, and with MultiPV the first move and score of each line.
+struct Search {
+    score: Option<i32>,
+    best_move: Option<String>,
+    lines: BTreeMap<usize, (String, i32)>,
+}
+
+impl UciEngine {
+    /// Split a command line such as `stockfish` on whitespace, like
+    /// `CommandSolver::from_command_line`.
+    pub fn from_command_line(line: &str, depth: u32) -> Result<Self, EngineError> {
+        let mut words = line.split_whitespace().map(str::to_string);
+        let program = words.next().ok_or(EngineError::EmptyCommand)?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+            depth,
+            timeout: None,
+            options: Vec::new(),
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_option(mut self, name: &str, value: &str) -> Self {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// A name for the engine in results, e.g. `stockfish (depth 8)`.
+    pub fn name(&self) -> String {
+        let mut name = format!("{} (depth {}", self.program, self.depth);
+        for (option, value) in &self.options {
+            name.push_str(&format!(", {} {}", option, value));
+        }
+        name.push(')');
+        name
+    }
+
+    /// How many centipawns playing `uci` in the position `fen` gives away
+    /// compared to the engine's best move.
+    pub async fn loss(&self, fen: &str, uci: &str) -> Result<u32, EngineError> {
+        let best = self.evaluate(fen, &[]).await?;
+        let after = -self.evaluate(fen, &[uci]).await?;
+        Ok(best.saturating_sub(after).max(0) as u32)
+    }
+
+    /// The engine's evaluation in centipawns, for the side to move, of the
+    /// position after playing `moves` (in UCI) from `fen`.
+    pub async fn evaluate(&self, fen: &str, moves: &[&str]) -> Result<i32, EngineError> {
+        self.run_search(fen, moves, 1).await?.score.ok_or_else(|| EngineError::NoScore {
+            program: self.program.clone(),
+        })
+    }
+
+    /// The move (in UCI) the engine plays in the position after playing
+    /// `moves` from `fen`.
+    pub async fn best_move(&self, fen: &str, moves: &[&str]) -> Result<String, EngineError> {
+        self.run_search(fen, moves, 1).await?.best_move.ok_or_else(|| EngineError::NoMove {
+            program: self.program.clone(),
+        })
+    }
+
+    /// The engine's `count` best moves (in UCI) in the position `fen`, best
+    /// first, each with its evaluation in centipawns for the side to move.
+    /// Fewer if the position has fewer legal moves.
+    pub async fn top_moves(&self, fen: &str, count: usize) -> Result<Vec<(String, i32)>, EngineError> {
+        let search = self.run_search(fen, &[], count.max(1)).await?;
+        if search.lines.is_empty() && search.best_move.is_some() {
+            return Err(EngineError::NoScore {
+                program: self.program.clone(),
+            });
+        }
+        Ok(search.lines.into_values().take(count).collect())
+    }
+
+    async fn run_search(&self, fen: &str, moves: &[&str], multipv: usize) -> Result<Search, EngineError> {
+        let search = self.search(fen, moves, multipv);
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, search).await.map_err(|_| EngineError::Timeout {
+                program: self.program.clone(),
+                timeout,
+            })?,
+            None => search.await,
+        }
+    }
+
+    async fn search(&self, fen: &str, moves: &[&str], multipv: usize) -> Result<Search, EngineError> {
+        let failed = |e: std::io::Error| EngineError::Io {
+            program: self.program.clone(),
+            reason: e.to_string(),
+        };
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(failed)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let position = if moves.is_empty() {
+            format!("position fen {}", fen)
+        } else {
+            format!("position fen {} moves {}", fen, moves.join(" "))
+        };
+        let mut options: String = self
+            .options
+            .iter()
+            .map(|(name, value)| format!("setoption name {} value {}\n", name, value))
+            .collect();
+        if multipv > 1 {
+            options.push_str(&format!("setoption name MultiPV value {}\n", multipv));
+        }
+        let commands = format!(
+            "uci\n{}isready\nucinewgame\n{}\ngo depth {}\n",
+            options, position, self.depth
+        );
+        stdin.write_all(commands.as_bytes()).await.map_err(failed)?;
+
+        // The last score reported before `bestmove` is from the deepest search.
+        let mut score = None;
+        let mut best_move = None;
+        let mut pvs = BTreeMap::new();
+        let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        while let Some(line) = lines.next_line().await.map_err(failed)? {
+            if let Some(rest) = line.strip_prefix("bestmove") {
+                // `bestmove (none)` when the side to move has no legal move.
+                best_move = rest.split_whitespace().next().filter(|m| *m != "(none)").map(str::to_string);
+                break;
+            }
+            if line.starts_with("info")
+                && let Some(line_score) = parse_score(&line)
+            {
+                let index = word_after(&line, "multipv").and_then(|n| n.parse().ok()).unwrap_or(1);
+                if index == 1 {
+                    score = Some(line_score);
+                }
+                if let Some(first) = word_after(&line, "pv") {
+                    pvs.insert(index, (first.to_string(), line_score));
+                }
+            }
+        }
+        let _ = stdin.write_all(b"quit\n").await;
+        drop(stdin);
+        let _ = child.wait().await;
+        Ok(Search {
+            score,
+            best_move,
+            lines: pvs,
+        })
+    }
+}
+
+/// The `score cp <n>` or `score mate <n>` of a UCI `info` line, in
+/// centipawns. Mates are worth `MATE_SCORE` less the moves to mate; `mate
+/// 0` means the side to move is mated.
+fn parse_score(line: &str) -> Option<i32> {
+    let mut words = line.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "score" {
+            let value: i32 = match (words.next()?, words.next()?.parse().ok()?) {
+                ("cp", cp) => cp,
+                ("mate", moves) if moves > 0 => MATE_SCORE - moves,
+                ("mate", moves) => -MATE_SCORE - moves,
+                _ => return None,
+            };
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The word following `key` in a UCI `info` line.
+fn word_after<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let mut words = line.split_whitespace();
+    words.by_ref().find(|word| *word == key)?;
+    words.next()
+}
@@ -1,185 +1,680 @@
-use rayon::prelude::*;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
+use futures::future::join_all;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
 use std::env;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{Semaphore, watch};
+use tracing::Instrument;
+#[cfg(feature = "openai")]
+use tracing::debug;
+use tracing::{error, info, info_span, warn};
 
-use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleScore};
+use crate::augment::Augmentation;
+use crate::budget::{
+    Budget, ComputeBudget, EXPECTED_ANSWER_TOKENS, IMAGE_TOKENS, ReasoningEffort, TimeBudget, UsageSummary, UsageTracker,
+    estimate_tokens,
+};
+use crate::command::{CommandRequest, CommandSolver};
+use crate::compression::{Compression, DEFAULT_PRIORITY, FittedPrompt, PromptBudget, PromptPart, fit_prompt};
+use crate::extraction::{ExtractedAnswer, ExtractionMethod, extract_answer, extract_confidence};
+use crate::judge::Judge;
+use crate::prompt::{Modality, PromptStyle, Representation, StateView, build_prompt_with_rules, confidence_request};
+use crate::engine::UciEngine;
+#[cfg(feature = "encrypt")]
+use crate::encryption::{self, EncryptionError, SolutionKey};
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleError, PuzzleScore, PuzzleSelection, Scorer, Shard};
+use crate::scoring::{AnswerScoring, StateAnswer};
+use crate::stopping::{EarlyStop, EarlyStopping, RunningScore};
+use crate::telemetry::Telemetry;
+use crate::tracking::{RunProgress, Tracker};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PassResults {
-    pub pass_at_1: f64,
-    pub pass_at_n: f64,
-    pub individual_pass_scores: Vec<Vec<PuzzleScore>>,
+pub use crate::results::{
+    AblationRow, AnswerDistribution, BenchmarkResult, ComparisonReport, ComputePoint, ComputeSweep,
+    ComputeSweepRow, GameTypeScore, GroupScore, PassResults, PromptAblation, RenderStyleBreakdown, RepresentationAblation, RunMetadata, StyleScore,
+};
+
+/// Why a request to the model API failed.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0} environment variable not set")]
+    MissingEnv(String),
+    #[error("API request failed: {0}")]
+    Request(String),
+    #[error("No choices in response")]
+    NoChoices,
+    #[error("No content in response")]
+    NoContent,
+    #[error("Solver command failed: {0}")]
+    Command(String),
+}
+
+#[derive(Debug, Error)]
+pub enum RunnerError {
+    #[error("No puzzle collection given")]
+    MissingPuzzles,
+    #[error(transparent)]
+    Puzzle(#[from] PuzzleError),
+    #[error("Failed to write results: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize results: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("The collection's solutions are encrypted; give the runner its solution key")]
+    EncryptedSolutions,
+    #[cfg(feature = "encrypt")]
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+}
+
+/// Connection details and default request parameters for an
+/// OpenAI-compatible provider. Unset fields fall back to the
+/// `OPENAI_API_KEY` / `OPENAI_BASE_URL` environment variables.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderProfile {
+    pub base_url: Option<String>,
+    /// The key itself; prefer `api_key_env` to keep keys out of config files.
+    pub api_key: Option<String>,
+    /// Environment variable holding the key.
+    pub api_key_env: Option<String>,
+    pub temperature: Option<f64>,
+    /// Context window of the provider's models in tokens, for those not
+    /// known to `budget::context_window`.
+    pub context_window: Option<u64>,
+}
+
+/// One state of a puzzle as answered by a solver.
+#[derive(Debug, Clone)]
+pub struct StateAttempt {
+    pub response: String,
+    pub extracted: ExtractedAnswer,
+    /// How the prompt was shortened to fit the solver's budget.
+    pub compression: Vec<Compression>,
+    /// How long the request took, retries included; `None` when the state
+    /// wasn't asked.
+    pub latency_ms: Option<u64>,
+}
+
+impl StateAttempt {
+    pub fn unanswered() -> Self {
+        Self {
+            response: String::new(),
+            extracted: ExtractedAnswer::not_found(),
+            compression: Vec::new(),
+            latency_ms: None,
+        }
+    }
+}
+
+/// How failed API calls are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Wait before the first retry; doubles after each further failure.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Ends a run early. Once triggered, no new puzzles are started, and puzzles
+/// still in flight after the runner's grace period are left out of the
+/// results. Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    triggered: Arc<watch::Sender<bool>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BenchmarkResult {
-    pub benchmark_name: String,
-    pub solver_name: String,
-    pub solver_description: String,
-    pub total_puzzles: usize,
-    pub total_score: f64,
-    pub max_possible_score: f64,
-    pub average_score: f64,
-    pub puzzle_scores: Vec<PuzzleScore>,
-    pub game_type_breakdown: Vec<GameTypeScore>,
-    pub timestamp: String,
-    pub pass_results: Option<PassResults>,
+impl Default for Interrupt {
+    fn default() -> Self {
+        Self {
+            triggered: Arc::new(watch::channel(false).0),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GameTypeScore {
-    pub game_type: String,
-    pub count: usize,
-    pub average_score: f64,
+impl Interrupt {
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    async fn wait(&self) {
+        let mut triggered = self.triggered.subscribe();
+        // The sender lives as long as `self`, so this only returns once triggered.
+        let _ = triggered.wait_for(|&triggered| triggered).await;
+    }
 }
 
+/// Evaluates solvers on a puzzle collection. Runs are async tasks with at
+/// most `concurrency` puzzles in flight; each `*_async` method has a
+/// blocking counterpart for synchronous callers.
 pub struct BenchmarkRunner {
     pub puzzles: PuzzleCollection,
+    concurrency: usize,
+    passes: usize,
+    retry: RetryPolicy,
+    scorer: Scorer,
+    /// Overrides the collection's `answer_scoring`.
+    answer_scoring: Option<AnswerScoring>,
+    engine: Option<UciEngine>,
+    output_file: Option<String>,
+    time_budget: Option<TimeBudget>,
+    judge: Option<Judge>,
+    trackers: Vec<Box<dyn Tracker>>,
+    progress: Mutex<HashMap<String, RunProgress>>,
+    telemetry: Option<Arc<Telemetry>>,
+    seeds: BTreeMap<String, u64>,
+    interrupt: Interrupt,
+    interrupt_grace: Duration,
+    /// Metadata keys results are broken down by.
+    breakdown_keys: Vec<String>,
+    early_stopping: Option<EarlyStopping>,
+    /// The shard of the collection the selection kept, if any.
+    shard: Option<Shard>,
+    /// Checksum of the collection as given, before `selection`.
+    collection_checksum: String,
+    selection: Option<PuzzleSelection>,
+    /// Stop asking about a puzzle after its first wrong answer.
+    sequential: bool,
+    /// Decrypts the collection's solutions for scoring.
+    #[cfg(feature = "encrypt")]
+    solution_key: Option<SolutionKey>,
+    /// Per solver, the score so far and whether it was enough to stop.
+    running_scores: Mutex<HashMap<String, (RunningScore, Option<EarlyStop>)>>,
 }
 
 impl BenchmarkRunner {
+    /// Configure a runner step by step; see `BenchmarkRunnerBuilder`.
+    pub fn builder() -> BenchmarkRunnerBuilder {
+        BenchmarkRunnerBuilder::default()
+    }
+
     pub fn new(puzzles: PuzzleCollection) -> Self {
-        Self { puzzles }
+        let concurrency = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_concurrency(puzzles, concurrency)
+    }
+
+    /// Create a runner whose parallel runs evaluate at most `concurrency`
+    /// puzzles at a time.
+    pub fn with_concurrency(puzzles: PuzzleCollection, concurrency: usize) -> Self {
+        Self {
+            collection_checksum: puzzles.source_checksum(),
+            selection: None,
+            puzzles,
+            concurrency: concurrency.max(1),
+            passes: 1,
+            retry: RetryPolicy::default(),
+            scorer: Scorer::default(),
+            answer_scoring: None,
+            engine: None,
+            output_file: None,
+            time_budget: None,
+            judge: None,
+            trackers: Vec::new(),
+            progress: Mutex::new(HashMap::new()),
+            telemetry: None,
+            seeds: BTreeMap::new(),
+            interrupt: Interrupt::default(),
+            interrupt_grace: Duration::from_secs(30),
+            breakdown_keys: Vec::new(),
+            early_stopping: None,
+            shard: None,
+            sequential: false,
+            #[cfg(feature = "encrypt")]
+            solution_key: None,
+            running_scores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a seed that influenced the run (e.g. puzzle shuffling) in the
+    /// results' metadata.
+    pub fn with_seed(mut self, name: &str, seed: u64) -> Self {
+        self.seeds.insert(name.to_string(), seed);
+        self
+    }
+
+    /// A handle that stops this runner's runs early, e.g. from a Ctrl-C handler.
+    pub fn interrupt(&self) -> Interrupt {
+        self.interrupt.clone()
+    }
+
+    fn metadata(&self, solver: &Solver) -> RunMetadata {
+        let mut environment = BTreeMap::new();
+        environment.insert("os".to_string(), env::consts::OS.to_string());
+        environment.insert("arch".to_string(), env::consts::ARCH.to_string());
+        environment.insert("concurrency".to_string(), self.concurrency.to_string());
+        if self.sequential {
+            environment.insert("sequential".to_string(), "true".to_string());
+        }
+        // Endpoint only; keys and other secrets are never recorded.
+        if let Some(base_url) = &solver.base_url {
+            environment.insert("openai_base_url".to_string(), base_url.clone());
+        }
+
+        RunMetadata {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: option_env!("BOARDGAMEBENCH_GIT_COMMIT").map(str::to_string),
+            collection_name: self.puzzles.name.clone(),
+            collection_checksum: self.collection_checksum.clone(),
+            solver_parameters: solver.parameters(),
+            seeds: self.seeds.clone(),
+            environment,
+            shard: self.shard,
+            selection: self.selection.clone(),
+        }
+    }
+
+    /// Record a span per evaluated puzzle; pass the same `Telemetry` to the
+    /// solvers so their API calls nest under it.
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Report run progress and results to `tracker`.
+    pub fn with_tracker(mut self, tracker: Box<dyn Tracker>) -> Self {
+        self.trackers.push(tracker);
+        self
+    }
+
+    pub fn start_tracking(&self, config: &serde_json::Value) {
+        for tracker in &self.trackers {
+            tracker.start(config);
+        }
     }
 
-    pub fn from_file(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn end_tracking(&self) {
+        for tracker in &self.trackers {
+            tracker.end();
+        }
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.flush();
+        }
+    }
+
+    fn reset_progress(&self, solver: &Solver, total: usize) {
+        self.running_scores.lock().unwrap().remove(solver.name());
+        self.progress.lock().unwrap().insert(
+            solver.name().to_string(),
+            RunProgress {
+                solver_name: solver.name().to_string(),
+                total,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Add `score` to the solver's running score and stop its run if
+    /// that settles it.
+    fn record_running_score(&self, solver: &Solver, score: &PuzzleScore) {
+        let Some(early_stopping) = &self.early_stopping else {
+            return;
+        };
+        let mut running_scores = self.running_scores.lock().unwrap();
+        let (running, stop) = running_scores.entry(solver.name().to_string()).or_default();
+        running.add(score);
+        if stop.is_none()
+            && let Some(reached) = early_stopping.check(running)
+        {
+            info!(
+                solver = %solver.name,
+                evaluated = reached.evaluated,
+                score = reached.score,
+                half_width = reached.half_width,
+                reason = ?reached.reason,
+                "stopping early"
+            );
+            *stop = Some(reached);
+        }
+    }
+
+    fn early_stop(&self, solver: &Solver) -> Option<EarlyStop> {
+        self.running_scores
+            .lock()
+            .unwrap()
+            .get(solver.name())
+            .and_then(|(_, stop)| stop.clone())
+    }
+
+    fn record_progress(&self, solver: &Solver, score: &PuzzleScore) {
+        if self.trackers.is_empty() {
+            return;
+        }
+        let progress = {
+            let mut progress = self.progress.lock().unwrap();
+            let entry = progress.entry(solver.name().to_string()).or_default();
+            entry.solver_name = solver.name().to_string();
+            entry.completed += 1;
+            entry.score += score.weight * score.score;
+            entry.max_possible_score += score.weight * score.max_possible_score;
+            entry.cost = solver.usage_summary().cost;
+            entry.clone()
+        };
+        for tracker in &self.trackers {
+            tracker.puzzle_finished(score, &progress);
+        }
+    }
+
+    /// Grade every response's reasoning with `judge` in addition to the move.
+    pub fn with_judge(mut self, judge: Judge) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Penalize answers to puzzles that took longer than `time_budget`.
+    pub fn with_time_budget(mut self, time_budget: TimeBudget) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Self, PuzzleError> {
         let puzzles = PuzzleCollection::load_from_file(file_path)?;
         Ok(Self::new(puzzles))
     }
 
-    pub fn run_benchmark(&self, solver: &Solver) -> BenchmarkResult {
-        let puzzle_scores: Vec<PuzzleScore> = self
-            .puzzles
-            .puzzles
+    /// Solve and score one puzzle once a permit is free, or `None` if the
+    /// solver's budget ran out before it was done.
+    async fn evaluate_puzzle(
+        &self,
+        solver: &Solver,
+        puzzle: &Puzzle,
+        permits: &Semaphore,
+    ) -> Option<PuzzleScore> {
+        let _permit = permits.acquire().await.expect("semaphore is never closed");
+        if solver.budget_exceeded() || self.interrupt.is_triggered() || self.early_stop(solver).is_some() {
+            return None;
+        }
+        let mut span = self.telemetry.as_ref().map(|telemetry| {
+            let mut span = telemetry.task_span("puzzle");
+            span.set_attribute("puzzle_id", puzzle.id.as_str());
+            span.set_attribute("solver", solver.name());
+            span
+        });
+
+        let work = async {
+            let start = Instant::now();
+            let (attempts, skipped_states) = if self.sequential && puzzle.game_states.len() > 1 {
+                // Each answer decides whether to go on, so the solutions are
+                // needed while solving; the solver still sees the puzzle as is.
+                let solved = self.with_solutions(puzzle);
+                let scorer = self.answer_scoring().scorer();
+                let correct = |i: usize, attempt: &StateAttempt| {
+                    scorer.credit(&solved, &self.puzzles, i, &StateAnswer::new(&attempt.extracted.answer)) >= 1.0
+                };
+                let attempts = solver
+                    .solve_puzzle_until(puzzle, &self.puzzles, &self.retry, correct)
+                    .await;
+                let wrong = attempts.iter().enumerate().position(|(i, attempt)| !correct(i, attempt));
+                let skipped_states = wrong.map_or(0, |i| attempts.len() - i - 1);
+                (attempts, skipped_states)
+            } else {
+                let attempts = solver
+                    .solve_puzzle_with_retry(puzzle, &self.puzzles, &self.retry)
+                    .await;
+                (attempts, 0)
+            };
+            let elapsed = start.elapsed();
+            // The solver has answered; only now are the solutions needed.
+            let solved = self.with_solutions(puzzle);
+
+            let mut reasoning_scores = Vec::new();
+            if let Some(judge) = &self.judge {
+                for (i, attempt) in attempts.iter().enumerate() {
+                    reasoning_scores.push(
+                        judge
+                            .grade_async(&solved, &self.puzzles, i, &attempt.response)
+                            .await,
+                    );
+                }
+            }
+            let engine_losses = match &self.engine {
+                Some(engine) if self.answer_scoring() == AnswerScoring::EngineGraded => {
+                    self.engine_losses(engine, &solved, &attempts).await
+                }
+                _ => Vec::new(),
+            };
+            (attempts, skipped_states, elapsed, reasoning_scores, engine_losses, solved)
+        }
+        .instrument(info_span!("puzzle", id = %puzzle.id, solver = %solver.name));
+        let scoped = async {
+            match &span {
+                Some(span) => span.scope(work).await,
+                None => work.await,
+            }
+        };
+        let grace_over = async {
+            self.interrupt.wait().await;
+            tokio::time::sleep(self.interrupt_grace).await;
+        };
+        let (attempts, skipped_states, elapsed, reasoning_scores, engine_losses, solved) = tokio::select! {
+            done = scoped => done,
+            _ = grace_over => {
+                warn!(puzzle = %puzzle.id, solver = %solver.name, "dropping puzzle still in flight after interrupt");
+                return None;
+            }
+        };
+        // A puzzle the budget cut short counts as not attempted, like the
+        // ones after it, rather than as failed.
+        if attempts.len() < puzzle.game_states.len() {
+            warn!(puzzle = %puzzle.id, solver = %solver.name, "dropping puzzle the budget ran out during");
+            return None;
+        }
+
+        let answers: Vec<StateAnswer> = attempts
             .iter()
-            .map(|puzzle| {
-                let solution = solver.solve_puzzle(puzzle, &self.puzzles);
-                puzzle.validate_solution(&solution)
+            .enumerate()
+            .map(|(i, attempt)| StateAnswer {
+                answer: &attempt.extracted.answer,
+                judge_score: reasoning_scores.get(i).copied().flatten(),
+                engine_loss: engine_losses.get(i).copied().flatten(),
+                confidence: solver.confidence.then(|| extract_confidence(&attempt.response)).flatten(),
             })
             .collect();
+        let mut score = self
+            .scorer
+            .score_with(&solved, &self.puzzles, &answers, self.answer_scoring().scorer());
+        if solver.confidence && score.forecasts.is_empty() {
+            score.forecasts = vec![None; attempts.len()];
+        }
+        score.extraction_methods = attempts.iter().map(|a| a.extracted.method).collect();
+        score.compression = attempts.iter().flat_map(|a| a.compression.iter().copied()).collect();
+        score.compression.sort();
+        score.compression.dedup();
+        score.skipped_states = skipped_states;
+        score.elapsed_ms = elapsed.as_millis() as u64;
+        score.request_latencies_ms = attempts.iter().filter_map(|attempt| attempt.latency_ms).collect();
+        score.reasoning_scores = reasoning_scores;
+        score.engine_losses = engine_losses;
+        score.responses = attempts.into_iter().map(|attempt| attempt.response).collect();
 
-        let total_score: f64 = puzzle_scores.iter().map(|s| s.score).sum();
-        let max_possible_score: f64 = puzzle_scores.iter().map(|s| s.max_possible_score).sum();
-        let total_puzzles = puzzle_scores.len();
-        let average_score = if max_possible_score > 0.0 {
-            total_score / max_possible_score
-        } else {
-            0.0
-        };
+        if let Some(time_budget) = &self.time_budget {
+            let credit = time_budget.credit(elapsed);
+            if credit < 1.0 {
+                score.unpenalized_score = Some(score.score);
+                score.score *= credit;
+            }
+        }
+
+        if let Some(span) = &mut span {
+            span.set_attribute("score", score.score);
+            span.set_attribute("max_possible_score", score.max_possible_score);
+        }
+        self.record_progress(solver, &score);
+        self.record_running_score(solver, &score);
+        Some(score)
+    }
+
+    /// Solve and score `puzzle` alone, as a run would, e.g. for a queue
+    /// worker; `None` if the solver's budget was exhausted or the runner
+    /// interrupted.
+    pub async fn evaluate_one(&self, solver: &Solver, puzzle: &Puzzle) -> Option<PuzzleScore> {
+        self.evaluate_puzzle(solver, puzzle, &Semaphore::new(1)).await
+    }
+
+    /// `puzzle` with its solutions decrypted, if they are encrypted and the
+    /// runner has the key.
+    fn with_solutions<'a>(&self, puzzle: &'a Puzzle) -> Cow<'a, Puzzle> {
+        #[cfg(feature = "encrypt")]
+        if let Some(key) = &self.solution_key {
+            match encryption::decrypt_puzzle(puzzle, key) {
+                Ok(solved) => return solved,
+                Err(e) => error!(puzzle = %puzzle.id, error = %e, "scoring without solutions"),
+            }
+        }
+        Cow::Borrowed(puzzle)
+    }
+
+    /// How answers are credited: the run's choice, else the collection's.
+    fn answer_scoring(&self) -> AnswerScoring {
+        self.answer_scoring.or(self.puzzles.answer_scoring).unwrap_or_default()
+    }
 
-        // Calculate game type breakdown
-        let mut game_type_scores: std::collections::HashMap<String, (usize, f64, f64)> =
-            std::collections::HashMap::new();
-        for score in &puzzle_scores {
-            let entry = game_type_scores
-                .entry(self.puzzles.game_type.clone())
-                .or_insert((0, 0.0, 0.0));
-            entry.0 += 1;
-            entry.1 += score.score;
-            entry.2 += score.max_possible_score;
-        }
-
-        let game_type_breakdown: Vec<GameTypeScore> = game_type_scores
-            .into_iter()
-            .map(|(game_type, (count, score, total_score))| GameTypeScore {
-                game_type,
-                count,
-                average_score: if total_score > 0.0 {
-                    score / total_score as f64
-                } else {
-                    0.0
+    /// How much each of `attempts` at a chess puzzle loses to `engine`'s
+    /// best move. Answers the puzzle accepts, and ones that aren't legal
+    /// moves, aren't graded.
+    async fn engine_losses(&self, engine: &UciEngine, puzzle: &Puzzle, attempts: &[StateAttempt]) -> Vec<Option<u32>> {
+        let notation = self.puzzles.answer_format_of(puzzle).grammar.move_notation();
+        let mut losses = Vec::with_capacity(attempts.len());
+        for (i, attempt) in attempts.iter().enumerate() {
+            let state = &puzzle.game_states[i];
+            let answer = &attempt.extracted.answer;
+            let uci = match notation {
+                Some(notation)
+                    if self.puzzles.game_type_of(puzzle) == "chess"
+                        && !puzzle.is_correct(&self.puzzles, i, answer) =>
+                {
+                    crate::notation::to_uci(notation, state, answer).ok()
+                }
+                _ => None,
+            };
+            let loss = match uci {
+                Some(uci) => match engine.loss(state, &uci).await {
+                    Ok(loss) => Some(loss),
+                    Err(e) => {
+                        warn!(puzzle = %puzzle.id, state = i, error = %e, "engine grading failed");
+                        None
+                    }
                 },
-            })
-            .collect();
+                None => None,
+            };
+            losses.push(loss);
+        }
+        losses
+    }
 
-        BenchmarkResult {
-            benchmark_name: format!("{} on {}", solver.name(), self.puzzles.name),
-            solver_name: solver.name().to_string(),
-            solver_description: solver.description().to_string(),
-            total_puzzles,
-            total_score,
-            max_possible_score,
-            average_score,
-            puzzle_scores,
-            game_type_breakdown,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            pass_results: None,
-        }
-    }
-
-    pub fn run_benchmark_parallel(&self, solver: &Solver, num_threads: usize) -> BenchmarkResult {
-        // Set the thread pool size for rayon
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build_global()
-            .expect("Failed to build thread pool");
-
-        let puzzle_scores: Vec<PuzzleScore> = self
-            .puzzles
-            .puzzles
-            .par_iter()
-            .map(|puzzle| {
-                let solution = solver.solve_puzzle(puzzle, &self.puzzles);
-                puzzle.validate_solution(&solution)
-            })
-            .collect();
+    /// Evaluate `solver` on every puzzle, at most `limit` at a time.
+    async fn evaluate_all(&self, solver: &Solver, limit: usize) -> Vec<PuzzleScore> {
+        let permits = Semaphore::new(limit);
+        join_all(
+            self.puzzles
+                .puzzles
+                .iter()
+                .map(|puzzle| self.evaluate_puzzle(solver, puzzle, &permits)),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
 
-        let total_score: f64 = puzzle_scores.iter().map(|s| s.score).sum();
-        let max_possible_score: f64 = puzzle_scores.iter().map(|s| s.max_possible_score).sum();
-        let total_puzzles = puzzle_scores.len();
-        let average_score = if max_possible_score > 0.0 {
-            total_score / max_possible_score
+    /// Run `solver` with the configured number of passes, writing the result
+    /// to the configured output file, if any.
+    pub fn run(&self, solver: &Solver) -> BenchmarkResult {
+        crate::runtime::block_on(self.run_async(solver))
+    }
+
+    pub async fn run_async(&self, solver: &Solver) -> BenchmarkResult {
+        let result = if self.passes > 1 {
+            self.run_benchmark_multiple_passes_async(solver, self.passes).await
         } else {
-            0.0
+            self.run_benchmark_async(solver).await
         };
+        if let Some(path) = &self.output_file
+            && let Err(e) = self.export_results(&result, path)
+        {
+            warn!(error = %e, path = %path, "could not export results");
+        }
+        result
+    }
 
-        // Calculate game type breakdown
-        let mut game_type_scores: std::collections::HashMap<String, (usize, f64, f64)> =
-            std::collections::HashMap::new();
-        for score in &puzzle_scores {
-            let entry = game_type_scores
-                .entry(self.puzzles.game_type.clone())
-                .or_insert((0, 0.0, 0.0));
-            entry.0 += 1;
-            entry.1 += score.score;
-            entry.2 += score.max_possible_score;
-        }
-
-        let game_type_breakdown: Vec<GameTypeScore> = game_type_scores
-            .into_iter()
-            .map(|(game_type, (count, score, total_score))| GameTypeScore {
-                game_type,
-                count,
-                average_score: if total_score > 0.0 {
-                    score / total_score as f64
-                } else {
-                    0.0
-                },
-            })
-            .collect();
+    /// Compare `solvers` with the configured number of passes, writing the
+    /// report to the configured output file, if any.
+    pub fn run_all(&self, solvers: &[&Solver]) -> ComparisonReport {
+        crate::runtime::block_on(self.run_all_async(solvers))
+    }
 
-        BenchmarkResult {
-            benchmark_name: format!("{} on {} (parallel)", solver.name(), self.puzzles.name),
-            solver_name: solver.name().to_string(),
-            solver_description: solver.description().to_string(),
-            total_puzzles,
-            total_score,
-            max_possible_score,
-            average_score,
-            puzzle_scores,
-            game_type_breakdown,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            pass_results: None,
+    pub async fn run_all_async(&self, solvers: &[&Solver]) -> ComparisonReport {
+        let report = if self.passes > 1 {
+            // One solver at a time so the concurrency limit holds overall.
+            let mut results = Vec::new();
+            for solver in solvers {
+                results.push(
+                    self.run_benchmark_multiple_passes_async(solver, self.passes)
+                        .await,
+                );
+            }
+            ComparisonReport::new(&self.puzzles.name, results)
+        } else {
+            self.run_comparison_async(solvers).await
+        };
+        if let Some(path) = &self.output_file
+            && let Err(e) = self.export_comparison(&report, path)
+        {
+            warn!(error = %e, path = %path, "could not export comparison");
         }
+        report
+    }
+
+    /// Evaluate one puzzle at a time, in collection order.
+    pub fn run_benchmark(&self, solver: &Solver) -> BenchmarkResult {
+        self.reset_progress(solver, self.puzzles.puzzles.len());
+        let puzzle_scores = crate::runtime::block_on(self.evaluate_all(solver, 1));
+
+        self.build_result(
+            solver,
+            format!("{} on {}", solver.name(), self.puzzles.name),
+            puzzle_scores,
+            None,
+        )
+    }
+
+    pub fn run_benchmark_parallel(&self, solver: &Solver) -> BenchmarkResult {
+        crate::runtime::block_on(self.run_benchmark_async(solver))
+    }
+
+    pub async fn run_benchmark_async(&self, solver: &Solver) -> BenchmarkResult {
+        self.reset_progress(solver, self.puzzles.puzzles.len());
+        let puzzle_scores = self.evaluate_all(solver, self.concurrency).await;
+
+        self.build_result(
+            solver,
+            format!("{} on {} (parallel)", solver.name(), self.puzzles.name),
+            puzzle_scores,
+            None,
+        )
     }
 
     pub fn export_results(
         &self,
         results: &BenchmarkResult,
         path: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), RunnerError> {
         let json = serde_json::to_string_pretty(results)?;
         std::fs::write(path, json)?;
         Ok(())
@@ -188,162 +683,429 @@ impl BenchmarkRunner {
     pub fn run_comparison(&self, solvers: &[&Solver]) -> Vec<BenchmarkResult> {
         solvers
             .iter()
-            .map(|solver| self.run_benchmark(*solver))
+            .map(|solver| self.run_benchmark(solver))
             .collect()
     }
 
-    pub fn run_benchmark_multiple_passes(
-        &self,
-        solver: &Solver,
-        num_threads: usize,
-        num_passes: usize,
-    ) -> BenchmarkResult {
-        // Set the thread pool size for rayon
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build_global()
-            .expect("Failed to build thread pool");
-
-        println!("Running {} passes for each puzzle...", num_passes);
+    pub fn run_comparison_parallel(&self, solvers: &[&Solver]) -> ComparisonReport {
+        crate::runtime::block_on(self.run_comparison_async(solvers))
+    }
 
-        // Run multiple passes in parallel
-        let all_pass_scores: Vec<Vec<PuzzleScore>> = (0..num_passes)
-            .into_par_iter()
-            .map(|pass| {
-                println!("Starting pass {}...", pass + 1);
-                let puzzle_scores: Vec<PuzzleScore> = self
-                    .puzzles
+    /// Run every solver on the collection at once, interleaving requests for
+    /// all (solver, puzzle) pairs under the runner's concurrency limit.
+    pub async fn run_comparison_async(&self, solvers: &[&Solver]) -> ComparisonReport {
+        for solver in solvers {
+            self.reset_progress(solver, self.puzzles.puzzles.len());
+        }
+        let permits = Semaphore::new(self.concurrency);
+        let per_solver = join_all(solvers.iter().map(|solver| {
+            join_all(
+                self.puzzles
                     .puzzles
-                    .par_iter()
-                    .map(|puzzle| {
-                        let solution = solver.solve_puzzle(puzzle, &self.puzzles);
-                        puzzle.validate_solution(&solution)
-                    })
-                    .collect();
-                puzzle_scores
+                    .iter()
+                    .map(|puzzle| self.evaluate_puzzle(solver, puzzle, &permits)),
+            )
+        }))
+        .await;
+
+        let results = solvers
+            .iter()
+            .zip(per_solver)
+            .map(|(solver, puzzle_scores)| {
+                self.build_result(
+                    solver,
+                    format!("{} on {} (parallel)", solver.name(), self.puzzles.name),
+                    puzzle_scores.into_iter().flatten().collect(),
+                    None,
+                )
             })
             .collect();
 
-        // Calculate pass@1 and pass@n
-        let pass_at_1 = Self::calculate_pass_rate(&all_pass_scores, 1);
-        let pass_at_n = Self::calculate_pass_rate(&all_pass_scores, num_passes);
+        ComparisonReport::new(&self.puzzles.name, results)
+    }
 
-        // Calculate aggregate scores across all passes
-        let aggregate_scores = Self::aggregate_scores(&all_pass_scores);
+    pub fn export_comparison(
+        &self,
+        report: &ComparisonReport,
+        path: &str,
+    ) -> Result<(), RunnerError> {
+        let json = serde_json::to_string_pretty(report)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
 
-        let total_score: f64 = aggregate_scores.iter().map(|s| s.score).sum();
-        let max_possible_score: f64 = aggregate_scores.iter().map(|s| s.max_possible_score).sum();
-        let total_puzzles = aggregate_scores.len();
-        let average_score = if max_possible_score > 0.0 {
-            total_score / max_possible_score
-        } else {
-            0.0
-        };
+    pub fn run_benchmark_multiple_passes(
+        &self,
+        solver: &Solver,
+        num_passes: usize,
+    ) -> BenchmarkResult {
+        crate::runtime::block_on(self.run_benchmark_multiple_passes_async(solver, num_passes))
+    }
 
-        // Calculate game type breakdown
-        let mut game_type_scores: std::collections::HashMap<String, (usize, f64, f64)> =
-            std::collections::HashMap::new();
-        for score in &aggregate_scores {
-            let entry = game_type_scores
-                .entry(self.puzzles.game_type.clone())
-                .or_insert((0, 0.0, 0.0));
-            entry.0 += 1;
-            entry.1 += score.score;
-            entry.2 += score.max_possible_score;
-        }
-
-        let game_type_breakdown: Vec<GameTypeScore> = game_type_scores
-            .into_iter()
-            .map(|(game_type, (count, score, total_score))| GameTypeScore {
-                game_type,
-                count,
-                average_score: if total_score > 0.0 {
-                    score / total_score as f64
-                } else {
-                    0.0
-                },
-            })
-            .collect();
+    pub async fn run_benchmark_multiple_passes_async(
+        &self,
+        solver: &Solver,
+        num_passes: usize,
+    ) -> BenchmarkResult {
+        info!(passes = num_passes, "running multiple passes for each puzzle");
+        self.reset_progress(solver, self.puzzles.puzzles.len() * num_passes);
 
-        BenchmarkResult {
-            benchmark_name: format!(
+        // Run multiple passes in parallel
+        let permits = Semaphore::new(self.concurrency);
+        let all_pass_scores: Vec<Vec<PuzzleScore>> = join_all((0..num_passes).map(|pass| {
+            let permits = &permits;
+            async move {
+                info!(pass = pass + 1, "starting pass");
+                join_all(
+                    self.puzzles
+                        .puzzles
+                        .iter()
+                        .map(|puzzle| self.evaluate_puzzle(solver, puzzle, permits)),
+                )
+                .await
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+        }))
+        .await;
+        let (pass_results, aggregate_scores) = PassResults::from_passes(all_pass_scores);
+
+        self.build_result(
+            solver,
+            format!(
                 "{} on {} ({} passes)",
                 solver.name(),
                 self.puzzles.name,
                 num_passes
             ),
-            solver_name: solver.name().to_string(),
-            solver_description: solver.description().to_string(),
-            total_puzzles,
-            total_score,
-            max_possible_score,
-            average_score,
-            puzzle_scores: aggregate_scores,
-            game_type_breakdown,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            pass_results: Some(PassResults {
-                pass_at_1,
-                pass_at_n,
-                individual_pass_scores: all_pass_scores,
-            }),
-        }
-    }
-
-    fn calculate_pass_rate(all_pass_scores: &[Vec<PuzzleScore>], k: usize) -> f64 {
-        let num_puzzles = all_pass_scores[0].len();
-        let mut passed_count = 0;
-
-        for puzzle_idx in 0..num_puzzles {
-            let mut max_score = 0.0;
-            for pass_scores in all_pass_scores.iter().take(k) {
-                let score = pass_scores[puzzle_idx].score;
-                let max_possible = pass_scores[puzzle_idx].max_possible_score;
-                if score == max_possible {
-                    max_score = max_possible;
-                    break;
-                }
-            }
-            if max_score > 0.0 {
-                passed_count += 1;
-            }
+            aggregate_scores,
+            Some(pass_results),
+        )
+    }
+
+    fn build_result(
+        &self,
+        solver: &Solver,
+        benchmark_name: String,
+        puzzle_scores: Vec<PuzzleScore>,
+        pass_results: Option<PassResults>,
+    ) -> BenchmarkResult {
+        let mut result = BenchmarkResult::summarize(
+            benchmark_name,
+            solver.name(),
+            solver.description(),
+            &self.puzzles,
+            puzzle_scores,
+        );
+        result.pass_results = pass_results;
+        result.summarize_latency(&self.puzzles);
+        result.usage = Some(solver.usage_summary());
+        result.budget_exceeded = solver.budget_exceeded();
+        result.interrupted = self.interrupt.is_triggered();
+        result.early_stop = self.early_stop(solver);
+        result.modality = solver.modality;
+        if solver.modality == Modality::Text {
+            result.representation = solver.representation;
+        }
+        result.prompt_style = solver.prompt_style;
+        result.compute_budget = solver.compute_budget();
+        if solver.modality == Modality::Image {
+            result.render_style_breakdown = RenderStyleBreakdown::new(&self.puzzles, &result.puzzle_scores);
         }
+        for key in &self.breakdown_keys {
+            result.add_metadata_breakdown(&self.puzzles, key);
+        }
+        result.metadata = Some(self.metadata(solver));
 
-        passed_count as f64 / num_puzzles as f64
+        for tracker in &self.trackers {
+            tracker.finish(&result);
+        }
+        result
     }
+}
 
-    fn aggregate_scores(all_pass_scores: &[Vec<PuzzleScore>]) -> Vec<PuzzleScore> {
-        let num_puzzles = all_pass_scores[0].len();
-        let mut aggregated = Vec::with_capacity(num_puzzles);
+/// Step-by-step configuration for a `BenchmarkRunner`. Only the puzzle
+/// collection is required.
+#[derive(Default)]
+pub struct BenchmarkRunnerBuilder {
+    puzzles: Option<PuzzleCollection>,
+    selection: Option<PuzzleSelection>,
+    concurrency: Option<usize>,
+    passes: Option<usize>,
+    retry: RetryPolicy,
+    scorer: Scorer,
+    answer_scoring: Option<AnswerScoring>,
+    engine: Option<UciEngine>,
+    judge: Option<Judge>,
+    time_budget: Option<TimeBudget>,
+    trackers: Vec<Box<dyn Tracker>>,
+    telemetry: Option<Arc<Telemetry>>,
+    output_file: Option<String>,
+    seeds: BTreeMap<String, u64>,
+    interrupt_grace: Option<Duration>,
+    breakdown_keys: Vec<String>,
+    early_stopping: Option<EarlyStopping>,
+    sequential: bool,
+    #[cfg(feature = "encrypt")]
+    solution_key: Option<SolutionKey>,
+}
 
-        for puzzle_idx in 0..num_puzzles {
-            let mut score = 0.0;
-            let mut max_possible_score = 0.0;
-            let puzzle_id = all_pass_scores[0][puzzle_idx].puzzle_id.clone();
+impl BenchmarkRunnerBuilder {
+    pub fn puzzles(mut self, puzzles: PuzzleCollection) -> Self {
+        self.puzzles = Some(puzzles);
+        self
+    }
 
-            for pass_scores in all_pass_scores {
-                score += pass_scores[puzzle_idx].score;
-                max_possible_score += pass_scores[puzzle_idx].max_possible_score;
-            }
+    /// Only run the puzzles matching `selection`.
+    pub fn select(mut self, selection: PuzzleSelection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Maximum number of puzzles evaluated at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Number of passes `run` and `run_all` make over each puzzle.
+    pub fn passes(mut self, passes: usize) -> Self {
+        self.passes = Some(passes);
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn scorer(mut self, scorer: Scorer) -> Self {
+        self.scorer = scorer;
+        self
+    }
+
+    /// How each answer is credited, overriding the collection's
+    /// `answer_scoring`.
+    pub fn answer_scoring(mut self, answer_scoring: AnswerScoring) -> Self {
+        self.answer_scoring = Some(answer_scoring);
+        self
+    }
+
+    /// The engine that grades wrong moves for `AnswerScoring::EngineGraded`.
+    pub fn engine(mut self, engine: UciEngine) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    pub fn judge(mut self, judge: Judge) -> Self {
+        self.judge = Some(judge);
+        self
+    }
 
-            aggregated.push(PuzzleScore {
-                puzzle_id,
-                score,
-                max_possible_score,
-            });
+    pub fn time_budget(mut self, time_budget: TimeBudget) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    pub fn tracker(mut self, tracker: Box<dyn Tracker>) -> Self {
+        self.trackers.push(tracker);
+        self
+    }
+
+    pub fn telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Where `run` and `run_all` write their results.
+    pub fn output_file(mut self, path: &str) -> Self {
+        self.output_file = Some(path.to_string());
+        self
+    }
+
+    pub fn seed(mut self, name: &str, seed: u64) -> Self {
+        self.seeds.insert(name.to_string(), seed);
+        self
+    }
+
+    /// How long puzzles in flight may still finish after an interrupt
+    /// (30 seconds by default).
+    pub fn interrupt_grace(mut self, grace: Duration) -> Self {
+        self.interrupt_grace = Some(grace);
+        self
+    }
+
+    /// Also break results down by the value of `key` in the puzzles'
+    /// metadata; see `BenchmarkResult::add_metadata_breakdown`.
+    pub fn breakdown_by(mut self, key: &str) -> Self {
+        self.breakdown_keys.push(key.to_string());
+        self
+    }
+
+    /// Stop each solver's run once its score is known well enough; see
+    /// `EarlyStopping`.
+    pub fn early_stopping(mut self, early_stopping: EarlyStopping) -> Self {
+        self.early_stopping = Some(early_stopping);
+        self
+    }
+
+    /// Stop asking about a multi-state puzzle after its first answer short
+    /// of full credit, as a puzzle line ends on a wrong move: the remaining
+    /// states go unanswered and score zero, and cost no requests.
+    pub fn sequential(mut self, sequential: bool) -> Self {
+        self.sequential = sequential;
+        self
+    }
+
+    /// Decrypt the collection's encrypted solutions with `key`, puzzle by
+    /// puzzle as each is scored; see `encryption`.
+    #[cfg(feature = "encrypt")]
+    pub fn solution_key(mut self, key: SolutionKey) -> Self {
+        self.solution_key = Some(key);
+        self
+    }
+
+    pub fn build(self) -> Result<BenchmarkRunner, RunnerError> {
+        let mut puzzles = self.puzzles.ok_or(RunnerError::MissingPuzzles)?;
+        #[cfg(feature = "encrypt")]
+        match &self.solution_key {
+            Some(key) => key.check(&puzzles)?,
+            None if puzzles.solution_key_id.is_some() => return Err(RunnerError::EncryptedSolutions),
+            None => {}
+        }
+        #[cfg(not(feature = "encrypt"))]
+        if puzzles.solution_key_id.is_some() {
+            return Err(RunnerError::EncryptedSolutions);
+        }
+        let collection_checksum = puzzles.source_checksum();
+        let mut seeds = self.seeds;
+        if let Some(selection) = &self.selection {
+            puzzles.select(selection);
+            if let Some(seed) = selection.shuffle_seed {
+                seeds.entry("shuffle".to_string()).or_insert(seed);
+            }
+            if let Some(sample) = &selection.sample {
+                seeds.entry("sample".to_string()).or_insert(sample.seed);
+            }
         }
 
-        aggregated
+        let mut runner = match self.concurrency {
+            Some(concurrency) => BenchmarkRunner::with_concurrency(puzzles, concurrency),
+            None => BenchmarkRunner::new(puzzles),
+        };
+        runner.passes = self.passes.unwrap_or(1).max(1);
+        runner.retry = self.retry;
+        runner.scorer = self.scorer;
+        runner.answer_scoring = self.answer_scoring;
+        runner.engine = self.engine;
+        runner.judge = self.judge;
+        runner.time_budget = self.time_budget;
+        runner.trackers = self.trackers;
+        runner.telemetry = self.telemetry;
+        runner.output_file = self.output_file;
+        runner.seeds = seeds;
+        runner.breakdown_keys = self.breakdown_keys;
+        runner.early_stopping = self.early_stopping;
+        runner.sequential = self.sequential;
+        runner.shard = self.selection.as_ref().and_then(|selection| selection.shard);
+        runner.collection_checksum = collection_checksum;
+        runner.selection = self.selection.filter(|selection| !selection.is_everything());
+        #[cfg(feature = "encrypt")]
+        {
+            runner.solution_key = self.solution_key;
+        }
+        if let Some(grace) = self.interrupt_grace {
+            runner.interrupt_grace = grace;
+        }
+        Ok(runner)
     }
 }
 
+/// Where a `Solver` sends its prompts.
+pub enum Backend {
+    #[cfg(feature = "openai")]
+    OpenAi(openai_api_rs::v1::api::Client),
+    Command(CommandSolver),
+}
+
 pub struct Solver {
     pub name: String,
     pub description: String,
     pub model: String,
-    pub client: openai_api_rs::v1::api::Client,
+    pub base_url: Option<String>,
+    pub backend: Backend,
+    pub budget: Budget,
+    pub usage: UsageTracker,
+    pub telemetry: Option<Arc<Telemetry>>,
+    pub temperature: Option<f64>,
+    /// Whether puzzles are shown as FEN text or as board images.
+    pub modality: Modality,
+    /// How states are written out in text prompts.
+    pub representation: Representation,
+    /// Perturbations applied to board images before they are sent.
+    pub augmentation: Augmentation,
+    /// Attach the moves leading to each state with its image, where the
+    /// puzzle has them.
+    pub sequences: bool,
+    /// How much guidance the instructions give.
+    pub prompt_style: PromptStyle,
+    /// Cap on the tokens of each response, e.g. to keep answer-only solvers
+    /// from reasoning before they answer.
+    pub max_completion_tokens: Option<u32>,
+    /// How long a reasoning model may think before answering.
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Ask for the confidence in each answer after it, for calibration
+    /// metrics; see `ConfidenceCalibration`.
+    pub confidence: bool,
+    /// The model's context window in tokens, if known; see `check_context`.
+    pub context_window: Option<u64>,
+    /// Most tokens a prompt may take before it is compressed; by default
+    /// whatever the context window leaves after the answer. See `prompt`.
+    pub prompt_budget: Option<u64>,
+    /// Which prompt parts compression keeps longest, most important first.
+    pub compression_priority: Vec<PromptPart>,
+}
+
+/// A prompt that, with room for its answer, would not fit the solver's
+/// context window.
+#[derive(Debug, Clone)]
+pub struct ContextOverflow {
+    pub puzzle_id: String,
+    pub state_index: usize,
+    pub prompt_tokens: u64,
+    pub answer_tokens: u64,
+    pub context_window: u64,
 }
 
 impl Solver {
+    /// A solver that runs `command` for every prompt; see `CommandSolver`.
+    pub fn from_command(command: CommandSolver) -> Self {
+        let command_line = command.command_line();
+        Self {
+            name: format!("Command Solver ({})", command_line),
+            description: format!("External solver `{}`", command_line),
+            model: command_line,
+            base_url: None,
+            backend: Backend::Command(command),
+            budget: Budget::default(),
+            usage: UsageTracker::default(),
+            telemetry: None,
+            temperature: None,
+            modality: Modality::Text,
+            representation: Representation::Fen,
+            augmentation: Augmentation::default(),
+            sequences: false,
+            prompt_style: PromptStyle::Full,
+            max_completion_tokens: None,
+            reasoning_effort: None,
+            confidence: false,
+            context_window: None,
+            prompt_budget: None,
+            compression_priority: DEFAULT_PRIORITY.to_vec(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -352,99 +1114,522 @@ impl Solver {
         &self.description
     }
 
-    pub fn test_api_reachability(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let prompt = "Please respond with the single word 'hello' to me.";
+    /// Request parameters that affect the model's answers.
+    pub fn parameters(&self) -> BTreeMap<String, serde_json::Value> {
+        let mut parameters = BTreeMap::new();
+        parameters.insert("model".to_string(), serde_json::json!(self.model));
+        parameters.insert("temperature".to_string(), serde_json::json!(self.temperature));
+        parameters.insert("modality".to_string(), serde_json::json!(self.modality));
+        if self.modality == Modality::Text && self.representation != Representation::Fen {
+            parameters.insert("representation".to_string(), serde_json::json!(self.representation));
+        }
+        if !self.augmentation.is_none() {
+            parameters.insert("augmentation".to_string(), serde_json::json!(self.augmentation.to_string()));
+        }
+        if self.modality == Modality::Image && self.sequences {
+            parameters.insert("sequences".to_string(), serde_json::json!(true));
+        }
+        if self.prompt_style != PromptStyle::Full {
+            parameters.insert("prompt_style".to_string(), serde_json::json!(self.prompt_style));
+        }
+        if let Some(max_tokens) = self.max_completion_tokens {
+            parameters.insert("max_completion_tokens".to_string(), serde_json::json!(max_tokens));
+        }
+        if let Some(effort) = self.reasoning_effort {
+            parameters.insert("reasoning_effort".to_string(), serde_json::json!(effort));
+        }
+        if self.confidence {
+            parameters.insert("confidence".to_string(), serde_json::json!(true));
+        }
+        if let Some(tokens) = self.prompt_budget {
+            parameters.insert("prompt_budget".to_string(), serde_json::json!(tokens));
+        }
+        if self.compression_priority != DEFAULT_PRIORITY {
+            parameters.insert("compression_priority".to_string(), serde_json::json!(self.compression_priority));
+        }
+        parameters
+    }
+
+    /// Show puzzles to a vision-capable model as their board images instead
+    /// of FEN text; see `Puzzle::images`.
+    pub fn with_modality(mut self, modality: Modality) -> Self {
+        self.modality = modality;
+        self
+    }
 
-        match tokio::runtime::Runtime::new()
-            .expect("Failed to create tokio runtime")
-            .block_on(async { self.call_openai_api(prompt).await })
-        {
-            Ok(response) => Ok(response),
-            Err(e) => Err(e),
+    /// Write states in text prompts as `representation`, e.g. a board
+    /// diagram instead of a FEN; see `build_text_prompt`.
+    pub fn with_representation(mut self, representation: Representation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Perturb board images before sending them, for robustness studies;
+    /// only affects the image modality.
+    pub fn with_augmentation(mut self, augmentation: Augmentation) -> Self {
+        self.augmentation = augmentation;
+        self
+    }
+
+    /// Show image-modality solvers the moves leading to each state along
+    /// with its board; see `Puzzle::sequences`.
+    pub fn with_sequences(mut self, sequences: bool) -> Self {
+        self.sequences = sequences;
+        self
+    }
+
+    /// Word the instructions in `style`, e.g. without any analysis
+    /// guidance; see `PromptStyle`.
+    pub fn with_prompt_style(mut self, style: PromptStyle) -> Self {
+        self.prompt_style = style;
+        self
+    }
+
+    /// Cut every response off after `max_tokens` tokens. Command solvers
+    /// are told the limit (see `CommandRequest::max_tokens`) but not held to
+    /// it.
+    pub fn with_max_completion_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_completion_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Ask reasoning models to think for `effort`; models and command
+    /// solvers without a reasoning effort ignore it.
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
+    /// Have the solver end each answer with how confident it is in it, so
+    /// results report its calibration; see `extract_confidence`.
+    pub fn with_confidence(mut self, confidence: bool) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Check prompts against a context window of `tokens`, for models
+    /// `budget::context_window` doesn't know.
+    pub fn with_context_window(mut self, tokens: u64) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Compress prompts of more than `tokens` tokens, rather than only
+    /// those that wouldn't fit the context window.
+    pub fn with_prompt_budget(mut self, tokens: u64) -> Self {
+        self.prompt_budget = Some(tokens);
+        self
+    }
+
+    /// Keep `priority`'s prompt parts longest when compressing, most
+    /// important first; parts left out are never shortened.
+    pub fn with_compression_priority(mut self, priority: Vec<PromptPart>) -> Self {
+        self.compression_priority = priority;
+        self
+    }
+
+    /// Room for the answer when checking prompts against the context window.
+    fn answer_tokens(&self) -> u64 {
+        self.max_completion_tokens.map_or(EXPECTED_ANSWER_TOKENS, u64::from)
+    }
+
+    /// The prompt for state `index` of `puzzle`, shown as a board image
+    /// (`image`) and with the moves leading to it (`sequence`) or as text.
+    /// Prompts over the prompt budget, or else over what the context window
+    /// leaves after the answer and images, are compressed to fit; see
+    /// `compression::fit_prompt`.
+    pub fn prompt(
+        &self,
+        puzzle: &Puzzle,
+        collection: &PuzzleCollection,
+        index: usize,
+        image: bool,
+        sequence: bool,
+    ) -> FittedPrompt {
+        let build = |rules: &str, representation: Representation| {
+            let view = match (image, sequence) {
+                (true, true) => StateView::Sequence,
+                (true, false) => StateView::Image,
+                (false, _) => StateView::Text(representation),
+            };
+            let prompt = build_prompt_with_rules(puzzle, collection, index, view, self.prompt_style, rules);
+            if self.confidence {
+                format!("{}\n\n{}", prompt, confidence_request(collection.language))
+            } else {
+                prompt
+            }
+        };
+        let attachments = u64::from(image) + u64::from(sequence);
+        let tokens = self.prompt_budget.or_else(|| {
+            self.context_window
+                .map(|window| window.saturating_sub(self.answer_tokens() + attachments * IMAGE_TOKENS))
+        });
+        match tokens {
+            Some(tokens) => {
+                let budget = PromptBudget {
+                    tokens,
+                    priority: self.compression_priority.clone(),
+                };
+                fit_prompt(&budget, &collection.game_rule, self.representation, build)
+            }
+            None => FittedPrompt {
+                text: build(&collection.game_rule, self.representation),
+                compression: Vec::new(),
+            },
+        }
+    }
+
+    /// Every prompt of `collection` whose estimated tokens (see
+    /// `estimate_tokens`), images included, plus room for the answer would
+    /// exceed the solver's context window. The answer gets
+    /// `max_completion_tokens`, or `EXPECTED_ANSWER_TOKENS` without a cap.
+    /// Empty when the window isn't known.
+    pub fn check_context(&self, collection: &PuzzleCollection) -> Vec<ContextOverflow> {
+        let Some(context_window) = self.context_window else {
+            return Vec::new();
+        };
+        let answer_tokens = self.answer_tokens();
+        let mut overflows = Vec::new();
+        for puzzle in &collection.puzzles {
+            for i in 0..puzzle.game_states.len() {
+                let image = self.modality == Modality::Image;
+                let sequence = image && self.sequences && puzzle.sequences.get(i).is_some();
+                let attachments = u64::from(image) + u64::from(sequence);
+                let prompt = self.prompt(puzzle, collection, i, image, sequence);
+                let prompt_tokens = estimate_tokens(&prompt.text) + attachments * IMAGE_TOKENS;
+                if prompt_tokens + answer_tokens > context_window {
+                    overflows.push(ContextOverflow {
+                        puzzle_id: puzzle.id.clone(),
+                        state_index: i,
+                        prompt_tokens,
+                        answer_tokens,
+                        context_window,
+                    });
+                }
+            }
+        }
+        overflows
+    }
+
+    /// The limits on each response this solver runs with.
+    pub fn compute_budget(&self) -> ComputeBudget {
+        ComputeBudget {
+            max_completion_tokens: self.max_completion_tokens,
+            reasoning_effort: self.reasoning_effort,
         }
     }
 
-    pub fn solve_puzzle(&self, puzzle: &Puzzle, puzzle_collection: &PuzzleCollection) -> Vec<String> {
-        let mut results = Vec::new();
-        let regex = Regex::new(r"\*\*Answer:\s*(\S+?)\*\*").unwrap();
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn budget_exceeded(&self) -> bool {
+        self.usage.exceeds(&self.budget)
+    }
+
+    pub fn usage_summary(&self) -> UsageSummary {
+        self.usage.summary(&self.budget.pricing)
+    }
+
+    pub fn test_api_reachability(&self) -> Result<String, ApiError> {
+        self.complete("Please respond with the single word 'hello' to me.")
+    }
+
+    /// Send a single prompt and block until the model's reply arrives.
+    pub fn complete(&self, prompt: &str) -> Result<String, ApiError> {
+        crate::runtime::block_on(self.complete_async(prompt))
+    }
+
+    /// Send a single prompt and wait for the model's reply.
+    pub async fn complete_async(&self, prompt: &str) -> Result<String, ApiError> {
+        self.request_async(&CommandRequest::prompt(prompt)).await
+    }
+
+    async fn request_async(&self, request: &CommandRequest<'_>) -> Result<String, ApiError> {
+        let mut span = self.telemetry.as_ref().map(|telemetry| {
+            let mut span = telemetry.task_span("api_call");
+            span.set_attribute("model", self.model.as_str());
+            span.set_attribute("prompt_chars", request.prompt.len());
+            span
+        });
+
+        let result = self.call_backend(request).await;
+
+        if let (Some(span), Some(telemetry)) = (&mut span, &self.telemetry) {
+            match &result {
+                Ok(response) => span.set_attribute("response_chars", response.len()),
+                Err(e) => {
+                    telemetry.record_error();
+                    span.set_error(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Like `complete_async`, retrying failures with exponential backoff
+    /// while the budget allows.
+    pub async fn complete_with_retry(
+        &self,
+        prompt: &str,
+        retry: &RetryPolicy,
+    ) -> Result<String, ApiError> {
+        self.request_with_retry(&CommandRequest::prompt(prompt), retry)
+            .await
+    }
+
+    async fn request_with_retry(
+        &self,
+        request: &CommandRequest<'_>,
+        retry: &RetryPolicy,
+    ) -> Result<String, ApiError> {
+        let mut backoff = retry.backoff;
+        let mut retries = 0;
+        loop {
+            match self.request_async(request).await {
+                Err(e) if retries < retry.max_retries && !self.budget_exceeded() => {
+                    retries += 1;
+                    warn!(error = %e, retry = retries, "API call failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Record spans and request/token counters for this solver's API calls.
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    pub fn solve_puzzle(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+    ) -> Vec<StateAttempt> {
+        crate::runtime::block_on(self.solve_puzzle_async(puzzle, puzzle_collection))
+    }
+
+    /// Ask for each state of `puzzle` in turn; later states are only asked
+    /// after earlier replies arrive.
+    pub async fn solve_puzzle_async(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+    ) -> Vec<StateAttempt> {
+        self.solve_puzzle_with_retry(puzzle, puzzle_collection, &RetryPolicy::default())
+            .await
+    }
+
+    /// Like `solve_puzzle_async`, retrying failed API calls per `retry`.
+    pub async fn solve_puzzle_with_retry(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+        retry: &RetryPolicy,
+    ) -> Vec<StateAttempt> {
+        self.solve_puzzle_until(puzzle, puzzle_collection, retry, |_, _| true)
+            .await
+    }
+
+    /// Like `solve_puzzle_with_retry`, leaving the states after the first
+    /// attempt `keep_going` rejects unanswered without asking about them.
+    /// Stops early, with fewer attempts than states, once the solver's
+    /// budget is exhausted.
+    pub async fn solve_puzzle_until(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+        retry: &RetryPolicy,
+        keep_going: impl Fn(usize, &StateAttempt) -> bool,
+    ) -> Vec<StateAttempt> {
+        let mut results: Vec<StateAttempt> = Vec::new();
+        let answer_format = puzzle_collection.answer_format_of(puzzle);
 
         for i in 0..puzzle.game_states.len() {
-            let prompt = self.build_prompt(puzzle, puzzle_collection, i);
+            if i > 0 && !keep_going(i - 1, &results[i - 1]) {
+                results.resize_with(puzzle.game_states.len(), StateAttempt::unanswered);
+                break;
+            }
+            if self.budget_exceeded() {
+                break;
+            }
+            let image = match self.modality {
+                Modality::Text => None,
+                Modality::Image => match puzzle_collection.state_image(puzzle, i) {
+                    Ok(Some(image)) => {
+                        let seed = self.augmentation.image_seed(&puzzle.id, i);
+                        match self.augmentation.apply_to_data_uri(&image, seed) {
+                            Ok(image) => Some(image),
+                            Err(e) => {
+                                error!(state = i, error = %e, "could not augment state image");
+                                results.push(StateAttempt::unanswered());
+                                continue;
+                            }
+                        }
+                    }
+                    Ok(None) => {
+                        error!(state = i, "puzzle has no image for this state");
+                        results.push(StateAttempt::unanswered());
+                        continue;
+                    }
+                    Err(e) => {
+                        error!(state = i, error = %e, "could not load state image");
+                        results.push(StateAttempt::unanswered());
+                        continue;
+                    }
+                },
+            };
+            // Puzzles without a sequence for this state are shown the board alone.
+            let sequence = match (&image, self.sequences) {
+                (Some(_), true) => puzzle_collection.state_sequence(puzzle, i).unwrap_or_else(|e| {
+                    warn!(state = i, error = %e, "could not load state sequence");
+                    None
+                }),
+                _ => None,
+            };
+            let prompt = self.prompt(puzzle, puzzle_collection, i, image.is_some(), sequence.is_some());
+            let request = CommandRequest {
+                prompt: &prompt.text,
+                puzzle_id: Some(&puzzle.id),
+                state_index: Some(i),
+                game_type: Some(puzzle_collection.game_type_of(puzzle)),
+                state: image.is_none().then_some(puzzle.game_states[i].as_str()),
+                answer_notation: Some(answer_format.grammar.description()),
+                image: image.as_deref(),
+                sequence: sequence.as_deref(),
+                max_tokens: self.max_completion_tokens,
+                reasoning_effort: self.reasoning_effort,
+            };
 
-            match tokio::runtime::Runtime::new()
-                .expect("Failed to create tokio runtime")
-                .block_on(async { self.call_openai_api(&prompt).await })
-            {
+            let start = Instant::now();
+            match self.request_with_retry(&request, retry).await {
                 Ok(response) => {
-                    if let Some(caps) = regex.captures_iter(&response).last() {
-                        let answer = caps
-                            .get(1)
-                            .map(|m| m.as_str().trim().to_lowercase())
-                            .unwrap();
-                        println!("Puzzle {} state {} Got {}, expected {}", puzzle.id, i, answer, puzzle.solutions[i]);
-                        results.push(answer);
+                    let extracted = extract_answer(
+                        &response,
+                        &puzzle.game_states[i],
+                        &answer_format,
+                    );
+                    if extracted.method == ExtractionMethod::NotFound {
+                        warn!(state = i, "no answer found in response");
                     } else {
-                        eprintln!(
-                            "No answer found in response for puzzle {} state {}",
-                            puzzle.id, i
+                        info!(
+                            state = i,
+                            answer = %extracted.answer,
+                            method = ?extracted.method,
+                            expected = ?puzzle.solutions.get(i),
+                            "extracted answer"
                         );
-                        results.push("".to_string());
                     }
+                    results.push(StateAttempt {
+                        response,
+                        extracted,
+                        compression: prompt.compression,
+                        latency_ms: Some(start.elapsed().as_millis() as u64),
+                    });
                 }
                 Err(e) => {
-                    eprintln!(
-                        "Error calling OpenAI API for puzzle {} state {}: {}",
-                        puzzle.id, i, e
-                    );
-                    results.push("".to_string());
+                    error!(state = i, error = %e, "solver request failed");
+                    // Failed and timed-out calls are often the slowest, so
+                    // they count towards the latency percentiles too.
+                    results.push(StateAttempt {
+                        latency_ms: Some(start.elapsed().as_millis() as u64),
+                        ..StateAttempt::unanswered()
+                    });
                 }
             }
         }
 
         results
     }
+
+    async fn call_backend(&self, request: &CommandRequest<'_>) -> Result<String, ApiError> {
+        match &self.backend {
+            #[cfg(feature = "openai")]
+            Backend::OpenAi(client) => match request.image {
+                Some(image) => {
+                    let images: Vec<&str> = request.sequence.into_iter().chain([image]).collect();
+                    self.call_openai_vision(client, request.prompt, &images).await
+                }
+                None if self.reasoning_effort.is_some() => self.call_openai_vision(client, request.prompt, &[]).await,
+                None => self.call_openai_api(client, request.prompt).await,
+            },
+            Backend::Command(command) => {
+                let reply = command.run(request).await?;
+                self.record_usage(reply.prompt_tokens, reply.completion_tokens);
+                Ok(reply.response)
+            }
+        }
+    }
+
+    fn record_usage(&self, prompt_tokens: u64, completion_tokens: u64) {
+        self.usage.record(prompt_tokens, completion_tokens);
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.record_request(prompt_tokens, completion_tokens);
+        }
+    }
 }
 
+#[cfg(feature = "openai")]
 impl Solver {
-    pub fn new(model: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let api_key = env::var("OPENAI_API_KEY")
-            .map_err(|_| "OPENAI_API_KEY environment variable not set")?;
+    /// A solver for `model` configured from the `OPENAI_API_KEY` and
+    /// `OPENAI_BASE_URL` environment variables.
+    pub fn new(model: String) -> Result<Self, ApiError> {
+        Self::from_profile(model, &ProviderProfile::default())
+    }
 
-        let base_url = env::var("OPENAI_BASE_URL").unwrap();
+    /// A solver for `model` on the provider described by `profile`.
+    pub fn from_profile(model: String, profile: &ProviderProfile) -> Result<Self, ApiError> {
+        let env_var = |name: &str| env::var(name).map_err(|_| ApiError::MissingEnv(name.to_string()));
+        let api_key = match &profile.api_key {
+            Some(api_key) => api_key.clone(),
+            None => env_var(profile.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY"))?,
+        };
+        let base_url = match &profile.base_url {
+            Some(base_url) => base_url.clone(),
+            None => env_var("OPENAI_BASE_URL")?,
+        };
 
-        let client = openai_api_rs::v1::api::Client::new_with_endpoint(base_url, api_key);
+        let client = openai_api_rs::v1::api::Client::new_with_endpoint(base_url.clone(), api_key);
+        let context_window = profile.context_window.or_else(|| crate::budget::context_window(&model));
 
         Ok(Self {
             name: format!("OpenAI Solver ({})", model),
             description: format!("OpenAI API solver using {} model", model),
             model,
-            client,
+            base_url: Some(base_url),
+            backend: Backend::OpenAi(client),
+            budget: Budget::default(),
+            usage: UsageTracker::default(),
+            telemetry: None,
+            temperature: Some(profile.temperature.unwrap_or(0.5)),
+            modality: Modality::Text,
+            representation: Representation::Fen,
+            augmentation: Augmentation::default(),
+            sequences: false,
+            prompt_style: PromptStyle::Full,
+            max_completion_tokens: None,
+            reasoning_effort: None,
+            confidence: false,
+            context_window,
+            prompt_budget: None,
+            compression_priority: DEFAULT_PRIORITY.to_vec(),
         })
     }
 
-    fn build_prompt(&self, puzzle: &Puzzle, puzzle_collection: &PuzzleCollection, index: usize) -> String {
-        let game_type = &puzzle_collection.game_type;
-        let goal = &puzzle_collection.goal;
-        let fen = &puzzle.game_states[index];
-        format!(
-            "You are a highly advanced AI specialized in solving abstract board game puzzles.
-Your task is to analyze the given game state and provide a detailed strategic evaluation along with the best possible move.
-Follow these guidelines to ensure optimal performance:
-1. **Understanding the Game Rules**: Begin by thoroughly explaining the rules of {game_type} in the context of the current puzzle. Highlight unique aspects like movement patterns of pieces, special moves, and endgame conditions.
-2. **Game State Analysis**: Assess the current state of the {game_type} board. Identify key factors such as:
-  - Material balance: Compare the pieces on both sides.
-  - Positioning: Evaluate the placement of pieces, control of the center, and potential threats.
-  - Tactical opportunities: Look for immediate tactical shots like forks, pins, or discovered attacks.
-  - Strategic considerations: Discuss long-term plans, weaknesses, and strengths of each side.
-3. **Best Move Recommendation**: Propose several moves based on your analysis. Think of possible responses from the opponent and how to counteract them. Choose the best move that maximizes your advantage or minimizes your losses.
-4. **Goal of the Puzzle**: Keep in mind that the primary objective is: {goal}. Tailor your analysis and move recommendations to align with this goal.
-5. **Formatting and Clarity**: Provide your final answer in the following format: **Answer: <your move here>**, where your move is represented in UCI notation, e.g., e2e4, e1g1 (castling), e7e8q (promotion). Ensure your response is separated from the analysis in one line for clarity.
-
-The puzzle is given by FEN string: {fen}",
-        )
-    }
-
-    async fn call_openai_api(&self, prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+    async fn call_openai_api(
+        &self,
+        client: &openai_api_rs::v1::api::Client,
+        prompt: &str,
+    ) -> Result<String, ApiError> {
         let request = openai_api_rs::v1::chat_completion::ChatCompletionRequest {
             model: self.model.clone(),
             messages: vec![openai_api_rs::v1::chat_completion::ChatCompletionMessage {
@@ -453,8 +1638,8 @@ The puzzle is given by FEN string: {fen}",
                 name: None,
                 function_call: None,
             }],
-            max_tokens: None,
-            temperature: Some(0.5),
+            max_tokens: self.max_completion_tokens.map(i64::from),
+            temperature: self.temperature,
             top_p: None,
             n: None,
             stream: None,
@@ -468,21 +1653,103 @@ The puzzle is given by FEN string: {fen}",
         };
 
         let start = Instant::now();
-        println!("call_openai_api(): starting at {}ms... ", start.elapsed().as_millis());
+        debug!(model = %self.model, prompt_chars = prompt.len(), "sending chat completion request");
 
-        let response = self.client.chat_completion(request).await?;
+        let response = client
+            .chat_completion(request)
+            .await
+            .map_err(|e| ApiError::Request(e.to_string()))?;
         let duration = start.elapsed();
 
-        println!("call_openai_api(): prompt_len={}, completion_len={}, duration={}ms", response.usage.prompt_tokens, response.usage.completion_tokens, duration.as_millis());
+        self.record_usage(
+            response.usage.prompt_tokens as u64,
+            response.usage.completion_tokens as u64,
+        );
+        debug!(
+            prompt_tokens = response.usage.prompt_tokens,
+            completion_tokens = response.usage.completion_tokens,
+            duration_ms = duration.as_millis() as u64,
+            "chat completion finished"
+        );
 
         if let Some(choice) = response.choices.first() {
             if let Some(content) = &choice.message.content {
                 Ok(content.to_string())
             } else {
-                Err("No content in response".into())
+                Err(ApiError::NoContent)
             }
         } else {
-            Err("No choices in response".into())
+            Err(ApiError::NoChoices)
         }
     }
+
+    /// Send `prompt` with `images` (`data:` URIs) attached, in order. The
+    /// chat types of `openai_api_rs` only carry text and have no reasoning
+    /// effort, so this posts the request directly; text prompts with a
+    /// reasoning effort come here without images.
+    async fn call_openai_vision(
+        &self,
+        client: &openai_api_rs::v1::api::Client,
+        prompt: &str,
+        images: &[&str],
+    ) -> Result<String, ApiError> {
+        let mut content = vec![serde_json::json!({ "type": "text", "text": prompt })];
+        content.extend(
+            images
+                .iter()
+                .map(|image| serde_json::json!({ "type": "image_url", "image_url": { "url": image } })),
+        );
+        let mut request = serde_json::json!({
+            "model": self.model,
+            "temperature": self.temperature,
+            "messages": [{
+                "role": "user",
+                "content": content,
+            }],
+        });
+        if images.is_empty() {
+            request["messages"][0]["content"] = serde_json::json!(prompt);
+        }
+        if let Some(max_tokens) = self.max_completion_tokens {
+            request["max_tokens"] = serde_json::json!(max_tokens);
+        }
+        if let Some(effort) = self.reasoning_effort {
+            request["reasoning_effort"] = serde_json::json!(effort);
+        }
+
+        let start = Instant::now();
+        debug!(model = %self.model, prompt_chars = prompt.len(), image_chars = images.iter().map(|image| image.len()).sum::<usize>(), "sending vision chat completion request");
+
+        let request_failed = |e: reqwest::Error| ApiError::Request(e.to_string());
+        let response = reqwest::Client::new()
+            .post(format!("{}/chat/completions", client.api_endpoint))
+            .bearer_auth(&client.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(request_failed)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Request(format!("{}: {}", status, body)));
+        }
+        let response: serde_json::Value = response.json().await.map_err(request_failed)?;
+        let duration = start.elapsed();
+
+        let tokens = |name: &str| response["usage"][name].as_u64().unwrap_or(0);
+        self.record_usage(tokens("prompt_tokens"), tokens("completion_tokens"));
+        debug!(
+            prompt_tokens = tokens("prompt_tokens"),
+            completion_tokens = tokens("completion_tokens"),
+            duration_ms = duration.as_millis() as u64,
+            "vision chat completion finished"
+        );
+
+        let choice = response["choices"].get(0).ok_or(ApiError::NoChoices)?;
+        choice["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(ApiError::NoContent)
+    }
 }
+
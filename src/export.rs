@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+
+use crate::results::BenchmarkResult;
+use crate::prompt::build_prompt;
+use crate::puzzle::{Provenance, PuzzleCollection};
+
+/// Results in the lm-evaluation-harness layout, plus one sample record per
+/// puzzle as the harness writes to its `samples_<task>.jsonl` files.
+pub struct LmEvalExport {
+    pub results: Value,
+    pub samples: Vec<Value>,
+}
+
+/// Convert a benchmark result into lm-evaluation-harness JSON. `acc` is the
+/// (weighted) average score and `exact_match` the fraction of puzzles fully
+/// solved; both report a standard error over puzzles. Given the collection
+/// the result was run on, the task config and each sample carry its
+/// provenance.
+pub fn to_lm_eval_harness(result: &BenchmarkResult, task: &str, collection: Option<&PuzzleCollection>) -> LmEvalExport {
+    let fractions: Vec<f64> = result
+        .puzzle_scores
+        .iter()
+        .map(|s| {
+            if s.max_possible_score > 0.0 {
+                s.score / s.max_possible_score
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let solved: Vec<f64> = result
+        .puzzle_scores
+        .iter()
+        .map(|s| if s.score == s.max_possible_score { 1.0 } else { 0.0 })
+        .collect();
+    let exact_match = mean(&solved);
+    let n = result.puzzle_scores.len();
+
+    let mut results = json!({
+        "results": {
+            task: {
+                "alias": task,
+                "acc,none": result.average_score,
+                "acc_stderr,none": stderr(&fractions),
+                "exact_match,none": exact_match,
+                "exact_match_stderr,none": stderr(&solved),
+            }
+        },
+        "group_subtasks": { task: [] },
+        "configs": {
+            task: {
+                "task": task,
+                "output_type": "generate_until",
+                "metric_list": [
+                    { "metric": "acc", "aggregation": "mean", "higher_is_better": true },
+                    { "metric": "exact_match", "aggregation": "mean", "higher_is_better": true },
+                ],
+                "description": result.benchmark_name,
+            }
+        },
+        "versions": { task: 1 },
+        "n-shot": { task: 0 },
+        "higher_is_better": { task: { "acc": true, "exact_match": true } },
+        "n-samples": { task: { "original": n, "effective": n } },
+        "config": {
+            "model": result.solver_name,
+            "model_args": result.solver_description,
+        },
+        "date": result.timestamp,
+    });
+    if let Some(collection) = collection
+        && !collection.provenance.is_empty()
+    {
+        results["configs"][task]["metadata"] = json!({ "provenance": collection.provenance });
+    }
+
+    let samples = result
+        .puzzle_scores
+        .iter()
+        .zip(&fractions)
+        .enumerate()
+        .map(|(doc_id, (score, acc))| {
+            let mut doc = json!({ "id": score.puzzle_id });
+            if let Some(provenance) = collection.and_then(|collection| provenance(collection, &score.puzzle_id)) {
+                doc["provenance"] = json!(provenance);
+            }
+            json!({
+                "doc_id": doc_id,
+                "doc": doc,
+                "resps": [score.answers],
+                "filtered_resps": score.answers,
+                "acc": acc,
+                "exact_match": if score.score == score.max_possible_score { 1.0 } else { 0.0 },
+            })
+        })
+        .collect();
+
+    LmEvalExport { results, samples }
+}
+
+/// Convert a collection into OpenAI Evals / simple-evals samples: one
+/// record per game state with the prompt as a chat message and the stored
+/// solution as the ideal answer. Each sample's metadata carries its puzzle's
+/// provenance, if known.
+pub fn to_openai_evals(collection: &PuzzleCollection) -> Vec<Value> {
+    collection
+        .puzzles
+        .iter()
+        .flat_map(|puzzle| {
+            let provenance = collection.provenance_of(puzzle);
+            (0..puzzle.game_states.len()).map(move |i| {
+                let mut metadata = json!({ "puzzle_id": puzzle.id, "state_index": i });
+                if !provenance.is_empty() {
+                    metadata["provenance"] = json!(provenance);
+                }
+                json!({
+                    "input": [
+                        { "role": "user", "content": build_prompt(puzzle, collection, i) }
+                    ],
+                    "ideal": puzzle.solutions[i],
+                    "metadata": metadata,
+                })
+            })
+        })
+        .collect()
+}
+
+/// The provenance of the puzzle with id `puzzle_id`, unless it is unknown.
+fn provenance(collection: &PuzzleCollection, puzzle_id: &str) -> Option<Provenance> {
+    let puzzle = collection.puzzles.iter().find(|puzzle| puzzle.id == puzzle_id)?;
+    Some(collection.provenance_of(puzzle)).filter(|provenance| !provenance.is_empty())
+}
+
+/// Which puzzles may go into a public release, by the license in their
+/// provenance. Licenses are SPDX identifiers, compared case-insensitively.
+/// Once either list is given, puzzles with no known license are dropped
+/// unless `allow_unknown`.
+#[derive(Debug, Clone, Default)]
+pub struct LicenseFilter {
+    /// Keep only puzzles under one of these licenses, unless empty.
+    pub allow: Vec<String>,
+    /// Drop puzzles under any of these licenses.
+    pub deny: Vec<String>,
+    /// Keep puzzles whose license is missing or `unknown`.
+    pub allow_unknown: bool,
+}
+
+/// Label for puzzles whose provenance names no license.
+pub const UNKNOWN_LICENSE: &str = "unknown";
+
+impl LicenseFilter {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    pub fn allows(&self, license: Option<&str>) -> bool {
+        let Some(license) = license.filter(|license| !license.eq_ignore_ascii_case(UNKNOWN_LICENSE)) else {
+            return self.is_empty() || self.allow_unknown;
+        };
+        let listed = |list: &[String]| list.iter().any(|l| l.eq_ignore_ascii_case(license));
+        (self.allow.is_empty() || listed(&self.allow)) && !listed(&self.deny)
+    }
+}
+
+/// How many puzzles of each license a filtered release kept and dropped.
+#[derive(Debug, Clone, Default)]
+pub struct LicenseSummary {
+    pub kept: BTreeMap<String, usize>,
+    pub dropped: BTreeMap<String, usize>,
+}
+
+/// `collection` with only the puzzles `filter` allows, each keeping its
+/// provenance; see `LicenseFilter`.
+pub fn filter_by_license(collection: &PuzzleCollection, filter: &LicenseFilter) -> (PuzzleCollection, LicenseSummary) {
+    let mut summary = LicenseSummary::default();
+    let mut puzzles = Vec::new();
+    for puzzle in &collection.puzzles {
+        let provenance = collection.provenance_of(puzzle);
+        let license = provenance.license.clone().unwrap_or_else(|| UNKNOWN_LICENSE.to_string());
+        if filter.allows(provenance.license.as_deref()) {
+            *summary.kept.entry(license).or_default() += 1;
+            puzzles.push(puzzle.clone());
+        } else {
+            *summary.dropped.entry(license).or_default() += 1;
+        }
+    }
+    let filtered = PuzzleCollection {
+        puzzles,
+        ..collection.clone()
+    };
+    (filtered, summary)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn stderr(values: &[f64]) -> f64 {
+    let n = values.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    (variance / n as f64).sqrt()
+}
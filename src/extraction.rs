@@ -0,0 +1,348 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "chess")]
+use crate::game::chess;
+#[cfg(feature = "chess")]
+use crate::notation;
+use crate::notation::MoveNotation;
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum ExtractionError {
+    #[error("Invalid answer pattern {pattern}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+    #[error("No answer found in response")]
+    NotFound,
+}
+
+/// How answers for a collection are written and pulled out of responses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AnswerFormat {
+    /// Regex whose first capture group is the answer; defaults to `**Answer: ...**`.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub grammar: AnswerGrammar,
+}
+
+/// The shape a well-formed answer must have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerGrammar {
+    #[default]
+    Uci,
+    /// Chess moves in standard algebraic notation, e.g. `Nf3`.
+    San,
+    /// Chess moves in long algebraic notation, e.g. `Ng1-f3`.
+    Lan,
+    /// Chess moves in ICCF numeric notation, e.g. `7163`.
+    Iccf,
+    /// Chess moves in English descriptive notation, e.g. `N-KB3`.
+    Descriptive,
+    GoCoordinate,
+    /// A chess position, for transcription tasks: a full FEN, or just its
+    /// piece placement field if that is what the solutions hold.
+    Fen,
+    Free,
+}
+
+impl AnswerGrammar {
+    /// The chess move notation answers are written in, if they are moves.
+    pub fn move_notation(&self) -> Option<MoveNotation> {
+        match self {
+            AnswerGrammar::Uci => Some(MoveNotation::Uci),
+            AnswerGrammar::San => Some(MoveNotation::San),
+            AnswerGrammar::Lan => Some(MoveNotation::Lan),
+            AnswerGrammar::Iccf => Some(MoveNotation::Iccf),
+            AnswerGrammar::Descriptive => Some(MoveNotation::Descriptive),
+            AnswerGrammar::GoCoordinate | AnswerGrammar::Fen | AnswerGrammar::Free => None,
+        }
+    }
+
+    fn token_pattern(&self) -> Option<&'static str> {
+        if let Some(notation) = self.move_notation() {
+            return Some(notation.token_pattern());
+        }
+        match self {
+            AnswerGrammar::GoCoordinate => Some(r"[a-hj-t](?:1[0-9]|[1-9])|pass"),
+            AnswerGrammar::Fen => Some(
+                r"[pnbrqkPNBRQK1-8]+(?:/[pnbrqkPNBRQK1-8]+){7}(?:\s+[wb](?:\s+(?:-|[KQkq]{1,4})(?:\s+(?:-|[a-h][36])(?:\s+\d+\s+\d+)?)?)?)?",
+            ),
+            _ => None,
+        }
+    }
+
+    /// Whether answers are compared with their case preserved.
+    fn is_case_sensitive(&self) -> bool {
+        self.move_notation().is_some_and(MoveNotation::is_case_sensitive)
+    }
+
+    pub fn description(&self) -> &'static str {
+        if let Some(notation) = self.move_notation() {
+            return notation.description();
+        }
+        match self {
+            AnswerGrammar::GoCoordinate => {
+                "Go board coordinates, column letter (skipping i) followed by row number, e.g., d4, q16, or pass"
+            }
+            AnswerGrammar::Fen => {
+                "FEN notation, e.g., rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+            }
+            _ => "the notation described in the game rules",
+        }
+    }
+
+    pub(crate) fn accepts(&self, answer: &str) -> bool {
+        match self.token_pattern() {
+            Some(pattern) => Regex::new(&format!("^(?:{})$", pattern))
+                .unwrap()
+                .is_match(answer),
+            None => true,
+        }
+    }
+}
+
+impl AnswerFormat {
+    /// Check that `pattern`, if any, is a regex with a capture group for the
+    /// answer.
+    pub fn validate(&self) -> Result<(), ExtractionError> {
+        if let Some(pattern) = &self.pattern {
+            custom_pattern(pattern)?;
+        }
+        Ok(())
+    }
+}
+
+/// `pattern` compiled, if it is a valid regex with a capture group.
+fn custom_pattern(pattern: &str) -> Result<Regex, ExtractionError> {
+    let invalid = |reason: String| ExtractionError::InvalidPattern {
+        pattern: pattern.to_string(),
+        reason,
+    };
+    let regex = Regex::new(pattern).map_err(|e| invalid(e.to_string()))?;
+    if regex.captures_len() < 2 {
+        return Err(invalid("it has no capture group for the answer".to_string()));
+    }
+    Ok(regex)
+}
+
+impl From<MoveNotation> for AnswerGrammar {
+    fn from(notation: MoveNotation) -> Self {
+        match notation {
+            MoveNotation::Uci => AnswerGrammar::Uci,
+            MoveNotation::San => AnswerGrammar::San,
+            MoveNotation::Lan => AnswerGrammar::Lan,
+            MoveNotation::Iccf => AnswerGrammar::Iccf,
+            MoveNotation::Descriptive => AnswerGrammar::Descriptive,
+        }
+    }
+}
+
+/// Which stage of the fallback chain produced an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionMethod {
+    /// The collection's own `AnswerFormat::pattern`.
+    CustomPattern,
+    BoldAnswer,
+    AnswerLine,
+    CodeSpan,
+    LegalMoveToken,
+    NotFound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedAnswer {
+    pub answer: String,
+    pub method: ExtractionMethod,
+}
+
+impl ExtractedAnswer {
+    pub fn not_found() -> Self {
+        Self {
+            answer: String::new(),
+            method: ExtractionMethod::NotFound,
+        }
+    }
+}
+
+/// Extract an answer from a model response, trying in order: the collection's
+/// answer pattern (`**Answer: ...**` by default), a plain "Answer:" line, the
+/// last code span, and finally the last grammar-matching token (for chess moves, only
+/// moves that are legal in `state`).
+///
+/// Failures are reported as a `NotFound` answer; see `try_extract_answer` to
+/// tell them apart.
+pub fn extract_answer(response: &str, state: &str, format: &AnswerFormat) -> ExtractedAnswer {
+    match try_extract_answer(response, state, format) {
+        Ok(extracted) => extracted,
+        Err(ExtractionError::NotFound) => ExtractedAnswer::not_found(),
+        Err(e) => {
+            warn!(error = %e, "answer extraction failed");
+            ExtractedAnswer::not_found()
+        }
+    }
+}
+
+/// Like `extract_answer`, but an invalid answer pattern or a response with no
+/// recognizable answer is an error.
+pub fn try_extract_answer(
+    response: &str,
+    state: &str,
+    format: &AnswerFormat,
+) -> Result<ExtractedAnswer, ExtractionError> {
+    if format.grammar == AnswerGrammar::Fen && format.pattern.is_none() {
+        return find_fen(response).ok_or(ExtractionError::NotFound);
+    }
+    let mut extracted = find_answer(response, state, format)?;
+    extracted.answer = normalize(extracted.answer, state, format.grammar);
+    Ok(extracted)
+}
+
+/// The confidence a response states in its answer, as asked for by
+/// `prompt::confidence_request`, as a probability in [0, 1]. Takes the last
+/// `Confidence: NN%`, bold or not; percentages over 100 don't count.
+pub fn extract_confidence(response: &str) -> Option<f64> {
+    let confidence = Regex::new(r"(?i)confidence(?:\*\*)?\s*:\s*(?:\*\*)?\s*(\d{1,3}(?:\.\d+)?)\s*%").unwrap();
+    let percent: f64 = confidence.captures_iter(response).last()?[1].parse().ok()?;
+    (percent <= 100.0).then_some(percent / 100.0)
+}
+
+/// FENs contain spaces and are case-sensitive, so the general stages, which
+/// take one lowercased token, would mangle them. Take the last FEN inside the
+/// last `**Answer: ...**`, or else the last FEN anywhere in the response,
+/// with its fields separated by single spaces.
+fn find_fen(response: &str) -> Option<ExtractedAnswer> {
+    let fen = Regex::new(AnswerGrammar::Fen.token_pattern()?).unwrap();
+    let last_fen = |text: &str| {
+        fen.find_iter(text)
+            .last()
+            .map(|m| m.as_str().split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    let bold = Regex::new(r"\*\*Answer:\s*(.+?)\*\*").unwrap();
+    if let Some(answer) = bold
+        .captures_iter(response)
+        .last()
+        .and_then(|caps| last_fen(caps.get(1)?.as_str()))
+    {
+        return Some(ExtractedAnswer {
+            answer,
+            method: ExtractionMethod::BoldAnswer,
+        });
+    }
+    last_fen(response).map(|answer| ExtractedAnswer {
+        answer,
+        method: ExtractionMethod::LegalMoveToken,
+    })
+}
+
+fn find_answer(
+    response: &str,
+    state: &str,
+    format: &AnswerFormat,
+) -> Result<ExtractedAnswer, ExtractionError> {
+    let (primary, method) = match &format.pattern {
+        Some(pattern) => (custom_pattern(pattern)?, ExtractionMethod::CustomPattern),
+        None => (Regex::new(r"\*\*Answer:\s*(\S+?)\*\*").unwrap(), ExtractionMethod::BoldAnswer),
+    };
+    if let Some(answer) = last_capture(&primary, response, format.grammar) {
+        return Ok(ExtractedAnswer { answer, method });
+    }
+
+    let answer_line = Regex::new(r"(?im)^[^\S\n]*(?:\*\*)?answer(?:\*\*)?:(?:\*\*)?[^\S\n]*`?([^\s`*]+)").unwrap();
+    let code_span = Regex::new(r"`([^`\s]+)`").unwrap();
+    let stages = [
+        (&answer_line, ExtractionMethod::AnswerLine),
+        (&code_span, ExtractionMethod::CodeSpan),
+    ];
+    for (regex, method) in stages {
+        if let Some(answer) = last_capture(regex, response, format.grammar).filter(|a| format.grammar.accepts(a)) {
+            return Ok(ExtractedAnswer { answer, method });
+        }
+    }
+
+    last_grammar_token(response, state, format.grammar)
+        .map(|answer| ExtractedAnswer {
+            answer,
+            method: ExtractionMethod::LegalMoveToken,
+        })
+        .ok_or(ExtractionError::NotFound)
+}
+
+fn last_capture(regex: &Regex, response: &str, grammar: AnswerGrammar) -> Option<String> {
+    regex
+        .captures_iter(response)
+        .last()
+        .and_then(|caps| caps.get(1))
+        .map(|m| matching_case(m.as_str().trim(), grammar))
+        .filter(|answer| !answer.is_empty())
+}
+
+/// `answer` lowercased, unless the grammar tells moves apart by case (`Bc4`
+/// and `bc4` are different SAN moves).
+fn matching_case(answer: &str, grammar: AnswerGrammar) -> String {
+    if grammar.is_case_sensitive() {
+        answer.to_string()
+    } else {
+        answer.to_lowercase()
+    }
+}
+
+fn last_grammar_token(response: &str, state: &str, grammar: AnswerGrammar) -> Option<String> {
+    let token = Regex::new(&format!(r"\b(?:{})\b", grammar.token_pattern()?)).unwrap();
+    let cased = matching_case(response, grammar);
+    let candidates = token.find_iter(&cased).map(|m| m.as_str());
+
+    let found = match grammar.move_notation() {
+        Some(notation) => last_legal_move(candidates, state, notation),
+        None => candidates.last(),
+    };
+
+    found.map(str::to_string)
+}
+
+/// Rewrite chess moves that are legal in `state` in canonical form, so e.g.
+/// king-takes-rook castling in UCI or `N-B3` for `N-KB3` in descriptive
+/// notation still match the solution.
+#[cfg(feature = "chess")]
+fn normalize(answer: String, state: &str, grammar: AnswerGrammar) -> String {
+    match grammar.move_notation() {
+        Some(MoveNotation::Uci) => chess::normalize_uci(state, &answer).unwrap_or(answer),
+        Some(notation) => notation::to_uci(notation, state, &answer)
+            .and_then(|uci| notation::from_uci(notation, state, &uci))
+            .unwrap_or(answer),
+        None => answer,
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+fn normalize(answer: String, _state: &str, _grammar: AnswerGrammar) -> String {
+    answer
+}
+
+#[cfg(feature = "chess")]
+fn last_legal_move<'a>(
+    candidates: impl Iterator<Item = &'a str>,
+    state: &str,
+    notation: MoveNotation,
+) -> Option<&'a str> {
+    candidates
+        .filter(|candidate| match notation {
+            MoveNotation::Uci => chess::is_legal(state, candidate),
+            _ => notation::to_uci(notation, state, candidate).is_ok(),
+        })
+        .last()
+}
+
+/// Without the `chess` feature moves can't be checked against the position,
+/// so the last move-shaped token wins.
+#[cfg(not(feature = "chess"))]
+fn last_legal_move<'a>(
+    candidates: impl Iterator<Item = &'a str>,
+    _state: &str,
+    _notation: MoveNotation,
+) -> Option<&'a str> {
+    candidates.last()
+}
@@ -0,0 +1,118 @@
+//! Accuracy against cost and latency. Each model's score on a collection is
+//! set against what it cost and how long it took per puzzle, and the
+//! models no other model beats on both counts — the Pareto frontier — are
+//! marked, since those are the only ones worth choosing between.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::results::BenchmarkResult;
+
+/// One model's score, cost and latency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FrontierPoint {
+    pub solver_name: String,
+    pub average_score: f64,
+    pub puzzles: usize,
+    /// Mean API cost per puzzle, when the run recorded its usage.
+    pub cost_per_puzzle: Option<f64>,
+    /// Mean wall-clock time per puzzle attempt, unless none was timed
+    /// (e.g. for results scored from answer files).
+    pub latency_ms: Option<f64>,
+    /// No other model scores at least as well for less, or better for as
+    /// much.
+    pub cost_optimal: bool,
+    /// No other model scores at least as well in less time, or better in
+    /// as much.
+    pub latency_optimal: bool,
+}
+
+/// Every model's point, best score first.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FrontierReport {
+    pub collection_checksum: Option<String>,
+    pub points: Vec<FrontierPoint>,
+}
+
+#[derive(Debug, Error)]
+pub enum FrontierError {
+    #[error("no results to compare")]
+    Empty,
+    #[error("the results are on different collections: {0} and {1}")]
+    DifferentCollections(String, String),
+}
+
+impl FrontierPoint {
+    pub fn new(result: &BenchmarkResult) -> Self {
+        let passes = result
+            .pass_results
+            .as_ref()
+            .map_or(1, |pass_results| pass_results.individual_pass_scores.len().max(1));
+        let attempts = (result.puzzle_scores.len() * passes).max(1) as f64;
+        let puzzles = result.total_puzzles.max(1) as f64;
+        Self {
+            solver_name: result.solver_name.clone(),
+            average_score: result.average_score,
+            puzzles: result.total_puzzles,
+            cost_per_puzzle: result.usage.as_ref().map(|usage| usage.cost / puzzles),
+            latency_ms: result
+                .puzzle_scores
+                .iter()
+                .any(|s| s.elapsed_ms > 0)
+                .then(|| result.puzzle_scores.iter().map(|s| s.elapsed_ms as f64).sum::<f64>() / attempts),
+            cost_optimal: false,
+            latency_optimal: false,
+        }
+    }
+}
+
+/// Whether `point` is dominated by one of `others` in score and `measure`,
+/// lower being better. Points without the measure are never optimal.
+fn dominated(point: &FrontierPoint, others: &[FrontierPoint], measure: fn(&FrontierPoint) -> Option<f64>) -> bool {
+    let Some(own) = measure(point) else {
+        return true;
+    };
+    others.iter().any(|other| {
+        measure(other).is_some_and(|theirs| {
+            other.average_score >= point.average_score
+                && theirs <= own
+                && (other.average_score > point.average_score || theirs < own)
+        })
+    })
+}
+
+/// The frontier of `results`, which must all be on the same collection
+/// when they record which one it was.
+pub fn frontier(results: &[BenchmarkResult]) -> Result<FrontierReport, FrontierError> {
+    if results.is_empty() {
+        return Err(FrontierError::Empty);
+    }
+    let checksums: Vec<&String> = results
+        .iter()
+        .filter_map(|result| Some(&result.metadata.as_ref()?.collection_checksum))
+        .collect();
+    if let Some(other) = checksums.iter().find(|checksum| **checksum != checksums[0]) {
+        return Err(FrontierError::DifferentCollections(checksums[0].clone(), (*other).clone()));
+    }
+
+    let mut points: Vec<FrontierPoint> = results.iter().map(FrontierPoint::new).collect();
+    let flags: Vec<(bool, bool)> = points
+        .iter()
+        .map(|point| {
+            (
+                !dominated(point, &points, |p| p.cost_per_puzzle),
+                !dominated(point, &points, |p| p.latency_ms),
+            )
+        })
+        .collect();
+    for (point, (cost_optimal, latency_optimal)) in points.iter_mut().zip(flags) {
+        point.cost_optimal = cost_optimal;
+        point.latency_optimal = latency_optimal;
+    }
+    points.sort_by(|a, b| b.average_score.total_cmp(&a.average_score));
+    Ok(FrontierReport {
+        collection_checksum: checksums.first().map(|checksum| (*checksum).clone()),
+        points,
+    })
+}
@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(feature = "chess")]
+pub mod chess;
+pub mod grid;
+
 #[derive(Debug, Error)]
 pub enum GameError {
     #[error("Invalid game definition: {0}")]
     InvalidDefinition(String),
+    #[error("Invalid game state: {0}")]
+    InvalidState(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
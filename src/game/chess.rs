@@ -0,0 +1,251 @@
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Color, EnPassantMode, Move, Position, Role};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ChessError {
+    #[error("Invalid position {fen}: {reason}")]
+    InvalidPosition { fen: String, reason: String },
+    #[error("Illegal move {uci} in {fen}: {reason}")]
+    IllegalMove { fen: String, uci: String, reason: String },
+}
+
+/// The position a standard game of chess starts from.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Why the rules end a game in a position, regardless of how it was
+/// reached. Repetitions depend on the game's history and are left to the
+/// caller; see `repetition_key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    /// The side to move is checkmated.
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    /// Fifty moves by each side without a capture or pawn move.
+    FiftyMoves,
+}
+
+/// Parse `fen` into a playable position.
+pub fn parse_fen(fen: &str) -> Result<Chess, ChessError> {
+    let invalid = |reason: String| ChessError::InvalidPosition {
+        fen: fen.to_string(),
+        reason,
+    };
+    Fen::from_ascii(fen.as_bytes())
+        .map_err(|e| invalid(e.to_string()))?
+        .into_position(CastlingMode::Standard)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// The legal move `uci` denotes in `position` (written as FEN `fen`).
+fn legal_move(position: &Chess, fen: &str, uci: &str) -> Result<Move, ChessError> {
+    let illegal = |reason: String| ChessError::IllegalMove {
+        fen: fen.to_string(),
+        uci: uci.to_string(),
+        reason,
+    };
+    UciMove::from_ascii(uci.as_bytes())
+        .map_err(|e| illegal(e.to_string()))?
+        .to_move(position)
+        .map_err(|e| illegal(e.to_string()))
+}
+
+/// Check that `uci` is a legal move in the position `fen`.
+pub fn check_move(fen: &str, uci: &str) -> Result<(), ChessError> {
+    legal_move(&parse_fen(fen)?, fen, uci).map(|_| ())
+}
+
+pub fn is_legal(fen: &str, uci: &str) -> bool {
+    check_move(fen, uci).is_ok()
+}
+
+pub fn white_to_move(fen: &str) -> Result<bool, ChessError> {
+    Ok(parse_fen(fen)?.turn() == Color::White)
+}
+
+/// Every legal move in the position `fen`, in UCI.
+pub fn legal_moves(fen: &str) -> Result<Vec<String>, ChessError> {
+    Ok(parse_fen(fen)?
+        .legal_moves()
+        .iter()
+        .map(|m| m.to_uci(CastlingMode::Standard).to_string())
+        .collect())
+}
+
+/// Play `uci` in the position `fen` and return the resulting position as FEN.
+pub fn play(fen: &str, uci: &str) -> Result<String, ChessError> {
+    let position = parse_fen(fen)?;
+    let m = legal_move(&position, fen, uci)?;
+    let after = position.play(m).map_err(|e| ChessError::IllegalMove {
+        fen: fen.to_string(),
+        uci: uci.to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(Fen::from_position(&after, EnPassantMode::Always).to_string())
+}
+
+/// Whether playing `uci` in the position `fen` checkmates the opponent.
+pub fn gives_checkmate(fen: &str, uci: &str) -> Result<bool, ChessError> {
+    let position = parse_fen(fen)?;
+    let m = legal_move(&position, fen, uci)?;
+    let after = position.play(m).map_err(|e| ChessError::IllegalMove {
+        fen: fen.to_string(),
+        uci: uci.to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(after.is_checkmate())
+}
+
+/// Whether after playing `uci` in the position `fen` the opponent can
+/// checkmate at once.
+pub fn allows_mate_in_one(fen: &str, uci: &str) -> Result<bool, ChessError> {
+    let position = parse_fen(fen)?;
+    let m = legal_move(&position, fen, uci)?;
+    let after = position.play(m).map_err(|e| ChessError::IllegalMove {
+        fen: fen.to_string(),
+        uci: uci.to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(after.legal_moves().iter().any(|reply| {
+        let mut next = after.clone();
+        next.play_unchecked(*reply);
+        next.is_checkmate()
+    }))
+}
+
+/// How the game ends in the position `fen`, if the rules end it there.
+pub fn ending(fen: &str) -> Result<Option<Ending>, ChessError> {
+    let position = parse_fen(fen)?;
+    Ok(if position.is_checkmate() {
+        Some(Ending::Checkmate)
+    } else if position.is_stalemate() {
+        Some(Ending::Stalemate)
+    } else if position.is_insufficient_material() {
+        Some(Ending::InsufficientMaterial)
+    } else if position.halfmoves() >= 100 {
+        Some(Ending::FiftyMoves)
+    } else {
+        None
+    })
+}
+
+/// Board, side to move, castling rights and en passant square: the parts
+/// of `position` that make two positions the same for repetitions.
+fn position_key(position: &Chess) -> String {
+    let fen = Fen::from_position(position, EnPassantMode::Legal).to_string();
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+/// Equal for positions `fen` that count as the same for the threefold
+/// repetition rule.
+pub fn repetition_key(fen: &str) -> Result<String, ChessError> {
+    Ok(position_key(&parse_fen(fen)?))
+}
+
+/// The `repetition_key` of `fen` and of every position after each of
+/// `moves` (in SAN, check marks and `!`/`?` annotations allowed), up to
+/// `max_plies` moves or the first move that isn't legal.
+pub fn san_positions(fen: &str, moves: &[&str], max_plies: usize) -> Result<Vec<String>, ChessError> {
+    let mut position = parse_fen(fen)?;
+    let mut keys = vec![position_key(&position)];
+    for san in moves.iter().take(max_plies) {
+        let Some(m) = SanPlus::from_ascii(san.trim_end_matches(['!', '?']).as_bytes())
+            .ok()
+            .and_then(|san| san.san.to_move(&position).ok())
+        else {
+            break;
+        };
+        position.play_unchecked(m);
+        keys.push(position_key(&position));
+    }
+    Ok(keys)
+}
+
+/// The legal move (in UCI) leading from `fen` to `next_fen`, comparing
+/// board, side to move, castling rights and en passant square.
+pub fn move_between(fen: &str, next_fen: &str) -> Result<String, ChessError> {
+    let position = parse_fen(fen)?;
+    let target = position_key(&parse_fen(next_fen)?);
+    position
+        .legal_moves()
+        .into_iter()
+        .find(|m| {
+            let mut after = position.clone();
+            after.play_unchecked(*m);
+            position_key(&after) == target
+        })
+        .map(|m| m.to_uci(CastlingMode::Standard).to_string())
+        .ok_or_else(|| ChessError::InvalidPosition {
+            fen: next_fen.to_string(),
+            reason: format!("not reachable in one move from {}", fen),
+        })
+}
+
+/// `moves` (UCI) played from `fen`, as PGN: `SetUp` and `FEN` tags, then
+/// the moves in SAN with move numbers.
+pub fn pgn(fen: &str, moves: &[String]) -> Result<String, ChessError> {
+    let mut position = parse_fen(fen)?;
+    let mut movetext = Vec::new();
+    for (i, uci) in moves.iter().enumerate() {
+        let current = Fen::from_position(&position, EnPassantMode::Legal).to_string();
+        let m = legal_move(&position, &current, uci)?;
+        let number = position.fullmoves();
+        match position.turn() {
+            Color::White => movetext.push(format!("{}.", number)),
+            Color::Black if i == 0 => movetext.push(format!("{}...", number)),
+            Color::Black => {}
+        }
+        movetext.push(SanPlus::from_move_and_play_unchecked(&mut position, m).to_string());
+    }
+    movetext.push("*".to_string());
+    Ok(format!("[SetUp \"1\"]\n[FEN \"{}\"]\n\n{}", fen, movetext.join(" ")))
+}
+
+/// The canonical spelling of a legal move, so equivalent answers compare
+/// equal: lowercase, and castling as the king's two-square move (`e1g1`
+/// rather than king-takes-rook `e1h1`). `None` if the move isn't legal.
+pub fn normalize_uci(fen: &str, uci: &str) -> Option<String> {
+    let position = parse_fen(fen).ok()?;
+    let m = legal_move(&position, fen, &uci.to_lowercase()).ok()?;
+    Some(m.to_uci(CastlingMode::Standard).to_string())
+}
+
+/// A piece on the board, with its square counted from a8 (file 0, rank 0)
+/// as the board is drawn with White at the bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardPiece {
+    pub file: u32,
+    pub rank_from_top: u32,
+    /// Color and role, e.g. `wp` for a white pawn or `bk` for the black king.
+    pub code: &'static str,
+}
+
+/// Every piece in the position `fen`.
+pub fn board_pieces(fen: &str) -> Result<Vec<BoardPiece>, ChessError> {
+    let position = parse_fen(fen)?;
+    Ok(position
+        .board()
+        .iter()
+        .map(|(square, piece)| BoardPiece {
+            file: u32::from(square.file()),
+            rank_from_top: 7 - u32::from(square.rank()),
+            code: match (piece.color, piece.role) {
+                (Color::White, Role::Pawn) => "wp",
+                (Color::White, Role::Knight) => "wn",
+                (Color::White, Role::Bishop) => "wb",
+                (Color::White, Role::Rook) => "wr",
+                (Color::White, Role::Queen) => "wq",
+                (Color::White, Role::King) => "wk",
+                (Color::Black, Role::Pawn) => "bp",
+                (Color::Black, Role::Knight) => "bn",
+                (Color::Black, Role::Bishop) => "bb",
+                (Color::Black, Role::Rook) => "br",
+                (Color::Black, Role::Queen) => "bq",
+                (Color::Black, Role::King) => "bk",
+            },
+        })
+        .collect())
+}
@@ -0,0 +1,63 @@
+//! Positions of games played by placing stones or discs on a grid: Go,
+//! gomoku, Othello and Connect Four.
+//!
+//! States are written like a FEN board: rows from top to bottom separated by
+//! `/`, one character per cell, `.` for empty, `x` (or `b`) for the first
+//! player's stones and `o` (or `w`) for the second player's. Anything after
+//! the board, such as the side to move, is separated by a space and ignored
+//! here. For example, a Connect Four position:
+//!
+//! ```text
+//! ......./......./......./......./...o.../..xxo.. o
+//! ```
+
+use super::GameError;
+
+/// Whose stone or disc occupies a cell. In Go, gomoku and Othello the first
+/// player is Black; in Connect Four, red.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stone {
+    First,
+    Second,
+}
+
+/// A grid position parsed from a state string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Option<Stone>>,
+}
+
+impl Grid {
+    pub fn parse(state: &str) -> Result<Self, GameError> {
+        let invalid = |reason: String| GameError::InvalidState(format!("{:?}: {}", state, reason));
+        let board = state.split_whitespace().next().unwrap_or_default();
+        let rows: Vec<&str> = board.split('/').collect();
+        let width = rows[0].chars().count();
+        if width == 0 {
+            return Err(invalid("empty board".to_string()));
+        }
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (index, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(invalid(format!("row {} has {} cells, expected {}", index + 1, row.chars().count(), width)));
+            }
+            for c in row.chars() {
+                cells.push(match c.to_ascii_lowercase() {
+                    '.' => None,
+                    'x' | 'b' => Some(Stone::First),
+                    'o' | 'w' => Some(Stone::Second),
+                    _ => return Err(invalid(format!("unexpected cell {:?}", c))),
+                });
+            }
+        }
+        Ok(Self { width, height: rows.len(), cells })
+    }
+
+    /// The stone at `column` (from the left) and `row` (from the top).
+    pub fn get(&self, column: usize, row: usize) -> Option<Stone> {
+        self.cells[row * self.width + column]
+    }
+}
@@ -0,0 +1,105 @@
+use image::{Rgba, RgbaImage};
+
+use boardgamebench::puzzle::{Arrow, BoardAnnotations, Orientation};
+
+use crate::{GenerationError, blend, board_cell};
+
+/// Lichess' last-move highlight.
+const HIGHLIGHT_COLOR: Rgba<u8> = Rgba([155, 199, 0, 255]);
+const HIGHLIGHT_OPACITY: f32 = 0.41;
+/// Lichess' default (green) arrow.
+const ARROW_COLOR: Rgba<u8> = Rgba([21, 120, 27, 255]);
+const ARROW_OPACITY: f32 = 0.8;
+
+/// The image column and row of a square named like `e4`.
+fn square_position(square: &str, orientation: Orientation) -> Result<(u32, u32), GenerationError> {
+    match square.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Ok(board_cell((file - b'a') as u32, (b'8' - rank) as u32, orientation))
+        }
+        _ => Err(GenerationError::InvalidAnnotation(format!("not a square: {:?}", square))),
+    }
+}
+
+/// Tint the highlighted squares; drawn before the pieces, like Lichess.
+pub fn draw_highlights(
+    board: &mut RgbaImage,
+    annotations: &BoardAnnotations,
+    square_size: u32,
+    orientation: Orientation,
+) -> Result<(), GenerationError> {
+    for square in &annotations.highlights {
+        let (column, row) = square_position(square, orientation)?;
+        for y in row * square_size..(row + 1) * square_size {
+            for x in column * square_size..(column + 1) * square_size {
+                blend(board.get_pixel_mut(x, y), HIGHLIGHT_COLOR, HIGHLIGHT_OPACITY);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draw the arrows from square center to square center; drawn over the
+/// pieces, like Lichess.
+pub fn draw_arrows(
+    board: &mut RgbaImage,
+    annotations: &BoardAnnotations,
+    square_size: u32,
+    orientation: Orientation,
+) -> Result<(), GenerationError> {
+    for arrow in &annotations.arrows {
+        draw_arrow(board, arrow, square_size, orientation)?;
+    }
+    Ok(())
+}
+
+fn draw_arrow(
+    board: &mut RgbaImage,
+    arrow: &Arrow,
+    square_size: u32,
+    orientation: Orientation,
+) -> Result<(), GenerationError> {
+    let center = |square: &str| {
+        square_position(square, orientation).map(|(column, row)| {
+            let half = square_size as f32 / 2.0;
+            ((column * square_size) as f32 + half, (row * square_size) as f32 + half)
+        })
+    };
+    let (x0, y0) = center(&arrow.from)?;
+    let (x1, y1) = center(&arrow.to)?;
+    let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+    if length == 0.0 {
+        return Ok(());
+    }
+    // Unit vectors along and across the arrow.
+    let (ux, uy) = ((x1 - x0) / length, (y1 - y0) / length);
+    let (nx, ny) = (-uy, ux);
+
+    let size = square_size as f32;
+    let shaft_width = size * 0.15;
+    let head_width = size * 0.45;
+    let head_length = (size * 0.4).min(length);
+    let shaft_end = length - head_length;
+
+    let reach = head_width;
+    let (min_x, max_x) = (x0.min(x1) - reach, x0.max(x1) + reach);
+    let (min_y, max_y) = (y0.min(y1) - reach, y0.max(y1) + reach);
+    for y in min_y.max(0.0) as u32..(max_y.ceil() as u32).min(board.height()) {
+        for x in min_x.max(0.0) as u32..(max_x.ceil() as u32).min(board.width()) {
+            let (dx, dy) = (x as f32 + 0.5 - x0, y as f32 + 0.5 - y0);
+            let along = dx * ux + dy * uy;
+            let across = (dx * nx + dy * ny).abs();
+            let inside = if (0.0..shaft_end).contains(&along) {
+                across <= shaft_width / 2.0
+            } else if (shaft_end..=length).contains(&along) {
+                across <= head_width / 2.0 * (length - along) / head_length
+            } else {
+                false
+            };
+            if inside {
+                blend(board.get_pixel_mut(x, y), ARROW_COLOR, ARROW_OPACITY);
+            }
+        }
+    }
+    Ok(())
+}
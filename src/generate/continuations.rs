@@ -0,0 +1,76 @@
+use boardgamebench::continuation::{self, ContinuationRules};
+use boardgamebench::engine::UciEngine;
+use boardgamebench::game::chess;
+use boardgamebench::puzzle::{GoalCondition, Puzzle};
+
+use crate::{GenerationError, LastMove, OrientationChoice};
+
+/// Longer puzzles whose opponent replies are the engine's, for `--engine`.
+#[derive(clap::Args, Debug)]
+pub struct Continuations {
+    /// Extend each puzzle past its first move with this UCI engine's
+    /// replies, e.g. "stockfish", instead of keeping only the first move
+    #[arg(long)]
+    pub engine: Option<String>,
+
+    /// Search depth of --engine
+    #[arg(long, default_value = "18")]
+    pub engine_depth: u32,
+
+    /// Solver moves in an extended puzzle, counting the first
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    pub continuation_moves: u32,
+
+    /// How many centipawns the engine's best move must be ahead of its
+    /// second best for a state to be kept
+    #[arg(long, default_value = "150")]
+    pub continuation_margin: i32,
+}
+
+impl Continuations {
+    /// Extend every puzzle with the engine's replies and the single clearly
+    /// best answer to each, stopping a line at the first state that has
+    /// more than one good answer. Does nothing without `--engine`.
+    pub fn extend(
+        &self,
+        puzzles: &mut [Puzzle],
+        last_move: LastMove,
+        orientation: OrientationChoice,
+    ) -> Result<(), GenerationError> {
+        let Some(command) = &self.engine else {
+            return Ok(());
+        };
+        let engine = UciEngine::from_command_line(command, self.engine_depth)
+            .map_err(|e| GenerationError::Engine(e.to_string()))?;
+        let rules = ContinuationRules {
+            solver_moves: self.continuation_moves as usize,
+            margin: self.continuation_margin,
+        };
+
+        let mut extended = 0;
+        for puzzle in puzzles.iter_mut() {
+            let line = continuation::extend(&engine, &puzzle.game_states[0], &puzzle.solutions[0], &rules)
+                .map_err(|e| GenerationError::Engine(e.to_string()))?;
+            if line.states.len() == 1 {
+                continue;
+            }
+            for j in 1..line.states.len() {
+                let after = chess::play(&line.states[j - 1], &line.solutions[j - 1])?;
+                let reply = chess::move_between(&after, &line.states[j])?;
+                puzzle.annotations.push(last_move.annotations(&reply));
+                puzzle.orientations.push(orientation.orientation(&line.states[j])?);
+            }
+            puzzle.game_states = line.states;
+            puzzle.solutions = line.solutions;
+            // Any mate is as good as the stored one, and the goal condition
+            // only applies to the last state, where a mate ends the line.
+            puzzle.goal_condition = line.ends_in_mate.then_some(GoalCondition::Checkmate);
+            puzzle
+                .metadata
+                .insert("continuation_engine".to_string(), serde_json::json!(engine.name()));
+            extended += 1;
+        }
+        println!("Extended {} of {} puzzles with {}", extended, puzzles.len(), engine.name());
+        Ok(())
+    }
+}
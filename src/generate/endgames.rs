@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+
+use boardgamebench::game::chess;
+use boardgamebench::puzzle::{GoalCondition, Provenance, Puzzle};
+use boardgamebench::tablebase::{LICHESS_TABLEBASE, Tablebase};
+
+use crate::{GenerationError, LastMove, OrientationChoice};
+
+/// Random placements tried per puzzle before giving up on an ending.
+const ATTEMPTS_PER_PUZZLE: usize = 200;
+/// Shortest forced mate, in plies, worth a technique puzzle.
+const MIN_DISTANCE: i32 = 6;
+
+/// Endgame technique puzzles graded against a tablebase, for `--endgames`.
+#[derive(clap::Args, Debug)]
+pub struct Endgames {
+    /// Instead of generating Lichess puzzles, write multi-move endgame
+    /// conversion puzzles for these endings to data/endgame_puzzles.json
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub endgames: Vec<EndgameKind>,
+
+    /// How many puzzles of each ending to write
+    #[arg(long, default_value = "10")]
+    pub endgame_puzzles: usize,
+
+    /// Most solver moves in an endgame puzzle
+    #[arg(long, default_value = "10", value_parser = clap::value_parser!(u32).range(1..))]
+    pub endgame_moves: u32,
+
+    /// Tablebase server to probe, answering like the Lichess tablebase
+    #[arg(long, default_value = LICHESS_TABLEBASE)]
+    pub tablebase_url: String,
+}
+
+/// An elementary ending, named by its material: the stronger side's king
+/// and pieces, then the weaker side's.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndgameKind {
+    /// King and queen against king
+    Kqk,
+    /// King and rook against king
+    Krk,
+    /// King and pawn against king
+    Kpk,
+    /// King, rook and pawn against king and rook
+    Krpkr,
+}
+
+impl EndgameKind {
+    fn name(self) -> &'static str {
+        match self {
+            EndgameKind::Kqk => "kqk",
+            EndgameKind::Krk => "krk",
+            EndgameKind::Kpk => "kpk",
+            EndgameKind::Krpkr => "krpkr",
+        }
+    }
+
+    /// The pieces besides the kings, as White's FEN letters: the stronger
+    /// side's, then the weaker side's.
+    fn pieces(self) -> (&'static [char], &'static [char]) {
+        match self {
+            EndgameKind::Kqk => (&['Q'], &[]),
+            EndgameKind::Krk => (&['R'], &[]),
+            EndgameKind::Kpk => (&['P'], &[]),
+            EndgameKind::Krpkr => (&['R', 'P'], &['R']),
+        }
+    }
+
+    /// A random legal position of this ending with the stronger side, of
+    /// either color, to move; `None` if the placement drawn isn't legal.
+    fn random_position(self, rng: &mut SmallRng) -> Option<String> {
+        let strong_white = rng.random_bool(0.5);
+        let (strong, weak) = self.pieces();
+        let letter = |piece: char, white: bool| if white { piece } else { piece.to_ascii_lowercase() };
+        let mut pieces = vec![letter('K', strong_white), letter('K', !strong_white)];
+        pieces.extend(strong.iter().map(|&piece| letter(piece, strong_white)));
+        pieces.extend(weak.iter().map(|&piece| letter(piece, !strong_white)));
+
+        let mut board = [[None; 8]; 8];
+        let squares: Vec<usize> = (0..64).collect();
+        for (&square, &piece) in squares.choose_multiple(rng, pieces.len()).zip(&pieces) {
+            let (rank, file) = (square / 8, square % 8);
+            if piece.eq_ignore_ascii_case(&'p') && (rank == 0 || rank == 7) {
+                return None;
+            }
+            board[rank][file] = Some(piece);
+        }
+        let placement: Vec<String> = board
+            .iter()
+            .map(|rank| {
+                let mut row = String::new();
+                let mut empty = 0;
+                for square in rank {
+                    match square {
+                        Some(piece) => {
+                            if empty > 0 {
+                                row.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            row.push(*piece);
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    row.push_str(&empty.to_string());
+                }
+                row
+            })
+            .collect();
+        let fen = format!("{} {} - - 0 1", placement.join("/"), if strong_white { 'w' } else { 'b' });
+        let playable = chess::parse_fen(&fen).is_ok() && matches!(chess::ending(&fen), Ok(None));
+        playable.then_some(fen)
+    }
+}
+
+impl Endgames {
+    /// For each ending, puzzles from random won positions at least
+    /// `MIN_DISTANCE` plies from mate. Each state's solution is a
+    /// tablebase-optimal move and each reply the tablebase's most stubborn
+    /// defense, for up to `--endgame-moves` solver moves or until mate.
+    /// The metadata lists every state's best and winning moves as
+    /// `tablebase`, for the `tablebase-optimal` scorer.
+    pub fn generate(
+        &self,
+        seed: u64,
+        last_move: LastMove,
+        orientation: OrientationChoice,
+    ) -> Result<Vec<Puzzle>, GenerationError> {
+        let tablebase = Tablebase::new(&self.tablebase_url);
+        let probe = |fen: &str| tablebase.probe(fen).map_err(|e| GenerationError::Tablebase(e.to_string()));
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut puzzles = Vec::new();
+
+        for &kind in &self.endgames {
+            let mut made = 0;
+            for _ in 0..self.endgame_puzzles * ATTEMPTS_PER_PUZZLE {
+                if made == self.endgame_puzzles {
+                    break;
+                }
+                let Some(start) = kind.random_position(&mut rng) else {
+                    continue;
+                };
+                let entry = probe(&start)?;
+                let distance = entry.dtm.or(entry.dtz).map_or(0, i32::abs);
+                if !entry.is_win() || distance < MIN_DISTANCE {
+                    continue;
+                }
+
+                let mut states = Vec::new();
+                let mut solutions = Vec::new();
+                let mut annotations = Vec::new();
+                let mut orientations = Vec::new();
+                let mut tablebase_moves = Vec::new();
+                let (mut fen, mut entry, mut reply) = (start.clone(), entry, None::<String>);
+                let mut mates = false;
+                while solutions.len() < self.endgame_moves as usize && entry.is_win() {
+                    let Some(solution) = entry.best_moves().first().and_then(|uci| chess::normalize_uci(&fen, uci))
+                    else {
+                        break;
+                    };
+                    annotations.push(reply.as_deref().map(|uci| last_move.annotations(uci)).unwrap_or_default());
+                    orientations.push(orientation.orientation(&fen)?);
+                    tablebase_moves.push(serde_json::json!({
+                        "best": entry.best_moves(),
+                        "winning": entry.winning_moves(),
+                    }));
+                    states.push(fen.clone());
+                    solutions.push(solution.clone());
+
+                    let after = chess::play(&fen, &solution)?;
+                    if chess::ending(&after)?.is_some() {
+                        mates = chess::gives_checkmate(&fen, &solution)?;
+                        break;
+                    }
+                    let defense = probe(&after)?;
+                    let Some(best_defense) = defense.moves.first() else {
+                        break;
+                    };
+                    let defense_uci = best_defense.uci.clone();
+                    fen = chess::play(&after, &defense_uci)?;
+                    if chess::ending(&fen)?.is_some() {
+                        break;
+                    }
+                    entry = probe(&fen)?;
+                    reply = Some(defense_uci);
+                }
+                if states.is_empty() {
+                    continue;
+                }
+
+                made += 1;
+                puzzles.push(Puzzle {
+                    id: format!("endgame_{}_{:02}", kind.name(), made),
+                    description: format!(
+                        "{} conversion graded against a tablebase, {} plies from mate",
+                        kind.name().to_uppercase(),
+                        distance
+                    ),
+                    game_type: None,
+                    game_states: states,
+                    solutions,
+                    encrypted_solutions: None,
+                    rating: None,
+                    rating_deviation: None,
+                    weight: 1.0,
+                    state_weights: Vec::new(),
+                    images: Vec::new(),
+                    sequences: Vec::new(),
+                    annotations,
+                    orientations,
+                    history: None,
+                    render_style: None,
+                    tags: vec!["endgame".to_string(), kind.name().to_string()],
+                    provenance: Provenance::default(),
+                    metadata: BTreeMap::from([
+                        ("ending".to_string(), serde_json::json!(kind.name())),
+                        ("tablebase".to_string(), serde_json::Value::Array(tablebase_moves)),
+                    ]),
+                    // Any mate is as good as the tablebase's at the last state.
+                    goal_condition: mates.then_some(GoalCondition::Checkmate),
+                    goal: None,
+                });
+            }
+            println!("Generated {} {} puzzles", made, kind.name());
+        }
+        Ok(puzzles)
+    }
+}
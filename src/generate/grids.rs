@@ -0,0 +1,195 @@
+use image::{Rgba, RgbaImage};
+
+use boardgamebench::game::grid::{Grid, Stone};
+
+use crate::labels::CoordinateLabels;
+use crate::layout::Layout;
+use crate::{GenerationError, blend};
+
+const WOOD: Rgba<u8> = Rgba([220, 179, 92, 255]);
+const FELT: Rgba<u8> = Rgba([0, 120, 60, 255]);
+const CONNECT_FOUR_BLUE: Rgba<u8> = Rgba([20, 70, 190, 255]);
+const LINE: Rgba<u8> = Rgba([30, 30, 30, 255]);
+const BLACK_STONE: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const WHITE_STONE: Rgba<u8> = Rgba([245, 245, 245, 255]);
+const RED_DISC: Rgba<u8> = Rgba([210, 30, 30, 255]);
+const YELLOW_DISC: Rgba<u8> = Rgba([240, 200, 20, 255]);
+const HOLE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Games whose states are grids of stones or discs; see
+/// `boardgamebench::game::grid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridGame {
+    Go,
+    Gomoku,
+    Othello,
+    ConnectFour,
+}
+
+impl GridGame {
+    /// The game for a collection's `game_type`, if it is a grid game.
+    pub fn from_game_type(game_type: &str) -> Option<Self> {
+        match game_type.to_ascii_lowercase().as_str() {
+            "go" => Some(GridGame::Go),
+            "gomoku" => Some(GridGame::Gomoku),
+            "othello" | "reversi" => Some(GridGame::Othello),
+            "connect_four" | "connect four" | "connect4" => Some(GridGame::ConnectFour),
+            _ => None,
+        }
+    }
+
+    /// Column labels, left to right, and row labels, top to bottom.
+    fn coordinates(self, grid: &Grid) -> (Vec<String>, Vec<String>) {
+        match self {
+            // Go coordinates skip the letter i.
+            GridGame::Go => (
+                ('a'..='z').filter(|&c| c != 'i').take(grid.width).map(String::from).collect(),
+                (1..=grid.height).rev().map(|row| row.to_string()).collect(),
+            ),
+            GridGame::Gomoku => (
+                ('a'..='z').take(grid.width).map(String::from).collect(),
+                (1..=grid.height).rev().map(|row| row.to_string()).collect(),
+            ),
+            // Othello rows count down from the top.
+            GridGame::Othello => (
+                ('a'..='z').take(grid.width).map(String::from).collect(),
+                (1..=grid.height).map(|row| row.to_string()).collect(),
+            ),
+            GridGame::ConnectFour => ((1..=grid.width).map(|column| column.to_string()).collect(), Vec::new()),
+        }
+    }
+}
+
+/// Render `state` with `layout.square_size` pixels per cell, labelling
+/// columns and rows in a margin when `labels` is given.
+pub fn render(
+    game: GridGame,
+    state: &str,
+    labels: Option<&CoordinateLabels>,
+    layout: &Layout,
+) -> Result<RgbaImage, GenerationError> {
+    let grid = Grid::parse(state)?;
+    let cell = layout.square_size;
+    let (width, height) = (grid.width as u32 * cell, grid.height as u32 * cell);
+    let center = |index: usize| (index as u32 * cell) as f32 + cell as f32 / 2.0;
+    let line_width = (cell as f32 / 25.0).max(1.0);
+
+    let background = match game {
+        GridGame::Go | GridGame::Gomoku => WOOD,
+        GridGame::Othello => FELT,
+        GridGame::ConnectFour => CONNECT_FOUR_BLUE,
+    };
+    let mut board = RgbaImage::from_pixel(width, height, background);
+
+    match game {
+        // Stones sit on the intersections of lines through the cell centers.
+        GridGame::Go | GridGame::Gomoku => {
+            let (left, right) = (center(0), center(grid.width - 1));
+            let (top, bottom) = (center(0), center(grid.height - 1));
+            let half = line_width / 2.0;
+            for column in 0..grid.width {
+                fill_rect(&mut board, center(column) - half, top - half, line_width, bottom - top + line_width, LINE);
+            }
+            for row in 0..grid.height {
+                fill_rect(&mut board, left - half, center(row) - half, right - left + line_width, line_width, LINE);
+            }
+            if game == GridGame::Go {
+                for (column, row) in star_points(grid.width, grid.height) {
+                    fill_circle(&mut board, center(column), center(row), cell as f32 * 0.1, LINE);
+                }
+            }
+        }
+        // Discs sit inside the squares.
+        GridGame::Othello => {
+            for i in 0..=grid.width {
+                fill_rect(&mut board, (i as u32 * cell) as f32 - line_width / 2.0, 0.0, line_width, height as f32, LINE);
+            }
+            for i in 0..=grid.height {
+                fill_rect(&mut board, 0.0, (i as u32 * cell) as f32 - line_width / 2.0, width as f32, line_width, LINE);
+            }
+        }
+        GridGame::ConnectFour => {}
+    }
+
+    let radius = cell as f32
+        * match game {
+            GridGame::Go | GridGame::Gomoku => 0.47,
+            GridGame::Othello | GridGame::ConnectFour => 0.4,
+        };
+    for row in 0..grid.height {
+        for column in 0..grid.width {
+            let (x, y) = (center(column), center(row));
+            let stone = grid.get(column, row);
+            match (game, stone) {
+                (GridGame::ConnectFour, stone) => {
+                    let color = match stone {
+                        None => HOLE,
+                        Some(Stone::First) => RED_DISC,
+                        Some(Stone::Second) => YELLOW_DISC,
+                    };
+                    fill_circle(&mut board, x, y, radius, color);
+                }
+                (_, Some(Stone::First)) => fill_circle(&mut board, x, y, radius, BLACK_STONE),
+                (_, Some(Stone::Second)) => {
+                    // Outlined, so white stones stand out on light boards.
+                    fill_circle(&mut board, x, y, radius, LINE);
+                    fill_circle(&mut board, x, y, radius - line_width, WHITE_STONE);
+                }
+                (_, None) => {}
+            }
+        }
+    }
+
+    if let Some(labels) = labels {
+        let (columns, rows) = game.coordinates(&grid);
+        board = labels.draw_grid(&board, cell, &columns, &rows);
+    }
+    Ok(layout.finish(board))
+}
+
+/// The hoshi of the standard Go board sizes.
+fn star_points(width: usize, height: usize) -> Vec<(usize, usize)> {
+    let points: &[usize] = match (width, height) {
+        (19, 19) => &[3, 9, 15],
+        (13, 13) => &[3, 6, 9],
+        (9, 9) => &[2, 4, 6],
+        _ => return Vec::new(),
+    };
+    let mut stars: Vec<(usize, usize)> =
+        points.iter().flat_map(|&column| points.iter().map(move |&row| (column, row))).collect();
+    // 9x9 boards only mark the corners and the center.
+    if width == 9 {
+        stars.retain(|&(column, row)| (column != 4) == (row != 4));
+    }
+    stars
+}
+
+fn fill_rect(image: &mut RgbaImage, x: f32, y: f32, width: f32, height: f32, color: Rgba<u8>) {
+    let (x0, y0) = (x.round().max(0.0) as u32, y.round().max(0.0) as u32);
+    let (x1, y1) = (
+        ((x + width).round() as u32).min(image.width()),
+        ((y + height).round() as u32).min(image.height()),
+    );
+    for py in y0..y1 {
+        for px in x0..x1 {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// An antialiased disc centered on (`cx`, `cy`).
+fn fill_circle(image: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Rgba<u8>) {
+    let x0 = (cx - radius - 1.0).max(0.0) as u32;
+    let y0 = (cy - radius - 1.0).max(0.0) as u32;
+    let x1 = ((cx + radius + 1.0).ceil() as u32).min(image.width());
+    let y1 = ((cy + radius + 1.0).ceil() as u32).min(image.height());
+    for py in y0..y1 {
+        for px in x0..x1 {
+            let distance = ((px as f32 + 0.5 - cx).powi(2) + (py as f32 + 0.5 - cy).powi(2)).sqrt();
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                blend(image.get_pixel_mut(px, py), color, coverage);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use image::{Rgba, RgbaImage};
+
+use boardgamebench::puzzle::Orientation;
+
+use crate::{GenerationError, blend, board_cell};
+
+/// Where rank and file labels are drawn.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelPlacement {
+    /// In the corners of the edge squares, in black or white, whichever
+    /// stands out on the square.
+    Inside,
+    /// In a margin around the board.
+    Outside,
+}
+
+/// Rank (1-8) and file (a-h) labels for rendered boards, so models can read
+/// UCI squares off the image.
+pub struct CoordinateLabels {
+    pub placement: LabelPlacement,
+    pub font: FontVec,
+    /// Glyph height in pixels.
+    pub size: f32,
+}
+
+const MARGIN_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const OUTSIDE_TEXT_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+impl CoordinateLabels {
+    pub fn load(placement: LabelPlacement, font_path: &str, size: f32) -> Result<Self, GenerationError> {
+        let font = FontVec::try_from_vec(std::fs::read(font_path)?)
+            .map_err(|e| GenerationError::MissingAssets(format!("invalid font {}: {}", font_path, e)))?;
+        Ok(Self { placement, font, size })
+    }
+
+    /// Label `board`, a board seen from `orientation` whose squares are
+    /// `square_size` pixels wide; `empty_board` is the same board without
+    /// pieces, for picking label colors. Outside labels grow the image by a
+    /// margin.
+    pub fn draw(
+        &self,
+        board: RgbaImage,
+        empty_board: &RgbaImage,
+        square_size: u32,
+        orientation: Orientation,
+    ) -> RgbaImage {
+        match self.placement {
+            LabelPlacement::Inside => self.draw_inside(board, empty_board, square_size, orientation),
+            LabelPlacement::Outside => {
+                let columns: Vec<String> = (0..8).map(|column| file_label(column, orientation)).collect();
+                let rows: Vec<String> = (0..8).map(|row| rank_label(row, orientation)).collect();
+                self.draw_outside(&board, square_size, &columns, &rows)
+            }
+        }
+    }
+
+    /// Label the cells of a non-chess board in a margin, whatever the
+    /// placement: `columns` from the left, `rows` from the top.
+    pub fn draw_grid(&self, board: &RgbaImage, cell_size: u32, columns: &[String], rows: &[String]) -> RgbaImage {
+        self.draw_outside(board, cell_size, columns, rows)
+    }
+
+    fn draw_inside(
+        &self,
+        mut board: RgbaImage,
+        empty_board: &RgbaImage,
+        square_size: u32,
+        orientation: Orientation,
+    ) -> RgbaImage {
+        let padding = square_size as f32 * 0.05;
+        // Average over the square, as board themes may be textured.
+        let square_color = |column: u32, row: u32| {
+            let square = image::imageops::crop_imm(
+                empty_board,
+                column * square_size,
+                row * square_size,
+                square_size,
+                square_size,
+            )
+            .to_image();
+            let mut sums = [0u64; 3];
+            for pixel in square.pixels() {
+                for channel in 0..3 {
+                    sums[channel] += pixel[channel] as u64;
+                }
+            }
+            let n = (square_size * square_size) as u64;
+            Rgba([(sums[0] / n) as u8, (sums[1] / n) as u8, (sums[2] / n) as u8, 255])
+        };
+
+        let label_color = |column: u32, row: u32| {
+            let square = square_color(column, row);
+            let luminance = 0.299 * square[0] as f32 + 0.587 * square[1] as f32 + 0.114 * square[2] as f32;
+            if luminance > 127.0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        };
+
+        for column in 0..8 {
+            let text = file_label(column, orientation);
+            let color = label_color(column, 7);
+            let x = (column + 1) as f32 * square_size as f32 - padding - self.text_width(&text);
+            let y = 8.0 * square_size as f32 - padding - self.text_height();
+            self.draw_text(&mut board, &text, x, y, color);
+        }
+        for row in 0..8 {
+            let text = rank_label(row, orientation);
+            let color = label_color(0, row);
+            let y = row as f32 * square_size as f32 + padding;
+            self.draw_text(&mut board, &text, padding, y, color);
+        }
+        board
+    }
+
+    fn draw_outside(&self, board: &RgbaImage, square_size: u32, columns: &[String], rows: &[String]) -> RgbaImage {
+        let margin = (self.size * 1.6).ceil() as u32;
+        let mut labelled = RgbaImage::from_pixel(
+            board.width() + 2 * margin,
+            board.height() + 2 * margin,
+            MARGIN_COLOR,
+        );
+        image::imageops::overlay(&mut labelled, board, margin as i64, margin as i64);
+
+        let center = |offset: u32, index: u32, extent: f32| {
+            (offset + index * square_size) as f32 + (square_size as f32 - extent) / 2.0
+        };
+        let margin_center = |extent: f32| (margin as f32 - extent) / 2.0;
+        for (column, text) in (0..).zip(columns) {
+            let x = center(margin, column, self.text_width(text));
+            let y = (margin + board.height()) as f32 + margin_center(self.text_height());
+            self.draw_text(&mut labelled, text, x, y, OUTSIDE_TEXT_COLOR);
+        }
+        for (row, text) in (0..).zip(rows) {
+            let x = margin_center(self.text_width(text));
+            let y = center(margin, row, self.text_height());
+            self.draw_text(&mut labelled, text, x, y, OUTSIDE_TEXT_COLOR);
+        }
+        labelled
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(self.size));
+        text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum()
+    }
+
+    /// Height of the labels' glyphs, which are all digits and lowercase
+    /// letters without descenders.
+    fn text_height(&self) -> f32 {
+        self.font.as_scaled(PxScale::from(self.size)).ascent() * 0.75
+    }
+
+    /// Draw `text` with its top-left corner at (`x`, `y`), blending `color`
+    /// into the image by glyph coverage.
+    fn draw_text(&self, image: &mut RgbaImage, text: &str, x: f32, y: f32, color: Rgba<u8>) {
+        let scaled = self.font.as_scaled(PxScale::from(self.size));
+        // Baseline so that the glyphs' top sits at `y`.
+        let baseline = y + self.text_height();
+        let mut caret = x;
+        for c in text.chars() {
+            let glyph = scaled.glyph_id(c).with_scale_and_position(self.size, point(caret, baseline));
+            caret += scaled.h_advance(glyph.id);
+            let Some(outlined) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px as u32 >= image.width() || py as u32 >= image.height() {
+                    return;
+                }
+                blend(image.get_pixel_mut(px as u32, py as u32), color, coverage);
+            });
+        }
+    }
+}
+
+/// The file of the squares in image column `column`.
+fn file_label(column: u32, orientation: Orientation) -> String {
+    let (file, _) = board_cell(column, 0, orientation);
+    char::from(b'a' + file as u8).to_string()
+}
+
+/// The rank of the squares in image row `row`.
+fn rank_label(row: u32, orientation: Orientation) -> String {
+    let (_, rank_from_top) = board_cell(0, row, orientation);
+    (8 - rank_from_top).to_string()
+}
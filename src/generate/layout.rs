@@ -0,0 +1,168 @@
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{self, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::GenerationError;
+
+const MARGIN_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Size and placement of the rendered board.
+#[derive(clap::Args, Debug)]
+pub struct Layout {
+    /// Width of a board square in pixels; board textures and pieces are
+    /// scaled to fit
+    #[arg(long, default_value = "150")]
+    pub square_size: u32,
+
+    /// Blank border around the board (and its outside labels) in pixels
+    #[arg(long, default_value = "0")]
+    pub margin: u32,
+
+    /// Scale the finished image so its longer side is this many pixels
+    #[arg(long)]
+    pub output_size: Option<u32>,
+
+    /// Filter for scaling board textures, pieces and the finished image
+    #[arg(long, value_enum, default_value = "gaussian")]
+    pub resize_filter: ResizeFilter,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl Layout {
+    /// Scale `image` to a `width` by `height` texture, if it isn't one.
+    pub fn fit(&self, image: DynamicImage, width: u32, height: u32) -> RgbaImage {
+        if image.width() == width && image.height() == height {
+            image.to_rgba8()
+        } else {
+            image.resize_exact(width, height, self.resize_filter.into()).to_rgba8()
+        }
+    }
+
+    /// Add the margin to a finished board and scale it to the output size.
+    pub fn finish(&self, board: RgbaImage) -> RgbaImage {
+        let mut image = if self.margin > 0 {
+            let mut framed = RgbaImage::from_pixel(
+                board.width() + 2 * self.margin,
+                board.height() + 2 * self.margin,
+                MARGIN_COLOR,
+            );
+            image::imageops::overlay(&mut framed, &board, self.margin as i64, self.margin as i64);
+            framed
+        } else {
+            board
+        };
+        if let Some(size) = self.output_size {
+            let longer = image.width().max(image.height());
+            if longer != size {
+                let scale = |extent: u32| ((extent as u64 * size as u64) as f64 / longer as f64).round().max(1.0) as u32;
+                image = image::imageops::resize(
+                    &image,
+                    scale(image.width()),
+                    scale(image.height()),
+                    self.resize_filter.into(),
+                );
+            }
+        }
+        image
+    }
+}
+
+/// File format and compression of the saved board images.
+#[derive(clap::Args, Debug)]
+pub struct Encoding {
+    /// Format of the board images
+    #[arg(long, value_enum, default_value = "png")]
+    pub image_format: ImageFormat,
+
+    /// JPEG quality, from 1 to 100
+    #[arg(long, default_value = "90", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub jpeg_quality: u8,
+
+    /// PNG compression effort
+    #[arg(long, value_enum, default_value = "default")]
+    pub png_compression: PngCompression,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// Lossy, at `--jpeg-quality`; transparency is flattened onto white
+    Jpeg,
+    /// Lossless
+    Webp,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl Encoding {
+    pub fn extension(&self) -> &'static str {
+        match self.image_format {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self.image_format {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+
+    pub fn encode(&self, image: &RgbaImage) -> Result<Vec<u8>, GenerationError> {
+        let mut bytes = Vec::new();
+        let cursor = Cursor::new(&mut bytes);
+        match self.image_format {
+            ImageFormat::Png => {
+                let compression = match self.png_compression {
+                    PngCompression::Fast => png::CompressionType::Fast,
+                    PngCompression::Default => png::CompressionType::Default,
+                    PngCompression::Best => png::CompressionType::Best,
+                };
+                image.write_with_encoder(PngEncoder::new_with_quality(cursor, compression, png::FilterType::Adaptive))?;
+            }
+            ImageFormat::Jpeg => {
+                // JPEG has no alpha channel, so flatten onto white.
+                let mut flat = RgbaImage::from_pixel(image.width(), image.height(), MARGIN_COLOR);
+                image::imageops::overlay(&mut flat, image, 0, 0);
+                let rgb = DynamicImage::ImageRgba8(flat).to_rgb8();
+                rgb.write_with_encoder(JpegEncoder::new_with_quality(cursor, self.jpeg_quality))?;
+            }
+            ImageFormat::Webp => {
+                image.write_with_encoder(WebPEncoder::new_lossless(cursor))?;
+            }
+        }
+        Ok(bytes)
+    }
+}
@@ -1,27 +1,215 @@
-use shakmaty::fen::Fen;
-use shakmaty::uci::UciMove;
-use shakmaty::*;
-use std::error::Error;
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 use rand::prelude::*;
 use rand::SeedableRng;
 use rand::rngs::SmallRng;
-use image::open;
+use sha2::{Digest, Sha256};
+use clap::Parser;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::{Rgba, RgbaImage, open};
+use thiserror::Error;
+
+use boardgamebench::augment::{self, Augmentation};
+use boardgamebench::extraction::{AnswerFormat, AnswerGrammar};
+use boardgamebench::game::GameError;
+use boardgamebench::game::chess::{self, ChessError};
+use boardgamebench::notation::NotationError;
+use boardgamebench::prompt::PromptLanguage;
+use boardgamebench::puzzle::{
+    Arrow, BoardAnnotations, GoalCondition, MoveHistory, Orientation, Provenance, Puzzle, PuzzleCollection, PuzzleError,
+    RenderStyle, Translation,
+};
+use boardgamebench::scoring::AnswerScoring;
+
+mod annotations;
+#[cfg(feature = "runner")]
+mod continuations;
+#[cfg(feature = "runner")]
+mod endgames;
+mod grids;
+mod labels;
+mod layout;
+mod openings;
+mod sequences;
+
+#[cfg(feature = "runner")]
+use continuations::Continuations;
+#[cfg(feature = "runner")]
+use endgames::Endgames;
+use grids::GridGame;
+use labels::{CoordinateLabels, LabelPlacement};
+use layout::{Encoding, Layout};
+use openings::Openings;
+use sequences::{EncodedSequence, Sequences};
+
+/// Generate a chess puzzle collection from the Lichess puzzle database
+#[derive(Parser, Debug)]
+struct GenerateArgs {
+    /// Instead of generating puzzles, render board images for every state
+    /// of this collection (chess, go, gomoku, othello or connect_four) and
+    /// save it back with the images attached
+    #[arg(long)]
+    render_collection: Option<String>,
+
+    /// Store board images inline in the collection instead of as files
+    #[arg(long)]
+    embed_images: bool,
+
+    /// Draw rank and file labels on the board images
+    #[arg(long, value_enum, requires = "coordinate_font")]
+    coordinates: Option<LabelPlacement>,
+
+    /// TrueType or OpenType font for the coordinate labels
+    #[arg(long)]
+    coordinate_font: Option<String>,
+
+    /// Height of the coordinate labels in pixels
+    #[arg(long, default_value = "28")]
+    coordinate_size: f32,
+
+    /// How the opponent's last move is marked on the board images
+    #[arg(long, value_enum, default_value = "highlight")]
+    last_move: LastMove,
+
+    /// Which side is at the bottom of the board images
+    #[arg(long, value_enum, default_value = "side-to-move")]
+    orientation: OrientationChoice,
+
+    #[command(flatten)]
+    layout: Layout,
+
+    #[command(flatten)]
+    encoding: Encoding,
+
+    #[command(flatten)]
+    sequences: Sequences,
+
+    #[cfg(feature = "runner")]
+    #[command(flatten)]
+    continuations: Continuations,
+
+    #[command(flatten)]
+    openings: Openings,
+
+    #[cfg(feature = "runner")]
+    #[command(flatten)]
+    endgames: Endgames,
+
+    /// Also write data/lichess_transcription_puzzles.json, asking for each
+    /// puzzle's position to be read off its image as a full FEN or just the
+    /// piece placement
+    #[arg(long, value_enum)]
+    transcription: Option<TranscriptionFields>,
+
+    /// Perturb the board images, e.g. "jpeg=30,blur=1.5,rotate=3,perspective=0.05,jitter=0.2"
+    #[arg(long)]
+    augment: Option<Augmentation>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OrientationChoice {
+    /// The side to move in the puzzle position
+    SideToMove,
+    /// Always White
+    White,
+    /// Always Black
+    Black,
+}
+
+impl OrientationChoice {
+    fn orientation(self, fen: &str) -> Result<Orientation, GenerationError> {
+        Ok(match self {
+            OrientationChoice::SideToMove if chess::white_to_move(fen)? => Orientation::White,
+            OrientationChoice::SideToMove => Orientation::Black,
+            OrientationChoice::White => Orientation::White,
+            OrientationChoice::Black => Orientation::Black,
+        })
+    }
+}
 
-use boardgamebench::puzzle::{Puzzle, PuzzleCollection};
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TranscriptionFields {
+    /// Only the piece placement field
+    Placement,
+    /// All six FEN fields
+    Fen,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LastMove {
+    /// Tint its from and to squares
+    Highlight,
+    /// Draw an arrow along it
+    Arrow,
+    /// Leave it unmarked
+    None,
+}
+
+impl LastMove {
+    /// Annotations marking `uci`, the move that led to the puzzle position.
+    fn annotations(self, uci: &str) -> BoardAnnotations {
+        let (from, to) = (uci[..2].to_string(), uci[2..4].to_string());
+        match self {
+            LastMove::Highlight => BoardAnnotations {
+                highlights: vec![from, to],
+                ..Default::default()
+            },
+            LastMove::Arrow => BoardAnnotations {
+                arrows: vec![Arrow { from, to }],
+                ..Default::default()
+            },
+            LastMove::None => BoardAnnotations::default(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum GenerationError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Chess(#[from] ChessError),
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error(transparent)]
+    Puzzle(#[from] PuzzleError),
+    #[error(transparent)]
+    Notation(#[from] NotationError),
+    #[error("No renderer for game type {0}")]
+    UnsupportedGame(String),
+    #[error("Missing assets: {0}")]
+    MissingAssets(String),
+    #[error("Invalid board annotation: {0}")]
+    InvalidAnnotation(String),
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+    #[error(transparent)]
+    Augment(#[from] augment::AugmentError),
+    #[error("Failed to serialize collection: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "runner")]
+    #[error("Engine error: {0}")]
+    Engine(String),
+    #[cfg(feature = "runner")]
+    #[error("Tablebase error: {0}")]
+    Tablebase(String),
+}
 
 #[derive(Debug, Clone)]
 struct PuzzleData {
+    id: String,
     rating: f64,
+    rating_deviation: f64,
     fen: String,
     moves: String,
     game_url: String,
     themes: String,
 }
 
-fn read_puzzle_database(file_path: &str) -> Result<Vec<PuzzleData>, Box<dyn Error>> {
+fn read_puzzle_database(file_path: &str) -> Result<Vec<PuzzleData>, GenerationError> {
     let file = File::open(file_path)?;
     let reader = BufReader::new(file);
     let mut puzzles = Vec::new();
@@ -31,16 +219,20 @@ fn read_puzzle_database(file_path: &str) -> Result<Vec<PuzzleData>, Box<dyn Erro
         let fields: Vec<&str> = line.split(',').collect();
 
         if fields.len() >= 9 {
+            let id = fields[0].to_string();
             let fen = fields[1].to_string();
             let moves = fields[2].to_string();
             let rating = fields[3].parse::<f64>().unwrap_or(0.0);
+            let rating_deviation = fields[4].parse::<f64>().unwrap_or(0.0);
             let _popularity = fields[5].parse::<f64>().unwrap_or(0.0);
             let _nb_plays = fields[6].parse::<f64>().unwrap_or(0.0);
             let themes = fields[7].to_string();
             let game_url = fields[8].to_string();
 
             puzzles.push(PuzzleData {
+                id,
                 rating,
+                rating_deviation,
                 fen,
                 moves,
                 game_url,
@@ -75,7 +267,9 @@ fn generate_puzzles_from_data(
     puzzle_type: &str,
     count: usize,
     seed: u64,
-) -> Result<Vec<Puzzle>, Box<dyn Error>> {
+    last_move: LastMove,
+    orientation: OrientationChoice,
+) -> Result<Vec<Puzzle>, GenerationError> {
     let mut rng = SmallRng::seed_from_u64(seed);
     let mut selected_puzzles: Vec<&PuzzleData> = puzzle_data.iter().collect();
     selected_puzzles = selected_puzzles.partial_shuffle(&mut rng, count).0.to_vec();
@@ -86,22 +280,46 @@ fn generate_puzzles_from_data(
         let moves: Vec<&str> = puzzle.moves.split_whitespace().collect();
 
         // Calculate the FEN after the first move
-        let pos = Chess::from_setup(
-            Setup::from(Fen::from_ascii(puzzle.fen.as_bytes())?),
-            CastlingMode::Standard,
-        )?;
-        let move0: UciMove = moves[0].parse()?;
-        let chess_move = move0.to_move(&pos)?;
-        let pos_after_move = pos.play(chess_move)?;
-        let fen_after_move = Fen::from_position(&pos_after_move, EnPassantMode::Always);
+        let fen_after_move = chess::play(&puzzle.fen, moves[0])?;
 
         let move1 = moves[1].to_string();
+        let orientation = orientation.orientation(&fen_after_move)?;
 
         let puzzle_obj = Puzzle {
             id: format!("chess_{}_{:02}", puzzle_type, i + 1),
             description: format!("Chess {} puzzle from {}", puzzle_type, puzzle.game_url),
-            game_states: vec![fen_after_move.to_string()],
+            game_type: None,
+            game_states: vec![fen_after_move],
             solutions: vec![move1],
+            encrypted_solutions: None,
+            rating: Some(puzzle.rating),
+            rating_deviation: Some(puzzle.rating_deviation),
+            weight: 1.0,
+            state_weights: Vec::new(),
+            images: Vec::new(),
+            sequences: Vec::new(),
+            annotations: vec![last_move.annotations(moves[0])],
+            orientations: vec![orientation],
+            history: Some(MoveHistory {
+                start: puzzle.fen.clone(),
+                moves: vec![moves[0].to_string()],
+            }),
+            render_style: None,
+            tags: puzzle.themes.split_whitespace().map(str::to_string).collect(),
+            provenance: Provenance {
+                source_id: Some(puzzle.id.clone()),
+                url: Some(puzzle.game_url.clone()),
+                ..Provenance::default()
+            },
+            metadata: BTreeMap::new(),
+            // Only the first solver move is kept, so only mates in one end
+            // the puzzle with mate.
+            goal_condition: puzzle
+                .themes
+                .split_whitespace()
+                .any(|theme| theme == "mateIn1")
+                .then_some(GoalCondition::Checkmate),
+            goal: None,
         };
 
         puzzles.push(puzzle_obj);
@@ -110,7 +328,7 @@ fn generate_puzzles_from_data(
     Ok(puzzles)
 }
 
-fn load_board_themes() -> Result<Vec<String>, Box<dyn Error>> {
+fn load_board_themes() -> Result<Vec<String>, GenerationError> {
     let board_dir = Path::new("images/chess/board");
     let mut themes = Vec::new();
 
@@ -119,19 +337,20 @@ fn load_board_themes() -> Result<Vec<String>, Box<dyn Error>> {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "png") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "png")
+                && let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
                     themes.push(stem.to_string());
                 }
-            }
         }
     }
 
+    // Sorted, as directory order varies between file systems.
+    themes.sort();
     println!("Loaded {} board themes: {:?}", themes.len(), themes);
     Ok(themes)
 }
 
-fn load_piece_styles() -> Result<Vec<String>, Box<dyn Error>> {
+fn load_piece_styles() -> Result<Vec<String>, GenerationError> {
     let pieces_dir = Path::new("images/chess/pieces");
     let mut styles = Vec::new();
 
@@ -140,98 +359,472 @@ fn load_piece_styles() -> Result<Vec<String>, Box<dyn Error>> {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_dir() {
-                if let Some(style_name) = path.file_name().and_then(|s| s.to_str()) {
+            if path.is_dir()
+                && let Some(style_name) = path.file_name().and_then(|s| s.to_str()) {
                     styles.push(style_name.to_string());
                 }
-            }
         }
     }
 
+    styles.sort();
     println!("Loaded {} piece styles: {:?}", styles.len(), styles);
     Ok(styles)
 }
 
-/// Generate a chess board image from FEN notation using random board and piece themes
+/// Board themes and piece styles available to render with.
+struct ImageAssets {
+    board_themes: Vec<String>,
+    piece_styles: Vec<String>,
+}
+
+impl ImageAssets {
+    fn load() -> Result<Self, GenerationError> {
+        let board_themes = load_board_themes()?;
+        if board_themes.is_empty() {
+            return Err(GenerationError::MissingAssets("no board themes found in images/chess/board/".to_string()));
+        }
+
+        let piece_styles = load_piece_styles()?;
+        if piece_styles.is_empty() {
+            return Err(GenerationError::MissingAssets("no piece styles found in images/chess/pieces/".to_string()));
+        }
+
+        Ok(Self { board_themes, piece_styles })
+    }
+
+    /// The board theme and piece style for the image named `key` (a
+    /// puzzle id), picked by a hash of `seed` and `key` so the same puzzle
+    /// always renders the same way.
+    fn style_for(&self, seed: u64, key: &str) -> RenderStyle {
+        let digest = Sha256::digest(format!("{}:{}", seed, key));
+        let mut rng = SmallRng::seed_from_u64(u64::from_le_bytes(digest[..8].try_into().unwrap()));
+        RenderStyle {
+            board_theme: self.board_themes.choose(&mut rng).unwrap().clone(),
+            piece_style: self.piece_styles.choose(&mut rng).unwrap().clone(),
+        }
+    }
+}
+
+/// Generate a chess board image from FEN notation, with a board theme and
+/// piece style picked by `seed`
 fn generate_board_image_from_fen(
     fen: &str,
     output_path: &str,
     seed: u64,
-) -> Result<(), Box<dyn Error>> {
-    let mut rng = SmallRng::seed_from_u64(seed);
+    labels: Option<&CoordinateLabels>,
+    layout: &Layout,
+) -> Result<(), GenerationError> {
+    let assets = ImageAssets::load()?;
+    let orientation = OrientationChoice::SideToMove.orientation(fen)?;
+    let style = assets.style_for(seed, "test_board");
+    let board_image = render_board_image(fen, &style, labels, None, orientation, layout)?;
+    board_image.save(output_path)?;
+    println!("Board image saved to: {}", output_path);
+
+    Ok(())
+}
+
+fn render_board_image(
+    fen: &str,
+    style: &RenderStyle,
+    labels: Option<&CoordinateLabels>,
+    annotations: Option<&BoardAnnotations>,
+    orientation: Orientation,
+    layout: &Layout,
+) -> Result<RgbaImage, GenerationError> {
+    println!(
+        "Generating board image with theme '{}' and piece style '{}'",
+        style.board_theme, style.piece_style
+    );
+
+    let board_path = format!("images/chess/board/{}.png", style.board_theme);
+    let board_image = open(&board_path)?;
+
+    let pieces = chess::board_pieces(fen)?;
+
+    let square_size = layout.square_size;
+    let mut board_image = layout.fit(board_image, square_size * 8, square_size * 8);
+    let empty_board = labels.map(|_| board_image.clone());
+    if let Some(annotations) = annotations {
+        annotations::draw_highlights(&mut board_image, annotations, square_size, orientation)?;
+    }
+
+    for piece in pieces {
+        let piece_path = format!("images/chess/pieces/{}/{}.png", style.piece_style, piece.code);
+        let piece_image = layout.fit(open(&piece_path)?, square_size, square_size);
 
-    let board_themes = load_board_themes()?;
-    if board_themes.is_empty() {
-        return Err("No board themes found in images/chess/board/".into());
+        let (column, row) = board_cell(piece.file, piece.rank_from_top, orientation);
+        let x = column * square_size;
+        let y = row * square_size;
+
+        image::imageops::overlay(&mut board_image, &piece_image, x as i64, y as i64);
     }
 
-    let piece_styles = load_piece_styles()?;
-    if piece_styles.is_empty() {
-        return Err("No piece styles found in images/chess/pieces/".into());
+    if let Some(annotations) = annotations {
+        annotations::draw_arrows(&mut board_image, annotations, square_size, orientation)?;
+    }
+    if let (Some(labels), Some(empty_board)) = (labels, &empty_board) {
+        board_image = labels.draw(board_image, empty_board, square_size, orientation);
     }
 
-    // Randomly select board theme and piece style
-    let board_theme = board_themes.choose(&mut rng).unwrap();
-    let piece_style = piece_styles.choose(&mut rng).unwrap();
-
-    println!("Generating board image with theme '{}' and piece style '{}'", board_theme, piece_style);
-
-    let board_path = format!("images/chess/board/{}.png", board_theme);
-    let mut board_image = open(&board_path)?;
-
-    let pos = Chess::from_setup(
-        Setup::from(Fen::from_ascii(fen.as_bytes())?),
-        CastlingMode::Standard,
-    )?;
-
-    let board = pos.board();
-
-    let square_size = 150;
-    let board_offset_x = 0;
-    let board_offset_y = 0;
-    
-    board_image = board_image.resize(
-        square_size * 8 + board_offset_x * 2,
-        square_size * 8 + board_offset_y * 2,
-        image::imageops::FilterType::Gaussian);
-
-    for rank in 0..8 {
-        for file in 0..8 {
-            let square = Square::from_coords(shakmaty::File::new(file), shakmaty::Rank::new(7 - rank)); // Convert to chess coordinates (a1 is bottom-left)
-            if let Some(piece) = board.piece_at(square) {
-                let piece_code = match (piece.color, piece.role) {
-                    (Color::White, Role::Pawn) => "wp",
-                    (Color::White, Role::Knight) => "wn",
-                    (Color::White, Role::Bishop) => "wb",
-                    (Color::White, Role::Rook) => "wr",
-                    (Color::White, Role::Queen) => "wq",
-                    (Color::White, Role::King) => "wk",
-                    (Color::Black, Role::Pawn) => "bp",
-                    (Color::Black, Role::Knight) => "bn",
-                    (Color::Black, Role::Bishop) => "bb",
-                    (Color::Black, Role::Rook) => "br",
-                    (Color::Black, Role::Queen) => "bq",
-                    (Color::Black, Role::King) => "bk",
+    Ok(layout.finish(board_image))
+}
+
+/// The image column and row of the square on `file` (0 for a) and
+/// `rank_from_top` (0 for rank 8) when seen from `orientation`. Flipping is
+/// its own inverse, so this also maps a column and row back to the square.
+fn board_cell(file: u32, rank_from_top: u32, orientation: Orientation) -> (u32, u32) {
+    match orientation {
+        Orientation::White => (file, rank_from_top),
+        Orientation::Black => (7 - file, 7 - rank_from_top),
+    }
+}
+
+/// Mix `color` into `pixel`, `coverage` being the share of `color`.
+fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+    for channel in 0..4 {
+        pixel[channel] = (pixel[channel] as f32 * (1.0 - coverage) + color[channel] as f32 * coverage).round() as u8;
+    }
+}
+
+/// Render each puzzle's first state and attach the image: embedded as a
+/// base64 data URI, or saved under `data/images/` and referenced by a path
+/// relative to the collection file.
+fn attach_board_images(
+    puzzles: &mut [Puzzle],
+    seed: u64,
+    labels: Option<&CoordinateLabels>,
+    args: &GenerateArgs,
+) -> Result<(), GenerationError> {
+    let assets = ImageAssets::load()?;
+    let dir = Path::new("data");
+
+    for puzzle in puzzles.iter_mut() {
+        let style = assets.style_for(seed, &puzzle.id);
+        let mut images = Vec::new();
+        let mut sequences = Vec::new();
+        for (index, state) in puzzle.game_states.iter().enumerate() {
+            // Puzzles extended with --engine have a file per state, as
+            // --render-collection names them.
+            let name = if puzzle.game_states.len() == 1 {
+                puzzle.id.clone()
+            } else {
+                format!("{}_{}", puzzle.id, index + 1)
+            };
+            let orientation = puzzle.orientations.get(index).copied().unwrap_or_default();
+            let board_image =
+                render_board_image(state, &style, labels, puzzle.annotations.get(index), orientation, &args.layout)?;
+            let board_image = match &args.augment {
+                Some(augmentation) => {
+                    augment::apply(augmentation, board_image, augmentation.image_seed(&puzzle.id, index))?
+                }
+                None => board_image,
+            };
+            images.push(store_image(&board_image, dir, &name, args.embed_images, &args.encoding)?);
+            if let Some(sequence) = render_sequence(puzzle, index, &style, orientation, labels, args)? {
+                sequences.push(sequence.store(dir, &format!("{}_sequence", name), args.embed_images)?);
+            }
+        }
+        puzzle.images = images;
+        puzzle.sequences = sequences;
+        puzzle.render_style = Some(style);
+    }
+
+    println!("Attached board images to {} puzzles", puzzles.len());
+    Ok(())
+}
+
+/// Render the positions leading to state `index` of a chess puzzle, in the
+/// style and orientation of its image, each marked with the move that
+/// reached it; `None` if no sequence was asked for.
+fn render_sequence(
+    puzzle: &Puzzle,
+    index: usize,
+    style: &RenderStyle,
+    orientation: Orientation,
+    labels: Option<&CoordinateLabels>,
+    args: &GenerateArgs,
+) -> Result<Option<EncodedSequence>, GenerationError> {
+    if args.sequences.sequence.is_none() {
+        return Ok(None);
+    }
+    let frames = args
+        .sequences
+        .positions(puzzle, index)?
+        .iter()
+        .map(|(fen, uci)| {
+            let annotations = uci.as_deref().map(|uci| args.last_move.annotations(uci));
+            render_board_image(fen, style, labels, annotations.as_ref(), orientation, &args.layout)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    args.sequences.encode(frames, &args.encoding)
+}
+
+/// Encode `image` and return it as a data URI if `embed`, or else save it
+/// as `images/<name>.<ext>` under `dir` and return that relative path.
+fn store_image(
+    image: &RgbaImage,
+    dir: &Path,
+    name: &str,
+    embed: bool,
+    encoding: &Encoding,
+) -> Result<String, GenerationError> {
+    let bytes = encoding.encode(image)?;
+    store_bytes(&bytes, dir, name, embed, encoding.extension(), encoding.mime_type())
+}
+
+/// Like `store_image`, for an already encoded file.
+fn store_bytes(
+    bytes: &[u8],
+    dir: &Path,
+    name: &str,
+    embed: bool,
+    extension: &str,
+    mime_type: &str,
+) -> Result<String, GenerationError> {
+    if embed {
+        return Ok(format!("data:{};base64,{}", mime_type, BASE64.encode(bytes)));
+    }
+    let path = format!("images/{}.{}", name, extension);
+    fs::create_dir_all(dir.join("images"))?;
+    fs::write(dir.join(&path), bytes)?;
+    Ok(path)
+}
+
+/// Render an image for every state of the collection at `path` that has
+/// none yet, by each puzzle's game type, and save the collection back.
+fn render_collection(
+    path: &str,
+    args: &GenerateArgs,
+    labels: Option<&CoordinateLabels>,
+) -> Result<(), GenerationError> {
+    if path.starts_with("builtin:") || path.ends_with(".jsonl") {
+        return Err(PuzzleError::FileError(format!("can only render into a .json collection file, not {}", path)).into());
+    }
+    let mut collection = PuzzleCollection::load_from_file(path)?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let mut assets = None;
+    let mut rendered = 0;
+
+    for i in 0..collection.puzzles.len() {
+        let puzzle = &collection.puzzles[i];
+        if !puzzle.images.is_empty() {
+            continue;
+        }
+        let game_type = collection.game_type_of(puzzle).to_string();
+        let mut images = Vec::new();
+        let mut sequences = Vec::new();
+        let mut orientations = Vec::new();
+        let mut render_style = None;
+        for (index, state) in puzzle.game_states.iter().enumerate() {
+            let board_image = if game_type.eq_ignore_ascii_case("chess") {
+                let assets = match &mut assets {
+                    Some(assets) => assets,
+                    None => assets.insert(ImageAssets::load()?),
                 };
+                let style = assets.style_for(3407, &puzzle.id);
+                let orientation = args.orientation.orientation(state)?;
+                let image =
+                    render_board_image(state, &style, labels, puzzle.annotations.get(index), orientation, &args.layout)?;
+                if let Some(sequence) = render_sequence(puzzle, index, &style, orientation, labels, args)? {
+                    let name = format!("{}_{}_sequence", puzzle.id, index + 1);
+                    sequences.push(sequence.store(dir, &name, args.embed_images)?);
+                }
+                orientations.push(orientation);
+                render_style = Some(style);
+                image
+            } else {
+                let game = GridGame::from_game_type(&game_type)
+                    .ok_or_else(|| GenerationError::UnsupportedGame(game_type.clone()))?;
+                grids::render(game, state, labels, &args.layout)?
+            };
+            let board_image = match &args.augment {
+                Some(augmentation) => {
+                    augment::apply(augmentation, board_image, augmentation.image_seed(&puzzle.id, index))?
+                }
+                None => board_image,
+            };
+            let name = format!("{}_{}", puzzle.id, index + 1);
+            images.push(store_image(&board_image, dir, &name, args.embed_images, &args.encoding)?);
+        }
+
+        let puzzle = &mut collection.puzzles[i];
+        puzzle.images = images;
+        puzzle.sequences = sequences;
+        puzzle.orientations = orientations;
+        puzzle.render_style = render_style;
+        rendered += 1;
+    }
 
-                let piece_path = format!("images/chess/pieces/{}/{}.png", piece_style, piece_code);
-                let piece_image = open(&piece_path)?;
+    collection.save_to_file(path)?;
+    println!("Rendered board images for {} puzzles in {}", rendered, path);
+    Ok(())
+}
 
-                let x = board_offset_x + (file as u32) * square_size;
-                let y = board_offset_y + (rank as u32) * square_size;
+/// The puzzles of `collection` as a transcription task: each puzzle's first
+/// state, shown by its image, is answered with its FEN (or placement).
+/// The generated collection's goal in the other prompt languages.
+fn goal_translations() -> BTreeMap<PromptLanguage, Translation> {
+    [
+        (PromptLanguage::German, "Finde den besten Zug, mit dem die Seite am Zug die gegebene Schachpartie gewinnt"),
+        (
+            PromptLanguage::Spanish,
+            "Encuentra la mejor jugada para que gane el jugador al que le toca mover en la partida de ajedrez dada",
+        ),
+        (
+            PromptLanguage::French,
+            "Trouve le meilleur coup permettant au joueur au trait de gagner la partie d'échecs donnée",
+        ),
+        (PromptLanguage::Chinese, "在给定的国际象棋对局中，为当前走棋方找出获胜的最佳着法"),
+    ]
+    .into_iter()
+    .map(|(language, goal)| (language, Translation { goal: goal.to_string(), game_rule: None, prompt_template: None }))
+    .collect()
+}
 
-                image::imageops::overlay(&mut board_image, &piece_image, x as i64, y as i64);
+fn transcription_collection(collection: &PuzzleCollection, fields: TranscriptionFields) -> PuzzleCollection {
+    let puzzles = collection
+        .puzzles
+        .iter()
+        .map(|puzzle| {
+            let fen = &puzzle.game_states[0];
+            let fen_fields: Vec<&str> = fen.split_whitespace().collect();
+            // Move counters can't be read off a board, so they are reset.
+            let solution = match fields {
+                TranscriptionFields::Placement => fen_fields[0].to_string(),
+                TranscriptionFields::Fen => format!("{} 0 1", fen_fields[..4].join(" ")),
+            };
+            Puzzle {
+                id: puzzle.id.replacen("chess_", "transcribe_", 1),
+                description: format!("Transcription of {}", puzzle.id),
+                game_states: vec![fen.clone()],
+                solutions: vec![solution],
+                encrypted_solutions: None,
+                images: puzzle.images.iter().take(1).cloned().collect(),
+                sequences: Vec::new(),
+                annotations: puzzle.annotations.iter().take(1).cloned().collect(),
+                orientations: puzzle.orientations.iter().take(1).copied().collect(),
+                state_weights: Vec::new(),
+                history: None,
+                goal_condition: None,
+                goal: None,
+                ..puzzle.clone()
             }
+        })
+        .collect();
+    let goal = match fields {
+        TranscriptionFields::Placement => "Write down the piece placement field of the position's FEN",
+        TranscriptionFields::Fen => {
+            "Write down the position's full FEN, including side to move, castling rights and en passant square, with the move counters as 0 1"
         }
+    };
+    PuzzleCollection {
+        name: "Lichess Board Transcription Collection".to_string(),
+        description: "Board images of the Lichess puzzle positions, to be transcribed as FEN".to_string(),
+        game_type: "chess".to_string(),
+        goal: goal.to_string(),
+        game_rule: "".to_string(),
+        answer_format: AnswerFormat {
+            grammar: AnswerGrammar::Fen,
+            ..AnswerFormat::default()
+        },
+        language: PromptLanguage::English,
+        translations: BTreeMap::new(),
+        prompt_template: None,
+        answer_scoring: None,
+        withheld_from: None,
+        solution_key_id: None,
+        provenance: collection.provenance.clone(),
+        puzzles,
+        base_dir: None,
+        encrypted_checksum: None,
+        selected: None,
     }
+}
 
-    board_image.save(output_path)?;
-    println!("Board image saved to: {}", output_path);
+/// Write data/opening_puzzles.json from the `--openings` ECO files.
+fn opening_collection(args: &GenerateArgs, labels: Option<&CoordinateLabels>) -> Result<(), GenerationError> {
+    let mut puzzles = args.openings.generate(3407, args.last_move, args.orientation)?;
+    attach_board_images(&mut puzzles, 3407, labels, args)?;
+    let collection = PuzzleCollection {
+        name: "Chess Opening Knowledge Collection".to_string(),
+        description: "Book moves and opening names from an ECO database, testing memorized opening theory rather than calculation".to_string(),
+        game_type: "chess".to_string(),
+        goal: "Answer the question about the opening of the given chess game.".to_string(),
+        game_rule: "".to_string(),
+        answer_format: AnswerFormat::default(),
+        language: PromptLanguage::English,
+        translations: BTreeMap::new(),
+        prompt_template: None,
+        answer_scoring: Some(AnswerScoring::OpeningBook),
+        withheld_from: None,
+        solution_key_id: None,
+        provenance: Provenance {
+            source: Some("lichess-org/chess-openings".to_string()),
+            source_id: None,
+            url: Some("https://github.com/lichess-org/chess-openings".to_string()),
+            license: Some("CC0-1.0".to_string()),
+        },
+        puzzles,
+        base_dir: None,
+        encrypted_checksum: None,
+        selected: None,
+    };
+    collection.save_to_file("data/opening_puzzles.json")?;
+    println!("Successfully generated opening_puzzles.json");
+    Ok(())
+}
 
+/// Write data/endgame_puzzles.json for the `--endgames` endings.
+#[cfg(feature = "runner")]
+fn endgame_collection(args: &GenerateArgs, labels: Option<&CoordinateLabels>) -> Result<(), GenerationError> {
+    let mut puzzles = args.endgames.generate(3407, args.last_move, args.orientation)?;
+    attach_board_images(&mut puzzles, 3407, labels, args)?;
+    let collection = PuzzleCollection {
+        name: "Tablebase Endgame Technique Collection".to_string(),
+        description: "Elementary endings to convert move by move, graded against tablebase-optimal play".to_string(),
+        game_type: "chess".to_string(),
+        goal: "Win the endgame as quickly as possible: play the best move for the side to move.".to_string(),
+        game_rule: "".to_string(),
+        answer_format: AnswerFormat::default(),
+        language: PromptLanguage::English,
+        translations: BTreeMap::new(),
+        prompt_template: None,
+        answer_scoring: Some(AnswerScoring::TablebaseOptimal),
+        withheld_from: None,
+        solution_key_id: None,
+        provenance: Provenance {
+            source: Some("tablebase".to_string()),
+            url: Some(args.endgames.tablebase_url.clone()),
+            ..Provenance::default()
+        },
+        puzzles,
+        base_dir: None,
+        encrypted_checksum: None,
+        selected: None,
+    };
+    collection.save_to_file("data/endgame_puzzles.json")?;
+    println!("Successfully generated endgame_puzzles.json");
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<(), GenerationError> {
+    let args = GenerateArgs::parse();
+    let labels = match (args.coordinates, &args.coordinate_font) {
+        (Some(placement), Some(font)) => Some(CoordinateLabels::load(placement, font, args.coordinate_size)?),
+        _ => None,
+    };
+    if let Some(path) = &args.render_collection {
+        return render_collection(path, &args, labels.as_ref());
+    }
+    if !args.openings.openings.is_empty() {
+        return opening_collection(&args, labels.as_ref());
+    }
+    #[cfg(feature = "runner")]
+    if !args.endgames.endgames.is_empty() {
+        return endgame_collection(&args, labels.as_ref());
+    }
+
     // Read the puzzle database
     let all_puzzles = read_puzzle_database("database/lichess_db_puzzle.csv")?;
     println!("Loaded {} puzzles from database", all_puzzles.len());
@@ -262,10 +855,15 @@ fn main() -> Result<(), Box<dyn Error>> {
         println!("Found {} {} puzzles", filtered_puzzles.len(), theme);
 
         // Generate 10 puzzles for this type
-        let puzzles = generate_puzzles_from_data(&filtered_puzzles, theme, 20, 3407)?;
+        let puzzles = generate_puzzles_from_data(&filtered_puzzles, theme, 20, 3407, args.last_move, args.orientation)?;
         all_generated_puzzles.extend(puzzles);
     }
 
+    #[cfg(feature = "runner")]
+    args.continuations
+        .extend(&mut all_generated_puzzles, args.last_move, args.orientation)?;
+    attach_board_images(&mut all_generated_puzzles, 3407, labels.as_ref(), &args)?;
+
     // Create the puzzle collection
     let collection = PuzzleCollection {
         name: "Lichess Multi-Type Chess Puzzles Collection".to_string(),
@@ -273,7 +871,23 @@ fn main() -> Result<(), Box<dyn Error>> {
         game_type: "chess".to_string(),
         goal: "Find the best move to win for current player in the given chess game.".to_string(),
         game_rule: "".to_string(),
+        answer_format: AnswerFormat::default(),
+        language: PromptLanguage::English,
+        translations: goal_translations(),
+        prompt_template: None,
+        answer_scoring: None,
+        withheld_from: None,
+        solution_key_id: None,
+        provenance: Provenance {
+            source: Some("lichess".to_string()),
+            source_id: None,
+            url: Some("https://database.lichess.org/#puzzles".to_string()),
+            license: Some("CC0-1.0".to_string()),
+        },
         puzzles: all_generated_puzzles,
+        base_dir: None,
+        encrypted_checksum: None,
+        selected: None,
     };
 
     // Save the collection
@@ -284,11 +898,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Successfully generated lichess_multi_type_puzzles.json");
     println!("Generated {} puzzles", collection.puzzles.len());
 
+    if let Some(fields) = args.transcription {
+        let transcription = transcription_collection(&collection, fields);
+        transcription.save_to_file("data/lichess_transcription_puzzles.json")?;
+        println!("Successfully generated lichess_transcription_puzzles.json");
+    }
+
     // Test the board image generation function
     println!("\nTesting board image generation...");
     let test_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; // Standard starting position
     let output_path = "output/images/test_board.png";
-    generate_board_image_from_fen(test_fen, output_path, 12345)?;
+    generate_board_image_from_fen(test_fen, output_path, 12345, labels.as_ref(), &args.layout)?;
     println!("Test board image generated successfully!");
 
     Ok(())
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+use rand::SeedableRng;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+
+use boardgamebench::game::chess;
+use boardgamebench::notation::{self, MoveNotation};
+use boardgamebench::puzzle::{MoveHistory, Provenance, Puzzle, PuzzleGoal};
+
+use crate::{GenerationError, LastMove, OrientationChoice};
+
+/// Choices offered when an opening is to be named, the right one included.
+const NAME_CHOICES: usize = 4;
+
+/// Puzzles testing memorized opening knowledge, for `--openings`.
+#[derive(clap::Args, Debug)]
+pub struct Openings {
+    /// Instead of generating Lichess puzzles, write opening-knowledge
+    /// puzzles to data/opening_puzzles.json from these ECO files
+    /// (tab-separated eco, name and pgn columns, as in lichess-org/chess-openings)
+    #[arg(long, value_delimiter = ',')]
+    pub openings: Vec<String>,
+
+    /// How many puzzles of each kind (book move and opening name) to write
+    #[arg(long, default_value = "50")]
+    pub opening_puzzles: usize,
+}
+
+/// A named opening line, as moves in UCI from the starting position.
+struct OpeningLine {
+    eco: String,
+    name: String,
+    moves: Vec<String>,
+}
+
+impl Openings {
+    /// Read every line of the ECO files, skipping header rows.
+    fn read_lines(&self) -> Result<Vec<OpeningLine>, GenerationError> {
+        let mut lines = Vec::new();
+        for path in &self.openings {
+            for row in fs::read_to_string(path)?.lines() {
+                let fields: Vec<&str> = row.split('\t').collect();
+                if fields.len() < 3 || fields[0] == "eco" {
+                    continue;
+                }
+                let mut fen = chess::STARTING_FEN.to_string();
+                let mut moves = Vec::new();
+                // Skip move numbers such as `1.` and `12...`.
+                for san in fields[2].split_whitespace().filter(|word| !word.ends_with('.')) {
+                    let uci = notation::to_uci(MoveNotation::San, &fen, san)?;
+                    fen = chess::play(&fen, &uci)?;
+                    moves.push(uci);
+                }
+                if !moves.is_empty() {
+                    lines.push(OpeningLine {
+                        eco: fields[0].to_string(),
+                        name: fields[1].to_string(),
+                        moves,
+                    });
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    /// "Play the book move" puzzles from the positions just before the last
+    /// move of randomly chosen lines, and multiple-choice "name this opening"
+    /// puzzles from the positions the lines end in. Positions reached by
+    /// lines with different names are never asked to be named.
+    pub fn generate(
+        &self,
+        seed: u64,
+        last_move: LastMove,
+        orientation: OrientationChoice,
+    ) -> Result<Vec<Puzzle>, GenerationError> {
+        let lines = self.read_lines()?;
+        println!("Loaded {} opening lines", lines.len());
+
+        // Every book move from each position, with how many lines play it,
+        // and the names of the lines ending in each position.
+        let mut continuations: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+        let mut names: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for line in &lines {
+            let mut fen = chess::STARTING_FEN.to_string();
+            for uci in &line.moves {
+                *continuations
+                    .entry(chess::repetition_key(&fen)?)
+                    .or_default()
+                    .entry(uci.clone())
+                    .or_default() += 1;
+                fen = chess::play(&fen, uci)?;
+            }
+            names.entry(chess::repetition_key(&fen)?).or_default().insert(&line.name);
+        }
+
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut order: Vec<&OpeningLine> = lines.iter().collect();
+        order.shuffle(&mut rng);
+        let mut puzzles = Vec::new();
+
+        let mut asked = BTreeSet::new();
+        for line in &order {
+            if asked.len() == self.opening_puzzles {
+                break;
+            }
+            let (history, _) = line.moves.split_at(line.moves.len() - 1);
+            let fen = replay(history)?;
+            let key = chess::repetition_key(&fen)?;
+            if asked.contains(&key) {
+                continue;
+            }
+            let book = &continuations[&key];
+            // The main line is the move most lines continue with; positions
+            // without a single most played move have no main line to ask for.
+            let most = book.values().copied().max().unwrap_or(0);
+            let mut most_played = book.iter().filter(|(_, count)| **count == most);
+            let (Some((main_line, _)), None) = (most_played.next(), most_played.next()) else {
+                continue;
+            };
+            let main_line = main_line.clone();
+            asked.insert(key);
+            let mut puzzle = opening_puzzle(
+                format!("opening_book_{:03}", asked.len()),
+                "Main-line book move of an ECO opening line".to_string(),
+                history,
+                fen,
+                main_line,
+                PuzzleGoal::Custom {
+                    text: "Play the main-line opening book move in this position".to_string(),
+                },
+                line,
+                last_move,
+                orientation,
+            )?;
+            puzzle
+                .metadata
+                .insert("book_moves".to_string(), serde_json::json!(book.keys().collect::<Vec<_>>()));
+            puzzles.push(puzzle);
+        }
+
+        let all_names: Vec<&str> = lines.iter().map(|line| line.name.as_str()).collect::<BTreeSet<_>>().into_iter().collect();
+        let family = |name: &str| name.split(':').next().unwrap_or(name).trim().to_string();
+        let mut named = BTreeSet::new();
+        for line in &order {
+            if named.len() == self.opening_puzzles {
+                break;
+            }
+            let fen = replay(&line.moves)?;
+            let key = chess::repetition_key(&fen)?;
+            if names[&key].len() != 1 || !named.insert(key) {
+                continue;
+            }
+            // One wrong choice from the same family where there is one, so
+            // the family alone doesn't give the answer away.
+            let same_family: Vec<&str> = all_names
+                .iter()
+                .copied()
+                .filter(|name| *name != line.name && family(name) == family(&line.name))
+                .collect();
+            let other_families: Vec<&str> = all_names
+                .iter()
+                .copied()
+                .filter(|name| family(name) != family(&line.name))
+                .collect();
+            let mut choices = vec![line.name.clone()];
+            choices.extend(same_family.choose(&mut rng).map(|name| name.to_string()));
+            let needed = NAME_CHOICES - choices.len();
+            choices.extend(other_families.choose_multiple(&mut rng, needed).map(|name| name.to_string()));
+            choices.shuffle(&mut rng);
+            let answer = choices.iter().position(|name| *name == line.name).expect("the right name is a choice");
+
+            puzzles.push(opening_puzzle(
+                format!("opening_name_{:03}", named.len()),
+                "Name of an ECO opening line".to_string(),
+                &line.moves,
+                fen,
+                boardgamebench::puzzle::choice_letter(answer),
+                PuzzleGoal::MultipleChoice { choices },
+                line,
+                last_move,
+                orientation,
+            )?);
+        }
+
+        println!("Generated {} opening puzzles", puzzles.len());
+        Ok(puzzles)
+    }
+}
+
+/// The position after playing `moves` from the starting position.
+fn replay(moves: &[String]) -> Result<String, GenerationError> {
+    Ok(moves
+        .iter()
+        .try_fold(chess::STARTING_FEN.to_string(), |fen, uci| chess::play(&fen, uci))?)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn opening_puzzle(
+    id: String,
+    description: String,
+    history: &[String],
+    fen: String,
+    solution: String,
+    goal: PuzzleGoal,
+    line: &OpeningLine,
+    last_move: LastMove,
+    orientation: OrientationChoice,
+) -> Result<Puzzle, GenerationError> {
+    Ok(Puzzle {
+        id,
+        description,
+        game_type: None,
+        orientations: vec![orientation.orientation(&fen)?],
+        annotations: history.last().map(|uci| last_move.annotations(uci)).into_iter().collect(),
+        game_states: vec![fen],
+        solutions: vec![solution],
+        encrypted_solutions: None,
+        rating: None,
+        rating_deviation: None,
+        weight: 1.0,
+        state_weights: Vec::new(),
+        images: Vec::new(),
+        sequences: Vec::new(),
+        history: Some(MoveHistory {
+            start: chess::STARTING_FEN.to_string(),
+            moves: history.to_vec(),
+        }),
+        render_style: None,
+        tags: vec!["opening".to_string()],
+        provenance: Provenance::default(),
+        metadata: BTreeMap::from([
+            ("eco".to_string(), serde_json::json!(line.eco)),
+            ("opening".to_string(), serde_json::json!(line.name)),
+        ]),
+        goal_condition: None,
+        goal: Some(goal),
+    })
+}
@@ -0,0 +1,124 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+
+use boardgamebench::game::chess;
+use boardgamebench::puzzle::Puzzle;
+
+use crate::layout::Encoding;
+use crate::{GenerationError, store_bytes};
+
+const GAP_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Images of the moves leading to each state, for `Puzzle::sequences`.
+#[derive(clap::Args, Debug)]
+pub struct Sequences {
+    /// Also render the moves leading to each chess state, as an animated GIF
+    /// or a strip of frames side by side
+    #[arg(long, value_enum)]
+    pub sequence: Option<SequenceFormat>,
+
+    /// Most positions shown in a sequence, ending with the state itself
+    #[arg(long, default_value = "4", value_parser = clap::value_parser!(u32).range(2..))]
+    pub sequence_length: u32,
+
+    /// How long each GIF frame is shown, in milliseconds
+    #[arg(long, default_value = "1000")]
+    pub frame_delay: u32,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceFormat {
+    /// An animated GIF that loops through the positions; most chat APIs
+    /// only look at its first frame
+    Gif,
+    /// The positions side by side in one image, in the --image-format
+    Strip,
+}
+
+/// A rendered sequence, ready to be stored.
+pub struct EncodedSequence {
+    pub bytes: Vec<u8>,
+    pub extension: &'static str,
+    pub mime_type: &'static str,
+}
+
+impl EncodedSequence {
+    /// Embed or save the sequence like a board image; see `store_image`.
+    pub fn store(&self, dir: &Path, name: &str, embed: bool) -> Result<String, GenerationError> {
+        store_bytes(&self.bytes, dir, name, embed, self.extension, self.mime_type)
+    }
+}
+
+impl Sequences {
+    /// The positions leading to state `index` of `puzzle` (ending with the
+    /// state itself), each with the move that reached it: the puzzle's
+    /// history, then each earlier state's solution and the reply to it.
+    /// Only the state itself if the puzzle has no history and starts there.
+    pub fn positions(&self, puzzle: &Puzzle, index: usize) -> Result<Vec<(String, Option<String>)>, GenerationError> {
+        let mut positions = Vec::new();
+        match &puzzle.history {
+            Some(history) => {
+                positions.push((history.start.clone(), None));
+                for uci in &history.moves {
+                    let fen = chess::play(&positions[positions.len() - 1].0, uci)?;
+                    positions.push((fen, Some(uci.clone())));
+                }
+            }
+            None => positions.push((puzzle.game_states[0].clone(), None)),
+        }
+        for j in 0..index {
+            let after = chess::play(&puzzle.game_states[j], &puzzle.solutions[j])?;
+            let reply = chess::move_between(&after, &puzzle.game_states[j + 1])?;
+            positions.push((after, Some(puzzle.solutions[j].clone())));
+            positions.push((puzzle.game_states[j + 1].clone(), Some(reply)));
+        }
+        let skip = positions.len().saturating_sub(self.sequence_length as usize);
+        Ok(positions.split_off(skip))
+    }
+
+    /// Combine `frames` into the chosen format; `None` if no sequence was
+    /// asked for.
+    pub fn encode(&self, frames: Vec<RgbaImage>, encoding: &Encoding) -> Result<Option<EncodedSequence>, GenerationError> {
+        let Some(format) = self.sequence else {
+            return Ok(None);
+        };
+        Ok(Some(match format {
+            SequenceFormat::Gif => {
+                let mut bytes = Vec::new();
+                {
+                    let mut encoder = GifEncoder::new(Cursor::new(&mut bytes));
+                    encoder.set_repeat(Repeat::Infinite)?;
+                    let delay = Delay::from_numer_denom_ms(self.frame_delay, 1);
+                    encoder.encode_frames(frames.into_iter().map(|frame| Frame::from_parts(frame, 0, 0, delay)))?;
+                }
+                EncodedSequence {
+                    bytes,
+                    extension: "gif",
+                    mime_type: "image/gif",
+                }
+            }
+            SequenceFormat::Strip => EncodedSequence {
+                bytes: encoding.encode(&strip(&frames))?,
+                extension: encoding.extension(),
+                mime_type: encoding.mime_type(),
+            },
+        }))
+    }
+}
+
+/// `frames` left to right, separated by a gap a twentieth of their width.
+fn strip(frames: &[RgbaImage]) -> RgbaImage {
+    let gap = frames.first().map_or(0, |frame| frame.width() / 20);
+    let width = frames.iter().map(|frame| frame.width()).sum::<u32>() + gap * frames.len().saturating_sub(1) as u32;
+    let height = frames.iter().map(|frame| frame.height()).max().unwrap_or(0);
+    let mut image = RgbaImage::from_pixel(width, height, GAP_COLOR);
+    let mut x = 0;
+    for frame in frames {
+        image::imageops::overlay(&mut image, frame, x as i64, 0);
+        x += frame.width() + gap;
+    }
+    image
+}
@@ -0,0 +1,276 @@
+//! Held-out splits. A public copy of a collection, with every solution
+//! withheld, is all that solvers get to see; runs on it produce answer
+//! sheets, and whoever holds the private original scores those, reporting
+//! only aggregate scores so the solutions stay secret. Answer sheets also
+//! bring in answers from systems outside this crate, which `score_answers`
+//! turns into a full result.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::extraction::extract_answer;
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleScore, Scorer};
+use crate::results::{BenchmarkResult, RunMetadata};
+use crate::scoring::{AnswerScoring, StateAnswer};
+
+/// A solver's answers to a collection, one per state of each puzzle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AnswerSheet {
+    #[serde(default)]
+    pub solver_name: String,
+    #[serde(default)]
+    pub solver_description: String,
+    /// Checksum of the collection the answers were given on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection_checksum: Option<String>,
+    pub answers: BTreeMap<String, Vec<String>>,
+}
+
+/// What scoring an answer sheet on a held-out split reveals.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AggregateScore {
+    pub solver_name: String,
+    pub collection_name: String,
+    pub total_puzzles: usize,
+    /// Puzzles the sheet had answers for; the rest score zero.
+    pub answered: usize,
+    pub total_score: f64,
+    pub max_possible_score: f64,
+    pub average_score: f64,
+}
+
+#[derive(Debug, Error)]
+pub enum HoldoutError {
+    #[error("the answers were given on collection {found}, not the public copy {expected} of this one")]
+    WrongCollection { found: String, expected: String },
+    #[error("unknown puzzle ids: {0:?}")]
+    UnknownPuzzles(Vec<String>),
+    #[error("puzzle {0} is answered more than once")]
+    DuplicatePuzzle(String),
+    #[error("answer sheet I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid answer sheet: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl AnswerSheet {
+    /// The answers recorded in `result`.
+    pub fn from_result(result: &BenchmarkResult) -> Self {
+        Self {
+            solver_name: result.solver_name.clone(),
+            solver_description: result.solver_description.clone(),
+            collection_checksum: result.metadata.as_ref().map(|m| m.collection_checksum.clone()),
+            answers: result
+                .puzzle_scores
+                .iter()
+                .map(|score| (score.puzzle_id.clone(), score.answers.clone()))
+                .collect(),
+        }
+    }
+
+    /// Read answers in any of the accepted layouts: an answer sheet, a JSON
+    /// object mapping puzzle ids to answers, or JSONL with one
+    /// `{"puzzle_id": ..., "answers": [...]}` line per puzzle. A single
+    /// answer string stands for a one-state puzzle's answers.
+    pub fn parse(text: &str) -> Result<Self, HoldoutError> {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(text)
+            && value.get("puzzle_id").is_none()
+        {
+            if value.get("answers").is_some_and(serde_json::Value::is_object) {
+                return Ok(serde_json::from_value(value)?);
+            }
+            let answers: BTreeMap<String, FileAnswers> = serde_json::from_value(value)?;
+            return Ok(Self {
+                answers: answers.into_iter().map(|(id, answers)| (id, answers.into_vec())).collect(),
+                ..Default::default()
+            });
+        }
+        let mut answers = BTreeMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let line: AnswerLine = serde_json::from_str(line)?;
+            if answers.insert(line.puzzle_id.clone(), line.answers.into_vec()).is_some() {
+                return Err(HoldoutError::DuplicatePuzzle(line.puzzle_id));
+            }
+        }
+        Ok(Self {
+            answers,
+            ..Default::default()
+        })
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, HoldoutError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), HoldoutError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// One puzzle's answers in an answers file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FileAnswers {
+    One(String),
+    Each(Vec<String>),
+}
+
+impl FileAnswers {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            FileAnswers::One(answer) => vec![answer],
+            FileAnswers::Each(answers) => answers,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AnswerLine {
+    puzzle_id: String,
+    #[serde(alias = "answer")]
+    answers: FileAnswers,
+}
+
+/// The public copy of `collection`: every solution removed and
+/// `withheld_from` set to the original's checksum. Source ids and URLs,
+/// which lead to the original game and so to the solution, are removed
+/// from provenance and descriptions too; the source and license stay.
+/// Puzzle goals stay, so prompts read the same.
+pub fn withhold_solutions(collection: &PuzzleCollection) -> PuzzleCollection {
+    let url = Regex::new(r"(?i)(\s+from)?\s*\b[a-z][a-z0-9+.-]*://\S+").unwrap();
+    let mut public = collection.clone();
+    public.withheld_from = Some(collection.checksum());
+    public.provenance.source_id = None;
+    public.provenance.url = None;
+    for puzzle in &mut public.puzzles {
+        puzzle.solutions.clear();
+        puzzle.provenance.source_id = None;
+        puzzle.provenance.url = None;
+        puzzle.description = url.replace_all(&puzzle.description, "").trim().to_string();
+    }
+    public
+}
+
+/// Check that `sheet` answers `collection`: a sheet naming the collection
+/// it was answered on must name this one or its public copy, and every
+/// puzzle it answers must be in it.
+fn check_sheet(collection: &PuzzleCollection, sheet: &AnswerSheet) -> Result<(), HoldoutError> {
+    if let Some(found) = &sheet.collection_checksum {
+        let expected = withhold_solutions(collection).checksum();
+        if *found != expected && *found != collection.source_checksum() {
+            return Err(HoldoutError::WrongCollection {
+                found: found.clone(),
+                expected,
+            });
+        }
+    }
+    let unknown: Vec<String> = sheet
+        .answers
+        .keys()
+        .filter(|id| !collection.puzzles.iter().any(|p| &p.id == *id))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(HoldoutError::UnknownPuzzles(unknown));
+    }
+    Ok(())
+}
+
+/// Score `sheet`'s answers to `puzzle`; with `extract` they are raw
+/// responses, each state's answer is extracted from them as in a run, and
+/// they are kept for rescoring.
+fn score_puzzle(
+    puzzle: &Puzzle,
+    collection: &PuzzleCollection,
+    sheet: &AnswerSheet,
+    scorer: Scorer,
+    answer_scoring: AnswerScoring,
+    extract: bool,
+) -> PuzzleScore {
+    let given = sheet.answers.get(&puzzle.id).map(Vec::as_slice).unwrap_or_default();
+    if !extract {
+        let answers: Vec<StateAnswer> = given.iter().map(|answer| StateAnswer::new(answer)).collect();
+        return scorer.score_with(puzzle, collection, &answers, answer_scoring.scorer());
+    }
+    let answer_format = collection.answer_format_of(puzzle);
+    let extracted: Vec<_> = given
+        .iter()
+        .zip(&puzzle.game_states)
+        .map(|(response, state)| extract_answer(response, state, &answer_format))
+        .collect();
+    let answers: Vec<StateAnswer> = extracted.iter().map(|e| StateAnswer::new(&e.answer)).collect();
+    let mut score = scorer.score_with(puzzle, collection, &answers, answer_scoring.scorer());
+    score.extraction_methods = extracted.iter().map(|e| e.method).collect();
+    score.responses = given.to_vec();
+    score
+}
+
+/// Score `sheet` against the private `collection` and report only the
+/// totals. A sheet naming the collection it was answered on must name the
+/// public copy of this one.
+pub fn score_sheet(
+    collection: &PuzzleCollection,
+    sheet: &AnswerSheet,
+    scorer: Scorer,
+    answer_scoring: AnswerScoring,
+) -> Result<AggregateScore, HoldoutError> {
+    check_sheet(collection, sheet)?;
+    let (mut total_score, mut max_possible_score) = (0.0, 0.0);
+    for puzzle in &collection.puzzles {
+        let score = score_puzzle(puzzle, collection, sheet, scorer, answer_scoring, false);
+        total_score += score.score * score.weight;
+        max_possible_score += score.max_possible_score * score.weight;
+    }
+    Ok(AggregateScore {
+        solver_name: sheet.solver_name.clone(),
+        collection_name: collection.name.clone(),
+        total_puzzles: collection.puzzles.len(),
+        answered: sheet.answers.len(),
+        total_score,
+        max_possible_score,
+        average_score: if max_possible_score > 0.0 { total_score / max_possible_score } else { 0.0 },
+    })
+}
+
+/// Score `sheet` against `collection` into the full result a run giving
+/// the same answers would have, so answers generated by any system can be
+/// scored here. With `extract` the sheet holds raw responses to extract
+/// the answers from. Puzzles the sheet doesn't answer score zero.
+pub fn score_answers(
+    collection: &PuzzleCollection,
+    sheet: &AnswerSheet,
+    scorer: Scorer,
+    answer_scoring: AnswerScoring,
+    extract: bool,
+) -> Result<BenchmarkResult, HoldoutError> {
+    check_sheet(collection, sheet)?;
+    let puzzle_scores = collection
+        .puzzles
+        .iter()
+        .map(|puzzle| score_puzzle(puzzle, collection, sheet, scorer, answer_scoring, extract))
+        .collect();
+    let mut result = BenchmarkResult::summarize(
+        format!("{} on {} (answers)", sheet.solver_name, collection.name),
+        &sheet.solver_name,
+        &sheet.solver_description,
+        collection,
+        puzzle_scores,
+    );
+    result.metadata = Some(RunMetadata {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: option_env!("BOARDGAMEBENCH_GIT_COMMIT").map(str::to_string),
+        collection_name: collection.name.clone(),
+        collection_checksum: collection.source_checksum(),
+        solver_parameters: BTreeMap::new(),
+        seeds: BTreeMap::new(),
+        environment: BTreeMap::from([("scored_from".to_string(), "answers".to_string())]),
+        shard: None,
+        selection: None,
+    });
+    Ok(result)
+}
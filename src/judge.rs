@@ -0,0 +1,93 @@
+use regex::Regex;
+use tracing::{error, warn};
+
+use crate::evaluation::Solver;
+use crate::puzzle::{Puzzle, PuzzleCollection};
+
+/// Second-stage grader that asks a judge model to rate the reasoning in a
+/// solver's response against the puzzle's ground-truth line.
+pub struct Judge {
+    pub solver: Solver,
+}
+
+impl Judge {
+    pub fn new(solver: Solver) -> Self {
+        Self { solver }
+    }
+
+    /// Rate the response to state `index` of `puzzle`, normalized to [0, 1].
+    /// Returns `None` for empty responses or when the judge gave no score.
+    pub fn grade(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+        index: usize,
+        response: &str,
+    ) -> Option<f64> {
+        crate::runtime::block_on(self.grade_async(puzzle, puzzle_collection, index, response))
+    }
+
+    pub async fn grade_async(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+        index: usize,
+        response: &str,
+    ) -> Option<f64> {
+        if response.is_empty() {
+            return None;
+        }
+
+        let prompt = self.build_prompt(puzzle, puzzle_collection, index, response);
+        match self.solver.complete_async(&prompt).await {
+            Ok(verdict) => {
+                let regex = Regex::new(r"\*\*Score:\s*(\d+(?:\.\d+)?)\s*(?:/\s*10)?\*\*").unwrap();
+                let score = regex
+                    .captures_iter(&verdict)
+                    .last()
+                    .and_then(|caps| caps[1].parse::<f64>().ok())
+                    .map(|score| (score / 10.0).clamp(0.0, 1.0));
+                if score.is_none() {
+                    warn!(puzzle = %puzzle.id, state = index, "no score found in judge verdict");
+                }
+                score
+            }
+            Err(e) => {
+                error!(puzzle = %puzzle.id, state = index, error = %e, "judge call failed");
+                None
+            }
+        }
+    }
+
+    fn build_prompt(
+        &self,
+        puzzle: &Puzzle,
+        puzzle_collection: &PuzzleCollection,
+        index: usize,
+        response: &str,
+    ) -> String {
+        let game_type = puzzle_collection.game_type_of(puzzle);
+        let goal = &puzzle_collection.goal;
+        let state = &puzzle.game_states[index];
+        let line = puzzle.solutions[index..].join(" ");
+        format!(
+            "You are grading the reasoning of an AI that was asked to solve a {game_type} puzzle.
+The puzzle goal is: {goal}
+The position is: {state}
+The correct move is {correct}, and the full solution line from here is: {line}
+
+Rate the quality of the reasoning below on a scale from 0 to 10 using this rubric:
+- Accuracy: are the claims about the position (pieces, threats, legal moves) correct?
+- Relevance: does the analysis identify the key idea behind the correct move?
+- Calculation: are the considered variations sound and followed far enough?
+- Consistency: does the final answer follow from the analysis?
+Do not reward a correct final move that is not supported by the reasoning.
+
+Reply with a short justification followed by your rating in the format **Score: <0-10>**.
+
+Reasoning to grade:
+{response}",
+            correct = puzzle.solutions[index],
+        )
+    }
+}
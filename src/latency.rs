@@ -0,0 +1,119 @@
+//! Latency percentiles for planning big runs. Totals hide the slow tail
+//! that decides how long a run takes at a given concurrency, so results
+//! report the median, 90th and 99th percentile of how long each request to
+//! the solver and each whole puzzle took.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::PuzzleScore;
+
+/// Nearest-rank percentiles of a set of durations, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencyPercentiles {
+    pub count: usize,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencyPercentiles {
+    /// Percentiles of `samples`, or `None` if there are none.
+    pub fn from_samples(samples: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let mut samples: Vec<u64> = samples.into_iter().collect();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1]
+        };
+        Some(Self {
+            count: samples.len(),
+            p50_ms: percentile(50.0),
+            p90_ms: percentile(90.0),
+            p99_ms: percentile(99.0),
+            max_ms: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// How long requests and puzzles took in a run, or in part of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencySummary {
+    /// Each asked state's request, retries included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests: Option<LatencyPercentiles>,
+    /// Each puzzle's wall time, from the first request to the last answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub puzzles: Option<LatencyPercentiles>,
+}
+
+impl LatencySummary {
+    /// Latencies over `scores`, one per attempt at a puzzle (so each pass's
+    /// scores in multi-pass runs), or `None` if none were timed. Scores of
+    /// results written before timing was recorded count as untimed.
+    pub fn from_scores<'a>(scores: impl IntoIterator<Item = &'a PuzzleScore>) -> Option<Self> {
+        let scores: Vec<&PuzzleScore> = scores.into_iter().collect();
+        let summary = Self {
+            requests: LatencyPercentiles::from_samples(
+                scores.iter().flat_map(|score| score.request_latencies_ms.iter().copied()),
+            ),
+            puzzles: LatencyPercentiles::from_samples(
+                scores.iter().map(|score| score.elapsed_ms).filter(|elapsed| *elapsed > 0),
+            ),
+        };
+        (summary.requests.is_some() || summary.puzzles.is_some()).then_some(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Scorer;
+    use crate::puzzle::testing::{random_collection, responses};
+
+    #[test]
+    fn percentiles_take_the_nearest_rank() {
+        let percentiles = LatencyPercentiles::from_samples((1..=100).rev()).unwrap();
+        assert_eq!(
+            percentiles,
+            LatencyPercentiles {
+                count: 100,
+                p50_ms: 50,
+                p90_ms: 90,
+                p99_ms: 99,
+                max_ms: 100,
+            }
+        );
+
+        let few = LatencyPercentiles::from_samples([30, 10, 20]).unwrap();
+        assert_eq!((few.p50_ms, few.p90_ms, few.p99_ms, few.max_ms), (20, 30, 30, 30));
+        let one = LatencyPercentiles::from_samples([7]).unwrap();
+        assert_eq!((one.p50_ms, one.p99_ms), (7, 7));
+        assert_eq!(LatencyPercentiles::from_samples([]), None);
+    }
+
+    #[test]
+    fn untimed_scores_are_left_out() {
+        let collection = random_collection(3, 21);
+        let mut scores: Vec<PuzzleScore> = collection
+            .puzzles
+            .iter()
+            .zip(responses(&collection, 1.0, 0))
+            .map(|(puzzle, responses)| Scorer::PerState.score_responses(puzzle, &collection, &responses))
+            .collect();
+        assert_eq!(LatencySummary::from_scores(&scores), None);
+
+        scores[0].elapsed_ms = 500;
+        scores[0].request_latencies_ms = vec![200, 300];
+        scores[1].request_latencies_ms = vec![100];
+        let summary = LatencySummary::from_scores(&scores).unwrap();
+        let requests = summary.requests.unwrap();
+        assert_eq!((requests.count, requests.p50_ms, requests.max_ms), (3, 200, 300));
+        let puzzles = summary.puzzles.unwrap();
+        assert_eq!((puzzles.count, puzzles.p50_ms), (1, 500));
+    }
+}
@@ -1,10 +1,110 @@
 //! BoardgameBench - A benchmark for evaluating LLM performance on abstract board game puzzles
+//!
+//! The puzzle, extraction, scoring and result modules have no I/O runtime
+//! dependencies; everything that talks to models or services is behind the
+//! `runner` feature, so `--no-default-features` builds for wasm32.
 
+pub mod analysis;
+pub mod augment;
+#[cfg(feature = "runner")]
+pub mod blind;
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub mod blunders;
+pub mod budget;
+pub mod canary;
+pub mod compression;
+#[cfg(feature = "runner")]
+pub mod command;
+pub mod confidence;
+#[cfg(feature = "chess")]
+pub mod contamination;
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub mod continuation;
+pub mod diagram;
+pub mod duplicates;
+#[cfg(feature = "encrypt")]
+pub mod encryption;
+#[cfg(feature = "runner")]
+pub mod engine;
+#[cfg(feature = "runner")]
 pub mod evaluation;
+pub mod export;
+pub mod extraction;
+pub mod frontier;
 pub mod game;
+pub mod holdout;
+#[cfg(feature = "runner")]
+pub mod judge;
+pub mod latency;
+pub mod notation;
+#[cfg(feature = "runner")]
+pub mod notify;
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub mod play;
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub mod playout;
+pub mod prompt;
 pub mod puzzle;
+#[cfg(feature = "runner")]
+pub mod queue;
+pub mod rating;
+pub mod results;
+#[cfg(feature = "runner")]
+mod runtime;
+pub mod schema;
+pub mod scoring;
+pub mod signing;
+pub mod skills;
+pub mod stopping;
+#[cfg(feature = "runner")]
+pub mod tablebase;
+#[cfg(feature = "runner")]
+pub mod telemetry;
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub mod tournament;
+#[cfg(feature = "runner")]
+pub mod tracking;
+pub mod transcription;
 
 // Re-export commonly used types
-pub use evaluation::{BenchmarkResult, BenchmarkRunner, Solver};
+pub use analysis::{IrtFit, ModelAbility, PuzzleDifficulty, PuzzleReview, ReviewReason};
+pub use augment::{AugmentError, Augmentation};
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub use blunders::MoveQuality;
+pub use budget::{Budget, ComputeBudget, Pricing, ReasoningEffort, TimeBudget, UsageSummary};
+pub use canary::CanaryReport;
+pub use compression::{Compression, PromptBudget, PromptPart};
+#[cfg(feature = "runner")]
+pub use command::CommandSolver;
+pub use confidence::{ConfidenceCalibration, Forecast};
+pub use duplicates::{DuplicateGroup, DuplicatePolicy};
+#[cfg(feature = "runner")]
+pub use engine::UciEngine;
+#[cfg(feature = "runner")]
+pub use evaluation::{
+    ApiError, BenchmarkRunner, BenchmarkRunnerBuilder, Interrupt, ProviderProfile, RetryPolicy, RunnerError, Solver,
+};
+pub use extraction::{AnswerFormat, AnswerGrammar, ExtractedAnswer, ExtractionError, ExtractionMethod, extract_confidence};
+pub use frontier::{FrontierError, FrontierPoint, FrontierReport};
 pub use game::{Game, GameError};
-pub use puzzle::{Puzzle, PuzzleCollection, PuzzleError, PuzzleScore};
+pub use holdout::{AggregateScore, AnswerSheet, HoldoutError};
+pub use latency::{LatencyPercentiles, LatencySummary};
+pub use notation::{MoveNotation, NotationError};
+#[cfg(feature = "runner")]
+pub use judge::Judge;
+pub use puzzle::{
+    Arrow, BoardAnnotations, CollectionIssue, GoalCondition, MoveHistory, Orientation, Provenance, Puzzle, PuzzleCollection,
+    PuzzleError, PuzzleGoal, PuzzleScore, PuzzleSelection, PuzzleStream, RenderStyle, Sample, Scorer, Shard, Translation,
+};
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub use play::{GameRecord, GameResult, GameRules, PlayError, Player, Termination};
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub use playout::{PlayOut, PlayOutError, PlayOutReport};
+pub use rating::{CalibrationCurve, ModelRating};
+pub use scoring::{AnswerScorer, AnswerScoring, StateAnswer};
+pub use signing::{ResultSeal, SealError};
+pub use skills::{SkillAxis, SkillScore};
+pub use stopping::{EarlyStop, EarlyStopping, StopReason};
+#[cfg(all(feature = "runner", feature = "chess"))]
+pub use tournament::{Tournament, TournamentError, TournamentReport};
+pub use results::{BenchmarkResult, ComparisonReport, MergedRun, ResultFormatError};
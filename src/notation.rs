@@ -0,0 +1,350 @@
+//! Chess move notations besides UCI, so collections can store solutions and
+//! ask for answers in the notation a model handles best. Moves are
+//! converted through UCI with the `chess` feature.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NotationError {
+    #[error("{answer:?} is not a legal move in {notation} notation in {fen}")]
+    NoMove {
+        notation: MoveNotation,
+        fen: String,
+        answer: String,
+    },
+    #[error("{answer:?} could be several moves in {notation} notation in {fen}")]
+    Ambiguous {
+        notation: MoveNotation,
+        fen: String,
+        answer: String,
+    },
+    /// The position itself didn't parse.
+    #[error("{0}")]
+    InvalidPosition(String),
+    #[error("{0} can't be converted without the chess feature")]
+    Unsupported(MoveNotation),
+}
+
+/// A way of writing chess moves.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveNotation {
+    /// From and to squares, e.g. `g1f3`, `e7e8q`.
+    #[default]
+    Uci,
+    /// Standard algebraic notation, e.g. `Nf3`, `exd5`, `O-O`.
+    San,
+    /// Long algebraic notation, e.g. `Ng1-f3`, `e4xd5`.
+    Lan,
+    /// ICCF numeric notation, e.g. `7163`, with promotions as a fifth digit
+    /// (1 queen, 2 rook, 3 bishop, 4 knight).
+    Iccf,
+    /// English descriptive notation, e.g. `N-KB3`, `PxP`.
+    Descriptive,
+}
+
+impl std::fmt::Display for MoveNotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MoveNotation::Uci => "UCI",
+            MoveNotation::San => "SAN",
+            MoveNotation::Lan => "long algebraic",
+            MoveNotation::Iccf => "ICCF numeric",
+            MoveNotation::Descriptive => "descriptive",
+        })
+    }
+}
+
+impl MoveNotation {
+    pub(crate) fn token_pattern(self) -> &'static str {
+        match self {
+            MoveNotation::Uci => r"[a-h][1-8][a-h][1-8][qrbn]?",
+            MoveNotation::San => r"O-O-O|O-O|[KQRBN][a-h]?[1-8]?x?[a-h][1-8]|[a-h](?:x[a-h])?[1-8](?:=?[QRBN])?",
+            MoveNotation::Lan => r"O-O-O|O-O|[KQRBNP]?[a-h][1-8][-x][a-h][1-8](?:=?[QRBN])?",
+            MoveNotation::Iccf => r"[1-8]{4}[1-4]?",
+            MoveNotation::Descriptive => {
+                r"O-O-O|O-O|[KQRBNP](?:/[QK]?[RNB]?[1-8])?[-x](?:[QK]?[RNB]?[1-8]|[KQRBNP](?:/[QK]?[RNB]?[1-8])?)(?:=?[QRBN])?"
+            }
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            MoveNotation::Uci => "UCI notation, e.g., e2e4, e1g1 (castling), e7e8q (promotion)",
+            MoveNotation::San => {
+                "standard algebraic notation (SAN), e.g., e4, Nf3, exd5, O-O (castling), e8=Q (promotion)"
+            }
+            MoveNotation::Lan => {
+                "long algebraic notation, e.g., e2-e4, Ng1-f3, e4xd5, O-O (castling), e7-e8=Q (promotion)"
+            }
+            MoveNotation::Iccf => {
+                "ICCF numeric notation, files and ranks as digits 1-8, e.g., 5254 for e2-e4, 5171 for White castling kingside, 57581 for e7-e8 promoting to a queen (1 queen, 2 rook, 3 bishop, 4 knight)"
+            }
+            MoveNotation::Descriptive => {
+                "English descriptive notation, e.g., P-K4, N-KB3, PxP, O-O (castling), P-K8=Q (promotion)"
+            }
+        }
+    }
+
+    /// Whether answers keep their case: piece letters are uppercase in
+    /// algebraic notation, files lowercase.
+    pub(crate) fn is_case_sensitive(self) -> bool {
+        matches!(self, MoveNotation::San | MoveNotation::Lan | MoveNotation::Descriptive)
+    }
+}
+
+#[cfg(feature = "chess")]
+pub use convert::{from_uci, to_uci};
+
+/// Without the `chess` feature only UCI, which needs no position, can be
+/// "converted".
+#[cfg(not(feature = "chess"))]
+pub fn to_uci(notation: MoveNotation, _fen: &str, answer: &str) -> Result<String, NotationError> {
+    match notation {
+        MoveNotation::Uci => Ok(answer.to_lowercase()),
+        other => Err(NotationError::Unsupported(other)),
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+pub fn from_uci(notation: MoveNotation, _fen: &str, uci: &str) -> Result<String, NotationError> {
+    match notation {
+        MoveNotation::Uci => Ok(uci.to_lowercase()),
+        other => Err(NotationError::Unsupported(other)),
+    }
+}
+
+#[cfg(feature = "chess")]
+mod convert {
+    use shakmaty::san::San;
+    use shakmaty::{CastlingMode, CastlingSide, Chess, Color, Move, Position, Role, Square};
+
+    use super::{MoveNotation, NotationError};
+    use crate::game::chess::{normalize_uci, parse_fen};
+
+    /// The legal move `answer`, written in `notation`, denotes in `fen`, in
+    /// UCI. Check marks, `0-0` for castling and `Kt` for knights are
+    /// accepted, as are the less explicit spellings of a move (like `N-B3`
+    /// for `N-KB3`) as long as only one legal move fits.
+    pub fn to_uci(notation: MoveNotation, fen: &str, answer: &str) -> Result<String, NotationError> {
+        let position = position(fen)?;
+        let no_move = || NotationError::NoMove {
+            notation,
+            fen: fen.to_string(),
+            answer: answer.to_string(),
+        };
+        let written = clean(notation, answer);
+        if notation == MoveNotation::San {
+            let m = San::from_ascii(written.as_bytes())
+                .map_err(|_| no_move())?
+                .to_move(&position)
+                .map_err(|_| no_move())?;
+            return Ok(uci(m));
+        }
+
+        let mut matching = position
+            .legal_moves()
+            .into_iter()
+            .filter(|&m| spellings(notation, &position, m).contains(&written));
+        match (matching.next(), matching.next()) {
+            (Some(m), None) => Ok(uci(m)),
+            (Some(_), Some(_)) => Err(NotationError::Ambiguous {
+                notation,
+                fen: fen.to_string(),
+                answer: answer.to_string(),
+            }),
+            (None, _) => Err(no_move()),
+        }
+    }
+
+    /// The legal move `uci` in `fen` written in `notation`, in its most
+    /// preferred unambiguous spelling, so equivalent answers compare equal.
+    pub fn from_uci(notation: MoveNotation, fen: &str, uci_move: &str) -> Result<String, NotationError> {
+        let position = position(fen)?;
+        let legal = position.legal_moves();
+        let normalized = normalize_uci(fen, uci_move);
+        let m = legal
+            .iter()
+            .copied()
+            .find(|&m| Some(uci(m)) == normalized)
+            .ok_or_else(|| NotationError::NoMove {
+                notation: MoveNotation::Uci,
+                fen: fen.to_string(),
+                answer: uci_move.to_string(),
+            })?;
+        if notation == MoveNotation::San {
+            return Ok(San::from_move(&position, m).to_string());
+        }
+
+        let others: Vec<Vec<String>> = legal
+            .iter()
+            .filter(|&&other| other != m)
+            .map(|&other| spellings(notation, &position, other))
+            .collect();
+        let spellings = spellings(notation, &position, m);
+        let canonical = spellings
+            .iter()
+            .find(|spelling| !others.iter().any(|other| other.contains(spelling)))
+            .unwrap_or(&spellings[0]);
+        Ok(canonical.clone())
+    }
+
+    fn position(fen: &str) -> Result<Chess, NotationError> {
+        parse_fen(fen).map_err(|e| NotationError::InvalidPosition(e.to_string()))
+    }
+
+    fn uci(m: Move) -> String {
+        m.to_uci(CastlingMode::Standard).to_string()
+    }
+
+    /// `answer` stripped of annotations and spelled the way `spellings` does.
+    fn clean(notation: MoveNotation, answer: &str) -> String {
+        let mut written: String = answer.trim().chars().filter(|c| !c.is_whitespace()).collect();
+        if notation == MoveNotation::Descriptive {
+            written = written.to_uppercase().replace("KT", "N").replace('X', "x");
+            if let Some(stripped) = written.strip_suffix("CH") {
+                written = stripped.to_string();
+            }
+        }
+        if notation == MoveNotation::Uci {
+            written = written.to_lowercase();
+        }
+        written = written.trim_end_matches(['+', '#', '!', '?']).to_string();
+        if let Some(open) = written.rfind('(')
+            && written.ends_with(')')
+        {
+            written = format!("{}={}", &written[..open], &written[open + 1..written.len() - 1]);
+        }
+        written.replace("0-0", "O-O")
+    }
+
+    /// Ways of writing `m` in `notation`, most preferred first.
+    fn spellings(notation: MoveNotation, position: &Chess, m: Move) -> Vec<String> {
+        let uci = uci(m);
+        let (from, to) = (&uci[0..2], &uci[2..4]);
+        match notation {
+            MoveNotation::Uci => vec![uci.clone(), m.to_uci(CastlingMode::Chess960).to_string()],
+            MoveNotation::San => vec![San::from_move(position, m).to_string()],
+            MoveNotation::Lan => {
+                let piece = piece_letter(m.role());
+                let promotion = m.promotion().map(|role| role.upper_char());
+                let mut spellings = Vec::new();
+                if let Some(side) = m.castling_side() {
+                    spellings.push(castle(side).to_string());
+                }
+                let separators = if m.is_capture() { ['x', '-'] } else { ['-', 'x'] };
+                for prefix in [piece, if m.role() == Role::Pawn { "P" } else { piece }] {
+                    for separator in separators {
+                        let base = format!("{prefix}{from}{separator}{to}");
+                        match promotion {
+                            Some(promotion) => {
+                                spellings.push(format!("{base}={promotion}"));
+                                spellings.push(format!("{base}{promotion}"));
+                            }
+                            None => spellings.push(base),
+                        }
+                    }
+                }
+                spellings
+            }
+            MoveNotation::Iccf => {
+                let digits = |square: &str| {
+                    let bytes = square.as_bytes();
+                    format!("{}{}", bytes[0] - b'a' + 1, bytes[1] - b'0')
+                };
+                let promotion = match m.promotion() {
+                    Some(Role::Queen) => "1",
+                    Some(Role::Rook) => "2",
+                    Some(Role::Bishop) => "3",
+                    Some(Role::Knight) => "4",
+                    _ => "",
+                };
+                vec![format!("{}{}{}", digits(from), digits(to), promotion)]
+            }
+            MoveNotation::Descriptive => descriptive(position, m),
+        }
+    }
+
+    fn piece_letter(role: Role) -> &'static str {
+        match role {
+            Role::Pawn => "",
+            Role::Knight => "N",
+            Role::Bishop => "B",
+            Role::Rook => "R",
+            Role::Queen => "Q",
+            Role::King => "K",
+        }
+    }
+
+    fn castle(side: CastlingSide) -> &'static str {
+        match side {
+            CastlingSide::KingSide => "O-O",
+            CastlingSide::QueenSide => "O-O-O",
+        }
+    }
+
+    /// Descriptive spellings of `m`: the full destination square first
+    /// (`N-KB3`), then the file without its side (`N-B3`), then with the
+    /// piece's own square (`N/KN1-KB3`). Captures name the captured piece
+    /// (`NxP`), qualified with its square when needed (`NxP/Q5`).
+    fn descriptive(position: &Chess, m: Move) -> Vec<String> {
+        if let Some(side) = m.castling_side() {
+            return vec![castle(side).to_string()];
+        }
+        let color = position.turn();
+        let piece = m.role().upper_char();
+        // `from()` is only `None` for drops, which standard chess has none of.
+        let from = m.from().map(|square| square_names(square, color)[0].clone()).unwrap_or_default();
+        let targets = square_names(m.to(), color);
+        let mut bases = Vec::new();
+        match m.capture() {
+            Some(captured) => {
+                let captured = captured.upper_char();
+                bases.push(format!("{piece}x{captured}"));
+                for target in &targets {
+                    bases.push(format!("{piece}x{captured}/{target}"));
+                }
+                bases.push(format!("{piece}/{from}x{captured}/{}", targets[0]));
+            }
+            None => {
+                for target in &targets {
+                    bases.push(format!("{piece}-{target}"));
+                }
+                bases.push(format!("{piece}/{from}-{}", targets[0]));
+            }
+        }
+        match m.promotion() {
+            Some(role) => {
+                let promotion = role.upper_char();
+                bases
+                    .iter()
+                    .flat_map(|base| {
+                        [format!("{base}={promotion}"), format!("{base}{promotion}")]
+                    })
+                    .collect()
+            }
+            None => bases,
+        }
+    }
+
+    /// Descriptive names of `square` for `color`, ranks counted from that
+    /// side: the full name (`KB3`), then the name without the king's or
+    /// queen's side where the file has one (`B3`).
+    fn square_names(square: Square, color: Color) -> Vec<String> {
+        const FILES: [&str; 8] = ["QR", "QN", "QB", "Q", "K", "KB", "KN", "KR"];
+        let file = FILES[u32::from(square.file()) as usize];
+        let rank = match color {
+            Color::White => u32::from(square.rank()) + 1,
+            Color::Black => 8 - u32::from(square.rank()),
+        };
+        let mut names = vec![format!("{file}{rank}")];
+        if file.len() == 2 {
+            names.push(format!("{}{rank}", &file[1..]));
+        }
+        names
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::results::BenchmarkResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSummary {
+    pub solver_name: String,
+    pub average_score: f64,
+    pub total_score: f64,
+    pub max_possible_score: f64,
+    pub cost: Option<f64>,
+}
+
+/// Webhook payload sent when a run ends. `text` (Slack) and `content`
+/// (Discord) carry the same human-readable summary so either service can
+/// post it as-is; the other fields are for programmatic consumers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub text: String,
+    pub content: String,
+    pub status: String,
+    pub duration_secs: f64,
+    pub result_path: Option<String>,
+    pub error: Option<String>,
+    pub models: Vec<ModelSummary>,
+}
+
+impl Notification {
+    pub fn finished(results: &[BenchmarkResult], result_path: &str, duration: Duration) -> Self {
+        let models: Vec<ModelSummary> = results
+            .iter()
+            .map(|r| ModelSummary {
+                solver_name: r.solver_name.clone(),
+                average_score: r.average_score,
+                total_score: r.total_score,
+                max_possible_score: r.max_possible_score,
+                cost: r.usage.as_ref().map(|u| u.cost),
+            })
+            .collect();
+        let status = if results.iter().any(|r| r.interrupted) {
+            "interrupted"
+        } else if results.iter().any(|r| r.budget_exceeded) {
+            "budget_exceeded"
+        } else if results.iter().any(|r| !r.unanswered_puzzles.is_empty()) {
+            "incomplete"
+        } else {
+            "finished"
+        };
+
+        let mut text = format!(
+            "Benchmark run {} in {}.",
+            status.replace('_', " "),
+            format_duration(duration)
+        );
+        for model in &models {
+            text.push_str(&format!(
+                "\n• {}: {:.2}% ({}/{})",
+                model.solver_name,
+                model.average_score * 100.0,
+                model.total_score,
+                model.max_possible_score
+            ));
+            if let Some(cost) = model.cost {
+                text.push_str(&format!(", ${:.4}", cost));
+            }
+        }
+        text.push_str(&format!("\nResults: {}", result_path));
+
+        Self {
+            content: text.clone(),
+            text,
+            status: status.to_string(),
+            duration_secs: duration.as_secs_f64(),
+            result_path: Some(result_path.to_string()),
+            error: None,
+            models,
+        }
+    }
+
+    pub fn aborted(error: &str, duration: Duration) -> Self {
+        let text = format!(
+            "Benchmark run aborted after {}: {}",
+            format_duration(duration),
+            error
+        );
+        Self {
+            content: text.clone(),
+            text,
+            status: "aborted".to_string(),
+            duration_secs: duration.as_secs_f64(),
+            result_path: None,
+            error: Some(error.to_string()),
+            models: Vec::new(),
+        }
+    }
+}
+
+/// POST `notification` as JSON to `url`.
+pub fn notify(url: &str, notification: &Notification) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    crate::runtime::block_on(async {
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(notification)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("{}: {}", response.status(), response.text().await?).into());
+        }
+        Ok(())
+    })
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,295 @@
+//! Complete games of chess played move by move by solvers or UCI engines,
+//! with the rules engine deciding which moves are legal and how each game
+//! ends.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::engine::{EngineError, UciEngine};
+use crate::evaluation::{RetryPolicy, Solver};
+use crate::game::chess::{self, ChessError, Ending};
+use crate::notation;
+use crate::puzzle::{MoveHistory, Puzzle, PuzzleCollection, PuzzleGoal};
+
+#[derive(Debug, Error)]
+pub enum PlayError {
+    #[error(transparent)]
+    Chess(#[from] ChessError),
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+}
+
+/// One side of a game.
+#[derive(Clone, Copy)]
+pub enum Player<'a> {
+    /// A model, asked for each move like a puzzle.
+    Model(&'a Solver),
+    /// A UCI engine, whose best move is always played.
+    Engine(&'a UciEngine),
+}
+
+impl Player<'_> {
+    pub fn name(&self) -> String {
+        match self {
+            Player::Model(solver) => solver.name.clone(),
+            Player::Engine(engine) => engine.name(),
+        }
+    }
+}
+
+/// How games are played and cut short.
+#[derive(Debug, Clone, Copy)]
+pub struct GameRules {
+    /// Adjudicate a draw once each side has made this many moves.
+    pub max_moves: u32,
+    /// How many times a side is asked for a move before giving no legal
+    /// one forfeits the game.
+    pub move_attempts: u32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            max_moves: 100,
+            move_attempts: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// The points White gets: 1 for a win, ½ for a draw.
+    pub fn white_score(self) -> f64 {
+        match self {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+
+    /// The result as PGN writes it, e.g. `1-0`.
+    pub fn pgn(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    fn win_for(white: bool) -> Self {
+        if white { GameResult::WhiteWins } else { GameResult::BlackWins }
+    }
+}
+
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoves,
+    /// The same position came up for the third time.
+    Repetition,
+    /// `GameRules::max_moves` was reached.
+    MoveLimit,
+    /// A model gave no legal move in `GameRules::move_attempts` tries, or an
+    /// engine an illegal one.
+    Forfeit,
+}
+
+impl From<Ending> for Termination {
+    fn from(ending: Ending) -> Self {
+        match ending {
+            Ending::Checkmate => Termination::Checkmate,
+            Ending::Stalemate => Termination::Stalemate,
+            Ending::InsufficientMaterial => Termination::InsufficientMaterial,
+            Ending::FiftyMoves => Termination::FiftyMoves,
+        }
+    }
+}
+
+/// A finished game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub white: String,
+    pub black: String,
+    /// The position the game started from, as FEN.
+    pub start: String,
+    /// The moves played, in UCI.
+    pub moves: Vec<String>,
+    pub result: GameResult,
+    pub termination: Termination,
+    /// Answers from either side rejected as illegal or unreadable, each
+    /// followed by asking again.
+    #[serde(default)]
+    pub illegal_moves: usize,
+    /// Centipawns each move gave away, once annotated by an engine; see
+    /// `blunders::annotate_game`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub losses: Vec<u32>,
+}
+
+impl GameRecord {
+    /// The position the game ended in, as FEN.
+    pub fn final_position(&self) -> Result<String, ChessError> {
+        self.moves.iter().try_fold(self.start.clone(), |fen, uci| chess::play(&fen, uci))
+    }
+
+    /// The game as PGN with the players and result in its tags.
+    pub fn pgn(&self) -> Option<String> {
+        let pgn = chess::pgn(&self.start, &self.moves).ok()?;
+        let movetext = pgn.strip_suffix('*').unwrap_or(&pgn);
+        Some(format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n{}{}",
+            self.white,
+            self.black,
+            self.result.pgn(),
+            movetext,
+            self.result.pgn()
+        ))
+    }
+}
+
+/// Play a game of chess between `white` and `black` from `start`. A
+/// model's moves are asked for like a one-state puzzle of `collection`,
+/// with its rules, answer notation and prompt template and the game so far
+/// as the puzzle's history, so the solvers' presentation settings apply.
+pub async fn play_game(
+    white: Player<'_>,
+    black: Player<'_>,
+    start: &str,
+    collection: &PuzzleCollection,
+    rules: &GameRules,
+    retry: &RetryPolicy,
+) -> Result<GameRecord, PlayError> {
+    let mut fen = start.to_string();
+    let mut moves: Vec<String> = Vec::new();
+    let mut illegal_moves = 0;
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    *seen.entry(chess::repetition_key(&fen)?).or_default() += 1;
+
+    let (result, termination) = loop {
+        let white_to_move = chess::white_to_move(&fen)?;
+        if let Some(ending) = chess::ending(&fen)? {
+            let result = match ending {
+                // The side to move is the one mated.
+                Ending::Checkmate => GameResult::win_for(!white_to_move),
+                _ => GameResult::Draw,
+            };
+            break (result, ending.into());
+        }
+        if moves.len() as u32 >= 2 * rules.max_moves {
+            break (GameResult::Draw, Termination::MoveLimit);
+        }
+
+        let player = if white_to_move { white } else { black };
+        let mut played = None;
+        match player {
+            Player::Model(solver) => {
+                for _ in 0..rules.move_attempts.max(1) {
+                    match ask_move(solver, collection, start, &moves, &fen, retry).await {
+                        Some(uci) => {
+                            played = Some(uci);
+                            break;
+                        }
+                        None => illegal_moves += 1,
+                    }
+                }
+            }
+            Player::Engine(engine) => {
+                let best = engine.best_move(&fen, &[]).await?;
+                played = chess::normalize_uci(&fen, &best);
+            }
+        }
+        let Some(uci) = played else {
+            warn!(player = %player.name(), fen = %fen, "no legal move; forfeiting the game");
+            break (GameResult::win_for(!white_to_move), Termination::Forfeit);
+        };
+        fen = chess::play(&fen, &uci)?;
+        moves.push(uci);
+        let repetitions = seen.entry(chess::repetition_key(&fen)?).or_default();
+        *repetitions += 1;
+        if *repetitions >= 3 {
+            break (GameResult::Draw, Termination::Repetition);
+        }
+    };
+
+    info!(
+        white = %white.name(),
+        black = %black.name(),
+        moves = moves.len(),
+        result = result.pgn(),
+        ?termination,
+        "game finished"
+    );
+    Ok(GameRecord {
+        white: white.name(),
+        black: black.name(),
+        start: start.to_string(),
+        moves,
+        result,
+        termination,
+        illegal_moves,
+        losses: Vec::new(),
+    })
+}
+
+/// Ask `solver` for its move in `fen`, reached by `moves` from `start`;
+/// `None` if its answer isn't a legal move.
+async fn ask_move(
+    solver: &Solver,
+    collection: &PuzzleCollection,
+    start: &str,
+    moves: &[String],
+    fen: &str,
+    retry: &RetryPolicy,
+) -> Option<String> {
+    let notation = collection.answer_format.grammar.move_notation()?;
+    let puzzle = move_puzzle(start, moves, fen);
+    let attempts = solver.solve_puzzle_with_retry(&puzzle, collection, retry).await;
+    let answer = &attempts.first()?.extracted.answer;
+    let uci = notation::to_uci(notation, fen, answer).ok()?;
+    chess::normalize_uci(fen, &uci)
+}
+
+/// The one-state puzzle asking for the next move of a game.
+fn move_puzzle(start: &str, moves: &[String], fen: &str) -> Puzzle {
+    Puzzle {
+        id: format!("game-ply-{}", moves.len() + 1),
+        description: String::new(),
+        game_type: Some("chess".to_string()),
+        game_states: vec![fen.to_string()],
+        solutions: vec![String::new()],
+        encrypted_solutions: None,
+        rating: None,
+        rating_deviation: None,
+        weight: 1.0,
+        state_weights: Vec::new(),
+        images: Vec::new(),
+        sequences: Vec::new(),
+        annotations: Vec::new(),
+        orientations: Vec::new(),
+        history: Some(MoveHistory {
+            start: start.to_string(),
+            moves: moves.to_vec(),
+        }),
+        render_style: None,
+        tags: Vec::new(),
+        provenance: Default::default(),
+        metadata: Default::default(),
+        goal_condition: None,
+        goal: Some(PuzzleGoal::Custom {
+            text: "You are playing a game of chess. Play the best move for the side to move.".to_string(),
+        }),
+    }
+}
@@ -0,0 +1,218 @@
+//! Play-out evaluation: from each puzzle's position a model plays on
+//! against a fixed-strength engine, measuring sustained play rather than
+//! finding a single tactic.
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::blunders::{self, MoveQuality};
+use crate::engine::UciEngine;
+use crate::evaluation::{RetryPolicy, Solver};
+use crate::game::chess;
+use crate::play::{GameRecord, GameRules, PlayError, Player, Termination, play_game};
+use crate::puzzle::PuzzleCollection;
+
+#[derive(Debug, Error)]
+pub enum PlayOutError {
+    #[error("Failed to write the play-out report: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize the play-out report: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Games of a model against `engine` from the positions of `collection`'s
+/// chess puzzles, the model playing the side to move.
+pub struct PlayOut {
+    pub collection: PuzzleCollection,
+    pub engine: UciEngine,
+    rules: GameRules,
+    retry: RetryPolicy,
+    /// Maximum number of games played at once.
+    concurrency: usize,
+    /// Engine grading every move once a game is over. Usually `engine` at
+    /// full strength, as a weakened opponent misjudges moves.
+    annotator: Option<UciEngine>,
+}
+
+impl PlayOut {
+    /// Play-outs of at most 20 moves by each side.
+    pub fn new(collection: PuzzleCollection, engine: UciEngine) -> Self {
+        Self {
+            collection,
+            engine,
+            rules: GameRules {
+                max_moves: 20,
+                ..GameRules::default()
+            },
+            retry: RetryPolicy::default(),
+            concurrency: 1,
+            annotator: None,
+        }
+    }
+
+    pub fn with_rules(mut self, rules: GameRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Annotate every game with `engine`, so the report includes the
+    /// model's move quality.
+    pub fn with_annotator(mut self, engine: UciEngine) -> Self {
+        self.annotator = Some(engine);
+        self
+    }
+
+    pub fn run(&self, solver: &Solver) -> PlayOutReport {
+        crate::runtime::block_on(self.run_async(solver))
+    }
+
+    /// Play out every chess puzzle of the collection. Puzzles whose game
+    /// can't be finished, e.g. because the engine failed, are left out with
+    /// a warning.
+    pub async fn run_async(&self, solver: &Solver) -> PlayOutReport {
+        let permits = Semaphore::new(self.concurrency);
+        let puzzles = self
+            .collection
+            .puzzles
+            .iter()
+            .filter(|puzzle| self.collection.game_type_of(puzzle) == "chess")
+            .filter_map(|puzzle| Some((puzzle.id.as_str(), puzzle.game_states.first()?.as_str())));
+        let games = join_all(puzzles.map(|(puzzle_id, start)| {
+            let permits = &permits;
+            async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                self.play_out(solver, puzzle_id, start)
+                    .await
+                    .inspect_err(|e| warn!(puzzle_id, error = %e, "skipping play-out"))
+                    .ok()
+            }
+        }))
+        .await;
+        PlayOutReport::new(
+            &solver.name,
+            &self.engine.name(),
+            &self.collection.name,
+            games.into_iter().flatten().collect(),
+        )
+    }
+
+    async fn play_out(&self, solver: &Solver, puzzle_id: &str, start: &str) -> Result<PlayOutGame, PlayError> {
+        let model_white = chess::white_to_move(start)?;
+        let (model, engine) = (Player::Model(solver), Player::Engine(&self.engine));
+        let (white, black) = if model_white { (model, engine) } else { (engine, model) };
+        let mut game = play_game(white, black, start, &self.collection, &self.rules, &self.retry).await?;
+        if let Some(annotator) = &self.annotator {
+            game.losses = blunders::annotate_game(annotator, &game).await?;
+        }
+
+        let model_score = if model_white {
+            game.result.white_score()
+        } else {
+            1.0 - game.result.white_score()
+        };
+        // A game cut off by the move limit is scored by the engine's
+        // evaluation of where it stopped rather than as a draw.
+        let (final_eval, score) = if game.termination == Termination::MoveLimit {
+            let end = game.final_position()?;
+            let eval = self.engine.evaluate(&end, &[]).await?;
+            let eval = if chess::white_to_move(&end)? == model_white { eval } else { -eval };
+            (Some(eval), expected_score(eval))
+        } else {
+            (None, model_score)
+        };
+        Ok(PlayOutGame {
+            puzzle_id: puzzle_id.to_string(),
+            model_white,
+            final_eval,
+            score,
+            game,
+        })
+    }
+}
+
+/// The expected score, with draws as half a win, of a side `eval`
+/// centipawns ahead: the Elo curve, with every 400 centipawns multiplying
+/// the odds by ten.
+pub fn expected_score(eval: i32) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-(eval as f64) / 400.0))
+}
+
+/// A model's play-out from one puzzle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayOutGame {
+    pub puzzle_id: String,
+    pub model_white: bool,
+    /// The engine's evaluation, in centipawns for the model, of the
+    /// position a game cut off by the move limit stopped in.
+    #[serde(default)]
+    pub final_eval: Option<i32>,
+    /// 1 for a win, ½ for a draw, 0 for a loss; games cut off by the move
+    /// limit get the expected score of `final_eval`.
+    pub score: f64,
+    pub game: GameRecord,
+}
+
+/// How a model did playing on against an engine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayOutReport {
+    pub model_name: String,
+    pub engine: String,
+    pub collection_name: String,
+    pub timestamp: String,
+    /// Mean score over the games, as a percentage.
+    pub score: f64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Games cut off by the move limit and scored by the final evaluation.
+    pub unfinished: usize,
+    /// The model's moves, over the games annotated with
+    /// `PlayOut::with_annotator`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_quality: Option<MoveQuality>,
+    pub games: Vec<PlayOutGame>,
+}
+
+impl PlayOutReport {
+    pub fn new(model_name: &str, engine: &str, collection_name: &str, games: Vec<PlayOutGame>) -> Self {
+        let finished = || games.iter().filter(|game| game.final_eval.is_none());
+        let records: Vec<GameRecord> = games.iter().map(|game| game.game.clone()).collect();
+        let move_quality = blunders::move_quality(&records).remove(model_name);
+        let score = if games.is_empty() {
+            0.0
+        } else {
+            100.0 * games.iter().map(|game| game.score).sum::<f64>() / games.len() as f64
+        };
+        Self {
+            model_name: model_name.to_string(),
+            engine: engine.to_string(),
+            collection_name: collection_name.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            score,
+            wins: finished().filter(|game| game.score > 0.5).count(),
+            draws: finished().filter(|game| game.score == 0.5).count(),
+            losses: finished().filter(|game| game.score < 0.5).count(),
+            unfinished: games.len() - finished().count(),
+            move_quality,
+            games,
+        }
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), PlayOutError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
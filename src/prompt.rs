@@ -0,0 +1,319 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::diagram::{PieceSet, board_diagram};
+#[cfg(feature = "chess")]
+use crate::game::chess;
+use crate::extraction::AnswerGrammar;
+use crate::puzzle::{Orientation, Puzzle, PuzzleCollection, PuzzleGoal, choice_letter};
+
+mod templates;
+
+use templates::{fill, template};
+
+/// How game states are shown to the solver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Modality {
+    /// The state's FEN string, in the prompt text.
+    #[default]
+    Text,
+    /// The puzzle's pre-rendered board image, attached to the prompt.
+    Image,
+}
+
+/// How a state is written out in a text prompt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Representation {
+    /// The state string as stored, e.g. a FEN.
+    #[default]
+    Fen,
+    /// A board diagram with FEN piece letters (chess only).
+    Ascii,
+    /// A board diagram with chess piece symbols (chess only).
+    Unicode,
+    /// The game so far as PGN, from the puzzle's recorded history or its
+    /// first state (chess only).
+    Pgn,
+}
+
+/// The language prompts are written in. Collections give their goal and
+/// rules in it, or translate them (see `PuzzleCollection::translations`).
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+pub enum PromptLanguage {
+    #[default]
+    #[serde(rename = "en")]
+    #[value(name = "en")]
+    English,
+    #[serde(rename = "de")]
+    #[value(name = "de")]
+    German,
+    #[serde(rename = "es")]
+    #[value(name = "es")]
+    Spanish,
+    #[serde(rename = "fr")]
+    #[value(name = "fr")]
+    French,
+    #[serde(rename = "zh")]
+    #[value(name = "zh")]
+    Chinese,
+}
+
+impl PromptLanguage {
+    pub fn is_english(&self) -> bool {
+        *self == PromptLanguage::English
+    }
+}
+
+/// How much guidance the instructions around a state give.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromptStyle {
+    /// Step-by-step guidance: explain the rules, analyze the position,
+    /// weigh candidate moves.
+    #[default]
+    Full,
+    /// The goal and the answer format, nothing else.
+    Minimal,
+    /// The full guidance without the step asking to explain the rules.
+    NoRules,
+    /// Asks for the answer alone, without any analysis.
+    AnswerOnly,
+}
+
+impl PromptStyle {
+    pub fn label(self) -> &'static str {
+        match self {
+            PromptStyle::Full => "full",
+            PromptStyle::Minimal => "minimal",
+            PromptStyle::NoRules => "no-rules",
+            PromptStyle::AnswerOnly => "answer-only",
+        }
+    }
+}
+
+/// The request appended to prompts to have the answer followed by how
+/// confident the model is in it; see `extract_confidence`.
+pub fn confidence_request(language: PromptLanguage) -> &'static str {
+    template(language).confidence
+}
+
+/// Build the prompt asking a model to solve state `index` of `puzzle`.
+pub fn build_prompt(puzzle: &Puzzle, puzzle_collection: &PuzzleCollection, index: usize) -> String {
+    build_text_prompt(puzzle, puzzle_collection, index, Representation::Fen, PromptStyle::Full)
+}
+
+/// Like `build_prompt`, with the state written as `representation` and the
+/// instructions in `style`.
+/// Diagrams and PGN are only produced for chess; other games' states, and
+/// positions that can't be converted, are given as stored.
+pub fn build_text_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    representation: Representation,
+    style: PromptStyle,
+) -> String {
+    let view = StateView::Text(representation);
+    build_prompt_with_rules(puzzle, puzzle_collection, index, view, style, &puzzle_collection.game_rule)
+}
+
+/// How a state is shown in a prompt: written out as text, or as an
+/// attached board image with or without the moves leading to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateView {
+    Text(Representation),
+    Image,
+    Sequence,
+}
+
+/// The prompt `build_text_prompt`, `build_image_prompt` or
+/// `build_sequence_prompt` builds for `view`, with `rules` in place of the
+/// collection's `game_rule`, e.g. shortened to fit a context window (see
+/// `compression`).
+pub fn build_prompt_with_rules(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    view: StateView,
+    style: PromptStyle,
+    rules: &str,
+) -> String {
+    match view {
+        StateView::Text(representation) => text_prompt(puzzle, puzzle_collection, index, representation, style, rules),
+        StateView::Image => image_prompt(puzzle, puzzle_collection, index, style, rules),
+        StateView::Sequence => sequence_prompt(puzzle, puzzle_collection, index, style, rules),
+    }
+}
+
+fn text_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    representation: Representation,
+    style: PromptStyle,
+    rules: &str,
+) -> String {
+    let fen = &puzzle.game_states[index];
+    let template = template(puzzle_collection.language);
+    let is_chess = puzzle_collection.game_type_of(puzzle).eq_ignore_ascii_case("chess");
+    let described = match representation {
+        Representation::Fen => None,
+        _ if !is_chess => None,
+        Representation::Ascii | Representation::Unicode => {
+            let pieces = if representation == Representation::Ascii { PieceSet::Ascii } else { PieceSet::Unicode };
+            board_diagram(fen, pieces)
+                .ok()
+                .map(|diagram| fill(template.diagram, &[("legend", pieces.legend()), ("diagram", &diagram)]))
+        }
+        Representation::Pgn => pgn_so_far(puzzle, index).map(|pgn| fill(template.pgn, &[("pgn", &pgn)])),
+    };
+    let position = described.unwrap_or_else(|| fill(template.fen, &[("fen", fen)]));
+    instructions(puzzle, puzzle_collection, &position, style, rules)
+}
+
+/// PGN from the start of `puzzle`'s history (or its first state) to state
+/// `index`: the history's moves, then each earlier state's solution and the
+/// reply that led to the next state.
+#[cfg(feature = "chess")]
+fn pgn_so_far(puzzle: &Puzzle, index: usize) -> Option<String> {
+    let (start, mut moves) = match &puzzle.history {
+        Some(history) => (history.start.as_str(), history.moves.clone()),
+        None => (puzzle.game_states[0].as_str(), Vec::new()),
+    };
+    for j in 0..index {
+        let solution = puzzle.solutions.get(j)?;
+        let after = chess::play(&puzzle.game_states[j], solution).ok()?;
+        moves.push(solution.clone());
+        moves.push(chess::move_between(&after, &puzzle.game_states[j + 1]).ok()?);
+    }
+    chess::pgn(start, &moves).ok()
+}
+
+/// Without the `chess` feature moves can't be converted to SAN, so states
+/// are given as FEN.
+#[cfg(not(feature = "chess"))]
+fn pgn_so_far(_puzzle: &Puzzle, _index: usize) -> Option<String> {
+    None
+}
+
+/// Like `build_prompt`, but referring to the attached image of state
+/// `index` instead of spelling out the FEN.
+pub fn build_image_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    style: PromptStyle,
+) -> String {
+    image_prompt(puzzle, puzzle_collection, index, style, &puzzle_collection.game_rule)
+}
+
+fn image_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    style: PromptStyle,
+    rules: &str,
+) -> String {
+    let template = template(puzzle_collection.language);
+    let position = match puzzle.orientations.get(index) {
+        Some(Orientation::White) => template.image_white,
+        Some(Orientation::Black) => template.image_black,
+        None => template.image,
+    };
+    instructions(puzzle, puzzle_collection, position, style, rules)
+}
+
+/// Like `build_image_prompt`, for a request that attaches the sequence of
+/// moves leading to the position (see `Puzzle::sequences`) before the board.
+pub fn build_sequence_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    style: PromptStyle,
+) -> String {
+    sequence_prompt(puzzle, puzzle_collection, index, style, &puzzle_collection.game_rule)
+}
+
+fn sequence_prompt(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    index: usize,
+    style: PromptStyle,
+    rules: &str,
+) -> String {
+    let template = template(puzzle_collection.language);
+    let bottom = match puzzle.orientations.get(index) {
+        Some(Orientation::White) => template.sequence_white,
+        Some(Orientation::Black) => template.sequence_black,
+        None => "",
+    };
+    let position = fill(template.sequence, &[("bottom", bottom)]);
+    instructions(puzzle, puzzle_collection, &position, style, rules)
+}
+
+/// The collection's instructions in its language and `style` around
+/// `position`. A collection's own `prompt_template` replaces the full
+/// guidance; transcription collections, whose answer is the position itself
+/// rather than a move, get their own full guidance otherwise. The minimal
+/// and answer-only styles are worded for any answer. A puzzle's own goal
+/// replaces the collection's `{goal}`, and for word and letter answers
+/// `{notation}`; `rules` fills `{rules}`.
+fn instructions(
+    puzzle: &Puzzle,
+    puzzle_collection: &PuzzleCollection,
+    position: &str,
+    style: PromptStyle,
+    rules: &str,
+) -> String {
+    let template = template(puzzle_collection.language);
+    let transcribing = puzzle_collection.answer_format.grammar == AnswerGrammar::Fen;
+    let goal = match &puzzle.goal {
+        None | Some(PuzzleGoal::BestMove) => puzzle_collection.goal.clone(),
+        Some(PuzzleGoal::MateInN { moves }) => fill(template.mate_in, &[("moves", &moves.to_string())]),
+        Some(PuzzleGoal::Evaluate) => template.evaluate.to_string(),
+        Some(PuzzleGoal::MultipleChoice { choices }) => {
+            let choices: Vec<String> = choices
+                .iter()
+                .enumerate()
+                .map(|(i, choice)| format!("{}) {}", choice_letter(i), choice))
+                .collect();
+            fill(template.multiple_choice, &[("choices", &choices.join("; "))])
+        }
+        Some(PuzzleGoal::Survive) => template.survive.to_string(),
+        Some(PuzzleGoal::Custom { text }) => text.clone(),
+    };
+    let notation = match &puzzle.goal {
+        Some(PuzzleGoal::Evaluate) => template.evaluate_answer,
+        Some(PuzzleGoal::MultipleChoice { .. }) => template.choice_answer,
+        _ => puzzle_collection.answer_format.grammar.description(),
+    };
+    let text = match (style, &puzzle_collection.prompt_template) {
+        (PromptStyle::Minimal, _) => template.minimal.to_string(),
+        (PromptStyle::AnswerOnly, _) => template.answer_only.to_string(),
+        (_, Some(custom)) => custom.clone(),
+        _ if transcribing => template.transcription.to_string(),
+        (PromptStyle::Full | PromptStyle::NoRules, None) => {
+            let skip = if style == PromptStyle::NoRules { 1 } else { 0 };
+            let steps: Vec<String> = template.steps[skip..]
+                .iter()
+                .enumerate()
+                .map(|(i, step)| format!("{}. {}", i + 1, step))
+                .collect();
+            format!("{}\n{}\n\n{{position}}", template.intro, steps.join("\n"))
+        }
+    };
+    fill(
+        &text,
+        &[
+            ("game_type", puzzle_collection.game_type_of(puzzle)),
+            ("goal", &goal),
+            ("rules", rules),
+            ("notation", notation),
+            ("position", position),
+        ],
+    )
+}
@@ -0,0 +1,303 @@
+//! Prompt wording per language. Placeholders in braces are filled by
+//! `fill`. Every template keeps the `**Answer: ...**` marker in English,
+//! since answer extraction looks for it.
+
+use super::PromptLanguage;
+
+pub(super) struct Template {
+    /// Introduction to the full instructions for finding a move.
+    pub intro: &'static str,
+    /// The numbered guidelines after `intro`, starting with explaining the
+    /// rules: `{game_type}`, `{goal}`, `{notation}`.
+    pub steps: [&'static str; 5],
+    /// Instructions for transcribing a position, with the same
+    /// placeholders and `{position}`.
+    pub transcription: &'static str,
+    /// A one-line task for `PromptStyle::Minimal`, with the same
+    /// placeholders.
+    pub minimal: &'static str,
+    /// A task asking for nothing but the answer, for
+    /// `PromptStyle::AnswerOnly`.
+    pub answer_only: &'static str,
+    /// The goals of `PuzzleGoal`s, filling `{goal}`: `{moves}`.
+    pub mate_in: &'static str,
+    pub survive: &'static str,
+    pub evaluate: &'static str,
+    /// `{choices}`, each as `A) ...` and separated by semicolons.
+    pub multiple_choice: &'static str,
+    /// The answers to `evaluate` and `multiple_choice`, filling `{notation}`.
+    pub evaluate_answer: &'static str,
+    pub choice_answer: &'static str,
+    /// `{fen}`.
+    pub fen: &'static str,
+    /// `{legend}`, `{diagram}`.
+    pub diagram: &'static str,
+    /// `{pgn}`.
+    pub pgn: &'static str,
+    pub image: &'static str,
+    pub image_white: &'static str,
+    pub image_black: &'static str,
+    /// `{bottom}`: one of the two below, or nothing.
+    pub sequence: &'static str,
+    pub sequence_white: &'static str,
+    pub sequence_black: &'static str,
+    /// Asks for the answer's confidence after it, as `**Confidence: ...%**`.
+    pub confidence: &'static str,
+}
+
+pub(super) fn template(language: PromptLanguage) -> &'static Template {
+    match language {
+        PromptLanguage::English => &ENGLISH,
+        PromptLanguage::German => &GERMAN,
+        PromptLanguage::Spanish => &SPANISH,
+        PromptLanguage::French => &FRENCH,
+        PromptLanguage::Chinese => &CHINESE,
+    }
+}
+
+/// `template` with each `{name}` replaced by its value, in one pass so
+/// values containing braces are left alone.
+pub(super) fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        filled.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let name = &after[..close];
+            values.iter().find(|(key, _)| *key == name).map(|(_, value)| (close, *value))
+        });
+        match value {
+            Some((close, value)) => {
+                filled.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+static ENGLISH: Template = Template {
+    intro: "You are a highly advanced AI specialized in solving abstract board game puzzles.
+Your task is to analyze the given game state and provide a detailed strategic evaluation along with the best possible move.
+Follow these guidelines to ensure optimal performance:",
+    steps: [
+        "**Understanding the Game Rules**: Begin by thoroughly explaining the rules of {game_type} in the context of the current puzzle. Highlight unique aspects like movement patterns of pieces, special moves, and endgame conditions.",
+        "**Game State Analysis**: Assess the current state of the {game_type} board. Identify key factors such as:
+  - Material balance: Compare the pieces on both sides.
+  - Positioning: Evaluate the placement of pieces, control of the center, and potential threats.
+  - Tactical opportunities: Look for immediate tactical shots like forks, pins, or discovered attacks.
+  - Strategic considerations: Discuss long-term plans, weaknesses, and strengths of each side.",
+        "**Best Move Recommendation**: Propose several moves based on your analysis. Think of possible responses from the opponent and how to counteract them. Choose the best move that maximizes your advantage or minimizes your losses.",
+        "**Goal of the Puzzle**: Keep in mind that the primary objective is: {goal}. Tailor your analysis and move recommendations to align with this goal.",
+        "**Formatting and Clarity**: Provide your final answer in the following format: **Answer: <your move here>**, where your move is represented in {notation}. Ensure your response is separated from the analysis in one line for clarity.",
+    ],
+    transcription: "You are a highly advanced AI specialized in reading abstract board game positions.
+Your task is to write down the exact position of the given {game_type} board.
+Follow these guidelines to ensure optimal performance:
+1. **Reading the Board**: Go through the board rank by rank, from the top of the board as White sees it (rank 8) to the bottom (rank 1), and name the piece on every square, taking care to tell the colors and the piece types apart.
+2. **Game State**: Work out the side to move, castling rights and en passant square from the position and anything shown on the board, such as a highlighted last move.
+3. **Goal of the Task**: {goal}.
+4. **Formatting and Clarity**: Provide your final answer in the following format: **Answer: <your position here>**, where the position is represented in {notation}. Ensure your response is separated from the analysis in one line for clarity.
+
+{position}",
+    minimal: "This is a {game_type} puzzle, and the goal is: {goal}. Give your final answer as **Answer: <your answer here>**, where your answer is represented in {notation}.\n\n{position}",
+    answer_only: "This is a {game_type} puzzle, and the goal is: {goal}. Reply with nothing but **Answer: <your answer here>**, where your answer is represented in {notation}. Do not explain your answer or show any analysis.\n\n{position}",
+    mate_in: "find the first move of a forced checkmate in {moves}",
+    survive: "find a move after which the opponent has no immediate checkmate",
+    evaluate: "judge which side stands better in this position",
+    multiple_choice: "choose the correct option: {choices}",
+    evaluate_answer: "one of the words white, black or equal",
+    choice_answer: "the letter of your chosen option, e.g., A",
+    fen: "The puzzle is given by FEN string: {fen}",
+    diagram: "The puzzle is given by this board diagram, with White at the bottom. {legend}\n\n{diagram}",
+    pgn: "The puzzle is given by the game so far in PGN; find the move for the side to move next.\n\n{pgn}",
+    image: "The puzzle is given by the attached image of the board.",
+    image_white: "The puzzle is given by the attached image of the board, with White at the bottom.",
+    image_black: "The puzzle is given by the attached image of the board, with Black at the bottom.",
+    sequence: "The first attached image shows the moves that led to the puzzle, one position per frame (from left to right if they are side by side), and the second image is the board of the puzzle itself.{bottom}",
+    sequence_white: " White is at the bottom of both.",
+    sequence_black: " Black is at the bottom of both.",
+    confidence: "After your answer, add one more line saying how confident you are that it is correct, as a percentage from 0 to 100: **Confidence: <percentage>%**",
+};
+
+static GERMAN: Template = Template {
+    intro: "Du bist eine hochentwickelte KI, die auf das Lösen von Rätseln zu abstrakten Brettspielen spezialisiert ist.
+Deine Aufgabe ist es, die gegebene Spielstellung zu analysieren und eine ausführliche strategische Bewertung zusammen mit dem bestmöglichen Zug zu liefern.
+Befolge diese Richtlinien, um optimale Ergebnisse zu erzielen:",
+    steps: [
+        "**Die Spielregeln verstehen**: Erkläre zunächst gründlich die Regeln von {game_type} im Zusammenhang mit dem aktuellen Rätsel. Hebe Besonderheiten wie die Zugmöglichkeiten der Figuren, Sonderzüge und die Bedingungen für das Spielende hervor.",
+        "**Analyse der Stellung**: Beurteile die aktuelle Stellung auf dem Brett ({game_type}). Bestimme wichtige Faktoren wie:
+  - Materialverhältnis: Vergleiche die Figuren beider Seiten.
+  - Stellung: Bewerte die Platzierung der Figuren, die Kontrolle des Zentrums und mögliche Drohungen.
+  - Taktische Möglichkeiten: Suche nach unmittelbaren taktischen Schlägen wie Gabeln, Fesselungen oder Abzugsangriffen.
+  - Strategische Überlegungen: Erörtere langfristige Pläne sowie Schwächen und Stärken beider Seiten.",
+        "**Empfehlung des besten Zuges**: Schlage auf Grundlage deiner Analyse mehrere Züge vor. Überlege mögliche Antworten des Gegners und wie du ihnen begegnen kannst. Wähle den besten Zug, der deinen Vorteil maximiert oder deine Verluste minimiert.",
+        "**Ziel des Rätsels**: Denke daran, dass das Hauptziel lautet: {goal}. Richte deine Analyse und deine Zugempfehlungen an diesem Ziel aus.",
+        "**Format und Klarheit**: Gib deine endgültige Antwort in folgendem Format an: **Answer: <dein Zug>**, wobei das Wort „Answer“ auf Englisch bleibt und dein Zug in {notation} angegeben wird. Setze deine Antwort in einer eigenen Zeile von der Analyse ab.",
+    ],
+    transcription: "Du bist eine hochentwickelte KI, die darauf spezialisiert ist, Stellungen abstrakter Brettspiele zu lesen.
+Deine Aufgabe ist es, die genaue Stellung auf dem gegebenen Brett ({game_type}) aufzuschreiben.
+Befolge diese Richtlinien, um optimale Ergebnisse zu erzielen:
+1. **Das Brett lesen**: Gehe das Brett Reihe für Reihe durch, von oben aus Sicht von Weiß (Reihe 8) bis unten (Reihe 1), und benenne die Figur auf jedem Feld. Achte darauf, Farben und Figurenarten richtig zu unterscheiden.
+2. **Spielzustand**: Ermittle die Seite am Zug, die Rochaderechte und das En-passant-Feld aus der Stellung und allem, was auf dem Brett angezeigt wird, etwa einem hervorgehobenen letzten Zug.
+3. **Ziel der Aufgabe**: {goal}.
+4. **Format und Klarheit**: Gib deine endgültige Antwort in folgendem Format an: **Answer: <deine Stellung>**, wobei das Wort „Answer“ auf Englisch bleibt und die Stellung in {notation} angegeben wird. Setze deine Antwort in einer eigenen Zeile von der Analyse ab.
+
+{position}",
+    minimal: "Dies ist ein Rätsel ({game_type}), und das Ziel lautet: {goal}. Gib deine endgültige Antwort als **Answer: <deine Antwort>** an, wobei das Wort „Answer“ auf Englisch bleibt und die Antwort in {notation} geschrieben wird.\n\n{position}",
+    answer_only: "Dies ist ein Rätsel ({game_type}), und das Ziel lautet: {goal}. Antworte ausschließlich mit **Answer: <deine Antwort>**, wobei das Wort „Answer“ auf Englisch bleibt und die Antwort in {notation} geschrieben wird. Erkläre deine Antwort nicht und zeige keine Analyse.\n\n{position}",
+    mate_in: "finde den ersten Zug eines erzwungenen Matts in {moves}",
+    survive: "finde einen Zug, nach dem der Gegner kein sofortiges Matt hat",
+    evaluate: "beurteile, welche Seite in dieser Stellung besser steht",
+    multiple_choice: "wähle die richtige Option: {choices}",
+    evaluate_answer: "ein englisches Wort: white, black oder equal",
+    choice_answer: "den Buchstaben der gewählten Option, z. B. A",
+    fen: "Das Rätsel ist durch folgenden FEN-String gegeben: {fen}",
+    diagram: "Das Rätsel ist durch dieses Brettdiagramm gegeben, mit Weiß unten. {legend}\n\n{diagram}",
+    pgn: "Das Rätsel ist durch die bisherige Partie im PGN-Format gegeben; finde den Zug für die Seite, die als Nächstes am Zug ist.\n\n{pgn}",
+    image: "Das Rätsel ist durch das angehängte Bild des Bretts gegeben.",
+    image_white: "Das Rätsel ist durch das angehängte Bild des Bretts gegeben, mit Weiß unten.",
+    image_black: "Das Rätsel ist durch das angehängte Bild des Bretts gegeben, mit Schwarz unten.",
+    sequence: "Das erste angehängte Bild zeigt die Züge, die zum Rätsel geführt haben, eine Stellung pro Bild (von links nach rechts, wenn sie nebeneinander stehen), und das zweite Bild ist das Brett des Rätsels selbst.{bottom}",
+    sequence_white: " Auf beiden ist Weiß unten.",
+    sequence_black: " Auf beiden ist Schwarz unten.",
+    confidence: "Füge nach deiner Antwort eine weitere Zeile hinzu, die angibt, wie sicher du bist, dass sie richtig ist, als Prozentsatz von 0 bis 100: **Confidence: <Prozentsatz>%**, wobei das Wort „Confidence“ auf Englisch bleibt.",
+};
+
+static SPANISH: Template = Template {
+    intro: "Eres una IA muy avanzada especializada en resolver problemas de juegos de tablero abstractos.
+Tu tarea es analizar el estado de la partida dado y ofrecer una evaluación estratégica detallada junto con la mejor jugada posible.
+Sigue estas pautas para obtener el mejor resultado:",
+    steps: [
+        "**Comprender las reglas del juego**: Empieza explicando a fondo las reglas de {game_type} en el contexto del problema actual. Destaca aspectos particulares como los movimientos de las piezas, las jugadas especiales y las condiciones de final de partida.",
+        "**Análisis de la posición**: Evalúa el estado actual del tablero de {game_type}. Identifica factores clave como:
+  - Equilibrio material: compara las piezas de ambos bandos.
+  - Posición: evalúa la colocación de las piezas, el control del centro y las amenazas posibles.
+  - Oportunidades tácticas: busca golpes tácticos inmediatos como horquillas, clavadas o ataques a la descubierta.
+  - Consideraciones estratégicas: comenta los planes a largo plazo y las debilidades y fortalezas de cada bando.",
+        "**Recomendación de la mejor jugada**: Propón varias jugadas basadas en tu análisis. Piensa en las posibles respuestas del rival y en cómo contrarrestarlas. Elige la mejor jugada, la que maximice tu ventaja o minimice tus pérdidas.",
+        "**Objetivo del problema**: Ten en cuenta que el objetivo principal es: {goal}. Orienta tu análisis y tus recomendaciones hacia este objetivo.",
+        "**Formato y claridad**: Da tu respuesta final con el siguiente formato: **Answer: <tu jugada>**, manteniendo la palabra «Answer» en inglés, donde tu jugada se escribe en {notation}. Separa tu respuesta del análisis en una línea propia para mayor claridad.",
+    ],
+    transcription: "Eres una IA muy avanzada especializada en leer posiciones de juegos de tablero abstractos.
+Tu tarea es anotar la posición exacta del tablero de {game_type} dado.
+Sigue estas pautas para obtener el mejor resultado:
+1. **Leer el tablero**: Recorre el tablero fila por fila, desde arriba tal como lo ven las blancas (fila 8) hasta abajo (fila 1), y nombra la pieza de cada casilla, distinguiendo con cuidado los colores y los tipos de pieza.
+2. **Estado de la partida**: Deduce el bando que mueve, los derechos de enroque y la casilla de captura al paso a partir de la posición y de lo que muestre el tablero, como una última jugada resaltada.
+3. **Objetivo de la tarea**: {goal}.
+4. **Formato y claridad**: Da tu respuesta final con el siguiente formato: **Answer: <tu posición>**, manteniendo la palabra «Answer» en inglés, donde la posición se escribe en {notation}. Separa tu respuesta del análisis en una línea propia para mayor claridad.
+
+{position}",
+    minimal: "Este es un problema de {game_type} y el objetivo es: {goal}. Da tu respuesta final como **Answer: <tu respuesta>**, manteniendo la palabra «Answer» en inglés, donde la respuesta se escribe en {notation}.\n\n{position}",
+    answer_only: "Este es un problema de {game_type} y el objetivo es: {goal}. Responde únicamente con **Answer: <tu respuesta>**, manteniendo la palabra «Answer» en inglés, donde la respuesta se escribe en {notation}. No expliques tu respuesta ni muestres ningún análisis.\n\n{position}",
+    mate_in: "encuentra la primera jugada de un mate forzado en {moves}",
+    survive: "encuentra una jugada tras la cual el rival no tenga mate inmediato",
+    evaluate: "juzga qué bando está mejor en esta posición",
+    multiple_choice: "elige la opción correcta: {choices}",
+    evaluate_answer: "una palabra en inglés: white, black o equal",
+    choice_answer: "la letra de la opción elegida, p. ej., A",
+    fen: "El problema viene dado por la cadena FEN: {fen}",
+    diagram: "El problema viene dado por este diagrama del tablero, con las blancas abajo. {legend}\n\n{diagram}",
+    pgn: "El problema viene dado por la partida hasta ahora en PGN; encuentra la jugada del bando al que le toca mover.\n\n{pgn}",
+    image: "El problema viene dado por la imagen adjunta del tablero.",
+    image_white: "El problema viene dado por la imagen adjunta del tablero, con las blancas abajo.",
+    image_black: "El problema viene dado por la imagen adjunta del tablero, con las negras abajo.",
+    sequence: "La primera imagen adjunta muestra las jugadas que llevaron al problema, una posición por fotograma (de izquierda a derecha si están una al lado de otra), y la segunda imagen es el tablero del propio problema.{bottom}",
+    sequence_white: " En ambas, las blancas están abajo.",
+    sequence_black: " En ambas, las negras están abajo.",
+    confidence: "Después de tu respuesta, añade una línea más indicando qué tan seguro estás de que es correcta, como un porcentaje de 0 a 100: **Confidence: <porcentaje>%**, manteniendo la palabra «Confidence» en inglés.",
+};
+
+static FRENCH: Template = Template {
+    intro: "Tu es une IA très avancée, spécialisée dans la résolution de problèmes de jeux de plateau abstraits.
+Ta tâche est d'analyser l'état de jeu donné et de fournir une évaluation stratégique détaillée ainsi que le meilleur coup possible.
+Suis ces consignes pour obtenir les meilleurs résultats :",
+    steps: [
+        "**Comprendre les règles du jeu** : Commence par expliquer en détail les règles de {game_type} dans le contexte du problème actuel. Souligne les particularités comme le déplacement des pièces, les coups spéciaux et les conditions de fin de partie.",
+        "**Analyse de la position** : Évalue l'état actuel du plateau de {game_type}. Identifie les facteurs clés tels que :
+  - Équilibre matériel : compare les pièces des deux camps.
+  - Position : évalue le placement des pièces, le contrôle du centre et les menaces potentielles.
+  - Occasions tactiques : cherche des coups tactiques immédiats comme les fourchettes, les clouages ou les attaques à la découverte.
+  - Considérations stratégiques : discute des plans à long terme ainsi que des faiblesses et des forces de chaque camp.",
+        "**Recommandation du meilleur coup** : Propose plusieurs coups à partir de ton analyse. Envisage les réponses possibles de l'adversaire et la manière de les contrer. Choisis le meilleur coup, celui qui maximise ton avantage ou minimise tes pertes.",
+        "**Objectif du problème** : Garde à l'esprit que l'objectif principal est : {goal}. Adapte ton analyse et tes recommandations de coups à cet objectif.",
+        "**Mise en forme et clarté** : Donne ta réponse finale au format suivant : **Answer: <ton coup>**, en gardant le mot « Answer » en anglais, où ton coup est écrit en {notation}. Sépare ta réponse de l'analyse sur une ligne à part pour plus de clarté.",
+    ],
+    transcription: "Tu es une IA très avancée, spécialisée dans la lecture de positions de jeux de plateau abstraits.
+Ta tâche est de noter la position exacte du plateau de {game_type} donné.
+Suis ces consignes pour obtenir les meilleurs résultats :
+1. **Lire le plateau** : Parcours le plateau rangée par rangée, du haut tel que le voient les Blancs (rangée 8) jusqu'en bas (rangée 1), et nomme la pièce sur chaque case, en prenant soin de bien distinguer les couleurs et les types de pièces.
+2. **État de la partie** : Détermine le camp au trait, les droits de roque et la case de prise en passant à partir de la position et de tout ce que montre le plateau, comme un dernier coup mis en évidence.
+3. **Objectif de la tâche** : {goal}.
+4. **Mise en forme et clarté** : Donne ta réponse finale au format suivant : **Answer: <ta position>**, en gardant le mot « Answer » en anglais, où la position est écrite en {notation}. Sépare ta réponse de l'analyse sur une ligne à part pour plus de clarté.
+
+{position}",
+    minimal: "Voici un problème de {game_type}, et l'objectif est : {goal}. Donne ta réponse finale sous la forme **Answer: <ta réponse>**, en gardant le mot « Answer » en anglais, où la réponse est écrite en {notation}.\n\n{position}",
+    answer_only: "Voici un problème de {game_type}, et l'objectif est : {goal}. Réponds uniquement par **Answer: <ta réponse>**, en gardant le mot « Answer » en anglais, où la réponse est écrite en {notation}. N'explique pas ta réponse et ne montre aucune analyse.\n\n{position}",
+    mate_in: "trouve le premier coup d'un mat forcé en {moves}",
+    survive: "trouve un coup après lequel l'adversaire n'a pas de mat immédiat",
+    evaluate: "juge quel camp est mieux dans cette position",
+    multiple_choice: "choisis la bonne option : {choices}",
+    evaluate_answer: "un mot anglais : white, black ou equal",
+    choice_answer: "la lettre de l'option choisie, par ex. A",
+    fen: "Le problème est donné par la chaîne FEN : {fen}",
+    diagram: "Le problème est donné par ce diagramme du plateau, avec les Blancs en bas. {legend}\n\n{diagram}",
+    pgn: "Le problème est donné par la partie jusqu'ici en PGN ; trouve le coup du camp qui a le trait.\n\n{pgn}",
+    image: "Le problème est donné par l'image jointe du plateau.",
+    image_white: "Le problème est donné par l'image jointe du plateau, avec les Blancs en bas.",
+    image_black: "Le problème est donné par l'image jointe du plateau, avec les Noirs en bas.",
+    sequence: "La première image jointe montre les coups qui ont mené au problème, une position par image (de gauche à droite si elles sont côte à côte), et la seconde image est le plateau du problème lui-même.{bottom}",
+    sequence_white: " Sur les deux, les Blancs sont en bas.",
+    sequence_black: " Sur les deux, les Noirs sont en bas.",
+    confidence: "Après ta réponse, ajoute une ligne indiquant à quel point tu es sûr qu'elle est correcte, sous forme de pourcentage de 0 à 100 : **Confidence: <pourcentage>%**, en gardant le mot « Confidence » en anglais.",
+};
+
+static CHINESE: Template = Template {
+    intro: "你是一个高度先进的人工智能，专门解决抽象棋盘游戏谜题。
+你的任务是分析给定的局面，给出详细的战略评估以及最佳着法。
+请遵循以下准则以获得最佳表现：",
+    steps: [
+        "**理解游戏规则**：首先结合当前谜题，详细解释 {game_type} 的规则。重点说明棋子的走法、特殊着法和终局条件等独特之处。",
+        "**局面分析**：评估当前 {game_type} 棋盘的局面。找出关键因素，例如：
+  - 子力对比：比较双方的棋子。
+  - 局面位置：评估棋子的布置、对中心的控制以及潜在威胁。
+  - 战术机会：寻找双重攻击、牵制或闪击等直接的战术手段。
+  - 战略考虑：讨论长期计划以及双方的弱点和优势。",
+        "**最佳着法建议**：根据你的分析提出几种着法。考虑对手可能的应对以及如何化解。选择最能扩大优势或减少损失的最佳着法。",
+        "**谜题目标**：请记住，主要目标是：{goal}。让你的分析和着法建议围绕这一目标展开。",
+        "**格式与清晰度**：请按以下格式给出最终答案：**Answer: <你的着法>**，其中“Answer”一词保持英文，你的着法使用 {notation} 表示。请将答案单独放在一行，与分析分开。",
+    ],
+    transcription: "你是一个高度先进的人工智能，专门识读抽象棋盘游戏的局面。
+你的任务是写出给定 {game_type} 棋盘的准确局面。
+请遵循以下准则以获得最佳表现：
+1. **识读棋盘**：从白方视角的棋盘顶部（第 8 横排）到底部（第 1 横排）逐排查看，说出每个格子上的棋子，注意区分颜色和棋子种类。
+2. **局面状态**：根据局面以及棋盘上显示的信息（例如高亮的上一步着法），推断轮到哪一方走棋、易位权和吃过路兵的格子。
+3. **任务目标**：{goal}。
+4. **格式与清晰度**：请按以下格式给出最终答案：**Answer: <你的局面>**，其中“Answer”一词保持英文，局面使用 {notation} 表示。请将答案单独放在一行，与分析分开。
+
+{position}",
+    minimal: "这是一道 {game_type} 谜题，目标是：{goal}。请以 **Answer: <你的答案>** 的格式给出最终答案，其中“Answer”一词保持英文，答案使用 {notation} 表示。\n\n{position}",
+    answer_only: "这是一道 {game_type} 谜题，目标是：{goal}。只回复 **Answer: <你的答案>**，其中“Answer”一词保持英文，答案使用 {notation} 表示。不要解释你的答案，也不要展示任何分析。\n\n{position}",
+    mate_in: "找出 {moves} 步强制将杀的第一步",
+    survive: "找出一步棋，使对手无法立即将杀",
+    evaluate: "判断在此局面中哪一方更好",
+    multiple_choice: "选择正确的选项：{choices}",
+    evaluate_answer: "一个英文单词：white、black 或 equal",
+    choice_answer: "所选选项的字母，例如 A",
+    fen: "谜题由以下 FEN 字符串给出：{fen}",
+    diagram: "谜题由以下棋盘图给出，白方在下方。{legend}\n\n{diagram}",
+    pgn: "谜题由到目前为止的对局 PGN 给出；请为接下来走棋的一方找出着法。\n\n{pgn}",
+    image: "谜题由附带的棋盘图片给出。",
+    image_white: "谜题由附带的棋盘图片给出，白方在下方。",
+    image_black: "谜题由附带的棋盘图片给出，黑方在下方。",
+    sequence: "第一张附图展示了导致该谜题的着法，每帧一个局面（若并排显示则从左到右），第二张图是谜题本身的棋盘。{bottom}",
+    sequence_white: "两张图中白方都在下方。",
+    sequence_black: "两张图中黑方都在下方。",
+    confidence: "在答案之后，再加一行说明你对答案正确的把握有多大，用 0 到 100 的百分比表示：**Confidence: <百分比>%**，其中“Confidence”一词保持英文。",
+};
@@ -1,65 +1,891 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use crate::compression::Compression;
+use crate::confidence::Forecast;
+use crate::extraction::{AnswerFormat, AnswerGrammar, ExtractionMethod, extract_answer};
+use crate::notation::{self, MoveNotation, NotationError};
+use crate::prompt::PromptLanguage;
+use crate::scoring::{AnswerScorer, AnswerScoring, StateAnswer};
+use crate::transcription::transcription_score;
+
+pub mod testing;
+
 #[derive(Debug, Error)]
 pub enum PuzzleError {
     #[error("Invalid puzzle definition: {0}")]
     InvalidDefinition(String),
     #[error("File error: {0}")]
     FileError(String),
+    #[error("Unknown builtin collection: {0}")]
+    UnknownBuiltin(String),
 }
 
+/// Sample collections compiled into the crate, by name.
+const BUILTIN_COLLECTIONS: [(&str, &str); 1] =
+    [("chess_sample", include_str!("../data/sample_puzzles.json"))];
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PuzzleScore {
     pub puzzle_id: String,
     pub score: f64,
     pub max_possible_score: f64,
+    /// The answers that were scored, one per game state.
+    #[serde(default)]
+    pub answers: Vec<String>,
+    #[serde(default)]
+    pub extraction_methods: Vec<ExtractionMethod>,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Wall-clock time spent solving the puzzle; over several passes, the
+    /// slowest pass's.
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    /// How long each asked state's request took, retries included, in
+    /// order; states left unasked have none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_latencies_ms: Vec<u64>,
+    /// Score before a time-budget penalty was applied, if one was.
+    #[serde(default)]
+    pub unpenalized_score: Option<f64>,
+    /// Judge ratings of each state's reasoning in [0, 1], when a judge was used.
+    #[serde(default)]
+    pub reasoning_scores: Vec<Option<f64>>,
+    /// Centipawns each state's answer lost to the engine's best move, when
+    /// answers were engine-graded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engine_losses: Vec<Option<u32>>,
+    /// The raw response to each state, so answers can be extracted and
+    /// scored again later; see `BenchmarkResult::rescore`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub responses: Vec<String>,
+    /// The confidence stated for each state's answer and whether it was
+    /// right, when confidence was asked for; `None` where none was stated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forecasts: Vec<Option<Forecast>>,
+    /// States left unasked after a wrong answer in a sequential run; see
+    /// `BenchmarkRunnerBuilder::sequential`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub skipped_states: usize,
+    /// How any of the puzzle's prompts were shortened to fit the solver's
+    /// budget; see `compression::fit_prompt`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compression: Vec<Compression>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Puzzle {
     pub id: String,
     pub description: String,
+    /// Overrides the collection's `game_type` for this puzzle in mixed collections.
+    #[serde(default)]
+    pub game_type: Option<String>,
     pub game_states: Vec<String>,
     pub solutions: Vec<String>,
+    /// The solutions encrypted with the collection's solution key, in place
+    /// of `solutions`; see `encryption::encrypt_solutions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_solutions: Option<String>,
+    /// Source rating of the puzzle (e.g. Lichess puzzle rating), if known.
+    #[serde(default)]
+    pub rating: Option<f64>,
+    #[serde(default)]
+    pub rating_deviation: Option<f64>,
+    /// How much this puzzle counts towards the collection's total score.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// How much each state counts towards the puzzle's score, e.g. more for
+    /// the final winning move than for the only-moves leading to it; one
+    /// each when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub state_weights: Vec<f64>,
+    /// Pre-rendered board image per state, for image-based evaluation: a
+    /// path relative to the collection file, or a `data:image/png;base64,...`
+    /// URI embedding the image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    /// Per state, an animated GIF or a strip of frames showing the moves
+    /// that led to it, stored like `images`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequences: Vec<String>,
+    /// Marks drawn on each state's rendered board, such as the opponent's
+    /// last move.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<BoardAnnotations>,
+    /// Which side is at the bottom of each state's image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orientations: Vec<Orientation>,
+    /// How the first state was reached, for representations that show the
+    /// moves leading up to a position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<MoveHistory>,
+    /// The board theme and piece style the images were rendered with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render_style: Option<RenderStyle>,
+    /// Free-form labels such as themes (`fork`, `endgame`), for filtering
+    /// runs and breaking scores down.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Where the puzzle comes from and under what license, where it differs
+    /// from the collection's; see `PuzzleCollection::provenance_of`.
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub provenance: Provenance,
+    /// Arbitrary attributes such as difficulty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+    /// Accept any answer to the last state that meets this condition, e.g.
+    /// every mate of a mate-in-one, not just the stored solution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_condition: Option<GoalCondition>,
+    /// What the puzzle asks for, when it isn't the collection's `goal`. A
+    /// plain string is read as a custom goal.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "goal_or_text")]
+    #[schemars(with = "Option<GoalOrText>")]
+    pub goal: Option<PuzzleGoal>,
+}
+
+/// Where a puzzle or collection comes from and under what terms it may be
+/// shared, carried into exports so published datasets credit their source.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Provenance {
+    /// The database or project, e.g. `lichess`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The puzzle's id at its source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    /// Where the source, or the game the puzzle was taken from, can be seen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// SPDX license identifier, e.g. `CC0-1.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+impl Provenance {
+    pub fn is_empty(&self) -> bool {
+        *self == Provenance::default()
+    }
+
+    /// The field named `key` (`source`, `source_id`, `url` or `license`).
+    pub fn field(&self, key: &str) -> Option<&str> {
+        match key {
+            "source" => self.source.as_deref(),
+            "source_id" => self.source_id.as_deref(),
+            "url" => self.url.as_deref(),
+            "license" => self.license.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// These fields, with any missing taken from `fallback`.
+    pub fn or(&self, fallback: &Provenance) -> Provenance {
+        Provenance {
+            source: self.source.clone().or_else(|| fallback.source.clone()),
+            source_id: self.source_id.clone().or_else(|| fallback.source_id.clone()),
+            url: self.url.clone().or_else(|| fallback.url.clone()),
+            license: self.license.clone().or_else(|| fallback.license.clone()),
+        }
+    }
+}
+
+/// What a puzzle's final move has to achieve, checked by playing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum GoalCondition {
+    /// The move checkmates (chess).
+    Checkmate,
+    /// The move leaves the opponent no checkmate in one (chess).
+    Survive,
+}
+
+/// The kind of task a puzzle sets, which decides how it is asked and how
+/// answers are scored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PuzzleGoal {
+    /// Find the best move: the collection's goal, scored against the
+    /// solutions.
+    BestMove,
+    /// Find the first move of a forced mate in `moves`; any move that mates
+    /// is correct at the last state.
+    MateInN { moves: u32 },
+    /// Say which side stands better: `white`, `black` or `equal`.
+    Evaluate,
+    /// Pick one of `choices`, answering with its letter (`A`, `B`...).
+    MultipleChoice { choices: Vec<String> },
+    /// Find a move after which the opponent has no mate in one; any such
+    /// move is correct at the last state.
+    Survive,
+    /// A goal of the puzzle's own, scored against the solutions.
+    Custom { text: String },
+}
+
+impl PuzzleGoal {
+    /// The condition any correct final move meets, for goals that accept
+    /// more than the stored solution.
+    pub fn condition(&self) -> Option<GoalCondition> {
+        match self {
+            PuzzleGoal::MateInN { .. } => Some(GoalCondition::Checkmate),
+            PuzzleGoal::Survive => Some(GoalCondition::Survive),
+            _ => None,
+        }
+    }
+
+    /// Whether answers are words or letters rather than the collection's
+    /// moves.
+    pub fn has_free_answer(&self) -> bool {
+        matches!(self, PuzzleGoal::Evaluate | PuzzleGoal::MultipleChoice { .. })
+    }
+
+    /// The answers accepted at all, for goals with a fixed set.
+    pub fn valid_answers(&self) -> Option<Vec<String>> {
+        match self {
+            PuzzleGoal::Evaluate => Some(EVALUATIONS.iter().map(|e| e.to_string()).collect()),
+            PuzzleGoal::MultipleChoice { choices } => Some((0..choices.len()).map(choice_letter).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// The answers to `PuzzleGoal::Evaluate`.
+pub const EVALUATIONS: [&str; 3] = ["white", "black", "equal"];
+
+/// The letter answering the `i`th choice of a multiple-choice puzzle.
+pub fn choice_letter(i: usize) -> String {
+    char::from(b'A' + (i % 26) as u8).to_string()
+}
+
+/// How `Puzzle::goal` may be written: a `PuzzleGoal`, or a plain string
+/// for a custom goal.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+#[serde(untagged)]
+enum GoalOrText {
+    Goal(PuzzleGoal),
+    Text(String),
+}
+
+fn goal_or_text<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<PuzzleGoal>, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(text) => Ok(Some(PuzzleGoal::Custom { text })),
+        value => PuzzleGoal::deserialize(value).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Puzzle {
+    /// Whether `answer` solves state `i`: it is the stored solution (in
+    /// any case, for word and letter answers), or, at the last state, a move
+    /// meeting the puzzle's `goal_condition` or the one its goal implies.
+    pub fn is_correct(&self, collection: &PuzzleCollection, i: usize, answer: &str) -> bool {
+        let free = self.goal.as_ref().is_some_and(PuzzleGoal::has_free_answer);
+        if self
+            .solutions
+            .get(i)
+            .is_some_and(|solution| solution == answer || (free && solution.eq_ignore_ascii_case(answer)))
+        {
+            return true;
+        }
+        let Some(condition) = self.goal_condition.or_else(|| self.goal.as_ref()?.condition()) else {
+            return false;
+        };
+        i + 1 == self.game_states.len()
+            && collection.game_type_of(self) == "chess"
+            && collection.answer_format_of(self).grammar.move_notation().is_some_and(|notation| {
+                meets_chess_condition(condition, &self.game_states[i], answer, notation).unwrap_or(false)
+            })
+    }
+
+    /// How much state `index` counts towards the puzzle's score; see
+    /// `state_weights`.
+    pub fn state_weight(&self, index: usize) -> f64 {
+        self.state_weights.get(index).copied().unwrap_or(1.0)
+    }
+
+    /// Whether this is a canary puzzle, with a nonsense position and a
+    /// made-up solution; see `canary`.
+    pub fn is_canary(&self) -> bool {
+        self.metadata.get(crate::canary::CANARY_KEY) == Some(&serde_json::Value::Bool(true))
+    }
+
+    /// The metadata value under `key` as text: strings as they are, other
+    /// values as JSON.
+    pub fn metadata_text(&self, key: &str) -> Option<String> {
+        self.metadata.get(key).map(|value| match value {
+            serde_json::Value::String(text) => text.clone(),
+            value => value.to_string(),
+        })
+    }
+}
+
+/// Moves played from `start` that lead to a puzzle's first state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MoveHistory {
+    pub start: String,
+    /// UCI for chess, whatever the collection's answer notation.
+    pub moves: Vec<String>,
+}
+
+/// Assets a board image was rendered with, named after their files under
+/// `images/chess/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RenderStyle {
+    pub board_theme: String,
+    pub piece_style: String,
+}
+
+/// The side shown at the bottom of a rendered board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    #[default]
+    White,
+    Black,
+}
+
+/// Highlighted squares and arrows on a rendered board, as Lichess shows
+/// them to humans. Squares are named like `e4`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BoardAnnotations {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arrows: Vec<Arrow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Arrow {
+    pub from: String,
+    pub to: String,
 }
 
 impl Puzzle {
+    /// Score `results` against the stored solutions, each correct state
+    /// earning its `state_weight`.
     pub fn validate_solution(&self, results: &[String]) -> PuzzleScore {
         let mut score = 0.0;
         let n = self.game_states.len();
 
         for (i, result) in results.iter().enumerate() {
-            if i < n && result == &self.solutions[i] {
-                score += 1.0;
+            if i < n && self.solutions.get(i) == Some(result) {
+                score += self.state_weight(i);
             }
         }
 
         PuzzleScore {
             puzzle_id: self.id.clone(),
             score,
-            max_possible_score: n as f64,
+            max_possible_score: (0..n).map(|i| self.state_weight(i)).sum(),
+            answers: results.to_vec(),
+            extraction_methods: Vec::new(),
+            weight: self.weight,
+            elapsed_ms: 0,
+            request_latencies_ms: Vec::new(),
+            unpenalized_score: None,
+            reasoning_scores: Vec::new(),
+            engine_losses: Vec::new(),
+            responses: Vec::new(),
+            forecasts: Vec::new(),
+            skipped_states: 0,
+            compression: Vec::new(),
+        }
+    }
+}
+
+/// How a puzzle's answers are turned into a score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scorer {
+    /// One point (or the state's weight) per correctly answered state.
+    #[default]
+    PerState,
+    /// One point (or the state's weight) per state up to the first wrong
+    /// answer, rewarding how far along the line the solver gets.
+    Prefix,
+    /// Full marks only when every state is answered correctly.
+    AllOrNothing,
+}
+
+impl Scorer {
+    /// Score `answers` to `puzzle` from `collection`, crediting each with
+    /// the collection's `answer_scoring`; see `score_with`.
+    pub fn score(&self, puzzle: &Puzzle, collection: &PuzzleCollection, answers: &[String]) -> PuzzleScore {
+        let graded: Vec<StateAnswer> = answers.iter().map(|answer| StateAnswer::new(answer)).collect();
+        self.score_with(puzzle, collection, &graded, collection.answer_scoring.unwrap_or_default().scorer())
+    }
+
+    /// Score `answers` to `puzzle` from `collection`, crediting each state
+    /// with `scorer`. Per-state scoring adds up the credit and gives partial
+    /// credit to FEN transcriptions (see `transcription_score`); the other
+    /// scorers only count fully credited states.
+    pub fn score_with(
+        &self,
+        puzzle: &Puzzle,
+        collection: &PuzzleCollection,
+        answers: &[StateAnswer],
+        scorer: &dyn AnswerScorer,
+    ) -> PuzzleScore {
+        let texts: Vec<String> = answers.iter().map(|answer| answer.answer.to_string()).collect();
+        let mut score = puzzle.validate_solution(&texts);
+        let credit = |i: usize| answers.get(i).map_or(0.0, |answer| scorer.credit(puzzle, collection, i, answer));
+        let states = puzzle.game_states.len();
+        match self {
+            Scorer::PerState if collection.answer_format.grammar == AnswerGrammar::Fen => {
+                score.score = puzzle
+                    .solutions
+                    .iter()
+                    .zip(&texts)
+                    .enumerate()
+                    .map(|(i, (solution, answer))| puzzle.state_weight(i) * transcription_score(solution, answer))
+                    .sum();
+            }
+            Scorer::PerState => {
+                score.score = (0..states).map(|i| puzzle.state_weight(i) * credit(i)).sum();
+            }
+            Scorer::Prefix => {
+                score.score = (0..states)
+                    .take_while(|&i| credit(i) >= 1.0)
+                    .map(|i| puzzle.state_weight(i))
+                    .sum();
+            }
+            Scorer::AllOrNothing => {
+                score.score = if (0..states).all(|i| credit(i) >= 1.0) { score.max_possible_score } else { 0.0 };
+            }
+        }
+        if answers.iter().any(|answer| answer.confidence.is_some()) {
+            score.forecasts = (0..states)
+                .map(|i| {
+                    let confidence = answers.get(i)?.confidence?;
+                    Some(Forecast {
+                        confidence,
+                        correct: credit(i) >= 1.0,
+                    })
+                })
+                .collect();
+        }
+        score
+    }
+
+    /// Extract each state's answer from raw model responses and score them,
+    /// exactly as a benchmark run would (before judging and time penalties).
+    pub fn score_responses(
+        &self,
+        puzzle: &Puzzle,
+        collection: &PuzzleCollection,
+        responses: &[String],
+    ) -> PuzzleScore {
+        let answer_format = collection.answer_format_of(puzzle);
+        let extracted: Vec<_> = responses
+            .iter()
+            .zip(&puzzle.game_states)
+            .map(|(response, state)| extract_answer(response, state, &answer_format))
+            .collect();
+        let answers: Vec<String> = extracted.iter().map(|e| e.answer.clone()).collect();
+        let mut score = self.score(puzzle, collection, &answers);
+        score.extraction_methods = extracted.iter().map(|e| e.method).collect();
+        score
+    }
+}
+
+/// A problem found by `PuzzleCollection::check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionIssue {
+    /// The offending puzzle, or `None` for problems with the collection itself.
+    pub puzzle_id: Option<String>,
+    pub message: String,
+}
+
+/// Whether `solution` is a legal move in the chess position `state`, and,
+/// in notations with several spellings per move, written the way extracted
+/// answers are normalized to.
+#[cfg(feature = "chess")]
+fn check_chess_state(state: &str, solution: &str, notation: MoveNotation) -> Result<(), String> {
+    if notation == MoveNotation::Uci {
+        return crate::game::chess::check_move(state, solution).map_err(|e| e.to_string());
+    }
+    let canonical = notation::to_uci(notation, state, solution)
+        .and_then(|uci| notation::from_uci(notation, state, &uci))
+        .map_err(|e| e.to_string())?;
+    if canonical != solution {
+        return Err(format!("solution {:?} should be written {:?}", solution, canonical));
+    }
+    Ok(())
+}
+
+/// Whether `answer` (in `notation`) is a legal move in the chess position
+/// `state` that meets `condition`.
+#[cfg(feature = "chess")]
+fn meets_chess_condition(
+    condition: GoalCondition,
+    state: &str,
+    answer: &str,
+    notation: MoveNotation,
+) -> Result<bool, String> {
+    let uci = notation::to_uci(notation, state, answer).map_err(|e| e.to_string())?;
+    let met = match condition {
+        GoalCondition::Checkmate => crate::game::chess::gives_checkmate(state, &uci),
+        GoalCondition::Survive => crate::game::chess::allows_mate_in_one(state, &uci).map(|mate| !mate),
+    };
+    met.map_err(|e| e.to_string())
+}
+
+/// Without the `chess` feature moves can't be played, so only the stored
+/// solutions are accepted.
+#[cfg(not(feature = "chess"))]
+fn meets_chess_condition(
+    _condition: GoalCondition,
+    _state: &str,
+    _answer: &str,
+    _notation: MoveNotation,
+) -> Result<bool, String> {
+    Ok(false)
+}
+
+/// Without the `chess` feature positions can't be parsed, so every chess
+/// state passes.
+#[cfg(not(feature = "chess"))]
+fn check_chess_state(_state: &str, _solution: &str, _notation: MoveNotation) -> Result<(), String> {
+    Ok(())
+}
+
+/// Run-time selection of a subset of a collection's puzzles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PuzzleSelection {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Tag expressions a puzzle must match one of, when any are given; see
+    /// `matches_tag_expression`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_tags: Vec<String>,
+    /// Tag expressions a puzzle must match none of.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+    /// Metadata keys and the values (as in `Puzzle::metadata_text`) a
+    /// puzzle must have for them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shuffle_seed: Option<u64>,
+    /// Down-sample the filtered puzzles before shuffling, skipping and
+    /// limiting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<Sample>,
+    pub skip: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Keep only this shard of what the other options select.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+}
+
+/// Part `index` (from 1) of `count` deterministic parts of a selection, so
+/// a run can be split across machines: the selected puzzles are dealt out
+/// in order, the first to shard 1, the second to shard 2 and so on. Written
+/// `index/count`, e.g. `2/4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+impl std::str::FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a shard like 2/4, got {:?}", s);
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+        if index == 0 || index > count {
+            return Err(format!("shard {} must be between 1 and {}", index, count));
+        }
+        Ok(Self { index, count })
+    }
+}
+
+/// A seeded random subset of `size` puzzles. With `stratify_by`, puzzles
+/// are grouped by that metadata key (or by `game_type`, or by their whole
+/// set of `tags`) and every group keeps its share of the subset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Sample {
+    pub size: usize,
+    pub stratify_by: Option<String>,
+    pub seed: u64,
+}
+
+impl Sample {
+    /// The group `puzzle` is sampled from; puzzles without the key form a
+    /// group of their own.
+    fn stratum(&self, collection_game_type: &str, puzzle: &Puzzle) -> String {
+        match self.stratify_by.as_deref() {
+            None => String::new(),
+            Some("game_type") => puzzle.game_type.as_deref().unwrap_or(collection_game_type).to_string(),
+            Some("tags") => {
+                let mut tags = puzzle.tags.clone();
+                tags.sort();
+                tags.join("+")
+            }
+            Some(key) => puzzle.metadata_text(key).unwrap_or_default(),
+        }
+    }
+
+    /// Pick the sample from `puzzles`, keeping their order. Each group gets
+    /// its proportional share rounded down, and the puzzles left over go to
+    /// the groups with the largest remainders.
+    fn draw(&self, collection_game_type: &str, puzzles: Vec<Puzzle>) -> Vec<Puzzle> {
+        if self.size >= puzzles.len() {
+            return puzzles;
+        }
+        let mut strata: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, puzzle) in puzzles.iter().enumerate() {
+            strata.entry(self.stratum(collection_game_type, puzzle)).or_default().push(i);
+        }
+
+        let total = puzzles.len();
+        let mut quotas: Vec<(usize, usize)> = strata
+            .values()
+            .map(|members| (members.len() * self.size / total, members.len() * self.size % total))
+            .collect();
+        let assigned: usize = quotas.iter().map(|(quota, _)| quota).sum();
+        let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+        by_remainder.sort_by(|&a, &b| quotas[b].1.cmp(&quotas[a].1));
+        for &stratum in by_remainder.iter().take(self.size - assigned) {
+            quotas[stratum].0 += 1;
+        }
+
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        let mut chosen = vec![false; total];
+        for (mut members, (quota, _)) in strata.into_values().zip(quotas) {
+            members.shuffle(&mut rng);
+            for i in members.into_iter().take(quota) {
+                chosen[i] = true;
+            }
         }
+        puzzles
+            .into_iter()
+            .zip(chosen)
+            .filter_map(|(puzzle, chosen)| chosen.then_some(puzzle))
+            .collect()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl PuzzleSelection {
+    /// Whether every puzzle is run, in the collection's order.
+    pub fn is_everything(&self) -> bool {
+        self.ids.is_empty()
+            && self.game_type.is_none()
+            && self.theme.is_none()
+            && self.include_tags.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.metadata.is_empty()
+            && self.shuffle_seed.is_none()
+            && self.sample.is_none()
+            && self.skip == 0
+            && self.limit.is_none()
+            && self.shard.is_none()
+    }
+
+    /// Whether `puzzle` passes the id, game type, theme, tag and metadata
+    /// filters. A theme matches a tag, or is mentioned in the id or
+    /// description.
+    fn matches(&self, collection_game_type: &str, puzzle: &Puzzle) -> bool {
+        (self.ids.is_empty() || self.ids.contains(&puzzle.id))
+            && self
+                .game_type
+                .as_ref()
+                .is_none_or(|t| puzzle.game_type.as_deref().unwrap_or(collection_game_type) == t)
+            && self.theme.as_ref().is_none_or(|t| {
+                puzzle.tags.contains(t) || puzzle.id.contains(t.as_str()) || puzzle.description.contains(t.as_str())
+            })
+            && (self.include_tags.is_empty()
+                || self.include_tags.iter().any(|expression| matches_tag_expression(expression, &puzzle.tags)))
+            && !self.exclude_tags.iter().any(|expression| matches_tag_expression(expression, &puzzle.tags))
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| puzzle.metadata_text(key).as_ref() == Some(value))
+    }
+}
+
+/// Whether `tags` carry every tag of `expression`, which joins tags with
+/// `+` (e.g. `endgame+quietMove`).
+fn matches_tag_expression(expression: &str, tags: &[String]) -> bool {
+    expression.split('+').map(str::trim).all(|tag| tags.iter().any(|t| t == tag))
+}
+
+/// A collection's goal, rules and prompt template in another language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Translation {
+    pub goal: String,
+    /// Left as it is when missing, e.g. for collections without rules text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_rule: Option<String>,
+    /// When missing, prompts in this language use the built-in guidance
+    /// rather than a template written for another language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PuzzleCollection {
     pub name: String,
     pub description: String,
     pub game_type: String,
     pub goal: String,
     pub game_rule: String,
+    #[serde(default)]
+    pub answer_format: AnswerFormat,
+    /// The language `goal` and `game_rule` are written in, and prompts are
+    /// built in.
+    #[serde(default, skip_serializing_if = "PromptLanguage::is_english")]
+    pub language: PromptLanguage,
+    /// `goal` and `game_rule` in other languages; see `with_language`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<PromptLanguage, Translation>,
+    /// Instructions replacing the built-in, chess-flavored guidance, e.g.
+    /// for other games. `{game_type}`, `{goal}`, `{rules}` (`game_rule`),
+    /// `{notation}` and `{position}` are filled in; see `build_text_prompt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+    /// How answers are credited unless a run chooses otherwise; goal-based
+    /// when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer_scoring: Option<AnswerScoring>,
+    /// Checksum of the collection this public copy was made from by
+    /// withholding every solution; see `holdout::withhold_solutions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld_from: Option<String>,
+    /// Fingerprint of the key the puzzles' solutions are encrypted with;
+    /// see `encryption::SolutionKey::id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution_key_id: Option<String>,
+    /// Where the puzzles come from and under what license, unless a puzzle
+    /// says otherwise.
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub provenance: Provenance,
     pub puzzles: Vec<Puzzle>,
+    /// Directory of the file the collection was loaded from, which relative
+    /// image paths are resolved against.
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+    /// Checksum of the collection as loaded, before its solutions were
+    /// decrypted; see `source_checksum`.
+    #[serde(skip)]
+    pub encrypted_checksum: Option<String>,
+    /// The selection `PuzzleStream::collect_selected` already applied, which
+    /// `select` won't apply again.
+    #[serde(skip)]
+    pub selected: Option<PuzzleSelection>,
 }
 
 impl PuzzleCollection {
+    /// Parse a collection, rejecting an answer pattern that isn't a regex
+    /// with a capture group here rather than at every extraction.
+    pub fn from_json(json: &str) -> Result<Self, PuzzleError> {
+        let collection: Self = serde_json::from_str(json).map_err(|e| PuzzleError::InvalidDefinition(e.to_string()))?;
+        collection
+            .answer_format
+            .validate()
+            .map_err(|e| PuzzleError::InvalidDefinition(e.to_string()))?;
+        Ok(collection)
+    }
+
+    /// A sample collection embedded in the crate, usable without any data
+    /// files; see `builtin_names`.
+    pub fn builtin(name: &str) -> Result<Self, PuzzleError> {
+        let (_, json) = BUILTIN_COLLECTIONS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .ok_or_else(|| {
+                let available: Vec<&str> = Self::builtin_names().collect();
+                PuzzleError::UnknownBuiltin(format!("{} (available: {})", name, available.join(", ")))
+            })?;
+        Self::from_json(json)
+    }
+
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN_COLLECTIONS.iter().map(|(name, _)| *name)
+    }
+
+    /// Load a collection from a JSON file, or from a JSONL file (see
+    /// `stream_from_file`) if the path ends in `.jsonl`. `builtin:<name>`
+    /// loads an embedded collection instead (see `builtin`).
     pub fn load_from_file(file_path: &str) -> Result<Self, PuzzleError> {
+        if let Some(name) = file_path.strip_prefix("builtin:") {
+            return Self::builtin(name);
+        }
+        if file_path.ends_with(".jsonl") {
+            return Self::stream_from_file(file_path)?.collect_selected(&PuzzleSelection::default());
+        }
         let content = std::fs::read_to_string(file_path)
             .map_err(|e| PuzzleError::FileError(e.to_string()))?;
 
-        serde_json::from_str(&content).map_err(|e| PuzzleError::InvalidDefinition(e.to_string()))
+        let mut collection = Self::from_json(&content)?;
+        collection.base_dir = Path::new(file_path).parent().map(Path::to_path_buf);
+        Ok(collection)
+    }
+
+    /// Open a JSONL collection: the first line holds the collection's fields
+    /// without `puzzles`, and every following line one puzzle. Puzzles are
+    /// parsed lazily as the returned stream is iterated.
+    pub fn stream_from_file(file_path: &str) -> Result<PuzzleStream, PuzzleError> {
+        let file = File::open(file_path).map_err(|e| PuzzleError::FileError(e.to_string()))?;
+        let mut lines = BufReader::new(file).lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| PuzzleError::InvalidDefinition("empty collection file".to_string()))?
+            .map_err(|e| PuzzleError::FileError(e.to_string()))?;
+
+        let mut header: serde_json::Value = serde_json::from_str(&header)
+            .map_err(|e| PuzzleError::InvalidDefinition(format!("line 1: {}", e)))?;
+        if let Some(fields) = header.as_object_mut() {
+            fields.entry("puzzles").or_insert(serde_json::json!([]));
+        }
+        let mut collection: PuzzleCollection = serde_json::from_value(header)
+            .map_err(|e| PuzzleError::InvalidDefinition(format!("line 1: {}", e)))?;
+        collection
+            .answer_format
+            .validate()
+            .map_err(|e| PuzzleError::InvalidDefinition(format!("line 1: {}", e)))?;
+        collection.base_dir = Path::new(file_path).parent().map(Path::to_path_buf);
+
+        Ok(PuzzleStream {
+            collection,
+            lines,
+            line: 1,
+        })
     }
 
     pub fn save_to_file(&self, file_path: &str) -> Result<(), PuzzleError> {
@@ -70,10 +896,468 @@ impl PuzzleCollection {
     }
 
     pub fn filter_by_game_type(&self, game_type: &str) -> Vec<&Puzzle> {
-        if self.game_type == game_type {
-            self.puzzles.iter().collect()
-        } else {
-            Vec::new()
+        self.puzzles
+            .iter()
+            .filter(|p| self.game_type_of(p) == game_type)
+            .collect()
+    }
+
+    /// The board image of state `index` of `puzzle` as a data URI, reading
+    /// image files relative to `base_dir`; `None` if the puzzle has none.
+    pub fn state_image(&self, puzzle: &Puzzle, index: usize) -> Result<Option<String>, PuzzleError> {
+        puzzle.images.get(index).map(|image| self.load_image(image)).transpose()
+    }
+
+    /// Like `state_image`, for the sequence leading up to state `index`;
+    /// see `Puzzle::sequences`.
+    pub fn state_sequence(&self, puzzle: &Puzzle, index: usize) -> Result<Option<String>, PuzzleError> {
+        puzzle.sequences.get(index).map(|image| self.load_image(image)).transpose()
+    }
+
+    fn load_image(&self, image: &str) -> Result<String, PuzzleError> {
+        if image.starts_with("data:") {
+            return Ok(image.to_string());
+        }
+
+        let path = match &self.base_dir {
+            Some(base_dir) => base_dir.join(image),
+            None => PathBuf::from(image),
+        };
+        let media_type = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("webp") => "image/webp",
+            Some("gif") => "image/gif",
+            _ => {
+                return Err(PuzzleError::InvalidDefinition(format!(
+                    "unsupported image type: {}",
+                    path.display()
+                )));
+            }
+        };
+        let bytes = std::fs::read(&path)
+            .map_err(|e| PuzzleError::FileError(format!("{}: {}", path.display(), e)))?;
+        Ok(format!("data:{};base64,{}", media_type, BASE64.encode(bytes)))
+    }
+
+    /// SHA-256 (hex) of the collection's canonical JSON form, identifying
+    /// the exact puzzles and solutions a result was produced from.
+    pub fn checksum(&self) -> String {
+        let json = serde_json::to_vec(self).expect("collection serializes to JSON");
+        Sha256::digest(&json)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Checksum of the file the collection was loaded from: the encrypted
+    /// one if its solutions were decrypted since, which is what results
+    /// record and are verified against.
+    pub fn source_checksum(&self) -> String {
+        self.encrypted_checksum.clone().unwrap_or_else(|| self.checksum())
+    }
+
+    /// Check the collection for mistakes a benchmark run would only trip over
+    /// later: missing goal, a prompt template that leaves out the position,
+    /// duplicate ids, states without solutions (or any solution, once
+    /// withheld; encrypted solutions aren't checked), final solutions that miss
+    /// the puzzle's goal condition, solutions that aren't among a goal's
+    /// fixed answers, state weights that are negative or don't match the
+    /// states, and solutions that don't fit the answer grammar. Chess states must parse
+    /// as FEN and their solutions be legal (with the `chess` feature),
+    /// except in canaries.
+    pub fn check(&self) -> Vec<CollectionIssue> {
+        let mut issues = Vec::new();
+        let mut collection_issue = |message: &str| {
+            issues.push(CollectionIssue {
+                puzzle_id: None,
+                message: message.to_string(),
+            })
+        };
+        if self.goal.trim().is_empty() {
+            collection_issue("goal is empty");
+        }
+        if self.puzzles.is_empty() {
+            collection_issue("collection has no puzzles");
+        }
+        if let Some(template) = &self.prompt_template
+            && !template.contains("{position}")
+        {
+            collection_issue("prompt_template has no {position}, so prompts won't show the puzzle");
+        }
+
+        let collection_withheld = self.withheld_from.is_some();
+        let mut seen = HashSet::new();
+        for puzzle in &self.puzzles {
+            let mut issue = |message: String| {
+                issues.push(CollectionIssue {
+                    puzzle_id: Some(puzzle.id.clone()),
+                    message,
+                })
+            };
+            if !seen.insert(puzzle.id.as_str()) {
+                issue("duplicate puzzle id".to_string());
+            }
+            if puzzle.game_states.is_empty() {
+                issue("puzzle has no game states".to_string());
+            }
+            if collection_withheld && !puzzle.solutions.is_empty() {
+                issue("solution in a collection with withheld solutions".to_string());
+            } else if puzzle.encrypted_solutions.is_some() {
+                if !puzzle.solutions.is_empty() {
+                    issue("both plain and encrypted solutions".to_string());
+                }
+            } else if !collection_withheld && puzzle.game_states.len() != puzzle.solutions.len() {
+                issue(format!(
+                    "{} game states but {} solutions",
+                    puzzle.game_states.len(),
+                    puzzle.solutions.len()
+                ));
+            }
+            // Optional per-state lists must cover every state when given.
+            for (what, len) in [
+                ("state_weights", puzzle.state_weights.len()),
+                ("images", puzzle.images.len()),
+                ("sequences", puzzle.sequences.len()),
+                ("annotations", puzzle.annotations.len()),
+                ("orientations", puzzle.orientations.len()),
+            ] {
+                if len != 0 && len != puzzle.game_states.len() {
+                    issue(format!("{} game states but {} {}", puzzle.game_states.len(), len, what));
+                }
+            }
+
+            if puzzle.state_weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) {
+                issue("state weights must be finite and not negative".to_string());
+            } else if !puzzle.state_weights.is_empty() && puzzle.state_weights.iter().all(|weight| *weight == 0.0) {
+                issue("every state weight is zero, so the puzzle can't be scored".to_string());
+            }
+
+            if let Some(PuzzleGoal::MultipleChoice { choices }) = &puzzle.goal
+                && choices.is_empty()
+            {
+                issue("multiple-choice goal has no choices".to_string());
+            }
+            if let Some(valid) = puzzle.goal.as_ref().and_then(PuzzleGoal::valid_answers) {
+                for (i, solution) in puzzle.solutions.iter().enumerate() {
+                    if !valid.iter().any(|answer| answer.eq_ignore_ascii_case(solution)) {
+                        issue(format!("state {}: solution {:?} is not one of {}", i, solution, valid.join(", ")));
+                    }
+                }
+                continue;
+            }
+            let condition = puzzle.goal_condition.or_else(|| puzzle.goal.as_ref()?.condition());
+            for (i, (state, solution)) in puzzle.game_states.iter().zip(&puzzle.solutions).enumerate() {
+                if !self.answer_format.grammar.accepts(solution) {
+                    issue(format!(
+                        "state {}: solution {:?} is not in {}",
+                        i,
+                        solution,
+                        self.answer_format.grammar.description()
+                    ));
+                    continue;
+                }
+                // Transcriptions are positions, not moves, and canaries'
+                // positions are nonsense on purpose.
+                if self.game_type_of(puzzle) == "chess"
+                    && !puzzle.is_canary()
+                    && let Some(notation) = self.answer_format.grammar.move_notation()
+                {
+                    if let Err(message) = check_chess_state(state, solution, notation) {
+                        issue(format!("state {}: {}", i, message));
+                    } else if i + 1 == puzzle.game_states.len()
+                        && let Some(condition) = condition
+                        && meets_chess_condition(condition, state, solution, notation) == Ok(false)
+                    {
+                        issue(format!(
+                            "state {}: solution {:?} doesn't meet the goal condition {:?}",
+                            i, solution, condition
+                        ));
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Rewrite the chess puzzles' solutions in `notation` and expect answers
+    /// in it from now on. The collection's answers must already be chess
+    /// moves; converting needs the `chess` feature unless both sides are UCI.
+    pub fn with_notation(&mut self, notation: MoveNotation) -> Result<(), PuzzleError> {
+        let current = self.answer_format.grammar.move_notation().ok_or_else(|| {
+            PuzzleError::InvalidDefinition(format!(
+                "answers in {} can't be converted to {} notation",
+                self.answer_format.grammar.description(),
+                notation
+            ))
+        })?;
+        let invalid = |id: &str, e: NotationError| PuzzleError::InvalidDefinition(format!("{}: {}", id, e));
+        for puzzle in &mut self.puzzles {
+            if puzzle.game_type.as_deref().unwrap_or(&self.game_type) != "chess"
+                || puzzle.goal.as_ref().is_some_and(PuzzleGoal::has_free_answer)
+            {
+                continue;
+            }
+            for (state, solution) in puzzle.game_states.iter().zip(&mut puzzle.solutions) {
+                let uci = notation::to_uci(current, state, solution).map_err(|e| invalid(&puzzle.id, e))?;
+                *solution = notation::from_uci(notation, state, &uci).map_err(|e| invalid(&puzzle.id, e))?;
+            }
+        }
+        self.answer_format.grammar = notation.into();
+        Ok(())
+    }
+
+    /// Build prompts in `language`, with the goal, rules and prompt template
+    /// from `translations`. The current texts are kept as the translation into
+    /// the current language, so switching back restores them. Returns false
+    /// if there is no translation, leaving the goal and rules as they are.
+    pub fn with_language(&mut self, language: PromptLanguage) -> bool {
+        if language == self.language {
+            return true;
+        }
+        let Some(translation) = self.translations.remove(&language) else {
+            self.language = language;
+            return false;
+        };
+        let current = Translation {
+            goal: std::mem::replace(&mut self.goal, translation.goal),
+            game_rule: Some(match translation.game_rule {
+                Some(game_rule) => std::mem::replace(&mut self.game_rule, game_rule),
+                None => self.game_rule.clone(),
+            }),
+            prompt_template: std::mem::replace(&mut self.prompt_template, translation.prompt_template),
+        };
+        self.translations.entry(self.language).or_insert(current);
+        self.language = language;
+        true
+    }
+
+    /// How answers to `puzzle` are written: the collection's format, or any
+    /// word under the same pattern for goals answered in words or letters.
+    pub fn answer_format_of(&self, puzzle: &Puzzle) -> AnswerFormat {
+        let mut format = self.answer_format.clone();
+        if puzzle.goal.as_ref().is_some_and(PuzzleGoal::has_free_answer) {
+            format.grammar = AnswerGrammar::Free;
+        }
+        format
+    }
+
+    /// The puzzle's own game type, falling back to the collection's.
+    pub fn game_type_of<'a>(&'a self, puzzle: &'a Puzzle) -> &'a str {
+        puzzle.game_type.as_deref().unwrap_or(&self.game_type)
+    }
+
+    /// The puzzle's provenance, falling back field by field to the
+    /// collection's.
+    pub fn provenance_of(&self, puzzle: &Puzzle) -> Provenance {
+        puzzle.provenance.or(&self.provenance)
+    }
+
+    /// One collection with the puzzles of all `collections`, for running
+    /// several as one. The first collection's rules and prompt settings
+    /// apply; puzzles from the others keep their own game type and, where it
+    /// differs, their collection's goal. Every puzzle records the collection
+    /// it came from as its `collection` metadata and takes on that
+    /// collection's provenance, and image paths are resolved against their
+    /// own collection's directory. Collections whose
+    /// answers are written or scored differently, that use different prompt
+    /// templates or solution keys, or that share puzzle ids can't be
+    /// combined.
+    pub fn combine(collections: Vec<PuzzleCollection>) -> Result<Self, PuzzleError> {
+        let mut collections = collections.into_iter();
+        let Some(mut combined) = collections.next() else {
+            return Err(PuzzleError::InvalidDefinition("no collections to combine".to_string()));
+        };
+        let others: Vec<PuzzleCollection> = collections.collect();
+        if others.is_empty() {
+            return Ok(combined);
+        }
+
+        let answer_format = serde_json::to_value(&combined.answer_format).ok();
+        for other in &others {
+            let differs = if serde_json::to_value(&other.answer_format).ok() != answer_format {
+                Some("answer format")
+            } else if other.answer_scoring != combined.answer_scoring {
+                Some("answer scoring")
+            } else if other.prompt_template != combined.prompt_template {
+                Some("prompt template")
+            } else if other.solution_key_id != combined.solution_key_id || other.withheld_from != combined.withheld_from {
+                Some("solution key")
+            } else {
+                None
+            };
+            if let Some(differs) = differs {
+                return Err(PuzzleError::InvalidDefinition(format!(
+                    "collections {:?} and {:?} differ in {} and can't be combined",
+                    combined.name, other.name, differs
+                )));
+            }
+        }
+
+        let first = std::mem::take(&mut combined.puzzles);
+        let base_dir = combined.base_dir.take();
+        combined.selected = None;
+        let mut names = vec![combined.name.clone()];
+        let mut ids = HashSet::new();
+        let goal = combined.goal.clone();
+        let game_type = combined.game_type.clone();
+        let provenance = std::mem::take(&mut combined.provenance);
+        let sources = std::iter::once((combined.name.clone(), combined.game_type.clone(), combined.goal.clone(), provenance, base_dir, first))
+            .chain(others.into_iter().map(|other| {
+                names.push(other.name.clone());
+                (other.name, other.game_type, other.goal, other.provenance, other.base_dir, other.puzzles)
+            }))
+            .collect::<Vec<_>>();
+        for (name, source_game_type, source_goal, source_provenance, source_dir, puzzles) in sources {
+            let resolve = |path: &mut String| {
+                if let Some(dir) = &source_dir
+                    && !path.starts_with("data:")
+                {
+                    *path = dir.join(&*path).to_string_lossy().into_owned();
+                }
+            };
+            for mut puzzle in puzzles {
+                if !ids.insert(puzzle.id.clone()) {
+                    return Err(PuzzleError::InvalidDefinition(format!(
+                        "puzzle id {} appears in more than one collection",
+                        puzzle.id
+                    )));
+                }
+                if puzzle.game_type.is_none() && source_game_type != game_type {
+                    puzzle.game_type = Some(source_game_type.clone());
+                }
+                if matches!(puzzle.goal, None | Some(PuzzleGoal::BestMove)) && source_goal != goal {
+                    puzzle.goal = Some(PuzzleGoal::Custom { text: source_goal.clone() });
+                }
+                puzzle.provenance = puzzle.provenance.or(&source_provenance);
+                puzzle.images.iter_mut().for_each(resolve);
+                puzzle.sequences.iter_mut().for_each(resolve);
+                puzzle
+                    .metadata
+                    .entry("collection".to_string())
+                    .or_insert_with(|| serde_json::Value::String(name.clone()));
+                combined.puzzles.push(puzzle);
+            }
+        }
+        combined.name = names.join(" + ");
+        Ok(combined)
+    }
+
+    /// Keep only the puzzles matching `selection`. Id, game type, theme, tag
+    /// and metadata filters are applied first, then the optional sample and
+    /// seeded shuffle, then skip and limit, and finally the shard. Does
+    /// nothing if `collect_selected` already applied `selection`.
+    pub fn select(&mut self, selection: &PuzzleSelection) {
+        if self.selected.as_ref() == Some(selection) {
+            return;
+        }
+        self.puzzles.retain(|p| selection.matches(&self.game_type, p));
+
+        if let Some(sample) = &selection.sample {
+            self.puzzles = sample.draw(&self.game_type, std::mem::take(&mut self.puzzles));
+        }
+        if let Some(seed) = selection.shuffle_seed {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut keyed: Vec<(u64, Puzzle)> =
+                std::mem::take(&mut self.puzzles).into_iter().map(|p| (rng.random(), p)).collect();
+            keyed.sort_by_key(|(key, _)| *key);
+            self.puzzles = keyed.into_iter().map(|(_, p)| p).collect();
+        }
+
+        self.puzzles.drain(..selection.skip.min(self.puzzles.len()));
+        if let Some(limit) = selection.limit {
+            self.puzzles.truncate(limit);
+        }
+        if let Some(shard) = selection.shard {
+            let mut position = 0;
+            self.puzzles.retain(|_| {
+                position += 1;
+                (position - 1) % shard.count == shard.index - 1
+            });
+        }
+    }
+}
+
+/// Puzzles read one at a time from a JSONL collection; see
+/// `PuzzleCollection::stream_from_file`.
+pub struct PuzzleStream {
+    /// The collection's fields, with no puzzles.
+    pub collection: PuzzleCollection,
+    lines: Lines<BufReader<File>>,
+    line: usize,
+}
+
+impl PuzzleStream {
+    /// Read the puzzles `selection` picks into the collection, as `select`
+    /// would, holding as few as possible in memory. Without a shuffle or
+    /// sample, puzzles outside the shard are dropped as they're read and
+    /// reading stops once `limit` is reached; a shuffle with a limit keeps
+    /// only the `skip` + `limit` puzzles with the lowest shuffle keys. A
+    /// sample needs every matching puzzle.
+    pub fn collect_selected(mut self, selection: &PuzzleSelection) -> Result<PuzzleCollection, PuzzleError> {
+        let game_type = self.collection.game_type.clone();
+        let matching = std::iter::from_fn(|| self.next())
+            .filter(|puzzle| puzzle.as_ref().map_or(true, |puzzle| selection.matches(&game_type, puzzle)));
+        let in_shard = |position: usize| selection.shard.is_none_or(|shard| position % shard.count == shard.index - 1);
+        let puzzles = match (&selection.sample, selection.shuffle_seed, selection.limit) {
+            (None, None, limit) => {
+                let mut puzzles = Vec::new();
+                for (position, puzzle) in matching.skip(selection.skip).take(limit.unwrap_or(usize::MAX)).enumerate() {
+                    let puzzle = puzzle?;
+                    if in_shard(position) {
+                        puzzles.push(puzzle);
+                    }
+                }
+                puzzles
+            }
+            (None, Some(seed), Some(limit)) => {
+                let wanted = selection.skip + limit;
+                let mut rng = SmallRng::seed_from_u64(seed);
+                let mut keyed: Vec<(u64, Puzzle)> = Vec::new();
+                for puzzle in matching {
+                    keyed.push((rng.random(), puzzle?));
+                    if keyed.len() > 2 * wanted {
+                        keyed.sort_by_key(|(key, _)| *key);
+                        keyed.truncate(wanted);
+                    }
+                }
+                keyed.sort_by_key(|(key, _)| *key);
+                keyed
+                    .into_iter()
+                    .skip(selection.skip)
+                    .take(limit)
+                    .enumerate()
+                    .filter_map(|(position, (_, puzzle))| in_shard(position).then_some(puzzle))
+                    .collect()
+            }
+            _ => {
+                self.collection.puzzles = matching.collect::<Result<_, _>>()?;
+                self.collection.select(selection);
+                std::mem::take(&mut self.collection.puzzles)
+            }
+        };
+        self.collection.puzzles = puzzles;
+        self.collection.selected = Some(selection.clone());
+        Ok(self.collection)
+    }
+}
+
+impl Iterator for PuzzleStream {
+    type Item = Result<Puzzle, PuzzleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(PuzzleError::FileError(e.to_string()))),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(&line).map_err(|e| {
+                PuzzleError::InvalidDefinition(format!("line {}: {}", self.line, e))
+            }));
         }
     }
 }
@@ -0,0 +1,467 @@
+//! Synthetic puzzle collections for tests. `CollectionFactory` makes random
+//! collections that pass `PuzzleCollection::check`, of any size and mix of
+//! games and goals, the same for the same seed, so the scoring and runner
+//! pipeline can be exercised over many collections rather than a few
+//! hand-written ones. `responses` makes model responses to them that are
+//! right at a chosen rate.
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use crate::extraction::{AnswerFormat, AnswerGrammar};
+#[cfg(feature = "chess")]
+use crate::game::chess;
+use crate::prompt::PromptLanguage;
+use crate::puzzle::{EVALUATIONS, Provenance, Puzzle, PuzzleCollection, PuzzleGoal, choice_letter};
+
+/// Side of the Go boards the factory makes.
+const GO_SIZE: usize = 9;
+/// Go column letters, which skip i.
+const GO_COLUMNS: &str = "abcdefghj";
+/// Choices offered by multiple-choice puzzles.
+const CHOICES: usize = 4;
+/// Response given for a wrong answer: one no puzzle has, so it can't be
+/// credited by accident.
+const WRONG_RESPONSE: &str = "**Answer: none**";
+
+/// Positions with a mate in one for White, and the mating move.
+#[cfg(feature = "chess")]
+const MATES_IN_ONE: [(&str, &str); 4] = [
+    ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"),
+    ("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", "h5f7"),
+    ("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1", "f1f8"),
+    ("k7/8/1K6/8/8/8/8/7R w - - 0 1", "h1h8"),
+];
+
+/// A game the factory makes positions of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticGame {
+    /// Positions a few random legal moves from the start, answered with a
+    /// legal move in UCI.
+    #[cfg(feature = "chess")]
+    Chess,
+    /// 9x9 boards with random stones, answered with an empty point.
+    Go,
+}
+
+impl SyntheticGame {
+    pub fn game_type(self) -> &'static str {
+        match self {
+            #[cfg(feature = "chess")]
+            SyntheticGame::Chess => "chess",
+            SyntheticGame::Go => "go",
+        }
+    }
+
+    fn grammar(self) -> AnswerGrammar {
+        match self {
+            #[cfg(feature = "chess")]
+            SyntheticGame::Chess => AnswerGrammar::Uci,
+            SyntheticGame::Go => AnswerGrammar::GoCoordinate,
+        }
+    }
+}
+
+/// What the factory's puzzles ask for; see `PuzzleGoal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticGoal {
+    /// The collection's goal, with a random legal move as the solution.
+    BestMove,
+    /// A mate in one from a small set of known positions (chess only; other
+    /// games get a best-move puzzle instead).
+    MateInOne,
+    /// A random verdict of `EVALUATIONS`.
+    Evaluate,
+    /// A choice between random legal moves.
+    MultipleChoice,
+}
+
+/// Random puzzle collections for tests. Each puzzle is of a game and goal
+/// drawn from those given; best-move and evaluation puzzles have a number
+/// of states drawn from `states`, each following from the last by the
+/// solution and a random reply, while mate-in-one and multiple-choice
+/// puzzles have one. A collection of one game answers in its notation, a
+/// mixed one in free text.
+#[derive(Debug, Clone)]
+pub struct CollectionFactory {
+    seed: u64,
+    puzzles: usize,
+    states: RangeInclusive<usize>,
+    games: Vec<SyntheticGame>,
+    goals: Vec<SyntheticGoal>,
+}
+
+impl Default for CollectionFactory {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            puzzles: 10,
+            states: 1..=3,
+            #[cfg(feature = "chess")]
+            games: vec![SyntheticGame::Chess],
+            #[cfg(not(feature = "chess"))]
+            games: vec![SyntheticGame::Go],
+            goals: vec![SyntheticGoal::BestMove],
+        }
+    }
+}
+
+impl CollectionFactory {
+    /// Ten best-move chess puzzles (Go without the `chess` feature) of one
+    /// to three states, drawn with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { seed, ..Self::default() }
+    }
+
+    pub fn with_puzzles(mut self, puzzles: usize) -> Self {
+        self.puzzles = puzzles;
+        self
+    }
+
+    /// States per multi-state puzzle; an empty range is read as one.
+    pub fn with_states(mut self, states: RangeInclusive<usize>) -> Self {
+        self.states = states;
+        self
+    }
+
+    /// The games to draw from; all the defaults' when empty.
+    pub fn with_games(mut self, games: Vec<SyntheticGame>) -> Self {
+        if !games.is_empty() {
+            self.games = games;
+        }
+        self
+    }
+
+    /// The goals to draw from; best moves when empty.
+    pub fn with_goals(mut self, goals: Vec<SyntheticGoal>) -> Self {
+        if !goals.is_empty() {
+            self.goals = goals;
+        }
+        self
+    }
+
+    pub fn build(&self) -> PuzzleCollection {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        let first = self.games[0];
+        let mixed = self.games.iter().any(|game| *game != first);
+        let grammar = if mixed { AnswerGrammar::Free } else { first.grammar() };
+        let puzzles = (0..self.puzzles)
+            .map(|i| {
+                let game = *self.games.choose(&mut rng).unwrap();
+                let goal = *self.goals.choose(&mut rng).unwrap();
+                let mut puzzle = self.puzzle(game, goal, &mut rng);
+                puzzle.id = format!("synthetic_{:04}", i);
+                if mixed {
+                    puzzle.game_type = Some(game.game_type().to_string());
+                }
+                puzzle
+            })
+            .collect();
+        PuzzleCollection {
+            name: format!("Synthetic puzzles (seed {})", self.seed),
+            description: "Random puzzles generated for testing".to_string(),
+            game_type: first.game_type().to_string(),
+            goal: "Find the best move for the side to move.".to_string(),
+            game_rule: String::new(),
+            answer_format: AnswerFormat { pattern: None, grammar },
+            language: PromptLanguage::English,
+            translations: BTreeMap::new(),
+            prompt_template: None,
+            answer_scoring: None,
+            withheld_from: None,
+            solution_key_id: None,
+            provenance: Provenance {
+                source: Some("synthetic".to_string()),
+                source_id: None,
+                url: None,
+                license: Some("CC0-1.0".to_string()),
+            },
+            puzzles,
+            base_dir: None,
+            encrypted_checksum: None,
+            selected: None,
+        }
+    }
+
+    fn puzzle(&self, game: SyntheticGame, goal: SyntheticGoal, rng: &mut SmallRng) -> Puzzle {
+        #[cfg(feature = "chess")]
+        if goal == SyntheticGoal::MateInOne && game == SyntheticGame::Chess {
+            let (state, solution) = *MATES_IN_ONE.choose(rng).unwrap();
+            return synthetic_puzzle(
+                game,
+                vec![state.to_string()],
+                vec![solution.to_string()],
+                Some(PuzzleGoal::MateInN { moves: 1 }),
+                rng,
+            );
+        }
+
+        let states = match goal {
+            SyntheticGoal::BestMove | SyntheticGoal::Evaluate | SyntheticGoal::MateInOne => {
+                let (min, max) = (*self.states.start(), *self.states.end());
+                if self.states.is_empty() { 1 } else { rng.random_range(min.max(1)..=max.max(1)) }
+            }
+            SyntheticGoal::MultipleChoice => 1,
+        };
+        let (game_states, moves) = line(game, states, rng);
+        match goal {
+            SyntheticGoal::Evaluate => {
+                let verdicts = (0..game_states.len())
+                    .map(|_| EVALUATIONS.choose(rng).unwrap().to_string())
+                    .collect();
+                synthetic_puzzle(game, game_states, verdicts, Some(PuzzleGoal::Evaluate), rng)
+            }
+            SyntheticGoal::MultipleChoice => {
+                let mut choices = candidate_moves(game, &game_states[0]);
+                choices.retain(|m| *m != moves[0]);
+                choices.truncate(CHOICES - 1);
+                let answer = rng.random_range(0..=choices.len());
+                choices.insert(answer, moves[0].clone());
+                let goal = PuzzleGoal::MultipleChoice { choices };
+                synthetic_puzzle(game, game_states, vec![choice_letter(answer)], Some(goal), rng)
+            }
+            SyntheticGoal::BestMove | SyntheticGoal::MateInOne => synthetic_puzzle(game, game_states, moves, None, rng),
+        }
+    }
+}
+
+/// A random collection of `puzzles` best-move puzzles; see
+/// `CollectionFactory`.
+pub fn random_collection(puzzles: usize, seed: u64) -> PuzzleCollection {
+    CollectionFactory::new(seed).with_puzzles(puzzles).build()
+}
+
+/// Responses to each puzzle of `collection`, one per state, each giving the
+/// solution as a bold answer with probability `accuracy` and a wrong one
+/// otherwise.
+pub fn responses(collection: &PuzzleCollection, accuracy: f64, seed: u64) -> Vec<Vec<String>> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    collection
+        .puzzles
+        .iter()
+        .map(|puzzle| {
+            puzzle
+                .solutions
+                .iter()
+                .map(|solution| {
+                    if rng.random_bool(accuracy.clamp(0.0, 1.0)) {
+                        format!("**Answer: {}**", solution)
+                    } else {
+                        WRONG_RESPONSE.to_string()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn synthetic_puzzle(
+    game: SyntheticGame,
+    game_states: Vec<String>,
+    solutions: Vec<String>,
+    goal: Option<PuzzleGoal>,
+    rng: &mut SmallRng,
+) -> Puzzle {
+    Puzzle {
+        id: String::new(),
+        description: format!("Synthetic {} position", game.game_type()),
+        game_type: None,
+        game_states,
+        solutions,
+        encrypted_solutions: None,
+        rating: Some(rng.random_range(800..=2400) as f64),
+        rating_deviation: None,
+        weight: 1.0,
+        state_weights: Vec::new(),
+        images: Vec::new(),
+        sequences: Vec::new(),
+        annotations: Vec::new(),
+        orientations: Vec::new(),
+        history: None,
+        render_style: None,
+        tags: vec![game.game_type().to_string()],
+        provenance: Provenance::default(),
+        metadata: BTreeMap::new(),
+        goal_condition: None,
+        goal,
+    }
+}
+
+/// Up to `states` positions of `game`, each with a random legal move in it,
+/// the next position following from that move and a random reply. Stops
+/// early, after at least one position, if the game ends.
+fn line(game: SyntheticGame, states: usize, rng: &mut SmallRng) -> (Vec<String>, Vec<String>) {
+    loop {
+        let mut state = opening(game, rng);
+        let (mut game_states, mut moves) = (Vec::new(), Vec::new());
+        while game_states.len() < states {
+            let Some(m) = candidate_moves(game, &state).choose(rng).cloned() else {
+                break;
+            };
+            let after = play(game, &state, &m);
+            game_states.push(std::mem::replace(&mut state, after));
+            moves.push(m);
+            let Some(reply) = candidate_moves(game, &state).choose(rng).cloned() else {
+                break;
+            };
+            state = play(game, &state, &reply);
+        }
+        if !game_states.is_empty() {
+            return (game_states, moves);
+        }
+    }
+}
+
+/// A random position early in a game.
+fn opening(game: SyntheticGame, rng: &mut SmallRng) -> String {
+    let plies = rng.random_range(0..=20);
+    let mut state = match game {
+        #[cfg(feature = "chess")]
+        SyntheticGame::Chess => chess::STARTING_FEN.to_string(),
+        SyntheticGame::Go => format!("{} x", vec![".".repeat(GO_SIZE); GO_SIZE].join("/")),
+    };
+    for _ in 0..plies {
+        let Some(m) = candidate_moves(game, &state).choose(rng).cloned() else {
+            break;
+        };
+        state = play(game, &state, &m);
+    }
+    state
+}
+
+/// The moves considered legal in `state`: every legal chess move, or
+/// every empty Go point.
+fn candidate_moves(game: SyntheticGame, state: &str) -> Vec<String> {
+    match game {
+        #[cfg(feature = "chess")]
+        SyntheticGame::Chess => chess::legal_moves(state).unwrap_or_default(),
+        SyntheticGame::Go => {
+            let board = state.split_whitespace().next().unwrap_or_default();
+            board
+                .split('/')
+                .enumerate()
+                .flat_map(|(row, cells)| {
+                    cells
+                        .chars()
+                        .zip(GO_COLUMNS.chars())
+                        .filter(|(cell, _)| *cell == '.')
+                        .map(move |(_, column)| format!("{}{}", column, GO_SIZE - row))
+                })
+                .collect()
+        }
+    }
+}
+
+/// `state` after `m`, one of its `candidate_moves`. Go stones are placed
+/// without captures.
+fn play(game: SyntheticGame, state: &str, m: &str) -> String {
+    match game {
+        #[cfg(feature = "chess")]
+        SyntheticGame::Chess => chess::play(state, m).unwrap_or_else(|_| state.to_string()),
+        SyntheticGame::Go => {
+            let (board, side) = state.split_once(' ').unwrap_or((state, "x"));
+            let column = GO_COLUMNS.find(&m[..1]).unwrap_or_default();
+            let row = GO_SIZE - m[1..].parse::<usize>().unwrap_or(GO_SIZE);
+            let mut rows: Vec<Vec<char>> = board.split('/').map(|row| row.chars().collect()).collect();
+            rows[row][column] = side.chars().next().unwrap_or('x');
+            let next = if side == "x" { "o" } else { "x" };
+            let board: Vec<String> = rows.into_iter().map(|row| row.into_iter().collect()).collect();
+            format!("{} {}", board.join("/"), next)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Scorer;
+
+    const SCORERS: [Scorer; 3] = [Scorer::PerState, Scorer::Prefix, Scorer::AllOrNothing];
+    const GOALS: [SyntheticGoal; 4] =
+        [SyntheticGoal::BestMove, SyntheticGoal::MateInOne, SyntheticGoal::Evaluate, SyntheticGoal::MultipleChoice];
+
+    /// Collections over every game and goal, alone and mixed, for a range
+    /// of seeds.
+    fn collections() -> Vec<PuzzleCollection> {
+        let games = [
+            vec![SyntheticGame::Go],
+            #[cfg(feature = "chess")]
+            vec![SyntheticGame::Chess],
+            #[cfg(feature = "chess")]
+            vec![SyntheticGame::Chess, SyntheticGame::Go],
+        ];
+        (0..8)
+            .flat_map(|seed| {
+                games.iter().map(move |games| {
+                    CollectionFactory::new(seed)
+                        .with_puzzles(6)
+                        .with_games(games.clone())
+                        .with_goals(GOALS.to_vec())
+                        .build()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collections_pass_check() {
+        for collection in collections() {
+            assert!(collection.check().is_empty(), "{}: {:?}", collection.name, collection.check());
+        }
+    }
+
+    #[test]
+    fn same_seed_same_collection() {
+        let build = || serde_json::to_string(&CollectionFactory::new(7).with_goals(GOALS.to_vec()).build()).unwrap();
+        assert_eq!(build(), build());
+    }
+
+    #[test]
+    fn empty_state_range_gives_one_state() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let collection = CollectionFactory::new(1).with_states(5..=2).build();
+        assert!(collection.puzzles.iter().all(|puzzle| puzzle.game_states.len() == 1));
+    }
+
+    #[test]
+    fn perfect_responses_score_the_maximum() {
+        for collection in collections() {
+            for (puzzle, responses) in collection.puzzles.iter().zip(responses(&collection, 1.0, 0)) {
+                for scorer in SCORERS {
+                    let score = scorer.score_responses(puzzle, &collection, &responses);
+                    assert!(score.max_possible_score > 0.0);
+                    assert_eq!(score.score, score.max_possible_score, "{} with {:?}", puzzle.id, scorer);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_or_missing_responses_score_nothing() {
+        for collection in collections() {
+            for (puzzle, responses) in collection.puzzles.iter().zip(responses(&collection, 0.0, 0)) {
+                for scorer in SCORERS {
+                    assert_eq!(scorer.score_responses(puzzle, &collection, &responses).score, 0.0);
+                    assert_eq!(scorer.score_responses(puzzle, &collection, &[]).score, 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stricter_scorers_never_score_higher() {
+        for collection in collections() {
+            for seed in 0..4 {
+                for (puzzle, responses) in collection.puzzles.iter().zip(responses(&collection, 0.5, seed)) {
+                    let [per_state, prefix, all_or_nothing] =
+                        SCORERS.map(|scorer| scorer.score_responses(puzzle, &collection, &responses).score);
+                    assert!(all_or_nothing <= prefix && prefix <= per_state, "{}", puzzle.id);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,769 @@
+//! Runs spread over a Redis queue: a coordinator pushes one task per model
+//! and puzzle, any number of worker processes pull, solve and push back the
+//! scores, and the coordinator summarizes them into ordinary results.
+//! Workers can join or leave at any time, so big sweeps scale with however
+//! many machines are available.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use crate::budget::TimeBudget;
+use crate::evaluation::{BenchmarkRunner, RetryPolicy, Solver};
+use crate::prompt::{Modality, PromptStyle, Representation};
+use crate::puzzle::{PuzzleCollection, PuzzleScore, Scorer};
+use crate::results::{BenchmarkResult, RunMetadata};
+use crate::scoring::AnswerScoring;
+
+/// Name the queue's Redis keys start with unless another is given.
+pub const DEFAULT_QUEUE: &str = "boardgamebench";
+/// How long one wait on Redis blocks before deadlines are checked again.
+const POLL_SECS: &str = "1";
+/// How long a worker's lease lasts unless renewed. A worker that lets it run
+/// out is taken for dead, and coordinators put its task back on the queue.
+const LEASE: Duration = Duration::from_secs(30);
+/// How long a run's replies are kept after the last one arrives, so replies
+/// to a coordinator that is gone don't pile up.
+const REPLIES_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error("invalid queue URL {0}: expected redis://[:password@]host[:port][/db]")]
+    Url(String),
+    #[error("queue connection failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("queue server error: {0}")]
+    Server(String),
+    #[error("invalid queue message: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// What workers need to solve and score a run's tasks, stored once per run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedRun {
+    pub collection: PuzzleCollection,
+    pub scorer: Scorer,
+    pub answer_scoring: Option<AnswerScoring>,
+    /// Retries of failed API calls, with exponential backoff.
+    #[serde(default)]
+    pub retries: u32,
+    /// Per-puzzle wall-clock budget in seconds; slower answers score zero.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_budget_secs: Option<f64>,
+    #[serde(default)]
+    pub modality: Modality,
+    #[serde(default)]
+    pub representation: Representation,
+    #[serde(default)]
+    pub prompt_style: PromptStyle,
+}
+
+/// One puzzle for one model to solve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueTask {
+    pub run_id: String,
+    pub model: String,
+    pub puzzle_id: String,
+}
+
+/// A worker's answer to a `QueueTask`: the score, or why there is none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueReply {
+    pub model: String,
+    pub puzzle_id: String,
+    pub worker: String,
+    pub solver_name: String,
+    pub solver_description: String,
+    pub score: Option<PuzzleScore>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A Redis server and the name of the queue on it. Tasks wait in the list
+/// `<name>:tasks`; each run keeps its settings under `<name>:run:<id>` and
+/// collects replies in `<name>:replies:<id>`, which expires an hour after the
+/// last one. A worker moves the task it is
+/// solving to `<name>:processing:<worker>` and removes it once replied to,
+/// renewing `<name>:lease:<worker>` meanwhile; workers are listed in the set
+/// `<name>:workers`. Needs Redis 6.2 or later.
+#[derive(Debug, Clone)]
+pub struct RedisQueue {
+    pub url: String,
+    pub name: String,
+}
+
+impl RedisQueue {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            name: DEFAULT_QUEUE.to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    fn tasks_key(&self) -> String {
+        format!("{}:tasks", self.name)
+    }
+
+    fn run_key(&self, run_id: &str) -> String {
+        format!("{}:run:{}", self.name, run_id)
+    }
+
+    fn replies_key(&self, run_id: &str) -> String {
+        format!("{}:replies:{}", self.name, run_id)
+    }
+
+    fn workers_key(&self) -> String {
+        format!("{}:workers", self.name)
+    }
+
+    fn processing_key(&self, worker: &str) -> String {
+        format!("{}:processing:{}", self.name, worker)
+    }
+
+    fn lease_key(&self, worker: &str) -> String {
+        format!("{}:lease:{}", self.name, worker)
+    }
+
+    async fn connect(&self) -> Result<Connection, QueueError> {
+        Connection::open(&self.url).await
+    }
+}
+
+/// Pushes a run's tasks and turns the replies into results.
+pub struct Coordinator {
+    pub queue: RedisQueue,
+    pub run: QueuedRun,
+    /// Give up on tasks no reply has come for after this long without any
+    /// reply; the results then only cover the puzzles answered.
+    pub idle_timeout: Duration,
+}
+
+impl Coordinator {
+    pub fn new(queue: RedisQueue, collection: PuzzleCollection) -> Self {
+        Self {
+            queue,
+            run: QueuedRun {
+                collection,
+                scorer: Scorer::default(),
+                answer_scoring: None,
+                retries: 0,
+                time_budget_secs: None,
+                modality: Modality::default(),
+                representation: Representation::default(),
+                prompt_style: PromptStyle::default(),
+            },
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+
+    pub fn with_scorer(mut self, scorer: Scorer) -> Self {
+        self.run.scorer = scorer;
+        self
+    }
+
+    pub fn with_answer_scoring(mut self, answer_scoring: AnswerScoring) -> Self {
+        self.run.answer_scoring = Some(answer_scoring);
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.run.retries = retries;
+        self
+    }
+
+    pub fn with_time_budget(mut self, limit: Duration) -> Self {
+        self.run.time_budget_secs = Some(limit.as_secs_f64());
+        self
+    }
+
+    /// Show the workers' solvers puzzles in `modality`, states written as
+    /// `representation`, with prompts in `prompt_style`.
+    pub fn with_prompts(mut self, modality: Modality, representation: Representation, prompt_style: PromptStyle) -> Self {
+        self.run.modality = modality;
+        self.run.representation = representation;
+        self.run.prompt_style = prompt_style;
+        self
+    }
+
+    /// Every model's result on the collection, in the order of `models`.
+    pub fn run(&self, models: &[String]) -> Result<Vec<BenchmarkResult>, QueueError> {
+        crate::runtime::block_on(self.run_async(models))
+    }
+
+    pub async fn run_async(&self, models: &[String]) -> Result<Vec<BenchmarkResult>, QueueError> {
+        let run_id = format!("{:016x}", rand::random::<u64>());
+        let (run_key, replies_key) = (self.queue.run_key(&run_id), self.queue.replies_key(&run_id));
+        let mut connection = self.queue.connect().await?;
+        connection.command(&[b"SET", run_key.as_bytes(), &serde_json::to_vec(&self.run)?]).await?;
+
+        let mut pending = BTreeSet::new();
+        let mut push = vec![b"RPUSH".to_vec(), self.queue.tasks_key().into_bytes()];
+        for model in models {
+            for puzzle in &self.run.collection.puzzles {
+                pending.insert((model.clone(), puzzle.id.clone()));
+                let task = QueueTask {
+                    run_id: run_id.clone(),
+                    model: model.clone(),
+                    puzzle_id: puzzle.id.clone(),
+                };
+                push.push(serde_json::to_vec(&task)?);
+            }
+        }
+        let total = pending.len();
+        if total > 0 {
+            let args: Vec<&[u8]> = push.iter().map(Vec::as_slice).collect();
+            connection.command(&args).await?;
+        }
+        info!(run_id = %run_id, tasks = total, queue = %self.queue.name, "queued tasks");
+
+        let mut scores: BTreeMap<&str, Vec<PuzzleScore>> = BTreeMap::new();
+        let mut solvers: HashMap<String, (String, String)> = HashMap::new();
+        let mut workers = BTreeSet::new();
+        let mut last_reply = Instant::now();
+        let mut last_requeue = Instant::now();
+        while !pending.is_empty() {
+            if last_reply.elapsed() >= self.idle_timeout {
+                warn!(remaining = pending.len(), total, "no reply within the idle timeout; giving up on the rest");
+                break;
+            }
+            if last_requeue.elapsed() >= LEASE {
+                let requeued = self.requeue_stale(&mut connection).await?;
+                if requeued > 0 {
+                    warn!(requeued, "queued the tasks of workers that stopped renewing their lease again");
+                    last_reply = Instant::now();
+                }
+                last_requeue = Instant::now();
+            }
+            let reply = connection
+                .command(&[b"BLPOP", replies_key.as_bytes(), POLL_SECS.as_bytes()])
+                .await?;
+            let Some(payload) = reply.into_array().and_then(|mut parts| parts.pop()).and_then(Reply::into_bytes)
+            else {
+                continue;
+            };
+            last_reply = Instant::now();
+            let reply: QueueReply = match serde_json::from_slice(&payload) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    warn!(error = %e, "skipping a malformed reply");
+                    continue;
+                }
+            };
+            if !pending.remove(&(reply.model.clone(), reply.puzzle_id.clone())) {
+                continue;
+            }
+            workers.insert(reply.worker.clone());
+            if let Some(error) = &reply.error {
+                warn!(model = %reply.model, puzzle = %reply.puzzle_id, worker = %reply.worker, error = %error, "task failed");
+            }
+            solvers
+                .entry(reply.model.clone())
+                .or_insert_with(|| (reply.solver_name.clone(), reply.solver_description.clone()));
+            if let (Some(score), Some(model)) = (reply.score, models.iter().find(|m| **m == reply.model)) {
+                scores.entry(model.as_str()).or_default().push(score);
+            }
+        }
+        connection.command(&[b"DEL", run_key.as_bytes(), replies_key.as_bytes()]).await?;
+
+        let collection = &self.run.collection;
+        let results = models
+            .iter()
+            .map(|model| {
+                let (name, description) = solvers.get(model).cloned().unwrap_or_else(|| (model.clone(), String::new()));
+                let mut result = BenchmarkResult::summarize(
+                    format!("{} on {} (queue {})", name, collection.name, self.queue.name),
+                    &name,
+                    &description,
+                    collection,
+                    scores.remove(model.as_str()).unwrap_or_default(),
+                );
+                result.unanswered_puzzles = pending
+                    .iter()
+                    .filter(|(pending_model, _)| pending_model == model)
+                    .map(|(_, puzzle_id)| puzzle_id.clone())
+                    .collect();
+                result.modality = self.run.modality;
+                result.representation = self.run.representation;
+                result.prompt_style = self.run.prompt_style;
+                result.metadata = Some(RunMetadata {
+                    crate_version: env!("CARGO_PKG_VERSION").to_string(),
+                    git_commit: option_env!("BOARDGAMEBENCH_GIT_COMMIT").map(str::to_string),
+                    collection_name: collection.name.clone(),
+                    collection_checksum: collection.source_checksum(),
+                    solver_parameters: BTreeMap::new(),
+                    seeds: BTreeMap::new(),
+                    environment: BTreeMap::from([
+                        ("queue".to_string(), self.queue.name.clone()),
+                        ("workers".to_string(), workers.len().to_string()),
+                    ]),
+                    shard: None,
+                    selection: None,
+                });
+                result
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Put the tasks of workers whose lease ran out back at the front of the
+    /// queue, returning how many there were.
+    async fn requeue_stale(&self, connection: &mut Connection) -> Result<usize, QueueError> {
+        let (tasks_key, workers_key) = (self.queue.tasks_key(), self.queue.workers_key());
+        let workers = connection.command(&[b"SMEMBERS", workers_key.as_bytes()]).await?.into_array().unwrap_or_default();
+        let mut requeued = 0;
+        for worker in workers.into_iter().filter_map(Reply::into_bytes) {
+            let worker = String::from_utf8_lossy(&worker).into_owned();
+            let lease_key = self.queue.lease_key(&worker);
+            if connection.command(&[b"EXISTS", lease_key.as_bytes()]).await?.into_integer() != Some(0) {
+                continue;
+            }
+            let processing_key = self.queue.processing_key(&worker);
+            let requeue: [&[u8]; 5] = [b"LMOVE", processing_key.as_bytes(), tasks_key.as_bytes(), b"RIGHT", b"LEFT"];
+            while connection.command(&requeue).await?.into_bytes().is_some() {
+                requeued += 1;
+            }
+            connection.command(&[b"SREM", workers_key.as_bytes(), worker.as_bytes()]).await?;
+        }
+        Ok(requeued)
+    }
+}
+
+/// Pulls tasks from a queue, solves them and pushes the scores back.
+pub struct Worker {
+    pub queue: RedisQueue,
+    /// Tasks worked on at once, each over its own connection.
+    pub concurrency: usize,
+    /// Stop once the queue has been empty this long; `None` waits forever.
+    pub idle_timeout: Option<Duration>,
+    /// How this worker is named in replies.
+    pub name: String,
+}
+
+/// How a worker solves and scores a run's tasks.
+struct RunTasks {
+    runner: BenchmarkRunner,
+    modality: Modality,
+    representation: Representation,
+    prompt_style: PromptStyle,
+}
+
+/// A run's tasks, or why they can't be scored with its settings.
+type RunScorer = Result<Arc<RunTasks>, String>;
+/// A run id and a model.
+type RunModel = (String, String);
+
+/// A worker's per-run scorers and per-run, per-model solvers, shared by its
+/// tasks.
+#[derive(Default)]
+struct WorkerState {
+    runners: Mutex<HashMap<String, Option<RunScorer>>>,
+    solvers: Mutex<HashMap<RunModel, Result<Arc<Solver>, String>>>,
+}
+
+impl Worker {
+    pub fn new(queue: RedisQueue) -> Self {
+        let name = format!("{}-{}", std::env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string()), std::process::id());
+        Self {
+            queue,
+            concurrency: 1,
+            idle_timeout: None,
+            name,
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Work until the queue stays empty for the idle timeout, creating a
+    /// solver for each model the first time a task asks for it. Returns how
+    /// many tasks were answered.
+    pub fn run<F>(&self, make_solver: F) -> Result<usize, QueueError>
+    where
+        F: Fn(&str) -> Result<Solver, String> + Send + Sync,
+    {
+        crate::runtime::block_on(self.run_async(make_solver))
+    }
+
+    pub async fn run_async<F>(&self, make_solver: F) -> Result<usize, QueueError>
+    where
+        F: Fn(&str) -> Result<Solver, String> + Send + Sync,
+    {
+        let state = WorkerState::default();
+        let loops = (0..self.concurrency).map(|i| self.work(&state, &make_solver, format!("{}/{}", self.name, i)));
+        let mut answered = 0;
+        for done in join_all(loops).await {
+            answered += done?;
+        }
+        Ok(answered)
+    }
+
+    /// One of the worker's loops, known to coordinators as `id`: take a
+    /// task into its processing list, solve it while renewing the lease,
+    /// reply, and only then remove it.
+    async fn work<F>(&self, state: &WorkerState, make_solver: &F, id: String) -> Result<usize, QueueError>
+    where
+        F: Fn(&str) -> Result<Solver, String> + Sync,
+    {
+        let mut connection = self.queue.connect().await?;
+        let (tasks_key, workers_key) = (self.queue.tasks_key(), self.queue.workers_key());
+        let (processing_key, lease_key) = (self.queue.processing_key(&id), self.queue.lease_key(&id));
+        let lease_ms = LEASE.as_millis().to_string();
+        let renew: [&[u8]; 5] = [b"SET", lease_key.as_bytes(), b"1", b"PX", lease_ms.as_bytes()];
+        let mut answered = 0;
+        let mut last_task = Instant::now();
+        loop {
+            if self.idle_timeout.is_some_and(|timeout| last_task.elapsed() >= timeout) {
+                connection.command(&[b"SREM", workers_key.as_bytes(), id.as_bytes()]).await?;
+                connection.command(&[b"DEL", lease_key.as_bytes()]).await?;
+                return Ok(answered);
+            }
+            // Lease first, so coordinators never see the worker listed without one.
+            connection.command(&renew).await?;
+            connection.command(&[b"SADD", workers_key.as_bytes(), id.as_bytes()]).await?;
+            let reply = connection
+                .command(&[b"BLMOVE", tasks_key.as_bytes(), processing_key.as_bytes(), b"LEFT", b"RIGHT", POLL_SECS.as_bytes()])
+                .await?;
+            let Some(payload) = reply.into_bytes() else {
+                continue;
+            };
+            last_task = Instant::now();
+            let task = match serde_json::from_slice::<QueueTask>(&payload) {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    warn!(error = %e, "skipping a malformed task");
+                    None
+                }
+            };
+            // Without a runner the run is over, was abandoned or can't be read.
+            if let Some(task) = task
+                && let Some(runner) = self.runner(state, &mut connection, &task.run_id).await?
+            {
+                let reply = match runner {
+                    Ok(run) => {
+                        let answer = self.answer(state, &run, make_solver, &task);
+                        tokio::pin!(answer);
+                        loop {
+                            tokio::select! {
+                                reply = &mut answer => break reply,
+                                _ = tokio::time::sleep(LEASE / 3) => {
+                                    connection.command(&renew).await?;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => QueueReply {
+                        error: Some(e),
+                        ..self.reply(&task)
+                    },
+                };
+                let replies_key = self.queue.replies_key(&task.run_id);
+                connection.command(&[b"RPUSH", replies_key.as_bytes(), &serde_json::to_vec(&reply)?]).await?;
+                let ttl = REPLIES_TTL.as_secs().to_string();
+                connection.command(&[b"EXPIRE", replies_key.as_bytes(), ttl.as_bytes()]).await?;
+                answered += 1;
+            }
+            connection.command(&[b"LREM", processing_key.as_bytes(), b"1", &payload]).await?;
+        }
+    }
+
+    /// The runner scoring `run_id`'s tasks, why none could be built from
+    /// the run's settings, or `None` if the run is gone.
+    async fn runner(
+        &self,
+        state: &WorkerState,
+        connection: &mut Connection,
+        run_id: &str,
+    ) -> Result<Option<RunScorer>, QueueError> {
+        if let Some(runner) = state.runners.lock().unwrap().get(run_id) {
+            return Ok(runner.clone());
+        }
+        let run_key = self.queue.run_key(run_id);
+        let runner = match connection.command(&[b"GET", run_key.as_bytes()]).await?.into_bytes() {
+            Some(payload) => match serde_json::from_slice::<QueuedRun>(&payload) {
+                Ok(run) => {
+                    let mut builder = BenchmarkRunner::builder()
+                        .puzzles(run.collection)
+                        .concurrency(1)
+                        .scorer(run.scorer)
+                        .retry(RetryPolicy {
+                            max_retries: run.retries,
+                            ..RetryPolicy::default()
+                        });
+                    if let Some(answer_scoring) = run.answer_scoring {
+                        builder = builder.answer_scoring(answer_scoring);
+                    }
+                    if let Some(limit) = run.time_budget_secs {
+                        builder = builder.time_budget(TimeBudget {
+                            limit: Duration::from_secs_f64(limit),
+                            decay_half_life: None,
+                        });
+                    }
+                    match builder.build() {
+                        Ok(runner) => Some(Ok(Arc::new(RunTasks {
+                            runner,
+                            modality: run.modality,
+                            representation: run.representation,
+                            prompt_style: run.prompt_style,
+                        }))),
+                        Err(e) => {
+                            warn!(run_id = %run_id, error = %e, "can't score the tasks of a run");
+                            Some(Err(format!("can't score the run's tasks: {}", e)))
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(run_id = %run_id, error = %e, "skipping the tasks of a run whose settings can't be read");
+                    None
+                }
+            },
+            None => None,
+        };
+        state.runners.lock().unwrap().insert(run_id.to_string(), runner.clone());
+        Ok(runner)
+    }
+
+    /// A reply to `task` without a score yet.
+    fn reply(&self, task: &QueueTask) -> QueueReply {
+        QueueReply {
+            model: task.model.clone(),
+            puzzle_id: task.puzzle_id.clone(),
+            worker: self.name.clone(),
+            solver_name: task.model.clone(),
+            solver_description: String::new(),
+            score: None,
+            error: None,
+        }
+    }
+
+    async fn answer<F>(&self, state: &WorkerState, run: &RunTasks, make_solver: &F, task: &QueueTask) -> QueueReply
+    where
+        F: Fn(&str) -> Result<Solver, String> + Sync,
+    {
+        let mut reply = self.reply(task);
+        let solver = state
+            .solvers
+            .lock()
+            .unwrap()
+            .entry((task.run_id.clone(), task.model.clone()))
+            .or_insert_with(|| {
+                make_solver(&task.model).map(|solver| {
+                    Arc::new(
+                        solver
+                            .with_modality(run.modality)
+                            .with_representation(run.representation)
+                            .with_prompt_style(run.prompt_style),
+                    )
+                })
+            })
+            .clone();
+        let solver = match solver {
+            Ok(solver) => solver,
+            Err(e) => {
+                reply.error = Some(e);
+                return reply;
+            }
+        };
+        reply.solver_name = solver.name().to_string();
+        reply.solver_description = solver.description().to_string();
+        let runner = &run.runner;
+        match runner.puzzles.puzzles.iter().find(|p| p.id == task.puzzle_id) {
+            Some(puzzle) => {
+                reply.score = runner.evaluate_one(&solver, puzzle).await;
+                if reply.score.is_none() {
+                    reply.error = Some("the solver's budget is exhausted".to_string());
+                }
+            }
+            None => reply.error = Some(format!("no puzzle {} in the run's collection", task.puzzle_id)),
+        }
+        reply
+    }
+}
+
+/// A Redis reply; error replies are returned as `QueueError::Server`.
+enum Reply {
+    Nil,
+    Integer(i64),
+    Bytes(Vec<u8>),
+    Array(Vec<Reply>),
+}
+
+impl Reply {
+    fn into_bytes(self) -> Option<Vec<u8>> {
+        match self {
+            Reply::Bytes(bytes) => Some(bytes),
+            Reply::Integer(n) => Some(n.to_string().into_bytes()),
+            Reply::Nil | Reply::Array(_) => None,
+        }
+    }
+
+    fn into_integer(self) -> Option<i64> {
+        match self {
+            Reply::Integer(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn into_array(self) -> Option<Vec<Reply>> {
+        match self {
+            Reply::Array(parts) => Some(parts),
+            _ => None,
+        }
+    }
+}
+
+/// A connection speaking just enough of the Redis protocol for the queue.
+struct Connection {
+    stream: BufReader<TcpStream>,
+}
+
+impl Connection {
+    /// Connect to `redis://[:password@]host[:port][/db]`, authenticating and
+    /// selecting the database if the URL says to.
+    async fn open(url: &str) -> Result<Self, QueueError> {
+        let invalid = || QueueError::Url(url.to_string());
+        let rest = url.strip_prefix("redis://").ok_or_else(invalid)?;
+        let (authority, db) = rest.split_once('/').unwrap_or((rest, ""));
+        let (password, host) = match authority.rsplit_once('@') {
+            Some((user_info, host)) => (Some(user_info.rsplit(':').next().unwrap_or(user_info)), host),
+            None => (None, authority),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let address = if host.contains(':') { host.to_string() } else { format!("{}:6379", host) };
+        let mut connection = Self {
+            stream: BufReader::new(TcpStream::connect(address).await?),
+        };
+        if let Some(password) = password.filter(|p| !p.is_empty()) {
+            connection.command(&[b"AUTH", password.as_bytes()]).await?;
+        }
+        if !db.is_empty() {
+            connection.command(&[b"SELECT", db.as_bytes()]).await?;
+        }
+        Ok(connection)
+    }
+
+    async fn command(&mut self, args: &[&[u8]]) -> Result<Reply, QueueError> {
+        let mut request = format!("*{}\r\n", args.len()).into_bytes();
+        for arg in args {
+            request.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            request.extend_from_slice(arg);
+            request.extend_from_slice(b"\r\n");
+        }
+        self.stream.get_mut().write_all(&request).await?;
+        self.read_reply().await
+    }
+
+    async fn read_reply(&mut self) -> Result<Reply, QueueError> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(QueueError::Server("connection closed".to_string()));
+        }
+        let line = line.trim_end();
+        let (kind, rest) = line.split_at(line.len().min(1));
+        let number = || rest.parse::<i64>().map_err(|_| QueueError::Server(format!("unexpected reply {:?}", line)));
+        match kind {
+            "+" => Ok(Reply::Bytes(rest.as_bytes().to_vec())),
+            "-" => Err(QueueError::Server(rest.to_string())),
+            ":" => Ok(Reply::Integer(number()?)),
+            "$" => {
+                let Ok(len) = usize::try_from(number()?) else {
+                    return Ok(Reply::Nil);
+                };
+                let mut bytes = vec![0; len + 2];
+                self.stream.read_exact(&mut bytes).await?;
+                bytes.truncate(len);
+                Ok(Reply::Bytes(bytes))
+            }
+            "*" => {
+                let Ok(len) = usize::try_from(number()?) else {
+                    return Ok(Reply::Nil);
+                };
+                let mut parts = Vec::with_capacity(len);
+                for _ in 0..len {
+                    parts.push(Box::pin(self.read_reply()).await?);
+                }
+                Ok(Reply::Array(parts))
+            }
+            _ => Err(QueueError::Server(format!("unexpected reply {:?}", line))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// A connection to a server that answers the first command with
+    /// `replies`, and the command it received.
+    async fn serve(replies: &'static [u8]) -> (Connection, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 256];
+            let read = stream.read(&mut request).await.unwrap();
+            request.truncate(read);
+            stream.write_all(replies).await.unwrap();
+            request
+        });
+        (Connection::open(&format!("redis://{}", address)).await.unwrap(), server)
+    }
+
+    #[tokio::test]
+    async fn commands_are_sent_as_bulk_strings() {
+        let (mut connection, server) = serve(b"+OK\r\n").await;
+        let reply = connection.command(&[b"SET", b"key", b"two\r\nlines"]).await.unwrap();
+        assert_eq!(reply.into_bytes().unwrap(), b"OK");
+        assert_eq!(server.await.unwrap(), b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$10\r\ntwo\r\nlines\r\n");
+    }
+
+    #[tokio::test]
+    async fn replies_are_parsed() {
+        let (mut connection, _server) =
+            serve(b"*4\r\n:42\r\n$5\r\na\r\nbc\r\n$-1\r\n*2\r\n$0\r\n\r\n*-1\r\n:-3\r\n-ERR wrong type\r\n").await;
+        let mut parts = connection.command(&[b"EVAL"]).await.unwrap().into_array().unwrap().into_iter();
+        assert_eq!(parts.next().unwrap().into_integer(), Some(42));
+        assert_eq!(parts.next().unwrap().into_bytes().unwrap(), b"a\r\nbc");
+        assert!(matches!(parts.next(), Some(Reply::Nil)));
+        let mut nested = parts.next().unwrap().into_array().unwrap().into_iter();
+        assert_eq!(nested.next().unwrap().into_bytes().unwrap(), b"");
+        assert!(matches!(nested.next(), Some(Reply::Nil)));
+        assert!(parts.next().is_none());
+
+        assert_eq!(connection.read_reply().await.unwrap().into_integer(), Some(-3));
+        assert!(matches!(connection.read_reply().await, Err(QueueError::Server(message)) if message == "ERR wrong type"));
+        assert!(matches!(connection.read_reply().await, Err(QueueError::Server(message)) if message == "connection closed"));
+    }
+
+    #[tokio::test]
+    async fn urls_need_the_redis_scheme_and_a_host() {
+        assert!(matches!(Connection::open("http://localhost").await, Err(QueueError::Url(_))));
+        assert!(matches!(Connection::open("redis:///0").await, Err(QueueError::Url(_))));
+    }
+}
@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::{LN_10, PI};
+
+use crate::results::BenchmarkResult;
+use crate::puzzle::PuzzleCollection;
+
+const INITIAL_RATING: f64 = 1500.0;
+const INITIAL_DEVIATION: f64 = 350.0;
+/// Used for puzzles whose source did not record a rating deviation.
+const DEFAULT_PUZZLE_DEVIATION: f64 = 75.0;
+
+/// Glicko rating of a solver, estimated from its puzzle outcomes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRating {
+    pub solver_name: String,
+    pub rating: f64,
+    pub deviation: f64,
+    pub games: usize,
+}
+
+/// A puzzle attempt viewed as a game against the puzzle.
+#[derive(Debug, Clone, Copy)]
+struct Outcome {
+    opponent_rating: f64,
+    opponent_deviation: f64,
+    /// Fraction of the puzzle solved, in [0, 1].
+    score: f64,
+}
+
+/// Treat every rated puzzle as a game between the solver and the puzzle
+/// (scored by the fraction of the puzzle solved) and compute one Glicko
+/// rating period per solver, starting from an unrated 1500 ± 350.
+/// Results from the same solver are pooled, so one rating can span
+/// several collections.
+pub fn rate_results(
+    collections: &[PuzzleCollection],
+    results: &[BenchmarkResult],
+) -> Vec<ModelRating> {
+    outcomes_by_solver(collections, results)
+        .into_iter()
+        .map(|(solver_name, outcomes)| {
+            let (rating, deviation) = glicko_period(INITIAL_RATING, INITIAL_DEVIATION, &outcomes);
+            ModelRating {
+                solver_name,
+                rating,
+                deviation,
+                games: outcomes.len(),
+            }
+        })
+        .collect()
+}
+
+/// Each solver's outcomes on the rated puzzles of `collections`, pooling
+/// results from the same solver.
+fn outcomes_by_solver(collections: &[PuzzleCollection], results: &[BenchmarkResult]) -> Vec<(String, Vec<Outcome>)> {
+    let puzzle_ratings: HashMap<&str, (f64, f64)> = collections
+        .iter()
+        .flat_map(|c| c.puzzles.iter())
+        .filter_map(|p| {
+            let rating = p.rating?;
+            Some((
+                p.id.as_str(),
+                (rating, p.rating_deviation.unwrap_or(DEFAULT_PUZZLE_DEVIATION)),
+            ))
+        })
+        .collect();
+
+    let mut games: Vec<(String, Vec<Outcome>)> = Vec::new();
+    for result in results {
+        let outcomes = result.puzzle_scores.iter().filter_map(|score| {
+            let &(rating, deviation) = puzzle_ratings.get(score.puzzle_id.as_str())?;
+            if score.max_possible_score <= 0.0 {
+                return None;
+            }
+            Some(Outcome {
+                opponent_rating: rating,
+                opponent_deviation: deviation,
+                score: score.score / score.max_possible_score,
+            })
+        });
+        match games.iter_mut().find(|(name, _)| name == &result.solver_name) {
+            Some((_, entry)) => entry.extend(outcomes),
+            None => games.push((result.solver_name.clone(), outcomes.collect())),
+        }
+    }
+
+    games
+}
+
+/// One Glicko-1 rating period.
+fn glicko_period(rating: f64, deviation: f64, outcomes: &[Outcome]) -> (f64, f64) {
+    if outcomes.is_empty() {
+        return (rating, deviation);
+    }
+
+    let q = LN_10 / 400.0;
+    let g = |rd: f64| 1.0 / (1.0 + 3.0 * q * q * rd * rd / (PI * PI)).sqrt();
+
+    let mut d_inv = 0.0;
+    let mut delta = 0.0;
+    for outcome in outcomes {
+        let g_j = g(outcome.opponent_deviation);
+        let expected =
+            1.0 / (1.0 + 10f64.powf(-g_j * (rating - outcome.opponent_rating) / 400.0));
+        d_inv += q * q * g_j * g_j * expected * (1.0 - expected);
+        delta += g_j * (outcome.score - expected);
+    }
+
+    let precision = 1.0 / (deviation * deviation) + d_inv;
+    (rating + q / precision * delta, (1.0 / precision).sqrt())
+}
+
+/// A solver's accuracy as a function of puzzle rating, binned and as a
+/// fitted logistic curve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationCurve {
+    pub solver_name: String,
+    pub puzzles: usize,
+    pub bins: Vec<RatingBin>,
+    /// Fitted log-odds of solving a puzzle rated 1500.
+    pub intercept: f64,
+    /// Change in log-odds per 400 rating points; negative when harder
+    /// puzzles are solved less often.
+    pub slope: f64,
+    /// The puzzle rating the solver is expected to solve half the time:
+    /// the "model puzzle rating". `None` if accuracy doesn't fall with
+    /// rating.
+    pub puzzle_rating: Option<f64>,
+}
+
+/// The puzzles whose rating falls in `[low, high)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingBin {
+    pub low: f64,
+    pub high: f64,
+    pub puzzles: usize,
+    /// Mean fraction of each puzzle solved.
+    pub accuracy: f64,
+    /// The fitted curve at the bin's mean puzzle rating.
+    pub fitted: f64,
+}
+
+impl CalibrationCurve {
+    /// The fitted chance of solving a puzzle rated `rating`.
+    pub fn expected(&self, rating: f64) -> f64 {
+        logistic(self.intercept + self.slope * (rating - INITIAL_RATING) / 400.0)
+    }
+}
+
+/// Precision of the normal prior on the intercept and slope, which keeps
+/// the fit finite for a solver that solved all or none of its puzzles.
+const CALIBRATION_PRIOR: f64 = 0.01;
+
+/// Fit a logistic curve of each solver's accuracy (fraction of each puzzle
+/// solved) against puzzle rating, and bin its accuracy by `bin_width`
+/// rating points, pooling results from the same solver like
+/// `rate_results`.
+pub fn calibration_curves(
+    collections: &[PuzzleCollection],
+    results: &[BenchmarkResult],
+    bin_width: f64,
+) -> Vec<CalibrationCurve> {
+    outcomes_by_solver(collections, results)
+        .into_iter()
+        .map(|(solver_name, outcomes)| {
+            let (intercept, slope) = fit_logistic(&outcomes);
+            let mut curve = CalibrationCurve {
+                solver_name,
+                puzzles: outcomes.len(),
+                bins: Vec::new(),
+                intercept,
+                slope,
+                puzzle_rating: (slope < 0.0).then(|| INITIAL_RATING - 400.0 * intercept / slope),
+            };
+
+            let mut bins: BTreeMap<i64, Vec<&Outcome>> = BTreeMap::new();
+            for outcome in &outcomes {
+                bins.entry((outcome.opponent_rating / bin_width).floor() as i64)
+                    .or_default()
+                    .push(outcome);
+            }
+            curve.bins = bins
+                .into_iter()
+                .map(|(bin, outcomes)| {
+                    let count = outcomes.len() as f64;
+                    let mean_rating = outcomes.iter().map(|o| o.opponent_rating).sum::<f64>() / count;
+                    RatingBin {
+                        low: bin as f64 * bin_width,
+                        high: (bin + 1) as f64 * bin_width,
+                        puzzles: outcomes.len(),
+                        accuracy: outcomes.iter().map(|o| o.score).sum::<f64>() / count,
+                        fitted: curve.expected(mean_rating),
+                    }
+                })
+                .collect();
+            curve
+        })
+        .collect()
+}
+
+/// Intercept and slope of the logistic regression of the outcomes' scores
+/// on their puzzles' ratings, by Newton's method; fractional scores count
+/// as fractional successes.
+fn fit_logistic(outcomes: &[Outcome]) -> (f64, f64) {
+    let (mut intercept, mut slope) = (0.0, 0.0);
+    for _ in 0..100 {
+        // Gradient and Hessian of the log-likelihood plus the prior.
+        let (mut g0, mut g1) = (-CALIBRATION_PRIOR * intercept, -CALIBRATION_PRIOR * slope);
+        let (mut h00, mut h01, mut h11) = (CALIBRATION_PRIOR, 0.0, CALIBRATION_PRIOR);
+        for outcome in outcomes {
+            let x = (outcome.opponent_rating - INITIAL_RATING) / 400.0;
+            let p = logistic(intercept + slope * x);
+            let w = p * (1.0 - p);
+            g0 += outcome.score - p;
+            g1 += (outcome.score - p) * x;
+            h00 += w;
+            h01 += w * x;
+            h11 += w * x * x;
+        }
+        let det = h00 * h11 - h01 * h01;
+        let step0 = (h11 * g0 - h01 * g1) / det;
+        let step1 = (h00 * g1 - h01 * g0) / det;
+        intercept += step0;
+        slope += step1;
+        if step0.abs().max(step1.abs()) < 1e-8 {
+            break;
+        }
+    }
+    (intercept, slope)
+}
+
+fn logistic(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
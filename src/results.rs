@@ -0,0 +1,1369 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use thiserror::Error;
+
+use crate::canary::CanaryReport;
+use crate::confidence::ConfidenceCalibration;
+use crate::budget::{ComputeBudget, TIME_CURVE_BUDGETS_SECS, TimeBudgetPoint, UsageSummary};
+use crate::prompt::{Modality, PromptLanguage, PromptStyle, Representation};
+use crate::extraction::extract_answer;
+use crate::frontier::FrontierReport;
+use crate::latency::LatencySummary;
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleScore, PuzzleSelection, RenderStyle, Scorer, Shard};
+use crate::scoring::{AnswerScoring, StateAnswer};
+use crate::signing::{self, ResultSeal};
+use crate::skills::{SkillScore, skill_profile};
+use crate::stopping::EarlyStop;
+
+/// Version of the result format this build writes. Bumped whenever fields
+/// are added or older files need upgrading to be read; see
+/// `BenchmarkResult::from_json`. 3 added `merged_from`, 4 `latency`.
+pub const RESULT_VERSION: u32 = 4;
+/// Version of result files written before `result_version` was recorded.
+const UNVERSIONED_RESULT_VERSION: u32 = 1;
+
+fn unversioned_result_version() -> u32 {
+    UNVERSIONED_RESULT_VERSION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PassResults {
+    pub pass_at_1: f64,
+    pub pass_at_n: f64,
+    pub individual_pass_scores: Vec<Vec<PuzzleScore>>,
+    /// Average score of each pass on its own.
+    #[serde(default)]
+    pub pass_average_scores: Vec<f64>,
+    /// Standard deviation of `pass_average_scores`.
+    #[serde(default)]
+    pub score_std_dev: f64,
+    #[serde(default)]
+    pub answer_distributions: Vec<AnswerDistribution>,
+}
+
+impl PassResults {
+    /// Pass statistics over every pass's scores, and each puzzle's scores
+    /// summed over the passes. Puzzles some pass skipped are left out.
+    pub fn from_passes(passes: Vec<Vec<PuzzleScore>>) -> (Self, Vec<PuzzleScore>) {
+        if passes.is_empty() {
+            let empty = PassResults {
+                pass_at_1: 0.0,
+                pass_at_n: 0.0,
+                individual_pass_scores: Vec::new(),
+                pass_average_scores: Vec::new(),
+                score_std_dev: 0.0,
+                answer_distributions: Vec::new(),
+            };
+            return (empty, Vec::new());
+        }
+        let passes = completed_in_every_pass(passes);
+        let pass_average_scores: Vec<f64> = passes.iter().map(|pass| average_score(pass)).collect();
+        let results = PassResults {
+            pass_at_1: calculate_pass_rate(&passes, 1),
+            pass_at_n: calculate_pass_rate(&passes, passes.len()),
+            score_std_dev: std_dev(&pass_average_scores),
+            pass_average_scores,
+            answer_distributions: answer_distributions(&passes),
+            individual_pass_scores: passes,
+        };
+        let aggregate = aggregate_scores(&results.individual_pass_scores);
+        (results, aggregate)
+    }
+
+    /// The share of puzzles fully solved in at least one of the first `k`
+    /// passes.
+    pub fn pass_at(&self, k: usize) -> f64 {
+        if self.individual_pass_scores.is_empty() {
+            return 0.0;
+        }
+        calculate_pass_rate(&self.individual_pass_scores, k)
+    }
+}
+
+/// How often each answer was given for every state of a puzzle across passes.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AnswerDistribution {
+    pub puzzle_id: String,
+    pub states: Vec<BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BenchmarkResult {
+    /// The result format the file was written in; see `RESULT_VERSION`.
+    #[serde(default = "unversioned_result_version")]
+    pub result_version: u32,
+    pub benchmark_name: String,
+    pub solver_name: String,
+    pub solver_description: String,
+    pub total_puzzles: usize,
+    pub total_score: f64,
+    pub max_possible_score: f64,
+    pub average_score: f64,
+    pub puzzle_scores: Vec<PuzzleScore>,
+    pub game_type_breakdown: Vec<GameTypeScore>,
+    /// Scores by the board theme and piece style of the images shown, for
+    /// image-modality runs on collections that record them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render_style_breakdown: Option<RenderStyleBreakdown>,
+    /// Scores by puzzle tag; a puzzle counts towards each of its tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tag_breakdown: Vec<GroupScore>,
+    /// Scores by skill, grouping the puzzles' themes; see `skills`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skill_profile: Vec<SkillScore>,
+    /// Scores by the value of each metadata key asked for; see
+    /// `add_metadata_breakdown`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata_breakdown: BTreeMap<String, Vec<GroupScore>>,
+    pub timestamp: String,
+    pub pass_results: Option<PassResults>,
+    /// Score the run would have had under a range of per-puzzle time limits.
+    #[serde(default)]
+    pub time_curve: Vec<TimeBudgetPoint>,
+    /// Percentiles of request latency and puzzle wall time; see
+    /// `summarize_latency`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencySummary>,
+    /// Mean judge rating of the responses' reasoning, in [0, 1].
+    #[serde(default)]
+    pub reasoning_score: Option<f64>,
+    /// How well the confidence the solver stated matched how often it was
+    /// right, when confidence was asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<ConfidenceCalibration>,
+    #[serde(default)]
+    pub usage: Option<UsageSummary>,
+    /// The run stopped early because the solver's budget ran out; only the
+    /// puzzles attempted before that are included.
+    #[serde(default)]
+    pub budget_exceeded: bool,
+    /// The run was interrupted (e.g. with Ctrl-C); only the puzzles finished
+    /// before that are included.
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Puzzles a queued run gave up waiting for after no worker replied for
+    /// its idle timeout; they are left out of the totals.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unanswered_puzzles: Vec<String>,
+    /// The run stopped once its score was known well enough; see
+    /// `EarlyStopping`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub early_stop: Option<EarlyStop>,
+    /// How the solver fared on the collection's canary puzzles, which are
+    /// left out of `puzzle_scores` and every total.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canaries: Option<CanaryReport>,
+    /// Whether the solver saw FEN text or board images.
+    #[serde(default)]
+    pub modality: Modality,
+    /// How states were written out in text prompts.
+    #[serde(default)]
+    pub representation: Representation,
+    /// How much guidance the instructions gave.
+    #[serde(default)]
+    pub prompt_style: PromptStyle,
+    /// The language the prompts were written in.
+    #[serde(default, skip_serializing_if = "PromptLanguage::is_english")]
+    pub prompt_language: PromptLanguage,
+    /// The limits each response was held to.
+    #[serde(default, skip_serializing_if = "ComputeBudget::is_unlimited")]
+    pub compute_budget: ComputeBudget,
+    #[serde(default)]
+    pub metadata: Option<RunMetadata>,
+    /// Digest of the answers and scores, and optionally a signature over
+    /// it; see the `signing` module.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seal: Option<ResultSeal>,
+    /// The runs this result was merged from and the puzzle scores taken
+    /// from each; see `BenchmarkResult::merge`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_from: Vec<MergedRun>,
+}
+
+/// One of the runs a merged result was combined from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MergedRun {
+    pub timestamp: String,
+    /// The run's `signing::digest`, which pins down its answers and scores.
+    pub digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    /// The puzzles whose scores in the merged result are this run's.
+    pub puzzle_ids: Vec<String>,
+}
+
+/// Everything needed to trace a published number back to its exact inputs.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RunMetadata {
+    pub crate_version: String,
+    pub git_commit: Option<String>,
+    pub collection_name: String,
+    pub collection_checksum: String,
+    pub solver_parameters: BTreeMap<String, serde_json::Value>,
+    pub seeds: BTreeMap<String, u64>,
+    /// Platform details and non-secret configuration.
+    pub environment: BTreeMap<String, String>,
+    /// The part of the selection this run covered, when it was split
+    /// across machines; see `BenchmarkResult::merge_shards`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    /// How the run's puzzles were picked from the collection
+    /// `collection_checksum` identifies, unless all were run in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<PuzzleSelection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GameTypeScore {
+    pub game_type: String,
+    pub count: usize,
+    pub average_score: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencySummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenderStyleBreakdown {
+    pub board_themes: Vec<StyleScore>,
+    pub piece_styles: Vec<StyleScore>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StyleScore {
+    pub style: String,
+    pub count: usize,
+    pub average_score: f64,
+}
+
+/// Scores of the puzzles sharing a tag or metadata value.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GroupScore {
+    pub group: String,
+    pub count: usize,
+    pub average_score: f64,
+}
+
+/// Weighted average score per group of `scored`, ordered by group.
+fn group_scores<'a>(scored: impl IntoIterator<Item = (&'a str, &'a PuzzleScore)>) -> Vec<GroupScore> {
+    let mut groups: BTreeMap<&str, (usize, f64, f64)> = BTreeMap::new();
+    for (group, score) in scored {
+        let entry = groups.entry(group).or_insert((0, 0.0, 0.0));
+        entry.0 += 1;
+        entry.1 += score.weight * score.score;
+        entry.2 += score.weight * score.max_possible_score;
+    }
+    groups
+        .into_iter()
+        .map(|(group, (count, score, total_score))| GroupScore {
+            group: group.to_string(),
+            count,
+            average_score: if total_score > 0.0 { score / total_score } else { 0.0 },
+        })
+        .collect()
+}
+
+impl RenderStyleBreakdown {
+    /// Group `puzzle_scores` by the render style recorded on their puzzles;
+    /// `None` if no scored puzzle has one.
+    pub fn new(collection: &PuzzleCollection, puzzle_scores: &[PuzzleScore]) -> Option<Self> {
+        let styles: HashMap<&str, &RenderStyle> = collection
+            .puzzles
+            .iter()
+            .filter_map(|p| Some((p.id.as_str(), p.render_style.as_ref()?)))
+            .collect();
+        let scored: Vec<(&RenderStyle, &PuzzleScore)> = puzzle_scores
+            .iter()
+            .filter_map(|score| Some((*styles.get(score.puzzle_id.as_str())?, score)))
+            .collect();
+        if scored.is_empty() {
+            return None;
+        }
+
+        let group = |key: fn(&RenderStyle) -> &str| {
+            let mut groups: BTreeMap<&str, (usize, f64, f64)> = BTreeMap::new();
+            for (style, score) in &scored {
+                let entry = groups.entry(key(style)).or_insert((0, 0.0, 0.0));
+                entry.0 += 1;
+                entry.1 += score.weight * score.score;
+                entry.2 += score.weight * score.max_possible_score;
+            }
+            groups
+                .into_iter()
+                .map(|(style, (count, score, total_score))| StyleScore {
+                    style: style.to_string(),
+                    count,
+                    average_score: if total_score > 0.0 { score / total_score } else { 0.0 },
+                })
+                .collect()
+        };
+        Some(Self {
+            board_themes: group(|style| &style.board_theme),
+            piece_styles: group(|style| &style.piece_style),
+        })
+    }
+}
+
+impl BenchmarkResult {
+    /// Totals, per-game-type breakdown, time curve and judge average for
+    /// `puzzle_scores` on `collection`, with the scores of canary puzzles
+    /// set apart in a `CanaryReport`. Run-specific fields (passes, usage,
+    /// metadata) are left empty for the caller to fill in.
+    pub fn summarize(
+        benchmark_name: String,
+        solver_name: &str,
+        solver_description: &str,
+        collection: &PuzzleCollection,
+        puzzle_scores: Vec<PuzzleScore>,
+    ) -> Self {
+        let canaries = CanaryReport::from_scores(collection, &puzzle_scores);
+        let mut puzzle_scores = puzzle_scores;
+        if canaries.is_some() {
+            let canary_ids: HashSet<&str> =
+                collection.puzzles.iter().filter(|p| p.is_canary()).map(|p| p.id.as_str()).collect();
+            puzzle_scores.retain(|score| !canary_ids.contains(score.puzzle_id.as_str()));
+        }
+        puzzle_scores.sort_by(|a, b| a.puzzle_id.cmp(&b.puzzle_id));
+
+        let total_score: f64 = puzzle_scores.iter().map(|s| s.weight * s.score).sum();
+        let max_possible_score: f64 = puzzle_scores
+            .iter()
+            .map(|s| s.weight * s.max_possible_score)
+            .sum();
+        let total_puzzles = puzzle_scores.len();
+        let average_score = if max_possible_score > 0.0 {
+            total_score / max_possible_score
+        } else {
+            0.0
+        };
+
+        // Calculate game type breakdown
+        let mut game_type_scores: HashMap<String, (usize, f64, f64)> = HashMap::new();
+        let puzzle_game_types: HashMap<&str, &str> = collection
+            .puzzles
+            .iter()
+            .map(|p| (p.id.as_str(), collection.game_type_of(p)))
+            .collect();
+        for score in &puzzle_scores {
+            let game_type = puzzle_game_types
+                .get(score.puzzle_id.as_str())
+                .copied()
+                .unwrap_or(&collection.game_type);
+            let entry = game_type_scores
+                .entry(game_type.to_string())
+                .or_insert((0, 0.0, 0.0));
+            entry.0 += 1;
+            entry.1 += score.weight * score.score;
+            entry.2 += score.weight * score.max_possible_score;
+        }
+
+        let mut game_type_breakdown: Vec<GameTypeScore> = game_type_scores
+            .into_iter()
+            .map(|(game_type, (count, score, total_score))| GameTypeScore {
+                game_type,
+                count,
+                average_score: if total_score > 0.0 {
+                    score / total_score
+                } else {
+                    0.0
+                },
+                latency: None,
+            })
+            .collect();
+
+        game_type_breakdown.sort_by(|a, b| a.game_type.cmp(&b.game_type));
+
+        let puzzle_tags: HashMap<&str, &[String]> = collection
+            .puzzles
+            .iter()
+            .map(|p| (p.id.as_str(), p.tags.as_slice()))
+            .collect();
+        let tag_breakdown = group_scores(puzzle_scores.iter().flat_map(|score| {
+            let tags = puzzle_tags.get(score.puzzle_id.as_str()).copied().unwrap_or_default();
+            tags.iter().map(move |tag| (tag.as_str(), score))
+        }));
+
+        let skill_profile = skill_profile(collection, &puzzle_scores);
+        let time_curve = time_curve(&puzzle_scores);
+        let judged: Vec<f64> = puzzle_scores
+            .iter()
+            .flat_map(|s| s.reasoning_scores.iter().flatten().copied())
+            .collect();
+        let reasoning_score =
+            (!judged.is_empty()).then(|| judged.iter().sum::<f64>() / judged.len() as f64);
+        let confidence = ConfidenceCalibration::from_scores(&puzzle_scores);
+
+        let mut result = BenchmarkResult {
+            result_version: RESULT_VERSION,
+            benchmark_name,
+            solver_name: solver_name.to_string(),
+            solver_description: solver_description.to_string(),
+            total_puzzles,
+            total_score,
+            max_possible_score,
+            average_score,
+            puzzle_scores,
+            game_type_breakdown,
+            render_style_breakdown: None,
+            tag_breakdown,
+            skill_profile,
+            metadata_breakdown: BTreeMap::new(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            pass_results: None,
+            time_curve,
+            latency: None,
+            reasoning_score,
+            confidence,
+            usage: None,
+            budget_exceeded: false,
+            interrupted: false,
+            unanswered_puzzles: Vec::new(),
+            early_stop: None,
+            canaries,
+            modality: Modality::default(),
+            representation: Representation::default(),
+            prompt_style: PromptStyle::default(),
+            compute_budget: ComputeBudget::default(),
+            prompt_language: collection.language,
+            metadata: None,
+            seal: None,
+            merged_from: Vec::new(),
+        };
+        result.summarize_latency(collection);
+        result
+    }
+
+    /// Work out `latency`, overall and per game type, from every attempt at
+    /// a puzzle: each pass's scores when there were several, so puzzle wall
+    /// times aren't summed over passes. Call again after setting
+    /// `pass_results`.
+    pub fn summarize_latency(&mut self, collection: &PuzzleCollection) {
+        let scores: Vec<&PuzzleScore> = match &self.pass_results {
+            Some(pass_results) if !pass_results.individual_pass_scores.is_empty() => {
+                pass_results.individual_pass_scores.iter().flatten().collect()
+            }
+            _ => self.puzzle_scores.iter().collect(),
+        };
+        self.latency = LatencySummary::from_scores(scores.iter().copied());
+        let game_types: HashMap<&str, &str> = collection
+            .puzzles
+            .iter()
+            .map(|p| (p.id.as_str(), collection.game_type_of(p)))
+            .collect();
+        for breakdown in &mut self.game_type_breakdown {
+            breakdown.latency = LatencySummary::from_scores(scores.iter().copied().filter(|score| {
+                game_types.get(score.puzzle_id.as_str()).copied().unwrap_or(&collection.game_type) == breakdown.game_type
+            }));
+        }
+    }
+
+    /// Break the scores down by the value of `key` in the puzzles'
+    /// `metadata`, e.g. `difficulty`, or else in their provenance, e.g.
+    /// `license`; puzzles without the key are left out.
+    pub fn add_metadata_breakdown(&mut self, collection: &PuzzleCollection, key: &str) {
+        let values: HashMap<&str, String> = collection
+            .puzzles
+            .iter()
+            .filter_map(|p| {
+                let value = p
+                    .metadata_text(key)
+                    .or_else(|| collection.provenance_of(p).field(key).map(str::to_string))?;
+                Some((p.id.as_str(), value))
+            })
+            .collect();
+        let scores = group_scores(
+            self.puzzle_scores
+                .iter()
+                .filter_map(|score| Some((values.get(score.puzzle_id.as_str())?.as_str(), score))),
+        );
+        self.metadata_breakdown.insert(key.to_string(), scores);
+    }
+
+    /// Score the recorded answers to `collection` again with `scorer` and
+    /// `answer_scoring`, e.g. after fixing a solution, without asking the
+    /// solver again. Answers are extracted anew from recorded responses,
+    /// judge ratings and engine losses are reused, and time-budget penalties
+    /// keep their share. With several passes every pass is rescored and the
+    /// pass statistics recomputed. Puzzles no longer in `collection` keep
+    /// their scores.
+    pub fn rescore(&mut self, collection: &PuzzleCollection, scorer: Scorer, answer_scoring: AnswerScoring) {
+        let puzzles: HashMap<&str, &Puzzle> = collection.puzzles.iter().map(|p| (p.id.as_str(), p)).collect();
+        let rescore = |score: &PuzzleScore| match puzzles.get(score.puzzle_id.as_str()) {
+            Some(puzzle) => rescored(puzzle, collection, scorer, answer_scoring, score),
+            None => score.clone(),
+        };
+        let puzzle_scores = match self.pass_results.take() {
+            Some(pass_results) => {
+                let passes = pass_results
+                    .individual_pass_scores
+                    .iter()
+                    .map(|pass| pass.iter().map(rescore).collect())
+                    .collect();
+                let (pass_results, aggregate) = PassResults::from_passes(passes);
+                self.pass_results = Some(pass_results);
+                aggregate
+            }
+            None => self.puzzle_scores.iter().map(rescore).collect(),
+        };
+
+        let fresh = Self::summarize(String::new(), "", "", collection, puzzle_scores);
+        self.total_puzzles = fresh.total_puzzles;
+        self.total_score = fresh.total_score;
+        self.max_possible_score = fresh.max_possible_score;
+        self.average_score = fresh.average_score;
+        self.game_type_breakdown = fresh.game_type_breakdown;
+        self.tag_breakdown = fresh.tag_breakdown;
+        self.skill_profile = fresh.skill_profile;
+        self.time_curve = fresh.time_curve;
+        self.confidence = fresh.confidence;
+        self.puzzle_scores = fresh.puzzle_scores;
+        if self.render_style_breakdown.is_some() {
+            self.render_style_breakdown = RenderStyleBreakdown::new(collection, &self.puzzle_scores);
+        }
+        let keys: Vec<String> = self.metadata_breakdown.keys().cloned().collect();
+        for key in keys {
+            self.add_metadata_breakdown(collection, &key);
+        }
+        self.summarize_latency(collection);
+    }
+
+    /// Read a result of any `result_version` up to this build's, upgrading
+    /// older ones; see `from_value`.
+    pub fn from_json(json: &str) -> Result<Self, ResultFormatError> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Read a result from parsed JSON. Results written before versioning
+    /// may lack totals and fields every result now has: the totals are
+    /// worked out from the puzzle scores and the rest left empty, and the
+    /// result comes back in this build's format. Newer results are read as
+    /// far as this build understands them and keep their version; see
+    /// `check_writable`.
+    pub fn from_value(mut value: serde_json::Value) -> Result<Self, ResultFormatError> {
+        let version = value
+            .get("result_version")
+            .and_then(serde_json::Value::as_u64)
+            .map_or(UNVERSIONED_RESULT_VERSION, |version| version as u32);
+        if version <= UNVERSIONED_RESULT_VERSION {
+            upgrade_unversioned(&mut value);
+        }
+        match serde_json::from_value::<Self>(value) {
+            Ok(mut result) => {
+                result.result_version = version.max(RESULT_VERSION);
+                Ok(result)
+            }
+            Err(e) if version > RESULT_VERSION => Err(ResultFormatError::Unreadable(version, e)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Fail if the result is from a newer `result_version` than this build
+    /// writes: fields it doesn't know were dropped when it was read, so
+    /// writing it back would lose them.
+    pub fn check_writable(&self) -> Result<(), ResultFormatError> {
+        if self.result_version > RESULT_VERSION {
+            return Err(ResultFormatError::Newer(self.result_version));
+        }
+        Ok(())
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, ResultFormatError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Recombine the results of one solver's `--shard` runs on `collection`
+    /// into the result of a single run; see `merge`. When the shards
+    /// recorded which part they covered, all parts must be there exactly
+    /// once, and no puzzle may be scored by more than one shard.
+    pub fn merge_shards(collection: &PuzzleCollection, shards: Vec<BenchmarkResult>) -> Result<Self, MergeError> {
+        let parts: Vec<Shard> = shards
+            .iter()
+            .filter_map(|shard| shard.metadata.as_ref()?.shard)
+            .collect();
+        if let Some(count) = parts.first().map(|part| part.count) {
+            if let Some(other) = parts.iter().find(|part| part.count != count) {
+                return Err(MergeError::MismatchedShard(*other, count));
+            }
+            for index in 1..=count {
+                let part = Shard { index, count };
+                match parts.iter().filter(|p| **p == part).count() {
+                    0 => return Err(MergeError::MissingShard(part)),
+                    1 => {}
+                    _ => return Err(MergeError::DuplicateShard(part)),
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for score in shards.iter().flat_map(|shard| &shard.puzzle_scores) {
+            if !seen.insert(score.puzzle_id.as_str()) {
+                return Err(MergeError::DuplicatePuzzle(score.puzzle_id.clone()));
+            }
+        }
+
+        Self::merge(collection, shards)
+    }
+
+    /// Combine results of one solver on `collection` into one canonical
+    /// result: shards, a run and its resumption, or a run and a rerun of the
+    /// puzzles it failed, all with the same number of passes and any run
+    /// metadata naming `collection`'s checksum. A puzzle scored by several
+    /// runs keeps the score (and pass scores) of the last. Usage and
+    /// canaries are pooled over every run, and every total and breakdown
+    /// recomputed. The result was cut short by the budget or an interruption
+    /// if some run was and puzzles of `collection` are still unscored, and
+    /// puzzles a queued run left unanswered stay listed unless another run
+    /// scored them. The metadata is the first run's, with the checksum of
+    /// `collection`. `merged_from` lists each run and the puzzles taken from
+    /// it, a run that was itself merged standing for the runs it lists.
+    pub fn merge(collection: &PuzzleCollection, runs: Vec<BenchmarkResult>) -> Result<Self, MergeError> {
+        let first = runs.first().ok_or(MergeError::Empty)?;
+        if let Some(other) = runs.iter().find(|run| run.solver_name != first.solver_name) {
+            return Err(MergeError::DifferentSolvers(first.solver_name.clone(), other.solver_name.clone()));
+        }
+        // Passes are merged by index, and puzzles missing from a pass dropped.
+        let pass_count = |run: &BenchmarkResult| run.pass_results.as_ref().map_or(1, |p| p.individual_pass_scores.len());
+        if let Some(other) = runs.iter().find(|run| pass_count(run) != pass_count(first)) {
+            return Err(MergeError::DifferentPasses(pass_count(first), pass_count(other)));
+        }
+        let checksum = collection.source_checksum();
+        if let Some(metadata) = runs
+            .iter()
+            .filter_map(|run| run.metadata.as_ref())
+            .find(|metadata| metadata.collection_checksum != checksum)
+        {
+            return Err(MergeError::DifferentCollection(metadata.collection_checksum.clone(), checksum));
+        }
+
+        // Later runs overwrite earlier ones, so each puzzle maps to the
+        // last run that scored it.
+        let owners: HashMap<&str, usize> = runs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, run)| run.puzzle_scores.iter().map(move |score| (score.puzzle_id.as_str(), i)))
+            .collect();
+        let taken = |i: usize, score: &PuzzleScore| owners.get(score.puzzle_id.as_str()) == Some(&i);
+
+        let mut puzzle_scores = Vec::new();
+        let mut passes: Vec<Vec<PuzzleScore>> = Vec::new();
+        let mut usage: Option<UsageSummary> = None;
+        let mut canaries: Option<CanaryReport> = None;
+        let mut merged_from = Vec::new();
+        for (i, run) in runs.iter().enumerate() {
+            puzzle_scores.extend(run.puzzle_scores.iter().filter(|score| taken(i, score)).cloned());
+            if let Some(pass_results) = &run.pass_results {
+                for (pass_index, pass) in pass_results.individual_pass_scores.iter().enumerate() {
+                    if passes.len() <= pass_index {
+                        passes.push(Vec::new());
+                    }
+                    passes[pass_index].extend(pass.iter().filter(|score| taken(i, score)).cloned());
+                }
+            }
+            if let Some(run_usage) = &run.usage {
+                let total = usage.get_or_insert_with(UsageSummary::default);
+                total.requests += run_usage.requests;
+                total.prompt_tokens += run_usage.prompt_tokens;
+                total.completion_tokens += run_usage.completion_tokens;
+                total.cost += run_usage.cost;
+            }
+            if let Some(run_canaries) = &run.canaries {
+                let total = canaries.get_or_insert_with(CanaryReport::default);
+                total.canaries += run_canaries.canaries;
+                total.reproduced.extend(run_canaries.reproduced.iter().cloned());
+            }
+            let kept: HashSet<&str> = run
+                .puzzle_scores
+                .iter()
+                .filter(|score| taken(i, score))
+                .map(|score| score.puzzle_id.as_str())
+                .collect();
+            merged_from.extend(sub_runs(run, &kept));
+        }
+        let pass_results = (!passes.is_empty()).then(|| PassResults::from_passes(passes).0);
+
+        let mut merged = Self::summarize(
+            first.benchmark_name.clone(),
+            &first.solver_name,
+            &first.solver_description,
+            collection,
+            puzzle_scores,
+        );
+        merged.pass_results = pass_results;
+        merged.summarize_latency(collection);
+        merged.usage = usage;
+        merged.canaries = canaries;
+        let complete = collection
+            .puzzles
+            .iter()
+            .filter(|puzzle| !puzzle.is_canary())
+            .all(|puzzle| owners.contains_key(puzzle.id.as_str()));
+        merged.budget_exceeded = !complete && runs.iter().any(|run| run.budget_exceeded);
+        merged.interrupted = !complete && runs.iter().any(|run| run.interrupted);
+        let unanswered: BTreeSet<&String> = runs
+            .iter()
+            .flat_map(|run| &run.unanswered_puzzles)
+            .filter(|id| !owners.contains_key(id.as_str()))
+            .collect();
+        merged.unanswered_puzzles = unanswered.into_iter().cloned().collect();
+        merged.modality = first.modality;
+        merged.representation = first.representation;
+        merged.prompt_style = first.prompt_style;
+        merged.prompt_language = first.prompt_language;
+        merged.compute_budget = first.compute_budget;
+        merged.metadata = first.metadata.clone().map(|mut metadata| {
+            metadata.collection_checksum = checksum;
+            metadata.shard = None;
+            metadata
+        });
+        if runs.iter().any(|run| run.render_style_breakdown.is_some()) {
+            merged.render_style_breakdown = RenderStyleBreakdown::new(collection, &merged.puzzle_scores);
+        }
+        let keys: BTreeSet<&String> = runs.iter().flat_map(|run| run.metadata_breakdown.keys()).collect();
+        for key in keys {
+            merged.add_metadata_breakdown(collection, key);
+        }
+        merged.merged_from = merged_from;
+        Ok(merged)
+    }
+}
+
+/// The runs `run` stands for in a merge that keeps its scores of `kept`:
+/// itself, or the runs it was merged from.
+fn sub_runs(run: &BenchmarkResult, kept: &HashSet<&str>) -> Vec<MergedRun> {
+    if run.merged_from.is_empty() {
+        return vec![MergedRun {
+            timestamp: run.timestamp.clone(),
+            digest: signing::digest(run),
+            shard: run.metadata.as_ref().and_then(|metadata| metadata.shard),
+            puzzle_ids: run
+                .puzzle_scores
+                .iter()
+                .map(|score| score.puzzle_id.clone())
+                .filter(|id| kept.contains(id.as_str()))
+                .collect(),
+        }];
+    }
+    run.merged_from
+        .iter()
+        .map(|sub_run| MergedRun {
+            puzzle_ids: sub_run.puzzle_ids.iter().filter(|id| kept.contains(id.as_str())).cloned().collect(),
+            ..sub_run.clone()
+        })
+        .collect()
+}
+
+/// Why results can't be merged; see `BenchmarkResult::merge` and
+/// `BenchmarkResult::merge_shards`.
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("no results to merge")]
+    Empty,
+    #[error("results are from different solvers: {0} and {1}")]
+    DifferentSolvers(String, String),
+    #[error("shard {0} is missing")]
+    MissingShard(Shard),
+    #[error("shard {0} is there more than once")]
+    DuplicateShard(Shard),
+    #[error("shard {0} doesn't fit a split into {1}")]
+    MismatchedShard(Shard, usize),
+    #[error("puzzle {0} was scored in more than one shard")]
+    DuplicatePuzzle(String),
+    #[error("results have different numbers of passes: {0} and {1}")]
+    DifferentPasses(usize, usize),
+    #[error("a result was run on collection {0}, not {1}")]
+    DifferentCollection(String, String),
+}
+
+/// Why a result file can't be read; see `BenchmarkResult::from_json`.
+#[derive(Debug, Error)]
+pub enum ResultFormatError {
+    #[error("can't read the result file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid result: {0}")]
+    Invalid(#[from] serde_json::Error),
+    #[error("result_version {0} is newer than this build reads (up to {RESULT_VERSION}): {1}")]
+    Unreadable(u32, serde_json::Error),
+    #[error("result_version {0} is newer than this build writes (up to {RESULT_VERSION}); rewriting it would lose data")]
+    Newer(u32),
+}
+
+/// Fill in what a result written before `result_version` may lack: names,
+/// description, timestamp and game type breakdown are left empty, and the
+/// totals are worked out from `puzzle_scores`.
+fn upgrade_unversioned(value: &mut serde_json::Value) {
+    let Some(result) = value.as_object_mut() else {
+        return;
+    };
+    let (mut total, mut max, mut count) = (0.0, 0.0, 0);
+    for score in result.get("puzzle_scores").and_then(|scores| scores.as_array()).into_iter().flatten() {
+        let field = |key: &str| score.get(key).and_then(serde_json::Value::as_f64).unwrap_or_default();
+        let weight = score.get("weight").and_then(serde_json::Value::as_f64).unwrap_or(1.0);
+        total += weight * field("score");
+        max += weight * field("max_possible_score");
+        count += 1;
+    }
+    let average = if max > 0.0 { total / max } else { 0.0 };
+    for (key, default) in [
+        ("benchmark_name", serde_json::json!("")),
+        ("solver_name", serde_json::json!("")),
+        ("solver_description", serde_json::json!("")),
+        ("timestamp", serde_json::json!("")),
+        ("puzzle_scores", serde_json::json!([])),
+        ("game_type_breakdown", serde_json::json!([])),
+        ("total_puzzles", serde_json::json!(count)),
+        ("total_score", serde_json::json!(total)),
+        ("max_possible_score", serde_json::json!(max)),
+        ("average_score", serde_json::json!(average)),
+    ] {
+        result.entry(key).or_insert(default);
+    }
+}
+
+/// `old`'s answers to `puzzle` scored again; see `BenchmarkResult::rescore`.
+fn rescored(
+    puzzle: &Puzzle,
+    collection: &PuzzleCollection,
+    scorer: Scorer,
+    answer_scoring: AnswerScoring,
+    old: &PuzzleScore,
+) -> PuzzleScore {
+    let format = collection.answer_format_of(puzzle);
+    let extracted: Vec<_> = old
+        .responses
+        .iter()
+        .zip(&puzzle.game_states)
+        .map(|(response, state)| extract_answer(response, state, &format))
+        .collect();
+    let texts: Vec<&str> = if old.responses.is_empty() {
+        old.answers.iter().map(String::as_str).collect()
+    } else {
+        extracted.iter().map(|e| e.answer.as_str()).collect()
+    };
+    let answers: Vec<StateAnswer> = texts
+        .iter()
+        .enumerate()
+        .map(|(i, answer)| StateAnswer {
+            answer,
+            judge_score: old.reasoning_scores.get(i).copied().flatten(),
+            engine_loss: old.engine_losses.get(i).copied().flatten(),
+            confidence: old.forecasts.get(i).copied().flatten().map(|forecast| forecast.confidence),
+        })
+        .collect();
+    let mut score = scorer.score_with(puzzle, collection, &answers, answer_scoring.scorer());
+    if score.forecasts.is_empty() {
+        score.forecasts = vec![None; old.forecasts.len()];
+    }
+    score.extraction_methods = if old.responses.is_empty() {
+        old.extraction_methods.clone()
+    } else {
+        extracted.iter().map(|e| e.method).collect()
+    };
+    score.elapsed_ms = old.elapsed_ms;
+    score.request_latencies_ms = old.request_latencies_ms.clone();
+    score.reasoning_scores = old.reasoning_scores.clone();
+    score.engine_losses = old.engine_losses.clone();
+    score.responses = old.responses.clone();
+    score.skipped_states = old.skipped_states;
+    score.compression = old.compression.clone();
+    if let Some(unpenalized) = old.unpenalized_score
+        && unpenalized > 0.0
+    {
+        score.unpenalized_score = Some(score.score);
+        score.score *= old.score / unpenalized;
+    }
+    score
+}
+
+/// Results of several solvers on the same collection.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComparisonReport {
+    pub collection_name: String,
+    pub timestamp: String,
+    pub results: Vec<BenchmarkResult>,
+    /// Each model's scores side by side, for runs that showed the same
+    /// puzzles in several representations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub representation_ablation: Option<RepresentationAblation>,
+    /// Each model's scores side by side, for runs that asked for the same
+    /// puzzles with several prompt styles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_ablation: Option<PromptAblation>,
+    /// Each model's score and spending under each compute budget, for
+    /// accuracy-vs-compute curves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_sweep: Option<ComputeSweep>,
+    /// Each model's score against its cost and latency, with the Pareto
+    /// optimal ones marked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frontier: Option<FrontierReport>,
+}
+
+impl ComparisonReport {
+    pub fn new(collection_name: &str, results: Vec<BenchmarkResult>) -> Self {
+        Self {
+            collection_name: collection_name.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            results,
+            representation_ablation: None,
+            prompt_ablation: None,
+            compute_sweep: None,
+            frontier: None,
+        }
+    }
+
+    /// Read a report, upgrading each of its results from older versions; see
+    /// `BenchmarkResult::from_value`.
+    pub fn from_json(json: &str) -> Result<Self, ResultFormatError> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let results = match value.get_mut("results") {
+            Some(serde_json::Value::Array(results)) => std::mem::take(results),
+            _ => return Err(<serde_json::Error as serde::de::Error>::missing_field("results").into()),
+        };
+        let mut report: Self = serde_json::from_value(value)?;
+        report.results = results
+            .into_iter()
+            .map(BenchmarkResult::from_value)
+            .collect::<Result<_, _>>()?;
+        Ok(report)
+    }
+
+    /// Results ordered from best to worst average score.
+    pub fn ranking(&self) -> Vec<&BenchmarkResult> {
+        let mut ranked: Vec<&BenchmarkResult> = self.results.iter().collect();
+        ranked.sort_by(|a, b| b.average_score.total_cmp(&a.average_score));
+        ranked
+    }
+}
+
+/// How each model scored with each way of showing it the puzzles (FEN, a
+/// board diagram, PGN, an image...).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RepresentationAblation {
+    pub representations: Vec<String>,
+    pub models: Vec<AblationRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AblationRow {
+    pub model: String,
+    /// Average score under each variant of the ablation, in its order.
+    pub average_scores: Vec<f64>,
+    /// Best score minus worst score.
+    pub spread: f64,
+}
+
+impl RepresentationAblation {
+    /// Tabulate `results` of running every model in `models` under every
+    /// representation, ordered model by model with the representations in
+    /// the order given.
+    pub fn new(models: &[String], representations: &[String], results: &[BenchmarkResult]) -> Self {
+        Self {
+            representations: representations.to_vec(),
+            models: ablation_rows(models, representations.len(), results),
+        }
+    }
+}
+
+/// How each model scored with each style of instructions (full guidance,
+/// minimal, answer only...).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptAblation {
+    pub prompt_styles: Vec<String>,
+    pub models: Vec<AblationRow>,
+}
+
+impl PromptAblation {
+    /// Like `RepresentationAblation::new`, with the results ordered model
+    /// by model with the prompt styles in the order given.
+    pub fn new(models: &[String], prompt_styles: &[String], results: &[BenchmarkResult]) -> Self {
+        Self {
+            prompt_styles: prompt_styles.to_vec(),
+            models: ablation_rows(models, prompt_styles.len(), results),
+        }
+    }
+}
+
+/// How each model did under each compute budget of a sweep.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeSweep {
+    pub budgets: Vec<ComputeBudget>,
+    pub models: Vec<ComputeSweepRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeSweepRow {
+    pub model: String,
+    /// One point per budget, in the sweep's order.
+    pub points: Vec<ComputePoint>,
+}
+
+/// A model's accuracy and what it spent under one budget.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComputePoint {
+    pub average_score: f64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+impl ComputeSweep {
+    /// Like `RepresentationAblation::new`, with the results ordered model
+    /// by model with the budgets in the order given.
+    pub fn new(models: &[String], budgets: &[ComputeBudget], results: &[BenchmarkResult]) -> Self {
+        let models = models
+            .iter()
+            .zip(results.chunks(budgets.len().max(1)))
+            .map(|(model, results)| ComputeSweepRow {
+                model: model.clone(),
+                points: results
+                    .iter()
+                    .map(|result| {
+                        let usage = result.usage.clone().unwrap_or_default();
+                        ComputePoint {
+                            average_score: result.average_score,
+                            completion_tokens: usage.completion_tokens,
+                            cost: usage.cost,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            budgets: budgets.to_vec(),
+            models,
+        }
+    }
+}
+
+/// One row per model from `results`, which hold `variants` consecutive
+/// results per model.
+fn ablation_rows(models: &[String], variants: usize, results: &[BenchmarkResult]) -> Vec<AblationRow> {
+    models
+        .iter()
+        .zip(results.chunks(variants.max(1)))
+        .map(|(model, results)| {
+            let average_scores: Vec<f64> = results.iter().map(|result| result.average_score).collect();
+            let best = average_scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let worst = average_scores.iter().copied().fold(f64::INFINITY, f64::min);
+            AblationRow {
+                model: model.clone(),
+                spread: if average_scores.is_empty() { 0.0 } else { best - worst },
+                average_scores,
+            }
+        })
+        .collect()
+}
+
+/// Average score under each hard limit in `TIME_CURVE_BUDGETS_SECS`,
+/// using scores before any time-budget penalty.
+/// Drop puzzles that some pass skipped (budget exhausted), keeping the
+/// per-pass score lists index-aligned.
+fn completed_in_every_pass(all_pass_scores: Vec<Vec<PuzzleScore>>) -> Vec<Vec<PuzzleScore>> {
+    let complete: Vec<String> = all_pass_scores[0]
+        .iter()
+        .map(|s| s.puzzle_id.clone())
+        .filter(|id| {
+            all_pass_scores
+                .iter()
+                .all(|pass| pass.iter().any(|s| &s.puzzle_id == id))
+        })
+        .collect();
+
+    all_pass_scores
+        .into_iter()
+        .map(|pass| {
+            pass.into_iter()
+                .filter(|s| complete.contains(&s.puzzle_id))
+                .collect()
+        })
+        .collect()
+}
+
+fn average_score(puzzle_scores: &[PuzzleScore]) -> f64 {
+    let score: f64 = puzzle_scores.iter().map(|s| s.weight * s.score).sum();
+    let max_possible_score: f64 = puzzle_scores
+        .iter()
+        .map(|s| s.weight * s.max_possible_score)
+        .sum();
+    if max_possible_score > 0.0 {
+        score / max_possible_score
+    } else {
+        0.0
+    }
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    variance.sqrt()
+}
+
+fn answer_distributions(all_pass_scores: &[Vec<PuzzleScore>]) -> Vec<AnswerDistribution> {
+    (0..all_pass_scores[0].len())
+        .map(|puzzle_idx| {
+            let mut states: Vec<BTreeMap<String, usize>> = Vec::new();
+            for pass_scores in all_pass_scores {
+                let answers = &pass_scores[puzzle_idx].answers;
+                if states.len() < answers.len() {
+                    states.resize(answers.len(), BTreeMap::new());
+                }
+                for (state, answer) in states.iter_mut().zip(answers) {
+                    *state.entry(answer.clone()).or_insert(0) += 1;
+                }
+            }
+            AnswerDistribution {
+                puzzle_id: all_pass_scores[0][puzzle_idx].puzzle_id.clone(),
+                states,
+            }
+        })
+        .collect()
+}
+
+fn calculate_pass_rate(all_pass_scores: &[Vec<PuzzleScore>], k: usize) -> f64 {
+    let num_puzzles = all_pass_scores[0].len();
+    if num_puzzles == 0 {
+        return 0.0;
+    }
+    let mut passed_count = 0;
+
+    for puzzle_idx in 0..num_puzzles {
+        let mut max_score = 0.0;
+        for pass_scores in all_pass_scores.iter().take(k) {
+            let score = pass_scores[puzzle_idx].score;
+            let max_possible = pass_scores[puzzle_idx].max_possible_score;
+            if score == max_possible {
+                max_score = max_possible;
+                break;
+            }
+        }
+        if max_score > 0.0 {
+            passed_count += 1;
+        }
+    }
+
+    passed_count as f64 / num_puzzles as f64
+}
+
+fn aggregate_scores(all_pass_scores: &[Vec<PuzzleScore>]) -> Vec<PuzzleScore> {
+    let num_puzzles = all_pass_scores[0].len();
+    let mut aggregated = Vec::with_capacity(num_puzzles);
+
+    for puzzle_idx in 0..num_puzzles {
+        let mut score = 0.0;
+        let mut max_possible_score = 0.0;
+        let mut extraction_methods = Vec::new();
+        let mut elapsed_ms = 0;
+        let mut request_latencies_ms = Vec::new();
+        let mut unpenalized_score = None;
+        let mut reasoning_scores = Vec::new();
+        let mut engine_losses = Vec::new();
+        let mut forecasts = Vec::new();
+        let mut skipped_states = 0;
+        let mut compression = Vec::new();
+        let puzzle_id = all_pass_scores[0][puzzle_idx].puzzle_id.clone();
+        let weight = all_pass_scores[0][puzzle_idx].weight;
+
+        for pass_scores in all_pass_scores {
+            score += pass_scores[puzzle_idx].score;
+            max_possible_score += pass_scores[puzzle_idx].max_possible_score;
+            extraction_methods.extend(&pass_scores[puzzle_idx].extraction_methods);
+            // The slowest pass, so the time stays per attempt like the
+            // budgets it is compared against.
+            elapsed_ms = elapsed_ms.max(pass_scores[puzzle_idx].elapsed_ms);
+            request_latencies_ms.extend(&pass_scores[puzzle_idx].request_latencies_ms);
+            reasoning_scores.extend(&pass_scores[puzzle_idx].reasoning_scores);
+            engine_losses.extend(&pass_scores[puzzle_idx].engine_losses);
+            forecasts.extend(&pass_scores[puzzle_idx].forecasts);
+            skipped_states += pass_scores[puzzle_idx].skipped_states;
+            compression.extend(&pass_scores[puzzle_idx].compression);
+            if let Some(unpenalized) = pass_scores[puzzle_idx].unpenalized_score {
+                *unpenalized_score.get_or_insert(0.0) +=
+                    unpenalized - pass_scores[puzzle_idx].score;
+            }
+        }
+
+        compression.sort();
+        compression.dedup();
+        aggregated.push(PuzzleScore {
+            puzzle_id,
+            score,
+            max_possible_score,
+            answers: Vec::new(),
+            extraction_methods,
+            weight,
+            elapsed_ms,
+            request_latencies_ms,
+            unpenalized_score: unpenalized_score.map(|penalty| score + penalty),
+            reasoning_scores,
+            engine_losses,
+            responses: Vec::new(),
+            forecasts,
+            skipped_states,
+            compression,
+        });
+    }
+
+    aggregated
+}
+
+fn time_curve(puzzle_scores: &[PuzzleScore]) -> Vec<TimeBudgetPoint> {
+    let max_possible_score: f64 = puzzle_scores
+        .iter()
+        .map(|s| s.weight * s.max_possible_score)
+        .sum();
+    if max_possible_score <= 0.0 {
+        return Vec::new();
+    }
+
+    TIME_CURVE_BUDGETS_SECS
+        .iter()
+        .map(|secs| {
+            let budget_ms = secs * 1000;
+            let score: f64 = puzzle_scores
+                .iter()
+                .filter(|s| s.elapsed_ms <= budget_ms)
+                .map(|s| s.weight * s.unpenalized_score.unwrap_or(s.score))
+                .sum();
+            TimeBudgetPoint {
+                budget_ms,
+                average_score: score / max_possible_score,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::testing::{random_collection, responses};
+
+    /// A result on the puzzles of `collection` at `indices`,
+    /// answered correctly with probability `accuracy`.
+    fn run(collection: &PuzzleCollection, indices: std::ops::Range<usize>, accuracy: f64, seed: u64) -> BenchmarkResult {
+        let all_responses = responses(collection, accuracy, seed);
+        let scores = indices
+            .map(|i| Scorer::PerState.score_responses(&collection.puzzles[i], collection, &all_responses[i]))
+            .collect();
+        let mut result = BenchmarkResult::summarize("test".to_string(), "solver", "", collection, scores);
+        result.metadata = Some(RunMetadata {
+            crate_version: String::new(),
+            git_commit: None,
+            collection_name: collection.name.clone(),
+            collection_checksum: collection.checksum(),
+            solver_parameters: BTreeMap::new(),
+            seeds: BTreeMap::new(),
+            environment: BTreeMap::new(),
+            shard: None,
+            selection: None,
+        });
+        result
+    }
+
+    #[test]
+    fn merge_keeps_the_last_score_of_each_puzzle() {
+        let collection = random_collection(6, 1);
+        let mut failed = run(&collection, 0..6, 0.0, 0);
+        failed.interrupted = true;
+        let rerun = run(&collection, 2..4, 1.0, 0);
+        let merged = BenchmarkResult::merge(&collection, vec![failed, rerun]).unwrap();
+
+        assert_eq!(merged.total_puzzles, 6);
+        for score in &merged.puzzle_scores {
+            let rerun = score.puzzle_id == collection.puzzles[2].id || score.puzzle_id == collection.puzzles[3].id;
+            assert_eq!(score.score == score.max_possible_score, rerun, "{}", score.puzzle_id);
+        }
+        let expected: f64 = merged.puzzle_scores.iter().map(|s| s.weight * s.score).sum();
+        assert_eq!(merged.total_score, expected);
+        assert!(!merged.interrupted, "every puzzle is scored");
+        assert_eq!(merged.merged_from.len(), 2);
+        assert_eq!(merged.merged_from[0].puzzle_ids.len(), 4);
+        assert_eq!(merged.merged_from[1].puzzle_ids.len(), 2);
+    }
+
+    #[test]
+    fn merge_of_a_partial_run_stays_interrupted() {
+        let collection = random_collection(6, 2);
+        let mut first = run(&collection, 0..2, 1.0, 0);
+        first.interrupted = true;
+        let second = run(&collection, 2..4, 1.0, 0);
+        let merged = BenchmarkResult::merge(&collection, vec![first, second]).unwrap();
+        assert_eq!(merged.total_puzzles, 4);
+        assert!(merged.interrupted);
+    }
+
+    #[test]
+    fn merge_refuses_mismatched_runs() {
+        let collection = random_collection(4, 3);
+        let other = random_collection(4, 4);
+
+        let mut renamed = run(&collection, 2..4, 1.0, 0);
+        renamed.solver_name = "other".to_string();
+        assert!(matches!(
+            BenchmarkResult::merge(&collection, vec![run(&collection, 0..2, 1.0, 0), renamed]),
+            Err(MergeError::DifferentSolvers(..))
+        ));
+
+        let mut two_passes = run(&collection, 2..4, 1.0, 0);
+        let pass = two_passes.puzzle_scores.clone();
+        two_passes.pass_results = Some(PassResults::from_passes(vec![pass.clone(), pass]).0);
+        assert!(matches!(
+            BenchmarkResult::merge(&collection, vec![run(&collection, 0..2, 1.0, 0), two_passes]),
+            Err(MergeError::DifferentPasses(1, 2))
+        ));
+
+        assert!(matches!(
+            BenchmarkResult::merge(&collection, vec![run(&collection, 0..2, 1.0, 0), run(&other, 2..4, 1.0, 0)]),
+            Err(MergeError::DifferentCollection(..))
+        ));
+        assert!(matches!(BenchmarkResult::merge(&collection, Vec::new()), Err(MergeError::Empty)));
+    }
+
+    #[test]
+    fn unversioned_results_are_upgraded() {
+        let collection = random_collection(5, 5);
+        let result = run(&collection, 0..5, 0.6, 1);
+        let mut value = serde_json::to_value(&result).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        for key in ["result_version", "solver_name", "total_puzzles", "total_score", "max_possible_score", "average_score"] {
+            fields.remove(key);
+        }
+
+        let upgraded = BenchmarkResult::from_value(value).unwrap();
+        assert_eq!(upgraded.result_version, RESULT_VERSION);
+        assert_eq!(upgraded.solver_name, "");
+        assert_eq!(upgraded.total_puzzles, 5);
+        assert!((upgraded.total_score - result.total_score).abs() < 1e-9);
+        assert!((upgraded.average_score - result.average_score).abs() < 1e-9);
+        assert!(upgraded.check_writable().is_ok());
+    }
+
+    #[test]
+    fn newer_results_are_read_but_not_written() {
+        let collection = random_collection(3, 6);
+        let mut value = serde_json::to_value(run(&collection, 0..3, 1.0, 0)).unwrap();
+        value["result_version"] = serde_json::json!(RESULT_VERSION + 1);
+        value["field_from_the_future"] = serde_json::json!(true);
+
+        let newer = BenchmarkResult::from_value(value.clone()).unwrap();
+        assert_eq!(newer.result_version, RESULT_VERSION + 1);
+        assert!(matches!(newer.check_writable(), Err(ResultFormatError::Newer(version)) if version == RESULT_VERSION + 1));
+
+        value.as_object_mut().unwrap().remove("puzzle_scores");
+        assert!(matches!(
+            BenchmarkResult::from_value(value),
+            Err(ResultFormatError::Unreadable(version, _)) if version == RESULT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn unreadable_files_are_reported() {
+        assert!(matches!(
+            BenchmarkResult::load_from_file("/nonexistent/results.json"),
+            Err(ResultFormatError::Io(_))
+        ));
+        assert!(matches!(BenchmarkResult::from_json("{"), Err(ResultFormatError::Invalid(_))));
+    }
+}
@@ -0,0 +1,51 @@
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
+
+/// Run `future` to completion from synchronous code. Outside a tokio
+/// runtime this uses a shared process-wide runtime. Inside a multi-threaded
+/// one (e.g. a tracker called from an async benchmark run) it blocks the
+/// current worker in place; a current-thread runtime can't spare its only
+/// thread, so there the future runs on the shared runtime from a thread of
+/// its own.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::CurrentThread => std::thread::scope(|scope| {
+            scope
+                .spawn(|| shared_runtime().block_on(future))
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }),
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+        Err(_) => shared_runtime().block_on(future),
+    }
+}
+
+fn shared_runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().expect("Failed to create tokio runtime"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_on_outside_a_runtime() {
+        assert_eq!(block_on(async { 1 }), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn block_on_inside_a_current_thread_runtime() {
+        assert_eq!(block_on(async { tokio::task::yield_now().await; 2 }), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn block_on_inside_a_multi_thread_runtime() {
+        assert_eq!(block_on(async { tokio::task::yield_now().await; 3 }), 3);
+    }
+}
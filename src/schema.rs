@@ -0,0 +1,47 @@
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::results::{BenchmarkResult, ComparisonReport};
+use crate::puzzle::PuzzleCollection;
+
+/// The file formats the benchmark reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// A puzzle collection (`PuzzleCollection`)
+    Puzzles,
+    /// A single solver's results (`BenchmarkResult`)
+    Results,
+    /// A multi-model comparison (`ComparisonReport`)
+    Comparison,
+}
+
+/// One place where a document does not match its schema.
+#[derive(Debug, Clone)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value, e.g. `/puzzles/3/solutions`.
+    pub path: String,
+    pub message: String,
+}
+
+/// The JSON Schema (draft 2020-12) for `kind`, derived from the Rust types.
+pub fn schema_for(kind: SchemaKind) -> Value {
+    let schema = match kind {
+        SchemaKind::Puzzles => schemars::schema_for!(PuzzleCollection),
+        SchemaKind::Results => schemars::schema_for!(BenchmarkResult),
+        SchemaKind::Comparison => schemars::schema_for!(ComparisonReport),
+    };
+    schema.to_value()
+}
+
+/// Check `document` against the schema for `kind`, returning every violation.
+pub fn validate(kind: SchemaKind, document: &Value) -> Vec<SchemaViolation> {
+    let validator = jsonschema::validator_for(&schema_for(kind))
+        .expect("derived schemas are valid JSON Schema");
+    validator
+        .iter_errors(document)
+        .map(|error| SchemaViolation {
+            path: error.instance_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect()
+}
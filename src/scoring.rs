@@ -0,0 +1,238 @@
+//! How much credit a single answer earns. `Scorer` decides how per-state
+//! credit adds up to a puzzle's score; an `AnswerScorer` decides the credit
+//! itself, so new strategies plug in without touching the aggregation.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::notation;
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleGoal, choice_letter};
+
+/// What is known about one state's answer when it is scored. Grades that
+/// need a model or an engine are gathered by the runner beforehand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StateAnswer<'a> {
+    pub answer: &'a str,
+    /// The judge's rating of the response's reasoning in [0, 1], when judged.
+    pub judge_score: Option<f64>,
+    /// How many centipawns the answer gives away compared to the engine's
+    /// best move, when engine-graded.
+    pub engine_loss: Option<u32>,
+    /// The confidence the solver stated in the answer, in [0, 1], when
+    /// asked for one.
+    pub confidence: Option<f64>,
+}
+
+impl<'a> StateAnswer<'a> {
+    pub fn new(answer: &'a str) -> Self {
+        Self {
+            answer,
+            ..Default::default()
+        }
+    }
+}
+
+/// A strategy for crediting the answer to one state of a puzzle.
+pub trait AnswerScorer: Send + Sync {
+    /// Credit in [0, 1] for `answer` to state `index` of `puzzle`.
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64;
+}
+
+/// Full credit for the stored solution, spelled exactly as stored.
+pub struct ExactMatch;
+
+impl AnswerScorer for ExactMatch {
+    fn credit(&self, puzzle: &Puzzle, _collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        credit(puzzle.solutions.get(index).is_some_and(|solution| solution == answer.answer))
+    }
+}
+
+/// Full credit for the stored solution however it is spelled: chess moves
+/// are compared as UCI moves, other answers without regard to case or
+/// surrounding whitespace.
+pub struct NotationNormalized;
+
+impl AnswerScorer for NotationNormalized {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        let Some(solution) = puzzle.solutions.get(index) else {
+            return 0.0;
+        };
+        let state = &puzzle.game_states[index];
+        let format = collection.answer_format_of(puzzle);
+        let same = match format.grammar.move_notation() {
+            Some(notation) if collection.game_type_of(puzzle) == "chess" => {
+                match (
+                    notation::to_uci(notation, state, solution),
+                    notation::to_uci(notation, state, answer.answer),
+                ) {
+                    (Ok(solution), Ok(answer)) => solution == answer,
+                    _ => solution == answer.answer,
+                }
+            }
+            _ => solution.trim().eq_ignore_ascii_case(answer.answer.trim()),
+        };
+        credit(same)
+    }
+}
+
+/// Centipawns lost at which an engine-graded answer earns no credit.
+pub const ENGINE_LOSS_SCALE: u32 = 300;
+
+/// Full credit for answers the puzzle accepts (see `GoalBased`), and
+/// partial credit for other moves by how little they lose to the engine's
+/// best move, falling to nothing at `ENGINE_LOSS_SCALE` centipawns.
+pub struct EngineGraded;
+
+impl AnswerScorer for EngineGraded {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        if puzzle.is_correct(collection, index, answer.answer) {
+            return 1.0;
+        }
+        answer
+            .engine_loss
+            .map_or(0.0, |loss| 1.0 - loss.min(ENGINE_LOSS_SCALE) as f64 / ENGINE_LOSS_SCALE as f64)
+    }
+}
+
+/// Full credit for the stored solution or any move meeting the puzzle's
+/// goal; see `Puzzle::is_correct`.
+pub struct GoalBased;
+
+impl AnswerScorer for GoalBased {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        credit(puzzle.is_correct(collection, index, answer.answer))
+    }
+}
+
+/// The judge's rating of the response's reasoning, or goal-based credit
+/// for states the judge didn't rate.
+pub struct JudgeModel;
+
+impl AnswerScorer for JudgeModel {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        answer
+            .judge_score
+            .unwrap_or_else(|| GoalBased.credit(puzzle, collection, index, answer))
+    }
+}
+
+/// Credit for a book move other than the main line, or for naming an
+/// opening of the right family but the wrong variation.
+pub const OPENING_PARTIAL_CREDIT: f64 = 0.5;
+
+/// Goal-based credit for opening-knowledge puzzles, plus partial credit
+/// for near misses: another of the position's `book_moves` (listed in the
+/// puzzle's metadata, in UCI) when the main line is asked for, or a choice
+/// from the same opening family (the name before `:`) when the opening is
+/// to be named.
+pub struct OpeningBook;
+
+impl AnswerScorer for OpeningBook {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        if puzzle.is_correct(collection, index, answer.answer) {
+            return 1.0;
+        }
+        let near_miss = match &puzzle.goal {
+            Some(PuzzleGoal::MultipleChoice { choices }) => {
+                let family = |letter: &str| {
+                    let i = (0..choices.len()).find(|&i| choice_letter(i).eq_ignore_ascii_case(letter.trim()))?;
+                    choices[i].split(':').next().map(str::trim)
+                };
+                match (puzzle.solutions.get(index), family(answer.answer)) {
+                    (Some(solution), Some(answered)) => family(solution) == Some(answered),
+                    _ => false,
+                }
+            }
+            _ => {
+                let book_moves = puzzle.metadata.get("book_moves");
+                book_moves.is_some_and(|moves| lists_move(moves, &answer_uci(puzzle, collection, index, answer)))
+            }
+        };
+        if near_miss { OPENING_PARTIAL_CREDIT } else { 0.0 }
+    }
+}
+
+/// Credit for a move that keeps a tablebase win but not by the shortest
+/// way.
+pub const TABLEBASE_WINNING_CREDIT: f64 = 0.5;
+
+/// Credit against tablebase-optimal play, for endgame technique puzzles
+/// whose metadata lists each state's moves as `tablebase`: an array with an
+/// object per state holding the `best` moves and all `winning` moves, in
+/// UCI. Full credit for a best move (or one the puzzle accepts, see
+/// `GoalBased`), `TABLEBASE_WINNING_CREDIT` for another winning move.
+pub struct TablebaseOptimal;
+
+impl AnswerScorer for TablebaseOptimal {
+    fn credit(&self, puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> f64 {
+        if puzzle.is_correct(collection, index, answer.answer) {
+            return 1.0;
+        }
+        let Some(moves) = puzzle.metadata.get("tablebase").and_then(|states| states.get(index)) else {
+            return 0.0;
+        };
+        let answered = answer_uci(puzzle, collection, index, answer);
+        if moves.get("best").is_some_and(|best| lists_move(best, &answered)) {
+            1.0
+        } else if moves.get("winning").is_some_and(|winning| lists_move(winning, &answered)) {
+            TABLEBASE_WINNING_CREDIT
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The answer to state `index` as a UCI move, if it is one in the
+/// collection's notation.
+fn answer_uci(puzzle: &Puzzle, collection: &PuzzleCollection, index: usize, answer: &StateAnswer) -> Option<String> {
+    let notation = collection.answer_format_of(puzzle).grammar.move_notation()?;
+    notation::to_uci(notation, &puzzle.game_states[index], answer.answer).ok()
+}
+
+/// Whether the JSON array `moves` holds the UCI move `uci`.
+fn lists_move(moves: &serde_json::Value, uci: &Option<String>) -> bool {
+    let (Some(moves), Some(uci)) = (moves.as_array(), uci) else {
+        return false;
+    };
+    moves.iter().any(|m| m.as_str() == Some(uci))
+}
+
+fn credit(correct: bool) -> f64 {
+    if correct { 1.0 } else { 0.0 }
+}
+
+/// The built-in `AnswerScorer`s, for choosing one in a collection file or
+/// on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnswerScoring {
+    /// `ExactMatch`.
+    ExactMatch,
+    /// `NotationNormalized`.
+    NotationNormalized,
+    /// `EngineGraded`: needs a UCI engine to grade wrong moves.
+    EngineGraded,
+    /// `GoalBased`.
+    #[default]
+    GoalBased,
+    /// `JudgeModel`: needs a judge model.
+    JudgeModel,
+    /// `OpeningBook`.
+    OpeningBook,
+    /// `TablebaseOptimal`.
+    TablebaseOptimal,
+}
+
+impl AnswerScoring {
+    pub fn scorer(self) -> &'static dyn AnswerScorer {
+        match self {
+            AnswerScoring::ExactMatch => &ExactMatch,
+            AnswerScoring::NotationNormalized => &NotationNormalized,
+            AnswerScoring::EngineGraded => &EngineGraded,
+            AnswerScoring::GoalBased => &GoalBased,
+            AnswerScoring::JudgeModel => &JudgeModel,
+            AnswerScoring::OpeningBook => &OpeningBook,
+            AnswerScoring::TablebaseOptimal => &TablebaseOptimal,
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Tamper-evident result files. A seal records the SHA-256 digest of what a
+//! result claims: the collection it was run on, the run metadata, and every
+//! puzzle's answers and score. Anyone can recompute the digest to spot a
+//! result edited after it was sealed; with the `sign` feature the digest
+//! can also be signed with an Ed25519 key, so a leaderboard can tell who
+//! produced a result, too.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::budget::UsageSummary;
+use crate::puzzle::{PuzzleCollection, PuzzleScore};
+use crate::results::{BenchmarkResult, RunMetadata};
+
+#[cfg(feature = "sign")]
+use base64::Engine;
+#[cfg(feature = "sign")]
+use base64::engine::general_purpose::STANDARD as BASE64;
+#[cfg(feature = "sign")]
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+
+/// Version of the sealed content new seals cover: 2 added pass results,
+/// the budget and interruption flags, and usage.
+pub const SEAL_VERSION: u32 = 2;
+
+fn first_seal_version() -> u32 {
+    1
+}
+
+/// A result's digest when it was sealed, and the signature over it if it
+/// was signed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResultSeal {
+    /// SHA-256 (hex) of the sealed content; see `digest`.
+    pub digest: String,
+    /// What `digest` covers; see `SEAL_VERSION`. Seals without one are
+    /// version 1.
+    #[serde(default = "first_seal_version")]
+    pub version: u32,
+    /// The signer's Ed25519 public key, base64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Ed25519 signature of `digest`, base64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SealError {
+    #[error("the result is not sealed")]
+    Unsealed,
+    #[error("the result is sealed with version {0}, newer than this build understands")]
+    NewerSeal(u32),
+    #[error("the result was changed after it was sealed: digest {computed} does not match the sealed {sealed}")]
+    Tampered { sealed: String, computed: String },
+    #[error("the result is not signed")]
+    Unsigned,
+    #[error("the signature does not match the result")]
+    BadSignature,
+    #[error("the result is signed by {0}, which is not a trusted key")]
+    UntrustedKey(String),
+    #[error("the result has no run metadata naming its collection")]
+    NoMetadata,
+    #[error("the result was run on collection {found}, not {expected}")]
+    WrongCollection { found: String, expected: String },
+    #[error("invalid signing key: {0}")]
+    Key(String),
+    #[error("checking signatures needs the `sign` feature")]
+    Unsupported,
+}
+
+/// What a seal covers, in a fixed order. Version 1 seals leave out
+/// `passes`, the flags and `usage`.
+#[derive(Serialize)]
+struct SealedContent<'a> {
+    solver_name: &'a str,
+    metadata: Option<&'a RunMetadata>,
+    total_score: f64,
+    max_possible_score: f64,
+    average_score: f64,
+    puzzles: Vec<SealedPuzzle<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    passes: Option<SealedPasses<'a>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    budget_exceeded: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interrupted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<&'a UsageSummary>,
+}
+
+#[derive(Serialize)]
+struct SealedPuzzle<'a> {
+    puzzle_id: &'a str,
+    answers: &'a [String],
+    score: f64,
+    max_possible_score: f64,
+}
+
+#[derive(Serialize)]
+struct SealedPasses<'a> {
+    pass_at_1: f64,
+    pass_at_n: f64,
+    passes: Vec<Vec<SealedPuzzle<'a>>>,
+}
+
+fn sealed_puzzles(scores: &[PuzzleScore]) -> Vec<SealedPuzzle<'_>> {
+    scores
+        .iter()
+        .map(|score| SealedPuzzle {
+            puzzle_id: &score.puzzle_id,
+            answers: &score.answers,
+            score: score.score,
+            max_possible_score: score.max_possible_score,
+        })
+        .collect()
+}
+
+/// SHA-256 (hex) of `result`'s solver name, run metadata (which holds the
+/// collection checksum), totals, each puzzle's answers and score, each
+/// pass's answers and scores, the budget and interruption flags, and usage.
+pub fn digest(result: &BenchmarkResult) -> String {
+    versioned_digest(result, SEAL_VERSION)
+}
+
+/// `digest` as a seal of `version` computed it.
+fn versioned_digest(result: &BenchmarkResult, version: u32) -> String {
+    let mut content = SealedContent {
+        solver_name: &result.solver_name,
+        metadata: result.metadata.as_ref(),
+        total_score: result.total_score,
+        max_possible_score: result.max_possible_score,
+        average_score: result.average_score,
+        puzzles: sealed_puzzles(&result.puzzle_scores),
+        passes: result.pass_results.as_ref().map(|pass_results| SealedPasses {
+            pass_at_1: pass_results.pass_at_1,
+            pass_at_n: pass_results.pass_at_n,
+            passes: pass_results.individual_pass_scores.iter().map(|pass| sealed_puzzles(pass)).collect(),
+        }),
+        budget_exceeded: result.budget_exceeded,
+        interrupted: result.interrupted,
+        usage: result.usage.as_ref(),
+    };
+    if version < 2 {
+        content.passes = None;
+        content.budget_exceeded = false;
+        content.interrupted = false;
+        content.usage = None;
+    }
+    let json = serde_json::to_vec(&content).expect("sealed content serializes to JSON");
+    Sha256::digest(&json).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Seal `result` as it is now, unsigned, replacing any earlier seal.
+pub fn seal(result: &mut BenchmarkResult) {
+    result.seal = Some(ResultSeal {
+        digest: digest(result),
+        version: SEAL_VERSION,
+        public_key: None,
+        signature: None,
+    });
+}
+
+/// Seal `result` and sign the seal with `key`.
+#[cfg(feature = "sign")]
+pub fn sign(result: &mut BenchmarkResult, key: &SigningKey) {
+    let digest = digest(result);
+    result.seal = Some(ResultSeal {
+        signature: Some(BASE64.encode(key.pair.sign(digest.as_bytes()))),
+        public_key: Some(key.public_key()),
+        digest,
+        version: SEAL_VERSION,
+    });
+}
+
+/// Check that `result` is unchanged since it was sealed and, if it was
+/// signed, that the signature holds. Returns the signer's public key for
+/// signed results.
+pub fn verify(result: &BenchmarkResult) -> Result<Option<String>, SealError> {
+    let seal = result.seal.as_ref().ok_or(SealError::Unsealed)?;
+    if seal.version > SEAL_VERSION {
+        return Err(SealError::NewerSeal(seal.version));
+    }
+    let computed = versioned_digest(result, seal.version);
+    if computed != seal.digest {
+        return Err(SealError::Tampered {
+            sealed: seal.digest.clone(),
+            computed,
+        });
+    }
+    let (Some(public_key), Some(signature)) = (&seal.public_key, &seal.signature) else {
+        return Ok(None);
+    };
+    verify_signature(&seal.digest, public_key, signature)?;
+    Ok(Some(public_key.clone()))
+}
+
+#[cfg(feature = "sign")]
+fn verify_signature(digest: &str, public_key: &str, signature: &str) -> Result<(), SealError> {
+    let public_key = BASE64.decode(public_key).map_err(|_| SealError::BadSignature)?;
+    let signature = BASE64.decode(signature).map_err(|_| SealError::BadSignature)?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(digest.as_bytes(), &signature)
+        .map_err(|_| SealError::BadSignature)
+}
+
+#[cfg(not(feature = "sign"))]
+fn verify_signature(_digest: &str, _public_key: &str, _signature: &str) -> Result<(), SealError> {
+    Err(SealError::Unsupported)
+}
+
+/// Check that `result` is sealed intact, signed by one of `trusted_keys`
+/// (base64 public keys) unless that is empty, and was run on exactly
+/// `collection` if given.
+pub fn verify_submission(
+    result: &BenchmarkResult,
+    collection: Option<&PuzzleCollection>,
+    trusted_keys: &[String],
+) -> Result<Option<String>, SealError> {
+    let signer = verify(result)?;
+    if !trusted_keys.is_empty() {
+        let signer = signer.as_ref().ok_or(SealError::Unsigned)?;
+        if !trusted_keys.contains(signer) {
+            return Err(SealError::UntrustedKey(signer.clone()));
+        }
+    }
+    if let Some(collection) = collection {
+        let metadata = result.metadata.as_ref().ok_or(SealError::NoMetadata)?;
+        let expected = collection.source_checksum();
+        if metadata.collection_checksum != expected {
+            return Err(SealError::WrongCollection {
+                found: metadata.collection_checksum.clone(),
+                expected,
+            });
+        }
+    }
+    Ok(signer)
+}
+
+/// An Ed25519 key pair for signing results, stored as base64 PKCS#8.
+#[cfg(feature = "sign")]
+pub struct SigningKey {
+    pair: Ed25519KeyPair,
+    pkcs8: Vec<u8>,
+}
+
+#[cfg(feature = "sign")]
+impl SigningKey {
+    pub fn generate() -> Result<Self, SealError> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+            .map_err(|_| SealError::Key("could not generate a key".to_string()))?;
+        Self::from_pkcs8(pkcs8.as_ref().to_vec())
+    }
+
+    fn from_pkcs8(pkcs8: Vec<u8>) -> Result<Self, SealError> {
+        let pair = Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| SealError::Key(e.to_string()))?;
+        Ok(Self { pair, pkcs8 })
+    }
+
+    /// The base64 public key verifiers check signatures against.
+    pub fn public_key(&self) -> String {
+        BASE64.encode(self.pair.public_key().as_ref())
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, SealError> {
+        let content = std::fs::read_to_string(path).map_err(|e| SealError::Key(format!("{}: {}", path, e)))?;
+        let pkcs8 = BASE64
+            .decode(content.trim())
+            .map_err(|e| SealError::Key(format!("{}: {}", path, e)))?;
+        Self::from_pkcs8(pkcs8)
+    }
+
+    /// Write the key to `path`, readable only by its owner on unix.
+    pub fn save_to_file(&self, path: &str) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(format!("{}\n", BASE64.encode(&self.pkcs8)).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Scorer;
+    use crate::puzzle::testing::{random_collection, responses};
+    use std::collections::BTreeMap;
+
+    fn result(collection: &PuzzleCollection) -> BenchmarkResult {
+        let scores = collection
+            .puzzles
+            .iter()
+            .zip(responses(collection, 0.5, 3))
+            .map(|(puzzle, responses)| Scorer::PerState.score_responses(puzzle, collection, &responses))
+            .collect();
+        let mut result = BenchmarkResult::summarize("test".to_string(), "solver", "", collection, scores);
+        result.metadata = Some(RunMetadata {
+            crate_version: String::new(),
+            git_commit: None,
+            collection_name: collection.name.clone(),
+            collection_checksum: collection.checksum(),
+            solver_parameters: BTreeMap::new(),
+            seeds: BTreeMap::new(),
+            environment: BTreeMap::new(),
+            shard: None,
+            selection: None,
+        });
+        result
+    }
+
+    #[test]
+    fn edits_after_sealing_are_caught() {
+        let collection = random_collection(4, 11);
+        let mut sealed = result(&collection);
+        assert!(matches!(verify(&sealed), Err(SealError::Unsealed)));
+        seal(&mut sealed);
+        assert_eq!(verify(&sealed).unwrap(), None);
+
+        let mut rescored = sealed.clone();
+        rescored.puzzle_scores[0].score = rescored.puzzle_scores[0].max_possible_score;
+        assert!(matches!(verify(&rescored), Err(SealError::Tampered { .. })));
+
+        let mut uninterrupted = sealed.clone();
+        uninterrupted.interrupted = !uninterrupted.interrupted;
+        assert!(matches!(verify(&uninterrupted), Err(SealError::Tampered { .. })));
+
+        let mut newer = sealed.clone();
+        newer.seal.as_mut().unwrap().version = SEAL_VERSION + 1;
+        assert!(matches!(verify(&newer), Err(SealError::NewerSeal(_))));
+    }
+
+    #[test]
+    fn first_version_seals_still_verify() {
+        let collection = random_collection(3, 12);
+        let mut old = result(&collection);
+        old.seal = Some(ResultSeal {
+            digest: versioned_digest(&old, 1),
+            version: 1,
+            public_key: None,
+            signature: None,
+        });
+        old.interrupted = true;
+        assert_eq!(verify(&old).unwrap(), None, "version 1 didn't cover the interruption flag");
+    }
+
+    #[test]
+    fn submissions_must_match_their_collection() {
+        let collection = random_collection(3, 13);
+        let mut sealed = result(&collection);
+        seal(&mut sealed);
+        assert!(verify_submission(&sealed, Some(&collection), &[]).is_ok());
+        assert!(matches!(
+            verify_submission(&sealed, Some(&random_collection(3, 14)), &[]),
+            Err(SealError::WrongCollection { .. })
+        ));
+        assert!(matches!(
+            verify_submission(&sealed, None, &["someone".to_string()]),
+            Err(SealError::Unsigned)
+        ));
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn signatures_name_their_signer() {
+        let collection = random_collection(3, 15);
+        let key = SigningKey::generate().unwrap();
+        let mut signed = result(&collection);
+        sign(&mut signed, &key);
+        assert_eq!(verify(&signed).unwrap(), Some(key.public_key()));
+        assert!(verify_submission(&signed, Some(&collection), &[key.public_key()]).is_ok());
+
+        let other = SigningKey::generate().unwrap();
+        assert!(matches!(
+            verify_submission(&signed, None, &[other.public_key()]),
+            Err(SealError::UntrustedKey(_))
+        ));
+        signed.seal.as_mut().unwrap().public_key = Some(other.public_key());
+        assert!(matches!(verify(&signed), Err(SealError::BadSignature)));
+    }
+}
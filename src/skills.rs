@@ -0,0 +1,156 @@
+//! Skill profiles. Puzzle themes are grouped into a few named skills —
+//! tactics, defense, endgame technique, calculation depth and rule
+//! knowledge — so a result can say what kind of player a model is, one
+//! score per skill, as for a radar chart.
+
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::{Puzzle, PuzzleCollection, PuzzleGoal, PuzzleScore};
+
+/// A skill a puzzle exercises, recognized from its tags (Lichess theme
+/// names, compared case-insensitively) and goal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkillAxis {
+    /// Forks, pins, sacrifices, mating patterns and other combinations.
+    Tactics,
+    /// Defending: parrying threats and holding the balance.
+    Defense,
+    /// Converting or holding endgames.
+    Endgame,
+    /// Seeing far ahead: long solutions and mates in three or more.
+    Calculation,
+    /// The less common rules: castling, en passant, promotion.
+    Rules,
+}
+
+/// Puzzles with at least this many states call for calculation.
+const LONG_SOLUTION_STATES: usize = 3;
+
+impl SkillAxis {
+    pub const ALL: [SkillAxis; 5] = [
+        SkillAxis::Tactics,
+        SkillAxis::Defense,
+        SkillAxis::Endgame,
+        SkillAxis::Calculation,
+        SkillAxis::Rules,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SkillAxis::Tactics => "tactics",
+            SkillAxis::Defense => "defense",
+            SkillAxis::Endgame => "endgame technique",
+            SkillAxis::Calculation => "calculation depth",
+            SkillAxis::Rules => "rule knowledge",
+        }
+    }
+
+    /// The themes, lowercased, that put a puzzle on this axis.
+    fn themes(self) -> &'static [&'static str] {
+        match self {
+            SkillAxis::Tactics => &[
+                "attraction",
+                "capturingdefender",
+                "clearance",
+                "deflection",
+                "discoveredattack",
+                "doublecheck",
+                "exposedking",
+                "fork",
+                "hangingpiece",
+                "interference",
+                "intermezzo",
+                "kingsideattack",
+                "queensideattack",
+                "pin",
+                "sacrifice",
+                "skewer",
+                "trappedpiece",
+                "xrayattack",
+                "mate",
+                "matein1",
+                "matein2",
+                "anastasiamate",
+                "arabianmate",
+                "backrankmate",
+                "bodenmate",
+                "doublebishopmate",
+                "dovetailmate",
+                "hookmate",
+                "smotheredmate",
+            ],
+            SkillAxis::Defense => &["defensivemove", "equality", "quietmove", "zugzwang"],
+            SkillAxis::Endgame => &[
+                "endgame",
+                "bishopendgame",
+                "knightendgame",
+                "pawnendgame",
+                "queenendgame",
+                "queenrookendgame",
+                "rookendgame",
+            ],
+            SkillAxis::Calculation => &["long", "verylong", "matein3", "matein4", "matein5"],
+            SkillAxis::Rules => &["castling", "enpassant", "promotion", "underpromotion"],
+        }
+    }
+
+    /// Whether `puzzle` exercises this skill. Besides tags, mate goals
+    /// count as tactics (and as calculation from mate in three), survival
+    /// goals as defense, and solutions of three or more moves as
+    /// calculation.
+    pub fn covers(self, puzzle: &Puzzle) -> bool {
+        let tagged = puzzle
+            .tags
+            .iter()
+            .any(|tag| self.themes().contains(&tag.to_ascii_lowercase().as_str()));
+        tagged
+            || match (self, &puzzle.goal) {
+                (SkillAxis::Tactics, Some(PuzzleGoal::MateInN { .. })) => true,
+                (SkillAxis::Calculation, Some(PuzzleGoal::MateInN { moves })) => *moves >= 3,
+                (SkillAxis::Defense, Some(PuzzleGoal::Survive)) => true,
+                _ => false,
+            }
+            || (self == SkillAxis::Calculation && puzzle.game_states.len() >= LONG_SOLUTION_STATES)
+    }
+}
+
+/// A result's score on one skill: the weighted average over the puzzles
+/// exercising it, a puzzle counting towards each of its skills.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SkillScore {
+    pub axis: SkillAxis,
+    pub puzzles: usize,
+    /// `None` if no scored puzzle exercises the skill.
+    pub average_score: Option<f64>,
+}
+
+/// The skill profile of `puzzle_scores` on `collection`: every axis in
+/// `SkillAxis::ALL` order, so profiles line up as radar charts. Empty if
+/// no scored puzzle exercises any skill.
+pub fn skill_profile(collection: &PuzzleCollection, puzzle_scores: &[PuzzleScore]) -> Vec<SkillScore> {
+    let puzzles: HashMap<&str, &Puzzle> = collection.puzzles.iter().map(|p| (p.id.as_str(), p)).collect();
+    let profile: Vec<SkillScore> = SkillAxis::ALL
+        .iter()
+        .map(|&axis| {
+            let covered: Vec<&PuzzleScore> = puzzle_scores
+                .iter()
+                .filter(|score| puzzles.get(score.puzzle_id.as_str()).is_some_and(|p| axis.covers(p)))
+                .collect();
+            let max_possible_score: f64 = covered.iter().map(|s| s.weight * s.max_possible_score).sum();
+            let total_score: f64 = covered.iter().map(|s| s.weight * s.score).sum();
+            SkillScore {
+                axis,
+                puzzles: covered.len(),
+                average_score: (max_possible_score > 0.0).then(|| total_score / max_possible_score),
+            }
+        })
+        .collect();
+    if profile.iter().all(|skill| skill.puzzles == 0) {
+        return Vec::new();
+    }
+    profile
+}
@@ -0,0 +1,217 @@
+//! Sequential testing for stopping a run early: once enough puzzles are in
+//! to pin the score down, or to tell it apart from a baseline, the rest
+//! only cost money.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::PuzzleScore;
+
+/// When a run may stop before every puzzle has been evaluated. The score's
+/// confidence interval is a normal approximation adjusted as Agresti–Coull's
+/// (see `RunningScore::interval`), checked after every puzzle; since it is looked at many times the real error rate is
+/// somewhat above `1 - confidence`, which `min_puzzles` keeps in check.
+/// Puzzles should be in random order (e.g. shuffled) for the running score
+/// to be a fair estimate of the whole collection's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarlyStopping {
+    /// Stop once the interval's half-width is at most this.
+    pub precision: Option<f64>,
+    /// Stop once the interval excludes this score, e.g. another model's.
+    pub baseline: Option<f64>,
+    pub confidence: f64,
+    /// Never stop before this many puzzles are scored.
+    pub min_puzzles: usize,
+}
+
+impl Default for EarlyStopping {
+    fn default() -> Self {
+        Self {
+            precision: None,
+            baseline: None,
+            confidence: 0.95,
+            min_puzzles: 30,
+        }
+    }
+}
+
+impl EarlyStopping {
+    pub fn with_precision(mut self, half_width: f64) -> Self {
+        self.precision = Some(half_width);
+        self
+    }
+
+    pub fn with_baseline(mut self, score: f64) -> Self {
+        self.baseline = Some(score);
+        self
+    }
+
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub fn with_min_puzzles(mut self, min_puzzles: usize) -> Self {
+        self.min_puzzles = min_puzzles;
+        self
+    }
+
+    /// Whether the scores in `estimate` are enough to stop, and why.
+    pub fn check(&self, estimate: &RunningScore) -> Option<EarlyStop> {
+        if estimate.count < self.min_puzzles.max(2) {
+            return None;
+        }
+        let score = estimate.mean();
+        let (center, half_width) = estimate.interval(normal_quantile(0.5 + self.confidence / 2.0));
+        let reason = if self.precision.is_some_and(|precision| half_width <= precision) {
+            StopReason::Precision
+        } else if self.baseline.is_some_and(|baseline| (center - baseline).abs() > half_width) {
+            StopReason::Baseline
+        } else {
+            return None;
+        };
+        Some(EarlyStop {
+            reason,
+            evaluated: estimate.count,
+            score,
+            half_width,
+            confidence: self.confidence,
+            baseline: self.baseline,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopReason {
+    /// The confidence interval got narrow enough.
+    Precision,
+    /// The confidence interval excluded the baseline.
+    Baseline,
+}
+
+/// Why and where a run stopped early, recorded in its results.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EarlyStop {
+    pub reason: StopReason,
+    /// Puzzles scored when the run stopped; ones still in flight finish
+    /// and are included in the results.
+    pub evaluated: usize,
+    pub score: f64,
+    /// Half-width of the confidence interval, which is centered a little
+    /// closer to one half than `score`; see `RunningScore::interval`.
+    pub half_width: f64,
+    pub confidence: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+}
+
+/// Running weighted score of the puzzles evaluated so far, as
+/// `BenchmarkResult::average_score` would compute it, with what is needed
+/// for its standard error.
+#[derive(Debug, Clone, Default)]
+pub struct RunningScore {
+    count: usize,
+    /// Sums of the earned (`y`) and possible (`x`) weighted scores and of
+    /// their squares and products.
+    sum_y: f64,
+    sum_x: f64,
+    sum_yy: f64,
+    sum_xx: f64,
+    sum_xy: f64,
+}
+
+impl RunningScore {
+    pub fn add(&mut self, score: &PuzzleScore) {
+        let y = score.weight * score.score;
+        let x = score.weight * score.max_possible_score;
+        self.count += 1;
+        self.sum_y += y;
+        self.sum_x += x;
+        self.sum_yy += y * y;
+        self.sum_xx += x * x;
+        self.sum_xy += x * y;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.sum_x > 0.0 { self.sum_y / self.sum_x } else { 0.0 }
+    }
+
+    /// Standard error of `mean`, a ratio estimator, by linearization.
+    pub fn standard_error(&self) -> f64 {
+        if self.count < 2 || self.sum_x <= 0.0 {
+            return f64::INFINITY;
+        }
+        let n = self.count as f64;
+        let ratio = self.mean();
+        let residuals = self.sum_yy - 2.0 * ratio * self.sum_xy + ratio * ratio * self.sum_xx;
+        let mean_x = self.sum_x / n;
+        (residuals.max(0.0) / (n - 1.0) / n).sqrt() / mean_x
+    }
+
+    /// Center and half-width of the confidence interval for `mean` with
+    /// normal quantile `z`. As in the Agresti–Coull interval, the center is
+    /// the mean as if `z²` more puzzles had been scored, half of them
+    /// solved, and the standard error is at least that of a proportion at
+    /// the center; so a run of only right or only wrong answers doesn't get
+    /// an interval of width zero.
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        if self.count < 2 || self.sum_x <= 0.0 {
+            return (self.mean(), f64::INFINITY);
+        }
+        let n = self.count as f64 + z * z;
+        let center = (self.count as f64 * self.mean() + z * z / 2.0) / n;
+        let floor = (center * (1.0 - center) / n).sqrt();
+        (center, z * self.standard_error().max(floor))
+    }
+}
+
+/// The standard normal quantile at `p`, by Acklam's rational approximation
+/// (relative error below 1.2e-9).
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    const LOW: f64 = 0.02425;
+
+    let p = p.clamp(f64::MIN_POSITIVE, 1.0 - f64::EPSILON);
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
@@ -0,0 +1,111 @@
+//! Endgame tablebase lookups over HTTP, in the format of the Lichess
+//! tablebase server, for generating and grading endgame technique puzzles.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The public Lichess tablebase, covering positions of up to seven pieces.
+pub const LICHESS_TABLEBASE: &str = "https://tablebase.lichess.ovh/standard";
+
+#[derive(Debug, Error)]
+pub enum TablebaseError {
+    #[error("tablebase request for {fen} failed: {reason}")]
+    Request { fen: String, reason: String },
+}
+
+/// What the tablebase knows about a position, for the side to move.
+/// Categories are `win`, `draw`, `loss` and softer variants such as
+/// `cursed-win` (a win spoiled by the fifty-move rule) or `unknown`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablebaseEntry {
+    pub category: String,
+    /// Distance to zeroing the fifty-move counter, in plies.
+    pub dtz: Option<i32>,
+    /// Distance to mate, in plies, where known.
+    pub dtm: Option<i32>,
+    /// The legal moves, best first.
+    #[serde(default)]
+    pub moves: Vec<TablebaseMove>,
+}
+
+/// A legal move and what the tablebase knows about the position after it,
+/// for the opponent: a move to a `loss` wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablebaseMove {
+    pub uci: String,
+    pub category: String,
+    pub dtz: Option<i32>,
+    pub dtm: Option<i32>,
+}
+
+impl TablebaseEntry {
+    pub fn is_win(&self) -> bool {
+        self.category == "win"
+    }
+
+    /// The moves as good as the best one: same result, distance to mate
+    /// and distance to zeroing.
+    pub fn best_moves(&self) -> Vec<&str> {
+        let Some(best) = self.moves.first() else {
+            return Vec::new();
+        };
+        self.moves
+            .iter()
+            .filter(|m| m.category == best.category && m.dtm == best.dtm && m.dtz == best.dtz)
+            .map(|m| m.uci.as_str())
+            .collect()
+    }
+
+    /// The moves that keep a won position won, optimal or not.
+    pub fn winning_moves(&self) -> Vec<&str> {
+        self.moves
+            .iter()
+            .filter(|m| m.category == "loss")
+            .map(|m| m.uci.as_str())
+            .collect()
+    }
+}
+
+/// A tablebase server answering `GET <url>?fen=<fen>`.
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    pub url: String,
+    client: reqwest::Client,
+}
+
+impl Default for Tablebase {
+    fn default() -> Self {
+        Self::new(LICHESS_TABLEBASE)
+    }
+}
+
+impl Tablebase {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn probe(&self, fen: &str) -> Result<TablebaseEntry, TablebaseError> {
+        crate::runtime::block_on(self.probe_async(fen))
+    }
+
+    pub async fn probe_async(&self, fen: &str) -> Result<TablebaseEntry, TablebaseError> {
+        let failed = |reason: String| TablebaseError::Request {
+            fen: fen.to_string(),
+            reason,
+        };
+        let response = self
+            .client
+            .get(&self.url)
+            .query(&[("fen", fen)])
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(failed(response.status().to_string()));
+        }
+        response.json().await.map_err(|e| failed(e.to_string()))
+    }
+}
@@ -0,0 +1,283 @@
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use tracing::warn;
+
+/// Spans buffered before an automatic export.
+const EXPORT_BATCH_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+struct SpanContext {
+    trace_id: u128,
+    span_id: u64,
+}
+
+thread_local! {
+    /// Span that new spans on this thread become children of.
+    static CURRENT_SPAN: RefCell<Option<SpanContext>> = const { RefCell::new(None) };
+}
+
+tokio::task_local! {
+    /// Span that async spans in this task become children of. Many tasks
+    /// share a thread, so async code can't use `CURRENT_SPAN`.
+    static TASK_SPAN: SpanContext;
+}
+
+struct FinishedSpan {
+    context: SpanContext,
+    parent_span_id: Option<u64>,
+    name: String,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(String, Value)>,
+    error: Option<String>,
+}
+
+/// OpenTelemetry spans and counters for benchmark runs, exported as
+/// OTLP/HTTP JSON to `<endpoint>/v1/traces` and `<endpoint>/v1/metrics`.
+pub struct Telemetry {
+    endpoint: String,
+    service_name: String,
+    start: SystemTime,
+    spans: Mutex<Vec<FinishedSpan>>,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+}
+
+/// An open span; it is recorded when dropped. While alive it is the parent
+/// of spans started on the same thread.
+pub struct Span<'a> {
+    telemetry: &'a Telemetry,
+    context: SpanContext,
+    parent: Option<SpanContext>,
+    name: String,
+    start: SystemTime,
+    attributes: Vec<(String, Value)>,
+    error: Option<String>,
+    /// Whether this span is the thread's current span while alive.
+    thread_current: bool,
+}
+
+impl Span<'_> {
+    /// Run `future` with this span as the parent of the async spans it starts.
+    pub fn scope<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        TASK_SPAN.scope(self.context, future)
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<Value>) {
+        self.attributes.push((key.to_string(), value.into()));
+    }
+
+    pub fn set_error(&mut self, message: impl ToString) {
+        self.error = Some(message.to_string());
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if self.thread_current {
+            CURRENT_SPAN.with(|current| *current.borrow_mut() = self.parent);
+        }
+        let span = FinishedSpan {
+            context: self.context,
+            parent_span_id: self.parent.map(|p| p.span_id),
+            name: std::mem::take(&mut self.name),
+            start: self.start,
+            end: SystemTime::now(),
+            attributes: std::mem::take(&mut self.attributes),
+            error: self.error.take(),
+        };
+        let full = {
+            let mut spans = self.telemetry.spans.lock().unwrap();
+            spans.push(span);
+            spans.len() >= EXPORT_BATCH_SIZE
+        };
+        if full {
+            self.telemetry.export_spans();
+        }
+    }
+}
+
+impl Telemetry {
+    pub fn new(endpoint: &str, service_name: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            service_name: service_name.to_string(),
+            start: SystemTime::now(),
+            spans: Mutex::new(Vec::new()),
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            prompt_tokens: AtomicU64::new(0),
+            completion_tokens: AtomicU64::new(0),
+        }
+    }
+
+    /// Start a span that is a child of this thread's current span, or the
+    /// root of a new trace.
+    pub fn span(&self, name: &str) -> Span<'_> {
+        let parent = CURRENT_SPAN.with(|current| *current.borrow());
+        let span = self.start_span(name, parent, true);
+        CURRENT_SPAN.with(|current| *current.borrow_mut() = Some(span.context));
+        span
+    }
+
+    /// Start a span in async code: a child of the enclosing `Span::scope`,
+    /// else of this thread's current span, else the root of a new trace.
+    /// Unlike `span`, it never becomes the thread's current span.
+    pub fn task_span(&self, name: &str) -> Span<'_> {
+        let parent = TASK_SPAN
+            .try_with(|context| *context)
+            .ok()
+            .or_else(|| CURRENT_SPAN.with(|current| *current.borrow()));
+        self.start_span(name, parent, false)
+    }
+
+    fn start_span(&self, name: &str, parent: Option<SpanContext>, thread_current: bool) -> Span<'_> {
+        let context = SpanContext {
+            trace_id: parent.map_or_else(rand::random, |p| p.trace_id),
+            span_id: rand::random(),
+        };
+        Span {
+            telemetry: self,
+            context,
+            parent,
+            name: name.to_string(),
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+            error: None,
+            thread_current,
+        }
+    }
+
+    pub fn record_request(&self, prompt_tokens: u64, completion_tokens: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.prompt_tokens.fetch_add(prompt_tokens, Ordering::Relaxed);
+        self.completion_tokens
+            .fetch_add(completion_tokens, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Export all buffered spans and the current counter values.
+    pub fn flush(&self) {
+        self.export_spans();
+        self.export_metrics();
+    }
+
+    fn export_spans(&self) {
+        let spans = std::mem::take(&mut *self.spans.lock().unwrap());
+        if spans.is_empty() {
+            return;
+        }
+        let spans: Vec<Value> = spans
+            .iter()
+            .map(|span| {
+                json!({
+                    "traceId": format!("{:032x}", span.context.trace_id),
+                    "spanId": format!("{:016x}", span.context.span_id),
+                    "parentSpanId": span.parent_span_id.map(|id| format!("{:016x}", id)).unwrap_or_default(),
+                    "name": span.name,
+                    "kind": 1,
+                    "startTimeUnixNano": nanos(span.start),
+                    "endTimeUnixNano": nanos(span.end),
+                    "attributes": attributes(&span.attributes),
+                    "status": match &span.error {
+                        Some(message) => json!({ "code": 2, "message": message }),
+                        None => json!({ "code": 1 }),
+                    },
+                })
+            })
+            .collect();
+        let body = json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{ "scope": { "name": "boardgamebench" }, "spans": spans }],
+            }]
+        });
+        self.post("/v1/traces", &body);
+    }
+
+    fn export_metrics(&self) {
+        let start = nanos(self.start);
+        let now = nanos(SystemTime::now());
+        let counter = |name: &str, description: &str, value: &AtomicU64| {
+            json!({
+                "name": name,
+                "description": description,
+                "sum": {
+                    "dataPoints": [{
+                        "asInt": value.load(Ordering::Relaxed).to_string(),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                    }],
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                },
+            })
+        };
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": self.resource(),
+                "scopeMetrics": [{
+                    "scope": { "name": "boardgamebench" },
+                    "metrics": [
+                        counter("bench.api.requests", "API requests sent", &self.requests),
+                        counter("bench.api.errors", "API requests that failed", &self.errors),
+                        counter("bench.tokens.prompt", "Prompt tokens used", &self.prompt_tokens),
+                        counter("bench.tokens.completion", "Completion tokens used", &self.completion_tokens),
+                    ],
+                }],
+            }]
+        });
+        self.post("/v1/metrics", &body);
+    }
+
+    fn resource(&self) -> Value {
+        json!({ "attributes": attributes(&[("service.name".to_string(), json!(self.service_name))]) })
+    }
+
+    fn post(&self, path: &str, body: &Value) {
+        let url = format!("{}{}", self.endpoint, path);
+        let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = crate::runtime::block_on(async {
+            let response = reqwest::Client::new().post(&url).json(body).send().await?;
+            if !response.status().is_success() {
+                return Err(format!("{}: {}", response.status(), response.text().await?).into());
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            warn!(%url, error = %e, "OTLP export failed");
+        }
+    }
+}
+
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+fn attributes(attributes: &[(String, Value)]) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Bool(b) => json!({ "boolValue": b }),
+                Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+                Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+                Value::String(s) => json!({ "stringValue": s }),
+                other => json!({ "stringValue": other.to_string() }),
+            };
+            json!({ "key": key, "value": value })
+        })
+        .collect()
+}
@@ -0,0 +1,305 @@
+//! Round-robin tournaments between solvers: every pair plays every opening
+//! once with each color, and the games give head-to-head records and Elo
+//! ratings.
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::LN_10;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::blunders::{self, MoveQuality};
+use crate::engine::UciEngine;
+use crate::evaluation::{RetryPolicy, Solver};
+use crate::game::chess::STARTING_FEN;
+use crate::play::{GameRecord, GameRules, Player, play_game};
+use crate::puzzle::PuzzleCollection;
+
+/// Rating every player starts from; the ratings average to it.
+const BASE_ELO: f64 = 1500.0;
+/// Precision of the normal prior on each strength (in logits), which keeps
+/// players who won or lost every game finite.
+const PRIOR_PRECISION: f64 = 0.1;
+const MAX_ITERATIONS: usize = 500;
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Error)]
+pub enum TournamentError {
+    #[error("Failed to write the tournament report: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize the tournament report: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Games between solvers, prompted like the puzzles of `collection`; see
+/// `play_game`.
+pub struct Tournament {
+    pub collection: PuzzleCollection,
+    /// Positions every pairing plays from, as FEN.
+    openings: Vec<String>,
+    rules: GameRules,
+    retry: RetryPolicy,
+    /// Maximum number of games played at once.
+    concurrency: usize,
+    /// Engine grading every move once a game is over.
+    annotator: Option<UciEngine>,
+}
+
+impl Tournament {
+    /// A tournament played from the standard starting position.
+    pub fn new(collection: PuzzleCollection) -> Self {
+        Self {
+            collection,
+            openings: vec![STARTING_FEN.to_string()],
+            rules: GameRules::default(),
+            retry: RetryPolicy::default(),
+            concurrency: 1,
+            annotator: None,
+        }
+    }
+
+    /// Play from the first state of each of the collection's chess puzzles
+    /// instead of the starting position.
+    pub fn from_puzzle_positions(mut self) -> Self {
+        self.openings = self
+            .collection
+            .puzzles
+            .iter()
+            .filter(|puzzle| self.collection.game_type_of(puzzle) == "chess")
+            .filter_map(|puzzle| puzzle.game_states.first().cloned())
+            .collect();
+        self
+    }
+
+    pub fn with_openings(mut self, openings: Vec<String>) -> Self {
+        self.openings = openings;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: GameRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Annotate every game with `engine`, so the standings include each
+    /// player's move quality.
+    pub fn with_annotator(mut self, engine: UciEngine) -> Self {
+        self.annotator = Some(engine);
+        self
+    }
+
+    pub fn openings(&self) -> &[String] {
+        &self.openings
+    }
+
+    pub fn run(&self, players: &[&Solver]) -> TournamentReport {
+        crate::runtime::block_on(self.run_async(players))
+    }
+
+    /// Play every pairing of `players` from every opening with both color
+    /// assignments. Openings that aren't valid positions are skipped.
+    pub async fn run_async(&self, players: &[&Solver]) -> TournamentReport {
+        let mut pairings = Vec::new();
+        for opening in &self.openings {
+            for (i, first) in players.iter().enumerate() {
+                for second in &players[i + 1..] {
+                    pairings.push((*first, *second, opening.as_str()));
+                    pairings.push((*second, *first, opening.as_str()));
+                }
+            }
+        }
+
+        let permits = Semaphore::new(self.concurrency);
+        let games = join_all(pairings.into_iter().map(|(white, black, opening)| {
+            let permits = &permits;
+            async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                let (white, black) = (Player::Model(white), Player::Model(black));
+                let mut game = play_game(white, black, opening, &self.collection, &self.rules, &self.retry)
+                    .await
+                    .inspect_err(|e| warn!(opening, error = %e, "skipping game"))
+                    .ok()?;
+                if let Some(engine) = &self.annotator {
+                    match blunders::annotate_game(engine, &game).await {
+                        Ok(losses) => game.losses = losses,
+                        Err(e) => warn!(opening, error = %e, "could not annotate game"),
+                    }
+                }
+                Some(game)
+            }
+        }))
+        .await;
+        TournamentReport::new(&self.collection.name, games.into_iter().flatten().collect())
+    }
+}
+
+/// The games of a tournament and what they say about the players.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentReport {
+    pub collection_name: String,
+    pub timestamp: String,
+    /// Players from the highest Elo down.
+    pub standings: Vec<Standing>,
+    pub head_to_head: Vec<HeadToHead>,
+    pub games: Vec<GameRecord>,
+}
+
+/// A player's record over the whole tournament.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: String,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// 1 per win and ½ per draw.
+    pub points: f64,
+    pub elo: f64,
+    /// Over the games annotated with `Tournament::with_annotator`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_quality: Option<MoveQuality>,
+}
+
+/// A player's record against one opponent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeadToHead {
+    pub player: String,
+    pub opponent: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// Wins, draws and losses.
+#[derive(Default)]
+struct Record {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl Record {
+    fn add(&mut self, score: f64) {
+        match score {
+            s if s > 0.5 => self.wins += 1,
+            s if s < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+impl TournamentReport {
+    pub fn new(collection_name: &str, games: Vec<GameRecord>) -> Self {
+        let mut records: BTreeMap<&str, Record> = BTreeMap::new();
+        let mut pairs: BTreeMap<(&str, &str), Record> = BTreeMap::new();
+        for game in &games {
+            let white_score = game.result.white_score();
+            for (player, opponent, score) in [
+                (&game.white, &game.black, white_score),
+                (&game.black, &game.white, 1.0 - white_score),
+            ] {
+                records.entry(player).or_default().add(score);
+                pairs.entry((player, opponent)).or_default().add(score);
+            }
+        }
+
+        let elo = elo_ratings(&games);
+        let mut quality = blunders::move_quality(&games);
+        let mut standings: Vec<Standing> = records
+            .into_iter()
+            .map(|(player, record)| Standing {
+                player: player.to_string(),
+                games: record.wins + record.draws + record.losses,
+                points: record.wins as f64 + 0.5 * record.draws as f64,
+                wins: record.wins,
+                draws: record.draws,
+                losses: record.losses,
+                elo: elo.get(player).copied().unwrap_or(BASE_ELO),
+                move_quality: quality.remove(player),
+            })
+            .collect();
+        standings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        let head_to_head = pairs
+            .into_iter()
+            .map(|((player, opponent), record)| HeadToHead {
+                player: player.to_string(),
+                opponent: opponent.to_string(),
+                wins: record.wins,
+                draws: record.draws,
+                losses: record.losses,
+            })
+            .collect();
+
+        Self {
+            collection_name: collection_name.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            standings,
+            head_to_head,
+            games,
+        }
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), TournamentError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Elo ratings of the players of `games`, fitted jointly (Bradley-Terry,
+/// with draws as half a win) so the order the games were played in doesn't
+/// matter. The ratings average to 1500.
+pub fn elo_ratings(games: &[GameRecord]) -> BTreeMap<String, f64> {
+    let mut players: Vec<&str> = games
+        .iter()
+        .flat_map(|game| [game.white.as_str(), game.black.as_str()])
+        .collect();
+    players.sort();
+    players.dedup();
+    let index = |name: &str| players.binary_search(&name).expect("every player is listed");
+    let results: Vec<(usize, usize, f64)> = games
+        .iter()
+        .map(|game| (index(&game.white), index(&game.black), game.result.white_score()))
+        .collect();
+
+    let mut strengths = vec![0.0; players.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut gradient: Vec<f64> = strengths.iter().map(|s| -PRIOR_PRECISION * s).collect();
+        let mut curvature = vec![PRIOR_PRECISION; players.len()];
+        for &(white, black, score) in &results {
+            let expected = 1.0 / (1.0 + (strengths[black] - strengths[white]).exp());
+            gradient[white] += score - expected;
+            gradient[black] -= score - expected;
+            let information = expected * (1.0 - expected);
+            curvature[white] += information;
+            curvature[black] += information;
+        }
+        let mut largest = 0.0f64;
+        for ((strength, gradient), curvature) in strengths.iter_mut().zip(gradient).zip(curvature) {
+            let step = gradient / curvature;
+            *strength += step;
+            largest = largest.max(step.abs());
+        }
+        if largest < TOLERANCE {
+            break;
+        }
+    }
+
+    let mean = strengths.iter().sum::<f64>() / strengths.len().max(1) as f64;
+    players
+        .into_iter()
+        .zip(strengths)
+        .map(|(player, strength)| (player.to_string(), BASE_ELO + (strength - mean) * 400.0 / LN_10))
+        .collect()
+}
@@ -0,0 +1,46 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::results::BenchmarkResult;
+use crate::puzzle::PuzzleScore;
+
+pub mod mlflow;
+#[cfg(feature = "wandb")]
+pub mod wandb;
+
+/// Running totals for the solver currently being benchmarked.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunProgress {
+    pub solver_name: String,
+    pub completed: usize,
+    pub total: usize,
+    pub score: f64,
+    pub max_possible_score: f64,
+    pub cost: f64,
+}
+
+impl RunProgress {
+    pub fn accuracy(&self) -> f64 {
+        if self.max_possible_score > 0.0 {
+            self.score / self.max_possible_score
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Receives run events from `BenchmarkRunner` for external experiment
+/// tracking. All methods default to doing nothing.
+pub trait Tracker: Send + Sync {
+    /// Called once before any puzzle is dispatched, with the run configuration.
+    fn start(&self, _config: &Value) {}
+
+    /// Called after every scored puzzle.
+    fn puzzle_finished(&self, _score: &PuzzleScore, _progress: &RunProgress) {}
+
+    /// Called with each solver's final result.
+    fn finish(&self, _result: &BenchmarkResult) {}
+
+    /// Called once after all results are in.
+    fn end(&self) {}
+}
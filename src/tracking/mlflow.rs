@@ -0,0 +1,274 @@
+use serde_json::{Value, json};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use super::{RunProgress, Tracker};
+use crate::results::BenchmarkResult;
+use crate::puzzle::PuzzleScore;
+
+/// Records a benchmark run in an MLflow tracking server through its REST
+/// API: run parameters, running and final metrics, and the result JSON
+/// uploaded as an artifact (requires a server with artifact proxying).
+pub struct MlflowTracker {
+    tracking_uri: String,
+    experiment_name: String,
+    token: Option<String>,
+    basic_auth: Option<(String, String)>,
+    run: Mutex<Option<MlflowRun>>,
+}
+
+#[derive(Clone)]
+struct MlflowRun {
+    experiment_id: String,
+    run_id: String,
+}
+
+impl MlflowTracker {
+    /// Create a tracker for `tracking_uri`, authenticating with
+    /// `MLFLOW_TRACKING_TOKEN` or `MLFLOW_TRACKING_USERNAME`/`MLFLOW_TRACKING_PASSWORD`
+    /// when set.
+    pub fn new(tracking_uri: &str, experiment_name: &str) -> Self {
+        let basic_auth = std::env::var("MLFLOW_TRACKING_USERNAME")
+            .ok()
+            .map(|user| {
+                let password = std::env::var("MLFLOW_TRACKING_PASSWORD").unwrap_or_default();
+                (user, password)
+            });
+        Self {
+            tracking_uri: tracking_uri.trim_end_matches('/').to_string(),
+            experiment_name: experiment_name.to_string(),
+            token: std::env::var("MLFLOW_TRACKING_TOKEN").ok(),
+            basic_auth,
+            run: Mutex::new(None),
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<reqwest::Body>,
+        content_type: &str,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}{}", self.tracking_uri, path);
+        crate::runtime::block_on(async {
+            let mut request = reqwest::Client::new()
+                .request(method, &url)
+                .header(reqwest::header::CONTENT_TYPE, content_type);
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            } else if let Some((user, password)) = &self.basic_auth {
+                request = request.basic_auth(user, Some(password));
+            }
+            if let Some(body) = body {
+                request = request.body(body);
+            }
+
+            let response = request.send().await?;
+            let status = response.status();
+            let text = response.text().await?;
+            if !status.is_success() {
+                return Err(format!("{}: {}", status, text).into());
+            }
+            Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+        })
+    }
+
+    fn post(&self, path: &str, body: &Value) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        self.request(
+            reqwest::Method::POST,
+            path,
+            Some(body.to_string().into()),
+            "application/json",
+        )
+    }
+
+    fn experiment_id(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let lookup = self.request(
+            reqwest::Method::GET,
+            &format!(
+                "/api/2.0/mlflow/experiments/get-by-name?experiment_name={}",
+                encode(&self.experiment_name)
+            ),
+            None,
+            "application/json",
+        );
+        if let Ok(found) = lookup
+            && let Some(id) = found["experiment"]["experiment_id"].as_str()
+        {
+            return Ok(id.to_string());
+        }
+
+        let created = self.post(
+            "/api/2.0/mlflow/experiments/create",
+            &json!({ "name": self.experiment_name }),
+        )?;
+        created["experiment_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "MLflow did not return an experiment id".into())
+    }
+
+    fn log_batch(&self, metrics: Vec<Value>, params: Vec<Value>) {
+        let Some(run) = self.run.lock().unwrap().clone() else {
+            return;
+        };
+        let body = json!({ "run_id": run.run_id, "metrics": metrics, "params": params });
+        if let Err(e) = self.post("/api/2.0/mlflow/runs/log-batch", &body) {
+            warn!(error = %e, "MLflow logging failed");
+        }
+    }
+
+    fn metric(key: &str, value: f64, step: usize) -> Value {
+        json!({
+            "key": key,
+            "value": value,
+            "timestamp": chrono::Utc::now().timestamp_millis(),
+            "step": step,
+        })
+    }
+}
+
+impl Tracker for MlflowTracker {
+    fn start(&self, config: &Value) {
+        let run = self.experiment_id().and_then(|experiment_id| {
+            let created = self.post(
+                "/api/2.0/mlflow/runs/create",
+                &json!({
+                    "experiment_id": experiment_id,
+                    "start_time": chrono::Utc::now().timestamp_millis(),
+                    "run_name": format!("bench-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S")),
+                }),
+            )?;
+            let run_id = created["run"]["info"]["run_id"]
+                .as_str()
+                .ok_or("MLflow did not return a run id")?
+                .to_string();
+            Ok(MlflowRun {
+                experiment_id,
+                run_id,
+            })
+        });
+
+        match run {
+            Ok(run) => {
+                info!(run_id = %run.run_id, uri = %self.tracking_uri, "logging to MLflow");
+                *self.run.lock().unwrap() = Some(run);
+            }
+            Err(e) => {
+                warn!(error = %e, "could not create MLflow run");
+                return;
+            }
+        }
+
+        // MLflow caps parameter values at 500 characters.
+        let params = config
+            .as_object()
+            .map(|object| {
+                object
+                    .iter()
+                    .map(|(k, v)| {
+                        let value: String = match v {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        }
+                        .chars()
+                        .take(500)
+                        .collect();
+                        json!({ "key": k, "value": value })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.log_batch(Vec::new(), params);
+    }
+
+    fn puzzle_finished(&self, _score: &PuzzleScore, progress: &RunProgress) {
+        let prefix = sanitize(&progress.solver_name);
+        self.log_batch(
+            vec![
+                Self::metric(&format!("{}.accuracy", prefix), progress.accuracy(), progress.completed),
+                Self::metric(&format!("{}.cost", prefix), progress.cost, progress.completed),
+            ],
+            Vec::new(),
+        );
+    }
+
+    fn finish(&self, result: &BenchmarkResult) {
+        let prefix = sanitize(&result.solver_name);
+        let mut metrics = vec![
+            Self::metric(&format!("{}.average_score", prefix), result.average_score, 0),
+            Self::metric(&format!("{}.total_score", prefix), result.total_score, 0),
+            Self::metric(
+                &format!("{}.max_possible_score", prefix),
+                result.max_possible_score,
+                0,
+            ),
+        ];
+        if let Some(usage) = &result.usage {
+            metrics.push(Self::metric(&format!("{}.cost", prefix), usage.cost, 0));
+        }
+        self.log_batch(metrics, Vec::new());
+
+        let Some(run) = self.run.lock().unwrap().clone() else {
+            return;
+        };
+        let json = match serde_json::to_string_pretty(result) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!(error = %e, "could not serialize result for MLflow");
+                return;
+            }
+        };
+        let path = format!(
+            "/api/2.0/mlflow-artifacts/artifacts/{}/{}/artifacts/{}_results.json",
+            encode(&run.experiment_id),
+            encode(&run.run_id),
+            encode(&prefix)
+        );
+        if let Err(e) = self.request(reqwest::Method::PUT, &path, Some(json.into()), "application/json")
+        {
+            warn!(error = %e, "could not upload MLflow artifact");
+        }
+    }
+
+    fn end(&self) {
+        let Some(run) = self.run.lock().unwrap().clone() else {
+            return;
+        };
+        let body = json!({
+            "run_id": run.run_id,
+            "status": "FINISHED",
+            "end_time": chrono::Utc::now().timestamp_millis(),
+        });
+        if let Err(e) = self.post("/api/2.0/mlflow/runs/update", &body) {
+            warn!(error = %e, "could not finish MLflow run");
+        }
+    }
+}
+
+/// MLflow keys only allow alphanumerics, `_ - . / ` and spaces.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_-./ ".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `text` percent-encoded for a URL's path segment or query value.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
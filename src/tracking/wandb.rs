@@ -0,0 +1,168 @@
+use serde_json::{Value, json};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use super::{RunProgress, Tracker};
+use crate::results::BenchmarkResult;
+use crate::puzzle::PuzzleScore;
+
+const DEFAULT_BASE_URL: &str = "https://api.wandb.ai";
+
+/// Logs a benchmark run to Weights & Biases through its HTTP API: the run
+/// config at start, accuracy/cost history per puzzle, and the final
+/// per-puzzle table in the run summary.
+pub struct WandbTracker {
+    base_url: String,
+    api_key: String,
+    entity: String,
+    project: String,
+    run_id: String,
+    state: Mutex<StreamState>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    history_offset: usize,
+    summary: serde_json::Map<String, Value>,
+}
+
+impl WandbTracker {
+    /// Create a tracker from `WANDB_API_KEY`, `WANDB_ENTITY` and optionally
+    /// `WANDB_BASE_URL`.
+    pub fn from_env(project: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let api_key = std::env::var("WANDB_API_KEY")
+            .map_err(|_| "WANDB_API_KEY environment variable not set")?;
+        let entity = std::env::var("WANDB_ENTITY")
+            .map_err(|_| "WANDB_ENTITY environment variable not set")?;
+        let base_url =
+            std::env::var("WANDB_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+
+        Ok(Self {
+            base_url,
+            api_key,
+            entity,
+            project: project.to_string(),
+            run_id: format!("bench-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S")),
+            state: Mutex::new(StreamState::default()),
+        })
+    }
+
+    fn post(&self, url: &str, body: &Value) -> Result<(), Box<dyn std::error::Error>> {
+        crate::runtime::block_on(async {
+            let response = reqwest::Client::new()
+                .post(url)
+                .basic_auth("api", Some(&self.api_key))
+                .json(body)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(format!("{}: {}", response.status(), response.text().await?).into());
+            }
+            Ok(())
+        })
+    }
+
+    fn file_stream(&self, body: &Value) {
+        let url = format!(
+            "{}/files/{}/{}/{}/file_stream",
+            self.base_url, self.entity, self.project, self.run_id
+        );
+        if let Err(e) = self.post(&url, body) {
+            warn!(error = %e, "W&B file stream update failed");
+        }
+    }
+}
+
+impl Tracker for WandbTracker {
+    fn start(&self, config: &Value) {
+        let wandb_config: serde_json::Map<String, Value> = config
+            .as_object()
+            .map(|object| {
+                object
+                    .iter()
+                    .map(|(k, v)| (k.clone(), json!({ "value": v })))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mutation = json!({
+            "query": "mutation UpsertBucket($name: String, $project: String, $entity: String, $config: JSONString) {
+                upsertBucket(input: {name: $name, modelName: $project, entityName: $entity, config: $config}) {
+                    bucket { id name }
+                }
+            }",
+            "variables": {
+                "name": self.run_id,
+                "project": self.project,
+                "entity": self.entity,
+                "config": Value::Object(wandb_config).to_string(),
+            },
+        });
+        match self.post(&format!("{}/graphql", self.base_url), &mutation) {
+            Ok(()) => info!(
+                entity = %self.entity,
+                project = %self.project,
+                run_id = %self.run_id,
+                "logging to W&B"
+            ),
+            Err(e) => warn!(error = %e, "could not create W&B run"),
+        }
+    }
+
+    fn puzzle_finished(&self, _score: &PuzzleScore, progress: &RunProgress) {
+        let row = json!({
+            "_step": progress.completed,
+            "solver": progress.solver_name,
+            "completed": progress.completed,
+            "accuracy": progress.accuracy(),
+            "cost": progress.cost,
+        });
+        let offset = {
+            let mut state = self.state.lock().unwrap();
+            state.history_offset += 1;
+            state.history_offset - 1
+        };
+        self.file_stream(&json!({
+            "files": {
+                "wandb-history.jsonl": { "offset": offset, "content": [row.to_string()] }
+            }
+        }));
+    }
+
+    fn finish(&self, result: &BenchmarkResult) {
+        let table = json!({
+            "_type": "table",
+            "columns": ["puzzle_id", "score", "max_possible_score", "answers", "elapsed_ms"],
+            "data": result.puzzle_scores.iter().map(|s| json!([
+                s.puzzle_id, s.score, s.max_possible_score, s.answers.join(" "), s.elapsed_ms
+            ])).collect::<Vec<_>>(),
+        });
+
+        let summary = {
+            let mut state = self.state.lock().unwrap();
+            let prefix = &result.solver_name;
+            state
+                .summary
+                .insert(format!("{}/average_score", prefix), json!(result.average_score));
+            state
+                .summary
+                .insert(format!("{}/total_score", prefix), json!(result.total_score));
+            if let Some(usage) = &result.usage {
+                state.summary.insert(format!("{}/cost", prefix), json!(usage.cost));
+            }
+            state
+                .summary
+                .insert(format!("{}/puzzle_scores", prefix), table);
+            Value::Object(state.summary.clone())
+        };
+
+        self.file_stream(&json!({
+            "files": {
+                "wandb-summary.json": { "offset": 0, "content": [summary.to_string()] }
+            }
+        }));
+    }
+
+    fn end(&self) {
+        self.file_stream(&json!({ "complete": true, "exitcode": 0 }));
+    }
+}
@@ -0,0 +1,70 @@
+//! Lenient scoring of board transcriptions, for perception tasks where the
+//! model reads a board (usually from an image) and writes it out as FEN.
+
+/// Credit for `answer` as a transcription of the FEN `expected`, in [0, 1].
+///
+/// The piece placement counts for half, scored by the share of `expected`'s
+/// squares that `answer` has right, rank by rank, so one misread piece costs
+/// little. The other fields `expected` has (side to move, castling rights, en
+/// passant square, clocks) share the other half and must match exactly. If
+/// `expected` is only a placement, the placement is the whole score.
+pub fn transcription_score(expected: &str, answer: &str) -> f64 {
+    let expected_fields: Vec<&str> = expected.split_whitespace().collect();
+    let answer_fields: Vec<&str> = answer.split_whitespace().collect();
+    let Some((expected_placement, expected_rest)) = expected_fields.split_first() else {
+        return 0.0;
+    };
+    let placement = placement_score(expected_placement, answer_fields.first().copied().unwrap_or_default());
+    if expected_rest.is_empty() {
+        return placement;
+    }
+
+    let matching = expected_rest
+        .iter()
+        .enumerate()
+        .filter(|(i, field)| answer_fields.get(i + 1) == Some(*field))
+        .count();
+    0.5 * placement + 0.5 * matching as f64 / expected_rest.len() as f64
+}
+
+/// Share of the squares of `expected` that `answer` has the same piece (or
+/// emptiness) on.
+fn placement_score(expected: &str, answer: &str) -> f64 {
+    let expected = squares(expected);
+    let answer = squares(answer);
+    let total: usize = expected.iter().map(Vec::len).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let matching: usize = expected
+        .iter()
+        .enumerate()
+        .map(|(rank, squares)| {
+            let answer_rank = answer.get(rank).map(Vec::as_slice).unwrap_or_default();
+            squares
+                .iter()
+                .zip(answer_rank)
+                .filter(|(expected, answer)| expected == answer)
+                .count()
+        })
+        .sum();
+    matching as f64 / total as f64
+}
+
+/// The ranks of a placement field, top first, with digits expanded to that
+/// many empty squares (`None`).
+fn squares(placement: &str) -> Vec<Vec<Option<char>>> {
+    placement
+        .split('/')
+        .map(|rank| {
+            let mut squares = Vec::new();
+            for c in rank.chars() {
+                match c.to_digit(10) {
+                    Some(empty) => squares.extend(std::iter::repeat_n(None, empty as usize)),
+                    None => squares.push(Some(c)),
+                }
+            }
+            squares
+        })
+        .collect()
+}